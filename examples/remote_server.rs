@@ -0,0 +1,41 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// remote_server.rs - Displays the frames sent by a blinkt::remote::RemoteOutput
+// client on a Blinkt! board. The server listens on port 7000, and serves one
+// client at a time.
+//
+// Any Blinkt can be pointed at the server from another machine by
+// constructing it with Blinkt::with_output(RemoteOutput::connect(addr)?, 8).
+
+use std::error::Error;
+
+use blinkt::remote::RemoteServer;
+use blinkt::Blinkt;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut blinkt = Blinkt::new()?;
+    let server = RemoteServer::bind("0.0.0.0:7000")?;
+
+    println!("Listening on {}", server.local_addr()?);
+    server.run(&mut blinkt)?;
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+#ifndef BLINKT_H
+#define BLINKT_H
+
+// Generated with cbindgen. Don't edit manually.
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct Blinkt Blinkt;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns a description of the last error that occurred on the calling
+// thread, or `NULL` if no error has occurred.
+//
+// The string remains valid until the next failing call on the same thread.
+const char *blinkt_last_error(void);
+
+// Creates a `Blinkt` that drives a Blinkt! board, as `Blinkt::new()`.
+//
+// Returns `NULL` on failure.
+Blinkt *blinkt_new(void);
+
+// Creates a `Blinkt` that drives a strip with `num_pixels` pixels through
+// the SPI peripheral, as `Blinkt::with_spi()`.
+//
+// Returns `NULL` on failure.
+Blinkt *blinkt_with_spi(uint32_t clock_speed_hz, uintptr_t num_pixels);
+
+// Releases a `Blinkt`. Does nothing if `blinkt` is `NULL`.
+//
+// # Safety
+//
+// `blinkt` must have been returned by `blinkt_new()` or
+// `blinkt_with_spi()`, and can't be used afterwards.
+void blinkt_destroy(Blinkt *blinkt);
+
+// Returns the number of pixels, or `0` if `blinkt` is `NULL`.
+//
+// # Safety
+//
+// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+// `blinkt_with_spi()`.
+uintptr_t blinkt_num_pixels(const Blinkt *blinkt);
+
+// Sets the red, green and blue values of a single pixel.
+//
+// # Safety
+//
+// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+// `blinkt_with_spi()`.
+void blinkt_set_pixel(Blinkt *blinkt, uintptr_t pixel, uint8_t red, uint8_t green, uint8_t blue);
+
+// Sets the red, green, blue and brightness values of a single pixel.
+//
+// # Safety
+//
+// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+// `blinkt_with_spi()`.
+void blinkt_set_pixel_rgbb(Blinkt *blinkt,
+                           uintptr_t pixel,
+                           uint8_t red,
+                           uint8_t green,
+                           uint8_t blue,
+                           float brightness);
+
+// Sets the brightness of a single pixel, from `0.0` to `1.0`.
+//
+// # Safety
+//
+// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+// `blinkt_with_spi()`.
+void blinkt_set_pixel_brightness(Blinkt *blinkt, uintptr_t pixel, float brightness);
+
+// Sets the red, green and blue values of all pixels.
+//
+// # Safety
+//
+// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+// `blinkt_with_spi()`.
+void blinkt_set_all_pixels(Blinkt *blinkt, uint8_t red, uint8_t green, uint8_t blue);
+
+// Sets the brightness of all pixels, from `0.0` to `1.0`.
+//
+// # Safety
+//
+// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+// `blinkt_with_spi()`.
+void blinkt_set_all_pixels_brightness(Blinkt *blinkt, float brightness);
+
+// Turns off all pixels.
+//
+// # Safety
+//
+// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+// `blinkt_with_spi()`.
+void blinkt_clear(Blinkt *blinkt);
+
+// Sets whether all pixels are turned off when the `Blinkt` is destroyed.
+//
+// # Safety
+//
+// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+// `blinkt_with_spi()`.
+void blinkt_set_clear_on_drop(Blinkt *blinkt, bool clear_on_drop);
+
+// Sends the color and brightness values to the pixels.
+//
+// Returns `0` on success, or `-1` on failure.
+//
+// # Safety
+//
+// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+// `blinkt_with_spi()`.
+int blinkt_show(Blinkt *blinkt);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif // BLINKT_H
@@ -0,0 +1,587 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Mirroring the edges of the screen onto a strip behind a TV or monitor.
+//!
+//! An [`Ambilight`] samples the Linux framebuffer, usually `/dev/fb0`, at a
+//! configurable rate. It averages the colors along the edges of the screen,
+//! and shows each average on the pixel behind that part of the screen.
+//!
+//! The placement of the strip is described as a list of edges, in the order
+//! in which the strip runs along them, together with the number of pixels
+//! on each edge. By default, the strip runs clockwise when looking at the
+//! screen: left to right along the top edge, top to bottom along the right
+//! edge, right to left along the bottom edge, and bottom to top along the
+//! left edge.
+//!
+//! ```rust,no_run
+//! use blinkt::ambilight::{Ambilight, Edge};
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 2 * (32 + 18))?;
+//!
+//! // The strip starts at the bottom left corner, and runs clockwise.
+//! let mut ambilight = Ambilight::new()?
+//!     .with_edge(Edge::Left, 18)
+//!     .with_edge(Edge::Top, 32)
+//!     .with_edge(Edge::Right, 18)
+//!     .with_edge(Edge::Bottom, 32);
+//!
+//! ambilight.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Ambilight`]: struct.Ambilight.html
+
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Blinkt, Error, Result};
+
+/// The default framebuffer device.
+pub const FRAMEBUFFER: &str = "/dev/fb0";
+
+const FBIOGET_VSCREENINFO: libc::c_ulong = 0x4600;
+// Size of `struct fb_var_screeninfo`, in 32-bit words.
+const VAR_SCREENINFO_LEN: usize = 40;
+// Maximum number of samples per region in each direction.
+const SAMPLES: usize = 8;
+const DEFAULT_FPS: f32 = 20.0;
+const DEFAULT_DEPTH: f32 = 0.1;
+
+/// An edge of the screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Edge {
+    /// The top edge, running from left to right.
+    Top,
+    /// The right edge, running from top to bottom.
+    Right,
+    /// The bottom edge, running from right to left.
+    Bottom,
+    /// The left edge, running from bottom to top.
+    Left,
+}
+
+/// How the color of an area along an edge is determined.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColorMode {
+    /// The average color of the area.
+    Average,
+    /// The most common color in the area, so small details, such as
+    /// subtitles or logos, don't affect the color.
+    Dominant,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Placement {
+    // `None` for a gap.
+    edge: Option<Edge>,
+    num_pixels: usize,
+    reversed: bool,
+}
+
+// The bit offset and length of a color channel.
+#[derive(Debug, Copy, Clone)]
+struct Channel {
+    offset: u32,
+    length: u32,
+}
+
+impl Channel {
+    fn value(self, pixel: u32) -> u8 {
+        if self.length == 0 {
+            return 0;
+        }
+
+        let max = (1u64 << self.length.min(32)) - 1;
+        let value = (u64::from(pixel) >> self.offset.min(31)) & max;
+
+        (value * 255 / max) as u8
+    }
+}
+
+// A read-only mapping of a framebuffer device.
+#[derive(Debug)]
+struct Framebuffer {
+    data: *const u8,
+    len: usize,
+    width: usize,
+    height: usize,
+    x_offset: usize,
+    y_offset: usize,
+    bytes_per_pixel: usize,
+    stride: usize,
+    red: Channel,
+    green: Channel,
+    blue: Channel,
+}
+
+impl Framebuffer {
+    fn open(path: &Path) -> Result<Framebuffer> {
+        let file = File::open(path)?;
+
+        let mut info = [0u32; VAR_SCREENINFO_LEN];
+        if unsafe {
+            libc::ioctl(
+                file.as_raw_fd(),
+                FBIOGET_VSCREENINFO as _,
+                info.as_mut_ptr(),
+            )
+        } == -1
+        {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        let bits_per_pixel = info[6] as usize;
+        if bits_per_pixel != 16 && bits_per_pixel != 24 && bits_per_pixel != 32 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unsupported framebuffer pixel format",
+            )));
+        }
+
+        let bytes_per_pixel = bits_per_pixel / 8;
+        let virtual_height = info[3] as usize;
+
+        // Rows may be padded, which is only reported through sysfs.
+        let stride = path
+            .file_name()
+            .and_then(|name| {
+                let path = Path::new("/sys/class/graphics").join(name).join("stride");
+                fs::read_to_string(path).ok()
+            })
+            .and_then(|stride| stride.trim().parse().ok())
+            .unwrap_or(info[2] as usize * bytes_per_pixel);
+
+        let len = stride * virtual_height;
+        if len == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "framebuffer is empty",
+            )));
+        }
+
+        let data = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+
+        if data == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        Ok(Framebuffer {
+            data: data as *const u8,
+            len,
+            width: info[0] as usize,
+            height: info[1] as usize,
+            x_offset: info[4] as usize,
+            y_offset: info[5] as usize,
+            bytes_per_pixel,
+            stride,
+            red: Channel {
+                offset: info[8],
+                length: info[9],
+            },
+            green: Channel {
+                offset: info[11],
+                length: info[12],
+            },
+            blue: Channel {
+                offset: info[14],
+                length: info[15],
+            },
+        })
+    }
+
+    // Returns the color of a pixel on the visible part of the screen.
+    fn pixel(&self, x: usize, y: usize) -> [u32; 3] {
+        let offset = (self.y_offset + y) * self.stride + (self.x_offset + x) * self.bytes_per_pixel;
+        if offset + self.bytes_per_pixel > self.len {
+            return [0; 3];
+        }
+
+        let mut value = 0u32;
+        for byte in 0..self.bytes_per_pixel {
+            // The screen is updated by other processes at any time.
+            let byte_value = unsafe { ptr::read_volatile(self.data.add(offset + byte)) };
+            value |= u32::from(byte_value) << (8 * byte);
+        }
+
+        [
+            u32::from(self.red.value(value)),
+            u32::from(self.green.value(value)),
+            u32::from(self.blue.value(value)),
+        ]
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.data as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+// The placement of a strip along the edges of an image, and the colors of
+// its pixels. Shared by `Ambilight` and `camera::Camera`.
+#[derive(Debug, Clone)]
+pub(crate) struct Zones {
+    placements: Vec<Placement>,
+    depth: f32,
+    mode: ColorMode,
+    smoothing: f32,
+    colors: Vec<[f32; 3]>,
+}
+
+impl Zones {
+    pub(crate) fn new() -> Zones {
+        Zones {
+            placements: Vec::new(),
+            depth: DEFAULT_DEPTH,
+            mode: ColorMode::Average,
+            smoothing: 0.0,
+            colors: Vec::new(),
+        }
+    }
+
+    // Adds `num_pixels` pixels along `edge`, or a gap if `edge` is `None`.
+    pub(crate) fn push(&mut self, edge: Option<Edge>, num_pixels: usize, reversed: bool) {
+        self.placements.push(Placement {
+            edge,
+            num_pixels,
+            reversed,
+        });
+    }
+
+    pub(crate) fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.max(0.01).min(0.5);
+    }
+
+    pub(crate) fn set_mode(&mut self, mode: ColorMode) {
+        self.mode = mode;
+    }
+
+    pub(crate) fn set_smoothing(&mut self, smoothing: f32) {
+        self.smoothing = smoothing.max(0.0).min(0.99);
+    }
+
+    pub(crate) fn num_pixels(&self) -> usize {
+        self.placements
+            .iter()
+            .map(|placement| placement.num_pixels)
+            .sum()
+    }
+
+    // Samples a `width` by `height` image through `pixel`, which returns
+    // the red, green and blue values at the specified coordinates, and
+    // sends the colors to the pixels.
+    pub(crate) fn show<F>(
+        &mut self,
+        blinkt: &mut Blinkt,
+        width: usize,
+        height: usize,
+        pixel: F,
+    ) -> Result<()>
+    where
+        F: Fn(usize, usize) -> [u32; 3],
+    {
+        let samples = self.sample(width, height, pixel);
+
+        self.colors.resize(samples.len(), [0.0; 3]);
+        for (index, (color, sample)) in self.colors.iter_mut().zip(samples).enumerate() {
+            for (channel, value) in color.iter_mut().zip(&sample) {
+                *channel = *channel * self.smoothing + *value as f32 * (1.0 - self.smoothing);
+            }
+
+            blinkt.set_pixel(
+                index,
+                color[0].round() as u8,
+                color[1].round() as u8,
+                color[2].round() as u8,
+            );
+        }
+
+        blinkt.swap();
+        blinkt.show()
+    }
+
+    // Returns the color of the area behind each pixel, in strip order.
+    fn sample<F>(&self, width: usize, height: usize, pixel: F) -> Vec<[u32; 3]>
+    where
+        F: Fn(usize, usize) -> [u32; 3],
+    {
+        let depth_x = ((width as f32 * self.depth) as usize).max(1).min(width);
+        let depth_y = ((height as f32 * self.depth) as usize).max(1).min(height);
+        let color = |x, y, width, height| region_color(x, y, width, height, self.mode, &pixel);
+
+        let mut samples = Vec::with_capacity(self.num_pixels());
+        for placement in &self.placements {
+            let count = placement.num_pixels;
+            for index in 0..count {
+                // Position along the edge in its default, clockwise direction.
+                let position = if placement.reversed {
+                    count - 1 - index
+                } else {
+                    index
+                };
+
+                let sample = match placement.edge {
+                    _ if width == 0 || height == 0 => [0; 3],
+                    None => [0; 3],
+                    Some(Edge::Top) => {
+                        let (start, len) = span(width, count, position);
+                        color(start, 0, len, depth_y)
+                    }
+                    Some(Edge::Right) => {
+                        let (start, len) = span(height, count, position);
+                        color(width - depth_x, start, depth_x, len)
+                    }
+                    Some(Edge::Bottom) => {
+                        let (start, len) = span(width, count, count - 1 - position);
+                        color(start, height - depth_y, len, depth_y)
+                    }
+                    Some(Edge::Left) => {
+                        let (start, len) = span(height, count, count - 1 - position);
+                        color(0, start, depth_x, len)
+                    }
+                };
+
+                samples.push(sample);
+            }
+        }
+
+        samples
+    }
+}
+
+// Returns the start and length of the `index`th of `count` equal parts of
+// `len`.
+fn span(len: usize, count: usize, index: usize) -> (usize, usize) {
+    let start = index * len / count;
+    let end = (index + 1) * len / count;
+
+    (start, (end - start).max(1).min(len - start.min(len)))
+}
+
+// Returns the average or dominant color of the specified area, based on a
+// grid of samples.
+fn region_color<F>(
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    mode: ColorMode,
+    pixel: &F,
+) -> [u32; 3]
+where
+    F: Fn(usize, usize) -> [u32; 3],
+{
+    let steps_x = width.min(SAMPLES).max(1);
+    let steps_y = height.min(SAMPLES).max(1);
+
+    // Sums of the samples, grouped by their color with 3 bits per channel
+    // for the dominant color, or all in one group for the average.
+    let mut groups: Vec<(u32, u32, [u32; 3])> = Vec::new();
+    for step_y in 0..steps_y {
+        for step_x in 0..steps_x {
+            // Sample the center of each cell.
+            let sample_x = x + (2 * step_x + 1) * width / (2 * steps_x);
+            let sample_y = y + (2 * step_y + 1) * height / (2 * steps_y);
+            let color = pixel(sample_x, sample_y);
+
+            let key = match mode {
+                ColorMode::Average => 0,
+                ColorMode::Dominant => {
+                    (color[0] >> 5) << 6 | (color[1] >> 5) << 3 | (color[2] >> 5)
+                }
+            };
+
+            let group = match groups.iter().position(|group| group.0 == key) {
+                Some(group) => group,
+                None => {
+                    groups.push((key, 0, [0; 3]));
+                    groups.len() - 1
+                }
+            };
+
+            let (_, ref mut count, ref mut sum) = groups[group];
+            *count += 1;
+            for (sum, value) in sum.iter_mut().zip(&color) {
+                *sum += value;
+            }
+        }
+    }
+
+    // The first of the largest groups wins.
+    let mut largest = &groups[0];
+    for group in &groups[1..] {
+        if group.1 > largest.1 {
+            largest = group;
+        }
+    }
+
+    let (_, count, sum) = *largest;
+    [sum[0] / count, sum[1] / count, sum[2] / count]
+}
+
+/// Mirrors the colors along the edges of the screen onto a strip.
+#[derive(Debug)]
+pub struct Ambilight {
+    framebuffer: Framebuffer,
+    zones: Zones,
+    frame_interval: Duration,
+}
+
+// The mapping is only read through volatile reads, and is never moved or
+// resized.
+unsafe impl Send for Ambilight {}
+
+impl Ambilight {
+    /// Constructs a new `Ambilight` that samples the default framebuffer
+    /// device.
+    pub fn new() -> Result<Ambilight> {
+        Ambilight::open(FRAMEBUFFER)
+    }
+
+    /// Constructs a new `Ambilight` that samples the framebuffer device at
+    /// `path`.
+    ///
+    /// The framebuffer needs to use 16, 24 or 32 bits per pixel. By default,
+    /// the screen is sampled 20 times per second, and no edges are placed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Ambilight> {
+        Ok(Ambilight {
+            framebuffer: Framebuffer::open(path.as_ref())?,
+            zones: Zones::new(),
+            frame_interval: Duration::from_micros((1_000_000.0 / DEFAULT_FPS) as u64),
+        })
+    }
+
+    /// Adds `num_pixels` pixels along `edge`, following the pixels that were
+    /// added previously.
+    pub fn with_edge(mut self, edge: Edge, num_pixels: usize) -> Ambilight {
+        self.zones.push(Some(edge), num_pixels, false);
+        self
+    }
+
+    /// Adds `num_pixels` pixels along `edge` running counterclockwise,
+    /// following the pixels that were added previously.
+    pub fn with_edge_reversed(mut self, edge: Edge, num_pixels: usize) -> Ambilight {
+        self.zones.push(Some(edge), num_pixels, true);
+        self
+    }
+
+    /// Skips `num_pixels` pixels, for instance where the strip runs around a
+    /// corner or behind a stand. Skipped pixels are turned off.
+    pub fn with_gap(mut self, num_pixels: usize) -> Ambilight {
+        self.zones.push(None, num_pixels, false);
+        self
+    }
+
+    /// Sets how far the sampled areas reach into the screen, as a fraction
+    /// of its width or height.
+    ///
+    /// `depth` is limited to a range of `0.01` to `0.5`. Defaults to `0.1`.
+    pub fn with_depth(mut self, depth: f32) -> Ambilight {
+        self.zones.set_depth(depth);
+        self
+    }
+
+    /// Sets how the color of each area is determined. Defaults to
+    /// `ColorMode::Average`.
+    pub fn with_mode(mut self, mode: ColorMode) -> Ambilight {
+        self.zones.set_mode(mode);
+        self
+    }
+
+    /// Sets the number of times per second the screen is sampled.
+    ///
+    /// The frame rate is limited to a range of 0.1 to 100 frames per
+    /// second. Defaults to 20.
+    pub fn with_fps(mut self, fps: f32) -> Ambilight {
+        let fps = fps.max(0.1).min(100.0);
+        self.frame_interval = Duration::from_micros((1_000_000.0 / fps) as u64);
+        self
+    }
+
+    /// Smooths out sudden changes, by blending each new color with the
+    /// previous one.
+    ///
+    /// `smoothing` ranges from `0.0`, which shows the new colors as they
+    /// are, to `1.0`, which never changes the colors. Defaults to `0.0`.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Ambilight {
+        self.zones.set_smoothing(smoothing);
+        self
+    }
+
+    /// Returns the width and height of the visible part of the screen.
+    pub fn screen_size(&self) -> (usize, usize) {
+        (self.framebuffer.width, self.framebuffer.height)
+    }
+
+    /// Returns the total number of pixels placed along the edges, including
+    /// any gaps.
+    pub fn num_pixels(&self) -> usize {
+        self.zones.num_pixels()
+    }
+
+    /// Samples the screen, and sends the colors to the pixels, until an
+    /// error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        let mut next_frame = Instant::now();
+
+        loop {
+            self.update(blinkt)?;
+
+            // Skip any frames we've missed.
+            let now = Instant::now();
+            next_frame += self.frame_interval;
+            if next_frame <= now {
+                next_frame = now + self.frame_interval;
+            }
+
+            thread::sleep(next_frame - now);
+        }
+    }
+
+    /// Samples the screen once, and sends the colors to the pixels.
+    pub fn update(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        let framebuffer = &self.framebuffer;
+
+        self.zones
+            .show(blinkt, framebuffer.width, framebuffer.height, |x, y| {
+                framebuffer.pixel(x, y)
+            })
+    }
+}
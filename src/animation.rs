@@ -0,0 +1,906 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Animations and the loops that drive them.
+//!
+//! The [`Animation`] trait describes an effect that renders a frame based on
+//! the time elapsed since it started. An [`Animator`] drives an animation at
+//! a target frame rate, either on the current thread or in the background,
+//! and takes care of the timing, sending each frame to the pixels, and
+//! stopping cleanly.
+//!
+//! [`play_once`] plays an effect on a shared `Blinkt` for a fixed duration on
+//! a separate thread, and returns an [`AnimationHandle`] that can be used to
+//! wait for the animation to finish, or to cancel it early. This makes it
+//! easy to sequence flows such as "flash green, then continue".
+//!
+//! [`Metered`] measures how long an animation takes to render each frame,
+//! and can hold it to a frame time budget.
+//!
+//! [`Animation`]: trait.Animation.html
+//! [`Animator`]: struct.Animator.html
+//! [`Metered`]: struct.Metered.html
+//! [`play_once`]: fn.play_once.html
+//! [`AnimationHandle`]: struct.AnimationHandle.html
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::color::Color;
+use crate::easing::Easing;
+use crate::frame::Frame;
+use crate::{Blinkt, Result};
+
+// Roughly 60 frames per second
+const FRAME_INTERVAL_MS: u64 = 16;
+
+/// An effect that renders frames based on the elapsed time.
+///
+/// `Animation` is implemented for any closure with a matching signature.
+pub trait Animation {
+    /// Renders the frame for time `t` since the animation started.
+    ///
+    /// `frame` contains the previously rendered frame, which allows effects
+    /// such as trails to build on top of it. Calculating the time elapsed
+    /// since the previous frame is left to the animation.
+    fn render(&mut self, t: Duration, frame: &mut Frame);
+
+    /// Returns `true` when the animation has finished at time `t`, and no
+    /// more frames should be rendered.
+    ///
+    /// By default, animations never finish on their own.
+    fn is_finished(&self, t: Duration) -> bool {
+        let _ = t;
+        false
+    }
+}
+
+impl<F> Animation for F
+where
+    F: FnMut(Duration, &mut Frame),
+{
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        self(t, frame)
+    }
+}
+
+/// Drives an [`Animation`] at a target frame rate.
+///
+/// Frames are scheduled at fixed intervals from the start of the animation.
+/// When rendering or sending a frame takes longer than the interval, the
+/// missed frames are skipped instead of being rendered late, so animations
+/// always stay in sync with the wall clock.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// # use std::time::Duration;
+/// #
+/// # use blinkt::animation::Animator;
+/// # use blinkt::{Blinkt, Frame};
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mut blinkt = Blinkt::new()?;
+///
+/// // Move a red dot along the strip, twice per second.
+/// let mut animation = |t: Duration, frame: &mut Frame| {
+///     let position = (t.subsec_millis() as usize % 500) * frame.len() / 500;
+///     frame.clear();
+///     frame.set_pixel(position, 255, 0, 0);
+/// };
+///
+/// Animator::new(60.0)
+///     .with_duration(Duration::from_secs(10))
+///     .run(&mut blinkt, &mut animation)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Animation`]: trait.Animation.html
+#[derive(Debug, Copy, Clone)]
+pub struct Animator {
+    frame_interval: Duration,
+    duration: Option<Duration>,
+}
+
+impl Animator {
+    /// Constructs a new `Animator` with the specified target frame rate, in
+    /// frames per second.
+    ///
+    /// The frame rate is limited to a range of 0.1 to 1000 frames per
+    /// second. By default, the animation runs until it finishes on its own,
+    /// or is cancelled.
+    pub fn new(fps: f32) -> Animator {
+        let fps = fps.max(0.1).min(1000.0);
+
+        Animator {
+            frame_interval: Duration::from_micros((1_000_000.0 / fps) as u64),
+            duration: None,
+        }
+    }
+
+    /// Stops the animation after `duration`.
+    ///
+    /// The last frame is always rendered at exactly `duration`.
+    pub fn with_duration(mut self, duration: Duration) -> Animator {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Returns the time between two consecutive frames.
+    pub fn frame_interval(&self) -> Duration {
+        self.frame_interval
+    }
+
+    /// Runs `animation` on the current thread, and blocks until it has
+    /// finished.
+    ///
+    /// Rendering starts from the current contents of the local buffer. After
+    /// each frame, the local buffer is updated and sent to the pixels.
+    ///
+    /// Returns an error if sending a frame to the pixels failed.
+    pub fn run<A>(&self, blinkt: &mut Blinkt, animation: &mut A) -> Result<()>
+    where
+        A: Animation + ?Sized,
+    {
+        let mut frame = blinkt.frame();
+
+        self.drive(animation, &mut frame, &AtomicBool::new(false), |frame| {
+            blinkt.set_frame(frame);
+            blinkt.swap();
+            blinkt.show()
+        })
+    }
+
+    /// Runs `animation` on a separate thread, and returns an
+    /// [`AnimationHandle`] that can be used to stop it or wait for it to
+    /// finish.
+    ///
+    /// `blinkt` is only locked while updating the local buffer and sending
+    /// a frame, so other threads can access it in between.
+    ///
+    /// [`AnimationHandle`]: struct.AnimationHandle.html
+    pub fn spawn<A>(self, blinkt: Arc<Mutex<Blinkt>>, mut animation: A) -> AnimationHandle
+    where
+        A: Animation + Send + 'static,
+    {
+        let done = Arc::new(AtomicBool::new(false));
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let done = done.clone();
+            let cancelled = cancelled.clone();
+
+            thread::spawn(move || {
+                let mut frame = lock(&blinkt).frame();
+                let result = self.drive(&mut animation, &mut frame, &cancelled, |frame| {
+                    let mut blinkt = lock(&blinkt);
+                    blinkt.set_frame(frame);
+                    blinkt.swap();
+                    blinkt.show()
+                });
+
+                done.store(true, Ordering::SeqCst);
+
+                result
+            })
+        };
+
+        AnimationHandle {
+            thread: Some(thread),
+            done,
+            cancelled,
+        }
+    }
+
+    fn drive<A, F>(
+        &self,
+        animation: &mut A,
+        frame: &mut Frame,
+        cancelled: &AtomicBool,
+        mut show: F,
+    ) -> Result<()>
+    where
+        A: Animation + ?Sized,
+        F: FnMut(&Frame) -> Result<()>,
+    {
+        let start = Instant::now();
+        let mut next_frame = Duration::from_millis(0);
+
+        while !cancelled.load(Ordering::SeqCst) {
+            let mut t = start.elapsed();
+            let last = match self.duration {
+                Some(duration) if t >= duration => {
+                    t = duration;
+                    true
+                }
+                _ => false,
+            };
+
+            if animation.is_finished(t) {
+                break;
+            }
+
+            animation.render(t, frame);
+            show(frame)?;
+
+            if last {
+                break;
+            }
+
+            // Skip any frames we've missed.
+            let elapsed = start.elapsed();
+            while next_frame <= elapsed {
+                next_frame += self.frame_interval;
+            }
+
+            if let Some(duration) = self.duration {
+                next_frame = next_frame.min(duration);
+            }
+
+            let elapsed = start.elapsed();
+            if next_frame > elapsed {
+                thread::sleep(next_frame - elapsed);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An animation that crossfades between two frames.
+///
+/// The transition finishes after rendering the target frame, so it can be
+/// run through an [`Animator`] without setting a duration. To transition
+/// from the current contents of a `Blinkt` and wait for it to finish, use
+/// [`Blinkt::transition_to`].
+///
+/// [`Animator`]: struct.Animator.html
+/// [`Blinkt::transition_to`]: ../struct.Blinkt.html#method.transition_to
+#[derive(Debug, Clone)]
+pub struct Transition {
+    from: Frame,
+    to: Frame,
+    duration: Duration,
+    easing: Easing,
+    finished: bool,
+}
+
+impl Transition {
+    /// Constructs a new `Transition` from `from` to `to`, taking `duration`
+    /// to complete, with its progress shaped by `easing`.
+    ///
+    /// If `to` contains fewer pixels than `from`, the remaining pixels keep
+    /// their original values.
+    pub fn new(from: Frame, to: Frame, duration: Duration, easing: Easing) -> Transition {
+        Transition {
+            from,
+            to,
+            duration,
+            easing,
+            finished: false,
+        }
+    }
+}
+
+impl Animation for Transition {
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        let progress = if t < self.duration {
+            self.easing
+                .apply(as_secs_f32(t) / as_secs_f32(self.duration))
+        } else {
+            self.finished = true;
+            1.0
+        };
+
+        for (idx, from) in self.from.iter().enumerate() {
+            let pixel = match self.to.pixel(idx) {
+                Some(to) => from.mix(to, progress),
+                None => *from,
+            };
+
+            if let Some(target) = frame.pixel_mut(idx) {
+                *target = pixel;
+            }
+        }
+    }
+
+    fn is_finished(&self, _t: Duration) -> bool {
+        self.finished
+    }
+}
+
+/// A sequence of keyframes that's played back as an animation.
+///
+/// Each keyframe specifies the contents of the strip at a point in time.
+/// In between keyframes, pixels are interpolated using the easing function
+/// of the next keyframe. Before the first keyframe, the first keyframe is
+/// shown, and after the last keyframe, the last keyframe is shown.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// # use std::time::Duration;
+/// #
+/// # use blinkt::animation::{Animator, Timeline};
+/// # use blinkt::easing::Easing;
+/// # use blinkt::{Blinkt, Color};
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mut blinkt = Blinkt::new()?;
+///
+/// let red = Color::new(255, 0, 0);
+/// let blue = Color::new(0, 0, 255);
+///
+/// let mut timeline = Timeline::new();
+/// timeline.add_color_keyframe(Duration::from_secs(0), &[red; 8], Easing::Linear);
+/// timeline.add_color_keyframe(Duration::from_secs(2), &[blue; 8], Easing::EaseInOutSine);
+/// timeline.add_color_keyframe(Duration::from_secs(4), &[red; 8], Easing::EaseInOutSine);
+/// timeline.set_looping(true);
+///
+/// Animator::new(60.0)
+///     .with_duration(Duration::from_secs(60))
+///     .run(&mut blinkt, &mut timeline)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    keyframes: Vec<(Duration, Frame, Easing)>,
+    looping: bool,
+    last_rendered: Option<Duration>,
+}
+
+impl Timeline {
+    /// Constructs a new, empty `Timeline`.
+    pub fn new() -> Timeline {
+        Timeline::default()
+    }
+
+    /// Adds a keyframe at time `time`, replacing any existing keyframe at the
+    /// same time.
+    ///
+    /// `easing` shapes the interpolation from the previous keyframe to this
+    /// one.
+    pub fn add_keyframe(&mut self, time: Duration, frame: Frame, easing: Easing) {
+        match self
+            .keyframes
+            .binary_search_by(|keyframe| keyframe.0.cmp(&time))
+        {
+            Ok(idx) => self.keyframes[idx] = (time, frame, easing),
+            Err(idx) => self.keyframes.insert(idx, (time, frame, easing)),
+        }
+    }
+
+    /// Adds a keyframe at time `time` with the specified color for each
+    /// pixel, replacing any existing keyframe at the same time.
+    ///
+    /// `easing` shapes the interpolation from the previous keyframe to this
+    /// one.
+    pub fn add_color_keyframe(&mut self, time: Duration, colors: &[Color], easing: Easing) {
+        let mut frame = Frame::new(colors.len());
+        for (idx, color) in colors.iter().enumerate() {
+            frame.set_pixel_color(idx, *color);
+        }
+
+        self.add_keyframe(time, frame, easing);
+    }
+
+    /// Removes all keyframes.
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+    }
+
+    /// Returns the number of keyframes.
+    pub fn len(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    /// Returns `true` if the timeline doesn't contain any keyframes.
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// Returns the time of the last keyframe.
+    pub fn duration(&self) -> Duration {
+        self.keyframes
+            .last()
+            .map_or(Duration::from_millis(0), |keyframe| keyframe.0)
+    }
+
+    /// Returns `true` if playback restarts after the last keyframe.
+    pub fn looping(&self) -> bool {
+        self.looping
+    }
+
+    /// When enabled, playback restarts from the beginning after the last
+    /// keyframe. Otherwise, the animation finishes after the last keyframe.
+    ///
+    /// By default, this is set to `false`.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Returns the interpolated frame at time `t`, or `None` if the timeline
+    /// doesn't contain any keyframes.
+    pub fn frame_at(&self, t: Duration) -> Option<Frame> {
+        let t = if self.looping {
+            let duration = as_nanos(self.duration());
+            if duration > 0 {
+                from_nanos(as_nanos(t) % duration)
+            } else {
+                t
+            }
+        } else {
+            t
+        };
+
+        let next = match self.keyframes.iter().position(|keyframe| keyframe.0 > t) {
+            Some(0) => return self.keyframes.first().map(|keyframe| keyframe.1.clone()),
+            Some(next) => next,
+            None => return self.keyframes.last().map(|keyframe| keyframe.1.clone()),
+        };
+
+        let (start, from, _) = &self.keyframes[next - 1];
+        let (end, to, easing) = &self.keyframes[next];
+        let progress = easing.apply(as_secs_f32(t - *start) / as_secs_f32(*end - *start));
+
+        let mut frame = from.clone();
+        for (idx, pixel) in frame.iter_mut().enumerate() {
+            if let Some(to) = to.pixel(idx) {
+                *pixel = pixel.mix(to, progress);
+            }
+        }
+
+        Some(frame)
+    }
+}
+
+impl Animation for Timeline {
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        self.last_rendered = Some(t);
+
+        if let Some(keyframe) = self.frame_at(t) {
+            for (idx, pixel) in keyframe.iter().enumerate() {
+                if let Some(target) = frame.pixel_mut(idx) {
+                    *target = *pixel;
+                }
+            }
+        }
+    }
+
+    // Make sure the last keyframe is rendered before finishing.
+    fn is_finished(&self, t: Duration) -> bool {
+        let duration = self.duration();
+        let rendered_last = match self.last_rendered {
+            Some(last_rendered) => last_rendered >= duration,
+            None => false,
+        };
+
+        !self.looping && t >= duration && rendered_last
+    }
+}
+
+/// Render time statistics of a [`Metered`] animation.
+///
+/// [`Metered`]: struct.Metered.html
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    frames: u64,
+    skipped: u64,
+    over_budget: u64,
+    total: Duration,
+    last: Duration,
+    max: Duration,
+}
+
+impl RenderStats {
+    /// Returns the number of frames that were rendered.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Returns the number of frames that were skipped because the animation
+    /// was demoted.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Returns the number of frames that took longer to render than the
+    /// budget.
+    pub fn over_budget(&self) -> u64 {
+        self.over_budget
+    }
+
+    /// Returns the time it took to render the most recent frame.
+    pub fn last(&self) -> Duration {
+        self.last
+    }
+
+    /// Returns the average time it took to render a frame.
+    pub fn mean(&self) -> Duration {
+        if self.frames == 0 {
+            return Duration::from_millis(0);
+        }
+
+        from_nanos(as_nanos(self.total) / self.frames)
+    }
+
+    /// Returns the longest time it took to render a frame.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+}
+
+/// What a [`Metered`] animation does when rendering a frame takes longer
+/// than its budget.
+///
+/// [`Metered`]: struct.Metered.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BudgetPolicy {
+    /// Counts the frame in [`RenderStats::over_budget`], and calls the
+    /// callbacks registered through [`Metered::on_over_budget`].
+    ///
+    /// [`RenderStats::over_budget`]: struct.RenderStats.html#method.over_budget
+    /// [`Metered::on_over_budget`]: struct.Metered.html#method.on_over_budget
+    Report,
+    /// Reports the frame, and halves the rate at which the animation is
+    /// rendered, down to every 8th frame. Skipped frames keep the previously
+    /// rendered contents. The full rate is restored gradually once frames
+    /// fit in the budget again.
+    Demote,
+}
+
+// Demoted animations render at least every MAX_DEMOTION'th frame.
+const MAX_DEMOTION: u32 = 8;
+// Number of consecutive frames within budget before a demoted animation
+// renders twice as often again.
+const RECOVERY_FRAMES: u32 = 30;
+
+type BudgetCallback = Box<dyn FnMut(&RenderStats) + Send>;
+
+/// An animation that measures how long another animation takes to render
+/// each frame, and optionally holds it to a budget.
+///
+/// Wrapping each layer in a `Metered` animation shows which effects use up
+/// the frame time when many layers are composed on a slow board, such as a
+/// Raspberry Pi Zero. With a budget and [`BudgetPolicy::Demote`], expensive
+/// effects are rendered less often instead of slowing down the whole frame.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// # use std::time::Duration;
+/// #
+/// # use blinkt::animation::{Animator, BudgetPolicy, Metered};
+/// # use blinkt::effects::Rainbow;
+/// # use blinkt::Blinkt;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mut blinkt = Blinkt::new()?;
+/// let mut rainbow = Metered::new(Rainbow::new())
+///     .with_budget(Duration::from_millis(2), BudgetPolicy::Demote);
+/// rainbow.on_over_budget(|stats| eprintln!("rainbow took {:?}", stats.last()));
+///
+/// Animator::new(60.0)
+///     .with_duration(Duration::from_secs(10))
+///     .run(&mut blinkt, &mut rainbow)?;
+///
+/// println!("average render time: {:?}", rainbow.stats().mean());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`BudgetPolicy::Demote`]: enum.BudgetPolicy.html#variant.Demote
+pub struct Metered<A> {
+    animation: A,
+    budget: Option<(Duration, BudgetPolicy)>,
+    stats: Arc<Mutex<RenderStats>>,
+    on_over_budget: Vec<BudgetCallback>,
+    demotion: u32,
+    frame: u32,
+    within_budget: u32,
+}
+
+impl<A: Animation> Metered<A> {
+    /// Constructs a new `Metered` animation that measures `animation`,
+    /// without a budget.
+    pub fn new(animation: A) -> Metered<A> {
+        Metered {
+            animation,
+            budget: None,
+            stats: Arc::new(Mutex::new(RenderStats::default())),
+            on_over_budget: Vec::new(),
+            demotion: 1,
+            frame: 0,
+            within_budget: 0,
+        }
+    }
+
+    /// Sets the maximum time rendering a single frame should take, and what
+    /// happens when a frame exceeds it.
+    pub fn with_budget(mut self, budget: Duration, policy: BudgetPolicy) -> Metered<A> {
+        self.budget = Some((budget, policy));
+        self
+    }
+
+    /// Registers a callback that's called after rendering a frame that took
+    /// longer than the budget, with the updated statistics.
+    pub fn on_over_budget<F>(&mut self, callback: F)
+    where
+        F: FnMut(&RenderStats) + Send + 'static,
+    {
+        self.on_over_budget.push(Box::new(callback));
+    }
+
+    /// Returns the render time statistics so far.
+    pub fn stats(&self) -> RenderStats {
+        *lock(&self.stats)
+    }
+
+    /// Returns a [`Meter`] that can be used to read the statistics after
+    /// the animation was moved to another thread, for instance through
+    /// [`Animator::spawn`].
+    ///
+    /// [`Meter`]: struct.Meter.html
+    /// [`Animator::spawn`]: struct.Animator.html#method.spawn
+    pub fn meter(&self) -> Meter {
+        Meter {
+            stats: self.stats.clone(),
+        }
+    }
+
+    /// Returns the number of frames the animation is rendered for, where
+    /// `1` means every frame. This is only higher than `1` while the
+    /// animation is demoted.
+    pub fn demotion(&self) -> u32 {
+        self.demotion
+    }
+
+    /// Returns the wrapped animation.
+    pub fn into_inner(self) -> A {
+        self.animation
+    }
+}
+
+impl<A: Animation> Animation for Metered<A> {
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        self.frame = self.frame.wrapping_add(1);
+        // The demotion is always a power of two.
+        if self.frame & (self.demotion - 1) != 0 {
+            lock(&self.stats).skipped += 1;
+            return;
+        }
+
+        let start = Instant::now();
+        self.animation.render(t, frame);
+        let elapsed = start.elapsed();
+
+        let stats = {
+            let mut stats = lock(&self.stats);
+            stats.frames += 1;
+            stats.total += elapsed;
+            stats.last = elapsed;
+            stats.max = stats.max.max(elapsed);
+
+            let (budget, policy) = match self.budget {
+                Some(budget) => budget,
+                None => return,
+            };
+
+            if elapsed <= budget {
+                self.within_budget += 1;
+                if self.within_budget >= RECOVERY_FRAMES && self.demotion > 1 {
+                    self.demotion /= 2;
+                    self.within_budget = 0;
+                }
+
+                return;
+            }
+
+            stats.over_budget += 1;
+            self.within_budget = 0;
+            if policy == BudgetPolicy::Demote {
+                self.demotion = (self.demotion * 2).min(MAX_DEMOTION);
+            }
+
+            *stats
+        };
+
+        for callback in &mut self.on_over_budget {
+            callback(&stats);
+        }
+    }
+
+    fn is_finished(&self, t: Duration) -> bool {
+        self.animation.is_finished(t)
+    }
+}
+
+impl<A: fmt::Debug> fmt::Debug for Metered<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metered")
+            .field("animation", &self.animation)
+            .field("budget", &self.budget)
+            .field("stats", &self.stats)
+            .field("demotion", &self.demotion)
+            .finish()
+    }
+}
+
+/// A handle to the render time statistics of a [`Metered`] animation.
+///
+/// [`Metered`]: struct.Metered.html
+#[derive(Debug, Clone)]
+pub struct Meter {
+    stats: Arc<Mutex<RenderStats>>,
+}
+
+impl Meter {
+    /// Returns the render time statistics so far.
+    pub fn stats(&self) -> RenderStats {
+        *lock(&self.stats)
+    }
+}
+
+/// A handle to an animation started through [`play_once`] or
+/// [`Animator::spawn`].
+///
+/// Dropping the handle doesn't cancel the animation.
+///
+/// [`play_once`]: fn.play_once.html
+/// [`Animator::spawn`]: struct.Animator.html#method.spawn
+#[derive(Debug)]
+pub struct AnimationHandle {
+    thread: Option<JoinHandle<Result<()>>>,
+    done: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl AnimationHandle {
+    /// Returns `true` if the animation has finished, was cancelled, or
+    /// stopped because of an error.
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::SeqCst)
+    }
+
+    /// Cancels the animation. The animation stops before rendering the next
+    /// frame, and leaves the pixels as they are.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Blocks until the animation has finished or was cancelled.
+    ///
+    /// Returns an error if sending a frame to the pixels failed.
+    pub fn await_done(mut self) -> Result<()> {
+        match self.thread.take() {
+            Some(thread) => thread.join().unwrap_or(Ok(())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Plays `effect` on `blinkt` for the specified duration, on a separate
+/// thread.
+///
+/// `effect` is called at roughly 60 frames per second with exclusive access
+/// to `blinkt`, and the animation progress as a floating point value between
+/// `0.0` and `1.0`. The last frame is always rendered with a progress of
+/// `1.0`. After each call, the local buffer is sent to the pixels.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// # use std::sync::{Arc, Mutex};
+/// # use std::time::Duration;
+/// #
+/// # use blinkt::animation;
+/// # use blinkt::Blinkt;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let blinkt = Arc::new(Mutex::new(Blinkt::new()?));
+///
+/// // Fade in green over 500 ms, and wait for the animation to finish.
+/// let handle = animation::play_once(
+///     blinkt.clone(),
+///     |blinkt, progress| blinkt.set_all_pixels(0, (255.0 * progress) as u8, 0),
+///     Duration::from_millis(500),
+/// );
+/// handle.await_done()?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn play_once<F>(
+    blinkt: Arc<Mutex<Blinkt>>,
+    mut effect: F,
+    duration: Duration,
+) -> AnimationHandle
+where
+    F: FnMut(&mut Blinkt, f32) + Send + 'static,
+{
+    let done = Arc::new(AtomicBool::new(false));
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let thread = {
+        let done = done.clone();
+        let cancelled = cancelled.clone();
+
+        thread::spawn(move || {
+            let result = (|| {
+                let start = Instant::now();
+
+                while !cancelled.load(Ordering::SeqCst) {
+                    let elapsed = start.elapsed();
+                    let progress = if duration > Duration::from_millis(0) {
+                        (as_secs_f32(elapsed) / as_secs_f32(duration)).min(1.0)
+                    } else {
+                        1.0
+                    };
+
+                    {
+                        let mut blinkt = lock(&blinkt);
+                        effect(&mut blinkt, progress);
+                        blinkt.swap();
+                        blinkt.show()?;
+                    }
+
+                    if progress >= 1.0 {
+                        break;
+                    }
+
+                    thread::sleep(Duration::from_millis(FRAME_INTERVAL_MS));
+                }
+
+                Ok(())
+            })();
+
+            done.store(true, Ordering::SeqCst);
+
+            result
+        })
+    };
+
+    AnimationHandle {
+        thread: Some(thread),
+        done,
+        cancelled,
+    }
+}
+
+// Locks the mutex, ignoring any poisoning caused by panics on other threads.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn as_nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + u64::from(duration.subsec_nanos())
+}
+
+fn from_nanos(nanos: u64) -> Duration {
+    Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+}
+
+#[inline]
+pub(crate) fn as_secs_f32(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + duration.subsec_nanos() as f32 / 1_000_000_000.0
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! APA102 and SK9822 frame encoding.
+//!
+//! This module contains the low-level protocol used by `Blinkt` and
+//! `FixedBlinkt`, without any I/O. Together with [`Pixel`], [`Color`] and
+//! [`ColorOrder`], it's available without the standard library when the
+//! default `std` feature is disabled, so the same code can drive a strip
+//! from a microcontroller, and be tested without access to the hardware.
+//!
+//! A frame consists of a start frame, one LED frame per pixel, and an end
+//! frame.
+//!
+//! ```rust
+//! use blinkt::apa102;
+//!
+//! let leds = [apa102::led_frame(255, 0, 0, 31), apa102::led_frame(0, 0, 255, 7)];
+//!
+//! let mut buffer = [0u8; 32];
+//! let len = apa102::encode_into(&leds, &mut buffer).unwrap();
+//! assert_eq!(len, apa102::encoded_len(leds.len()));
+//! assert_eq!(&buffer[4..8], &[0b1111_1111, 0, 0, 255]);
+//! ```
+//!
+//! [`Pixel`]: ../struct.Pixel.html
+//! [`Color`]: ../struct.Color.html
+//! [`ColorOrder`]: ../enum.ColorOrder.html
+
+use crate::color_order::ColorOrder;
+use crate::pixel::Pixel;
+
+/// The start frame (32*0), sent before the first LED frame.
+pub const START_FRAME: [u8; 4] = [0u8; 4];
+
+/// An LED frame that turns a pixel off (3*1, 5*0, 24*0). Used for
+/// sacrificial pixels.
+pub const OFF_FRAME: [u8; 4] = [0b1110_0000, 0, 0, 0];
+
+/// Encodes an LED frame (3*1, 5*brightness, 8*blue, 8*green, 8*red).
+///
+/// `brightness` is specified as a 5-bit value between `0` and `31`. Higher
+/// bits are ignored.
+#[inline]
+pub fn led_frame(red: u8, green: u8, blue: u8, brightness: u8) -> [u8; 4] {
+    [0b1110_0000 | (0b0001_1111 & brightness), blue, green, red]
+}
+
+/// Returns the length in bytes of the end frame for `num_pixels` pixels.
+///
+/// The end frame needs at least 1 clock pulse for every 2 pixels to make
+/// sure all data has propagated through the strip, plus 32 pulses for the
+/// SK9822. The SK9822 won't update any pixels until it receives the next
+/// start frame (32*0). The APA102 doesn't care if zeroes are sent instead of
+/// ones as the end frame, so the end frame consists of zeroes, which is
+/// compatible with both the APA102 and SK9822.
+#[inline]
+pub fn end_frame_len(num_pixels: usize) -> usize {
+    4 + (((num_pixels as f32 / 16.0f32) + 0.94f32) as usize)
+}
+
+/// Returns the length in bytes of an entire frame for `num_pixels` pixels,
+/// including the start and end frames.
+#[inline]
+pub fn encoded_len(num_pixels: usize) -> usize {
+    START_FRAME.len() + num_pixels * 4 + end_frame_len(num_pixels)
+}
+
+/// Encodes an entire frame for the specified LED frames into `buffer`.
+///
+/// Returns the number of bytes written, or `None` if `buffer` is shorter
+/// than [`encoded_len`].
+///
+/// [`encoded_len`]: fn.encoded_len.html
+pub fn encode_into(leds: &[[u8; 4]], buffer: &mut [u8]) -> Option<usize> {
+    encode(leds.len(), buffer, |idx, chunk| {
+        chunk.copy_from_slice(&leds[idx])
+    })
+}
+
+/// Encodes an entire frame for `pixels` into `buffer`, with the red, green
+/// and blue values of each pixel reordered for a strip that expects
+/// `color_order`.
+///
+/// Returns the number of bytes written, or `None` if `buffer` is shorter
+/// than [`encoded_len`].
+///
+/// [`encoded_len`]: fn.encoded_len.html
+pub fn encode_pixels_into(
+    pixels: &[Pixel],
+    color_order: ColorOrder,
+    buffer: &mut [u8],
+) -> Option<usize> {
+    encode(pixels.len(), buffer, |idx, chunk| {
+        let mut pixel = pixels[idx];
+        color_order.apply(&mut pixel);
+        chunk.copy_from_slice(pixel.bytes());
+    })
+}
+
+fn encode<F>(num_pixels: usize, buffer: &mut [u8], mut led: F) -> Option<usize>
+where
+    F: FnMut(usize, &mut [u8]),
+{
+    let len = encoded_len(num_pixels);
+    if buffer.len() < len {
+        return None;
+    }
+
+    buffer[..4].copy_from_slice(&START_FRAME);
+    for (idx, chunk) in buffer[4..4 + num_pixels * 4].chunks_mut(4).enumerate() {
+        led(idx, chunk);
+    }
+
+    for byte in &mut buffer[4 + num_pixels * 4..len] {
+        *byte = 0;
+    }
+
+    Some(len)
+}
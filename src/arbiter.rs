@@ -0,0 +1,118 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Priority-based arbitration between multiple frame sources.
+//!
+//! When several sources can drive the same LED strip, for instance an idle
+//! animation, a live stream and a doorbell notification, an `Arbiter` decides
+//! which source is in control. Higher priority sources preempt lower priority
+//! ones. When a source stops submitting frames for longer than the configured
+//! timeout, or explicitly releases control, the next highest priority source
+//! takes over again.
+
+use std::time::{Duration, Instant};
+
+use crate::{Blinkt, Pixel};
+
+#[derive(Debug, Clone)]
+struct Source {
+    priority: u8,
+    pixels: Vec<Pixel>,
+    updated: Instant,
+}
+
+/// Selects which of several frame sources controls the pixels.
+///
+/// Sources are identified by their priority, where a higher value takes
+/// precedence.
+#[derive(Debug, Clone)]
+pub struct Arbiter {
+    sources: Vec<Source>,
+    timeout: Duration,
+}
+
+impl Arbiter {
+    /// Constructs a new `Arbiter`.
+    ///
+    /// A source that hasn't submitted a frame for the duration of `timeout`
+    /// is considered quiet, and loses control to the next highest priority
+    /// source.
+    pub fn new(timeout: Duration) -> Arbiter {
+        Arbiter {
+            sources: Vec::new(),
+            timeout,
+        }
+    }
+
+    /// Submits a new frame for the source with the specified `priority`.
+    pub fn submit(&mut self, priority: u8, pixels: &[Pixel]) {
+        let now = Instant::now();
+
+        if let Some(source) = self.sources.iter_mut().find(|s| s.priority == priority) {
+            source.pixels.clear();
+            source.pixels.extend_from_slice(pixels);
+            source.updated = now;
+        } else {
+            self.sources.push(Source {
+                priority,
+                pixels: pixels.to_vec(),
+                updated: now,
+            });
+        }
+    }
+
+    /// Releases control for the source with the specified `priority`.
+    pub fn release(&mut self, priority: u8) {
+        self.sources.retain(|s| s.priority != priority);
+    }
+
+    /// Returns the priority of the source that's currently in control, or
+    /// `None` if all sources are quiet.
+    pub fn active(&self) -> Option<u8> {
+        self.active_source().map(|s| s.priority)
+    }
+
+    /// Copies the most recent frame of the source that's currently in control
+    /// to the local buffer of `blinkt`.
+    ///
+    /// Returns `false` if all sources are quiet, in which case the local buffer
+    /// is left unchanged. Call `show()` on `blinkt` to update the pixels.
+    pub fn apply(&mut self, blinkt: &mut Blinkt) -> bool {
+        let timeout = self.timeout;
+        self.sources.retain(|s| s.updated.elapsed() < timeout);
+
+        if let Some(source) = self.active_source() {
+            for (pixel, source_pixel) in blinkt.pixels.iter_mut().zip(&source.pixels) {
+                *pixel = *source_pixel;
+            }
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn active_source(&self) -> Option<&Source> {
+        self.sources
+            .iter()
+            .filter(|s| s.updated.elapsed() < self.timeout)
+            .max_by_key(|s| s.priority)
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Receiving DMX data from lighting consoles through Art-Net.
+//!
+//! An [`ArtNetNode`] listens for Art-Net packets, and copies the channel
+//! values of the ArtDmx packets for each mapped universe to a range of
+//! pixels. Each universe holds the red, green and blue values for up to 170
+//! consecutive pixels. The [`FlushPolicy`] determines when the received
+//! values are sent to the pixels. The node answers ArtPoll packets, so it
+//! can be discovered by lighting consoles.
+//!
+//! This module requires the `artnet` feature.
+//!
+//! ```rust,no_run
+//! use blinkt::artnet::{ArtNetNode, FlushPolicy};
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 300)?;
+//!
+//! // 300 pixels span two universes.
+//! let mut node = ArtNetNode::new()?;
+//! node.map_universe(0, 0);
+//! node.map_universe(1, 170);
+//! node.set_flush_policy(FlushPolicy::AllUniverses);
+//!
+//! node.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ArtNetNode`]: struct.ArtNetNode.html
+//! [`FlushPolicy`]: enum.FlushPolicy.html
+
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::dmx::{self, UniverseMap};
+use crate::{Blinkt, Result};
+
+pub use crate::dmx::FlushPolicy;
+
+/// The UDP port used by Art-Net.
+pub const PORT: u16 = 6454;
+
+const ID: &[u8; 8] = b"Art-Net\0";
+const OP_POLL: u16 = 0x2000;
+const OP_POLL_REPLY: u16 = 0x2100;
+const OP_DMX: u16 = 0x5000;
+const OP_SYNC: u16 = 0x5200;
+const POLL_REPLY_LEN: usize = 239;
+// Maximum ArtDmx packet size: an 18-byte header and 512 channels.
+const MAX_PACKET_LEN: usize = 530;
+
+/// An Art-Net node that drives the pixels of a `Blinkt`.
+#[derive(Debug)]
+pub struct ArtNetNode {
+    socket: UdpSocket,
+    universes: UniverseMap,
+    flush_policy: FlushPolicy,
+    name: String,
+    buffer: Vec<u8>,
+}
+
+impl ArtNetNode {
+    /// Constructs a new `ArtNetNode` listening on the standard Art-Net port
+    /// on all network interfaces.
+    pub fn new() -> Result<ArtNetNode> {
+        ArtNetNode::bind(("0.0.0.0", PORT))
+    }
+
+    /// Constructs a new `ArtNetNode` listening on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<ArtNetNode> {
+        Ok(ArtNetNode {
+            socket: UdpSocket::bind(addr)?,
+            universes: UniverseMap::default(),
+            flush_policy: FlushPolicy::AllUniverses,
+            name: "Blinkt".to_owned(),
+            buffer: vec![0u8; MAX_PACKET_LEN],
+        })
+    }
+
+    /// Returns the address the node is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Maps the 15-bit Art-Net port address `universe` onto the pixels
+    /// starting at `start_pixel`.
+    ///
+    /// The universe covers up to 170 pixels, depending on the number of
+    /// channels sent by the console. Values for pixels beyond the end of the
+    /// strip are ignored. Mapping a universe again replaces its start pixel.
+    pub fn map_universe(&mut self, universe: u16, start_pixel: usize) {
+        self.universes.map(universe, start_pixel);
+    }
+
+    /// Removes the mapping for `universe`.
+    pub fn unmap_universe(&mut self, universe: u16) {
+        self.universes.unmap(universe);
+    }
+
+    /// Returns the flush policy.
+    pub fn flush_policy(&self) -> FlushPolicy {
+        self.flush_policy
+    }
+
+    /// Sets the flush policy, which determines when the received values
+    /// are sent to the pixels.
+    ///
+    /// By default, this is set to `FlushPolicy::AllUniverses`.
+    pub fn set_flush_policy(&mut self, flush_policy: FlushPolicy) {
+        self.flush_policy = flush_policy;
+    }
+
+    /// Returns the name reported to lighting consoles.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sets the name reported to lighting consoles. Names are truncated to
+    /// 17 bytes.
+    ///
+    /// By default, this is set to `Blinkt`.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_owned();
+    }
+
+    /// Receives and processes packets until an error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Waits for a single packet, and processes it.
+    ///
+    /// Returns `Ok(true)` if a frame was sent to the pixels. Packets that
+    /// aren't valid Art-Net packets are ignored.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        let (len, source) = self.socket.recv_from(&mut self.buffer)?;
+        if len < 12 || &self.buffer[..8] != ID {
+            return Ok(false);
+        }
+
+        let opcode = u16::from(self.buffer[8]) | (u16::from(self.buffer[9]) << 8);
+        match opcode {
+            OP_POLL => {
+                self.reply_to_poll(source)?;
+                Ok(false)
+            }
+            OP_DMX if len >= 18 => {
+                let universe =
+                    u16::from(self.buffer[14]) | (u16::from(self.buffer[15] & 0x7f) << 8);
+                let channels = (usize::from(self.buffer[16]) << 8) | usize::from(self.buffer[17]);
+                let end = (18 + channels).min(len);
+
+                if !self
+                    .universes
+                    .apply(blinkt, universe, &self.buffer[18..end])
+                {
+                    return Ok(false);
+                }
+
+                let flush = match self.flush_policy {
+                    FlushPolicy::Immediate => true,
+                    FlushPolicy::AllUniverses => self.universes.all_received(),
+                    FlushPolicy::Sync => false,
+                };
+
+                if flush {
+                    dmx::flush(blinkt, &mut self.universes)?;
+                }
+
+                Ok(flush)
+            }
+            OP_SYNC if self.flush_policy == FlushPolicy::Sync => {
+                dmx::flush(blinkt, &mut self.universes)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // Sends an ArtPollReply describing the node and its first four mapped
+    // universes back to the controller.
+    fn reply_to_poll(&self, controller: SocketAddr) -> Result<()> {
+        let universes: Vec<u16> = self.universes.universes().take(4).collect();
+        let mut reply = vec![0u8; POLL_REPLY_LEN];
+
+        reply[..8].copy_from_slice(ID);
+        reply[8] = OP_POLL_REPLY as u8;
+        reply[9] = (OP_POLL_REPLY >> 8) as u8;
+        if let Ok(SocketAddr::V4(addr)) = self.socket.local_addr() {
+            reply[10..14].copy_from_slice(&addr.ip().octets());
+        }
+        reply[14] = PORT as u8;
+        reply[15] = (PORT >> 8) as u8;
+        // Firmware revision.
+        reply[17] = 1;
+
+        if let Some(&first) = universes.first() {
+            // Net and sub-net switches.
+            reply[18] = (first >> 8) as u8 & 0x7f;
+            reply[19] = (first >> 4) as u8 & 0x0f;
+        }
+
+        let name = self.name.as_bytes();
+        let short_len = name.len().min(17);
+        reply[26..26 + short_len].copy_from_slice(&name[..short_len]);
+        let long_len = name.len().min(63);
+        reply[44..44 + long_len].copy_from_slice(&name[..long_len]);
+
+        reply[173] = universes.len() as u8;
+        for (port, &universe) in universes.iter().enumerate() {
+            // DMX512 output port, with data being transmitted.
+            reply[174 + port] = 0x80;
+            reply[182 + port] = 0x80;
+            reply[190 + port] = (universe & 0x0f) as u8;
+        }
+
+        let reply_to = match controller.ip() {
+            IpAddr::V4(_) => controller,
+            IpAddr::V6(_) => return Ok(()),
+        };
+
+        self.socket.send_to(&reply, reply_to)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,845 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Audio level meters and spectrum analyzers.
+//!
+//! An [`Input`] captures audio from a sound card, such as a USB microphone or
+//! the line input of an audio HAT, through ALSA. A [`VuMeter`] shows its
+//! volume on the pixels as a bar that runs from green through yellow to red,
+//! with a peak indicator that briefly holds the highest recent level. A
+//! [`Spectrum`] splits it up into frequency bands, and shows those on the
+//! pixels of a strip or the columns of a [`Matrix`].
+//!
+//! This module requires the `audio` feature.
+//!
+//! ```rust,no_run
+//! use blinkt::audio::{Input, VuMeter};
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::new()?;
+//! let mut input = Input::new()?;
+//!
+//! VuMeter::new()
+//!     .with_range(-50.0, -6.0)
+//!     .with_thresholds(0.5, 0.8)
+//!     .run(&mut blinkt, &mut input)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ```rust,no_run
+//! use blinkt::audio::{Input, Spectrum};
+//! use blinkt::{Blinkt, Layout, Matrix, Palette};
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let blinkt = Blinkt::with_spi(16_000_000, 16 * 16)?;
+//! let mut matrix = Matrix::new(blinkt, 16, 16, Layout::Serpentine);
+//! let input = Input::new()?;
+//!
+//! Spectrum::new()
+//!     .with_palette(Palette::heat())
+//!     .with_smoothing(0.8)
+//!     .run_matrix(&mut matrix, &input)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Input`]: struct.Input.html
+//! [`VuMeter`]: struct.VuMeter.html
+//! [`Spectrum`]: struct.Spectrum.html
+//! [`Matrix`]: ../struct.Matrix.html
+
+use std::collections::VecDeque;
+use std::error;
+use std::f32::consts::PI;
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{SampleFormat, SizedSample, Stream, StreamConfig};
+
+use crate::animation::as_secs_f32;
+use crate::{Blinkt, Color, Error, Frame, Matrix, Palette, Result};
+
+const DEFAULT_FPS: f32 = 30.0;
+// Number of samples kept for, and analyzed by, the spectrum analyzer. At
+// 48 kHz, this results in frequency bins that are about 23 Hz wide.
+const WINDOW_SIZE: usize = 2048;
+const DEFAULT_MIN_DB: f32 = -60.0;
+const DEFAULT_MAX_DB: f32 = 0.0;
+const DEFAULT_YELLOW_THRESHOLD: f32 = 0.6;
+const DEFAULT_RED_THRESHOLD: f32 = 0.85;
+const DEFAULT_PEAK_HOLD_MS: u64 = 1000;
+// How fast the peak indicator falls once the hold time has passed, as a
+// fraction of the meter per second.
+const PEAK_FALL_RATE: f32 = 1.0;
+const DEFAULT_SPECTRUM_MIN_FREQ: f32 = 50.0;
+const DEFAULT_SPECTRUM_MAX_FREQ: f32 = 12_000.0;
+const DEFAULT_SPECTRUM_MIN_DB: f32 = -60.0;
+const DEFAULT_SPECTRUM_MAX_DB: f32 = -10.0;
+const DEFAULT_SPECTRUM_SMOOTHING: f32 = 0.7;
+
+// Audio received by the capture callback. The sum of squares covers the
+// samples that weren't picked up by a level meter yet, while the window keeps
+// the most recent samples for spectrum analysis.
+#[derive(Debug, Default)]
+struct Shared {
+    sum_squares: f64,
+    count: usize,
+    window: VecDeque<f32>,
+    error: Option<String>,
+}
+
+impl Shared {
+    fn check_error(&mut self) -> Result<()> {
+        match self.error.take() {
+            Some(err) => Err(Error::Io(io::Error::other(err))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Captures audio from an input device.
+///
+/// Capturing starts when the `Input` is constructed, and stops when it's
+/// dropped. Multichannel audio is mixed down to mono.
+pub struct Input {
+    // Capturing stops when the stream is dropped.
+    _stream: Stream,
+    shared: Arc<Mutex<Shared>>,
+    sample_rate: u32,
+    level: f32,
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Input")
+            .field("sample_rate", &self.sample_rate)
+            .field("level", &self.level)
+            .finish()
+    }
+}
+
+impl Input {
+    /// Constructs a new `Input` that captures audio from the default input
+    /// device.
+    pub fn new() -> Result<Input> {
+        let device = cpal::default_host().default_input_device().ok_or_else(|| {
+            Error::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no audio input device available",
+            ))
+        })?;
+
+        Input::with_device(&device)
+    }
+
+    /// Constructs a new `Input` that captures audio from the input device
+    /// called `name`.
+    ///
+    /// The names of the available devices are returned by
+    /// [`device_names`].
+    ///
+    /// [`device_names`]: #method.device_names
+    pub fn open(name: &str) -> Result<Input> {
+        for device in cpal::default_host().input_devices().map_err(audio_error)? {
+            if device.name().map(|n| n == name).unwrap_or(false) {
+                return Input::with_device(&device);
+            }
+        }
+
+        Err(Error::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("audio input device {} not found", name),
+        )))
+    }
+
+    /// Returns the names of the available input devices.
+    pub fn device_names() -> Result<Vec<String>> {
+        Ok(cpal::default_host()
+            .input_devices()
+            .map_err(audio_error)?
+            .filter_map(|device| device.name().ok())
+            .collect())
+    }
+
+    fn with_device<D: DeviceTrait>(device: &D) -> Result<Input> {
+        let supported = device.default_input_config().map_err(audio_error)?;
+        let config = supported.config();
+        let shared = Arc::new(Mutex::new(Shared::default()));
+
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream(device, &config, &shared, |s: f32| s),
+            SampleFormat::I16 => {
+                build_stream(device, &config, &shared, |s: i16| f32::from(s) / 32768.0)
+            }
+            SampleFormat::U16 => build_stream(device, &config, &shared, |s: u16| {
+                (f32::from(s) - 32768.0) / 32768.0
+            }),
+            SampleFormat::I32 => build_stream(device, &config, &shared, |s: i32| {
+                s as f32 / 2_147_483_648.0
+            }),
+            SampleFormat::U8 => build_stream(device, &config, &shared, |s: u8| {
+                (f32::from(s) - 128.0) / 128.0
+            }),
+            _ => Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unsupported audio sample format",
+            ))),
+        }?;
+
+        stream.play().map_err(audio_error)?;
+
+        Ok(Input {
+            _stream: stream,
+            shared,
+            sample_rate: config.sample_rate.0,
+            level: 0.0,
+        })
+    }
+
+    /// Returns the sample rate in Hz.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns the RMS level of the audio captured since the previous call,
+    /// between `0.0` (silence) and `1.0` (full scale).
+    ///
+    /// If no audio was captured in the meantime, the previous level is
+    /// returned again.
+    ///
+    /// Returns an error if capturing failed, for instance because the device
+    /// was unplugged.
+    pub fn level(&mut self) -> Result<f32> {
+        let mut shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+        shared.check_error()?;
+
+        if shared.count > 0 {
+            self.level = (shared.sum_squares / shared.count as f64).sqrt() as f32;
+            shared.sum_squares = 0.0;
+            shared.count = 0;
+        }
+
+        Ok(self.level)
+    }
+
+    /// Replaces the contents of `samples` with the most recently captured
+    /// audio, oldest sample first.
+    ///
+    /// Up to 2048 samples are kept, which covers about 43 ms at 48 kHz.
+    /// Fewer samples are returned shortly after capturing started.
+    ///
+    /// Returns an error if capturing failed, for instance because the device
+    /// was unplugged.
+    pub fn samples(&self, samples: &mut Vec<f32>) -> Result<()> {
+        let mut shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+        shared.check_error()?;
+
+        samples.clear();
+        samples.extend(shared.window.iter());
+
+        Ok(())
+    }
+}
+
+fn build_stream<D, T, F>(
+    device: &D,
+    config: &StreamConfig,
+    shared: &Arc<Mutex<Shared>>,
+    convert: F,
+) -> Result<Stream>
+where
+    D: DeviceTrait,
+    T: SizedSample,
+    F: Fn(T) -> f32 + Send + 'static,
+{
+    let channels = usize::from(config.channels.max(1));
+    let data_shared = shared.clone();
+    let error_shared = shared.clone();
+
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &_| {
+                let mut shared = data_shared.lock().unwrap_or_else(PoisonError::into_inner);
+                for frame in data.chunks(channels) {
+                    let sample =
+                        frame.iter().map(|&s| convert(s)).sum::<f32>() / frame.len() as f32;
+                    shared.sum_squares += f64::from(sample * sample);
+                    shared.count += 1;
+
+                    if shared.window.len() == WINDOW_SIZE {
+                        shared.window.pop_front();
+                    }
+                    shared.window.push_back(sample);
+                }
+            },
+            move |err| {
+                error_shared
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .error = Some(err.to_string());
+            },
+            None,
+        )
+        .map_err(audio_error)
+}
+
+fn audio_error<E>(err: E) -> Error
+where
+    E: Into<Box<dyn error::Error + Send + Sync>>,
+{
+    Error::Io(io::Error::other(err))
+}
+
+/// Shows an audio level as a bar, starting at the first pixel.
+///
+/// The bar is green at low levels, and turns yellow and then red as it
+/// crosses the thresholds set with [`with_thresholds`]. A single pixel marks
+/// the highest recent level, and falls back once the peak hold time has
+/// passed.
+///
+/// [`with_thresholds`]: #method.with_thresholds
+#[derive(Debug, Clone)]
+pub struct VuMeter {
+    min_db: f32,
+    max_db: f32,
+    yellow_threshold: f32,
+    red_threshold: f32,
+    colors: [Color; 3],
+    peak_hold: Duration,
+    smoothing: f32,
+    frame_interval: Duration,
+    level: f32,
+    peak: f32,
+    peak_time: Option<Instant>,
+    last_render: Option<Instant>,
+}
+
+impl VuMeter {
+    /// Constructs a new `VuMeter` that covers levels from -60 to 0 dBFS,
+    /// turns yellow at 60% and red at 85%, and holds peaks for one second.
+    pub fn new() -> VuMeter {
+        VuMeter {
+            min_db: DEFAULT_MIN_DB,
+            max_db: DEFAULT_MAX_DB,
+            yellow_threshold: DEFAULT_YELLOW_THRESHOLD,
+            red_threshold: DEFAULT_RED_THRESHOLD,
+            colors: [
+                Color::new(0, 255, 0),
+                Color::new(255, 192, 0),
+                Color::new(255, 0, 0),
+            ],
+            peak_hold: Duration::from_millis(DEFAULT_PEAK_HOLD_MS),
+            smoothing: 0.0,
+            frame_interval: Duration::from_micros((1_000_000.0 / DEFAULT_FPS) as u64),
+            level: 0.0,
+            peak: 0.0,
+            peak_time: None,
+            last_render: None,
+        }
+    }
+
+    /// Sets the levels in dBFS at which the meter is empty and full.
+    ///
+    /// Quiet sources, such as a microphone some distance away from the
+    /// speakers, fill more of the meter with a lower `max_db`.
+    pub fn with_range(mut self, min_db: f32, max_db: f32) -> VuMeter {
+        self.min_db = min_db;
+        self.max_db = max_db.max(min_db + 1.0);
+        self
+    }
+
+    /// Sets the fractions of the meter where the bar turns yellow and red.
+    pub fn with_thresholds(mut self, yellow: f32, red: f32) -> VuMeter {
+        self.yellow_threshold = yellow.max(0.0).min(1.0);
+        self.red_threshold = red.max(self.yellow_threshold).min(1.0);
+        self
+    }
+
+    /// Replaces the default green, yellow and red colors.
+    pub fn with_colors<C: Into<Color>>(mut self, green: C, yellow: C, red: C) -> VuMeter {
+        self.colors = [green.into(), yellow.into(), red.into()];
+        self
+    }
+
+    /// Sets how long the peak indicator stays at the highest level before it
+    /// starts to fall.
+    ///
+    /// A `peak_hold` of zero disables the peak indicator.
+    pub fn with_peak_hold(mut self, peak_hold: Duration) -> VuMeter {
+        self.peak_hold = peak_hold;
+        self
+    }
+
+    /// Sets how much of the previous level is kept each frame, between `0.0`
+    /// (no smoothing) and `1.0`. Rising levels are always shown immediately,
+    /// so this only slows down how fast the bar falls.
+    pub fn with_smoothing(mut self, smoothing: f32) -> VuMeter {
+        self.smoothing = smoothing.max(0.0).min(0.99);
+        self
+    }
+
+    /// Sets the number of times per second the meter is updated by
+    /// [`run`].
+    ///
+    /// The frame rate is limited to a range of 0.1 to 100 frames per
+    /// second. Defaults to 30.
+    ///
+    /// [`run`]: #method.run
+    pub fn with_fps(mut self, fps: f32) -> VuMeter {
+        let fps = fps.max(0.1).min(100.0);
+        self.frame_interval = Duration::from_micros((1_000_000.0 / fps) as u64);
+        self
+    }
+
+    /// Keeps showing the level of `input` on the pixels.
+    ///
+    /// Only returns when capturing audio or sending a frame to the pixels
+    /// failed.
+    pub fn run(&mut self, blinkt: &mut Blinkt, input: &mut Input) -> Result<()> {
+        let mut next_frame = Instant::now();
+
+        loop {
+            self.update(blinkt, input)?;
+
+            // Skip any frames we've missed.
+            let now = Instant::now();
+            next_frame += self.frame_interval;
+            if next_frame <= now {
+                next_frame = now + self.frame_interval;
+            }
+
+            thread::sleep(next_frame - now);
+        }
+    }
+
+    /// Shows the current level of `input` on the pixels once.
+    pub fn update(&mut self, blinkt: &mut Blinkt, input: &mut Input) -> Result<()> {
+        let level = input.level()?;
+        let mut frame = Frame::new(blinkt.num_pixels());
+
+        self.render(level, &mut frame);
+
+        blinkt.set_frame(&frame);
+        blinkt.swap();
+        blinkt.show()
+    }
+
+    /// Draws the meter for an RMS `level` between `0.0` and `1.0` onto
+    /// `frame`, which can be used to show levels from another source than an
+    /// [`Input`].
+    ///
+    /// [`Input`]: struct.Input.html
+    pub fn render(&mut self, level: f32, frame: &mut Frame) {
+        let now = Instant::now();
+        let elapsed = self
+            .last_render
+            .map(|last| as_secs_f32(now.duration_since(last)))
+            .unwrap_or(0.0);
+        self.last_render = Some(now);
+
+        let position = position(level, self.min_db, self.max_db);
+        self.level = smooth(self.level, position, self.smoothing);
+
+        let holding = self
+            .peak_time
+            .map(|time| now.duration_since(time) < self.peak_hold)
+            .unwrap_or(false);
+        if self.level >= self.peak {
+            self.peak = self.level;
+            self.peak_time = Some(now);
+        } else if !holding {
+            self.peak = (self.peak - PEAK_FALL_RATE * elapsed).max(self.level);
+        }
+
+        let len = frame.len();
+        let lit = (self.level * len as f32).round() as usize;
+
+        frame.clear();
+        for pixel in 0..lit.min(len) {
+            frame.set_pixel_color(pixel, self.color(pixel, len));
+        }
+
+        if self.peak_hold > Duration::from_secs(0) && self.peak > 0.0 && len > 0 {
+            let pixel = ((self.peak * len as f32).ceil() as usize).max(1).min(len) - 1;
+            frame.set_pixel_color(pixel, self.color(pixel, len));
+        }
+    }
+
+    fn color(&self, pixel: usize, len: usize) -> Color {
+        let position = (pixel as f32 + 0.5) / len as f32;
+
+        if position >= self.red_threshold {
+            self.colors[2]
+        } else if position >= self.yellow_threshold {
+            self.colors[1]
+        } else {
+            self.colors[0]
+        }
+    }
+}
+
+impl Default for VuMeter {
+    fn default() -> VuMeter {
+        VuMeter::new()
+    }
+}
+
+/// Shows the loudness of a range of frequencies, like the spectrum analyzer
+/// on a hi-fi.
+///
+/// The frequency range is divided into bands on a logarithmic scale, so every
+/// octave gets the same number of bands. On a strip, every pixel shows a
+/// band, with its brightness following the loudness. On a [`Matrix`], every
+/// column shows a band as a bar that rises from the bottom.
+///
+/// The colors are looked up in a [`Palette`], from the lowest to the highest
+/// frequency.
+///
+/// [`Matrix`]: ../struct.Matrix.html
+/// [`Palette`]: ../struct.Palette.html
+#[derive(Debug, Clone)]
+pub struct Spectrum {
+    min_freq: f32,
+    max_freq: f32,
+    min_db: f32,
+    max_db: f32,
+    smoothing: f32,
+    palette: Palette,
+    frame_interval: Duration,
+    levels: Vec<f32>,
+    samples: Vec<f32>,
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl Spectrum {
+    /// Constructs a new `Spectrum` that covers 50 Hz to 12 kHz, with levels
+    /// from -60 to -10 dBFS, shown in the colors of the rainbow.
+    pub fn new() -> Spectrum {
+        Spectrum {
+            min_freq: DEFAULT_SPECTRUM_MIN_FREQ,
+            max_freq: DEFAULT_SPECTRUM_MAX_FREQ,
+            min_db: DEFAULT_SPECTRUM_MIN_DB,
+            max_db: DEFAULT_SPECTRUM_MAX_DB,
+            smoothing: DEFAULT_SPECTRUM_SMOOTHING,
+            palette: Palette::rainbow(),
+            frame_interval: Duration::from_micros((1_000_000.0 / DEFAULT_FPS) as u64),
+            levels: Vec::new(),
+            samples: Vec::with_capacity(WINDOW_SIZE),
+            re: vec![0.0; WINDOW_SIZE],
+            im: vec![0.0; WINDOW_SIZE],
+        }
+    }
+
+    /// Sets the lowest and highest frequency in Hz.
+    ///
+    /// Frequencies above half the sample rate can't be captured, and are
+    /// left out.
+    pub fn with_frequency_range(mut self, min_freq: f32, max_freq: f32) -> Spectrum {
+        self.min_freq = min_freq.max(1.0);
+        self.max_freq = max_freq.max(self.min_freq * 2.0);
+        self
+    }
+
+    /// Sets the levels in dBFS at which a band is off and at full
+    /// brightness or height.
+    pub fn with_range(mut self, min_db: f32, max_db: f32) -> Spectrum {
+        self.min_db = min_db;
+        self.max_db = max_db.max(min_db + 1.0);
+        self
+    }
+
+    /// Sets how much of the previous level of a band is kept each frame,
+    /// between `0.0` (no smoothing) and `1.0`. Rising levels are always
+    /// shown immediately, so this only slows down how fast the bands fall.
+    /// Defaults to `0.7`.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Spectrum {
+        self.smoothing = smoothing.max(0.0).min(0.99);
+        self
+    }
+
+    /// Sets the palette the colors of the bands are looked up in.
+    pub fn with_palette(mut self, palette: Palette) -> Spectrum {
+        self.palette = palette;
+        self
+    }
+
+    /// Sets the number of times per second the spectrum is updated by
+    /// [`run`] and [`run_matrix`].
+    ///
+    /// The frame rate is limited to a range of 0.1 to 100 frames per
+    /// second. Defaults to 30.
+    ///
+    /// [`run`]: #method.run
+    /// [`run_matrix`]: #method.run_matrix
+    pub fn with_fps(mut self, fps: f32) -> Spectrum {
+        let fps = fps.max(0.1).min(100.0);
+        self.frame_interval = Duration::from_micros((1_000_000.0 / fps) as u64);
+        self
+    }
+
+    /// Returns the levels of the bands that were analyzed last, between
+    /// `0.0` and `1.0`, from the lowest to the highest frequency.
+    pub fn levels(&self) -> &[f32] {
+        &self.levels
+    }
+
+    /// Keeps showing the spectrum of `input` on a strip.
+    ///
+    /// Only returns when capturing audio or sending a frame to the pixels
+    /// failed.
+    pub fn run(&mut self, blinkt: &mut Blinkt, input: &Input) -> Result<()> {
+        let mut next_frame = Instant::now();
+
+        loop {
+            self.update(blinkt, input)?;
+
+            // Skip any frames we've missed.
+            let now = Instant::now();
+            next_frame += self.frame_interval;
+            if next_frame <= now {
+                next_frame = now + self.frame_interval;
+            }
+
+            thread::sleep(next_frame - now);
+        }
+    }
+
+    /// Keeps showing the spectrum of `input` on a matrix.
+    ///
+    /// Only returns when capturing audio or sending a frame to the pixels
+    /// failed.
+    pub fn run_matrix(&mut self, matrix: &mut Matrix, input: &Input) -> Result<()> {
+        let mut next_frame = Instant::now();
+
+        loop {
+            self.update_matrix(matrix, input)?;
+
+            // Skip any frames we've missed.
+            let now = Instant::now();
+            next_frame += self.frame_interval;
+            if next_frame <= now {
+                next_frame = now + self.frame_interval;
+            }
+
+            thread::sleep(next_frame - now);
+        }
+    }
+
+    /// Shows the current spectrum of `input` on a strip once, with a band
+    /// per pixel.
+    pub fn update(&mut self, blinkt: &mut Blinkt, input: &Input) -> Result<()> {
+        input.samples(&mut self.samples)?;
+
+        let mut frame = Frame::new(blinkt.num_pixels());
+        self.analyze(input.sample_rate(), frame.len());
+        self.draw(&mut frame);
+
+        blinkt.set_frame(&frame);
+        blinkt.swap();
+        blinkt.show()
+    }
+
+    /// Shows the current spectrum of `input` on a matrix once, with a band
+    /// per column.
+    pub fn update_matrix(&mut self, matrix: &mut Matrix, input: &Input) -> Result<()> {
+        input.samples(&mut self.samples)?;
+        self.analyze(input.sample_rate(), matrix.width());
+
+        let (width, height) = (matrix.width(), matrix.height());
+        matrix.clear();
+        for (x, level) in self.levels.iter().enumerate() {
+            let color = self.palette.color_at(band_position(x, width));
+            let lit = (level * height as f32).round() as usize;
+
+            for y in height - lit.min(height)..height {
+                matrix.set_xy(x, y, color);
+            }
+        }
+
+        matrix.blinkt_mut().swap();
+        matrix.show()
+    }
+
+    /// Analyzes `samples` captured at `sample_rate`, and draws the spectrum
+    /// onto `frame`, with a band per pixel.
+    ///
+    /// Only the last 2048 samples are analyzed. Shorter slices are padded
+    /// with silence.
+    pub fn render(&mut self, samples: &[f32], sample_rate: u32, frame: &mut Frame) {
+        let samples = &samples[samples.len().saturating_sub(WINDOW_SIZE)..];
+        self.samples.clear();
+        self.samples.extend_from_slice(samples);
+
+        self.analyze(sample_rate, frame.len());
+        self.draw(frame);
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let len = frame.len();
+
+        for (pixel, level) in self.levels.iter().enumerate() {
+            let color = self.palette.color_at(band_position(pixel, len));
+            frame.set_pixel_color(pixel, scale(color, *level));
+        }
+    }
+
+    // Updates the levels of `num_bands` bands from the samples.
+    fn analyze(&mut self, sample_rate: u32, num_bands: usize) {
+        let samples = &self.samples;
+        let padding = WINDOW_SIZE - samples.len();
+
+        // Apply a Hann window to reduce leakage between frequency bins.
+        for (i, (re, im)) in self.re.iter_mut().zip(self.im.iter_mut()).enumerate() {
+            let sample = if i < padding {
+                0.0
+            } else {
+                samples[i - padding]
+            };
+            let window = 0.5 - 0.5 * (2.0 * PI * i as f32 / WINDOW_SIZE as f32).cos();
+            *re = sample * window;
+            *im = 0.0;
+        }
+
+        fft(&mut self.re, &mut self.im);
+
+        // Scale the magnitudes so a full-scale sine wave results in 1.0,
+        // taking the window's coherent gain of 0.5 into account.
+        let bin_width = sample_rate as f32 / WINDOW_SIZE as f32;
+        let num_bins = WINDOW_SIZE / 2;
+        let (re, im) = (&self.re, &self.im);
+        let magnitude =
+            |bin: usize| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt() * 4.0 / WINDOW_SIZE as f32;
+
+        let min_freq = self.min_freq;
+        let max_freq = self.max_freq.min(sample_rate as f32 / 2.0);
+        let ratio = (max_freq / min_freq).max(1.0);
+        let edge = |band: usize| min_freq * ratio.powf(band as f32 / num_bands as f32);
+
+        self.levels.resize(num_bands, 0.0);
+        for band in 0..num_bands {
+            let (low, high) = (edge(band), edge(band + 1));
+
+            // Bands that are narrower than a bin at the low end of the
+            // spectrum use the bin closest to their center instead.
+            let first = ((low / bin_width).ceil() as usize).max(1);
+            let last = ((high / bin_width).ceil() as usize).min(num_bins);
+            let peak = if first < last {
+                (first..last).map(&magnitude).fold(0.0, f32::max)
+            } else {
+                let center = ((low * high).sqrt() / bin_width).round() as usize;
+                magnitude(center.max(1).min(num_bins - 1))
+            };
+
+            let level = position(peak, self.min_db, self.max_db);
+            self.levels[band] = smooth(self.levels[band], level, self.smoothing);
+        }
+    }
+}
+
+impl Default for Spectrum {
+    fn default() -> Spectrum {
+        Spectrum::new()
+    }
+}
+
+// Converts an amplitude between 0.0 and 1.0 to a position on a scale that
+// runs from min_db to max_db.
+fn position(level: f32, min_db: f32, max_db: f32) -> f32 {
+    if level <= 0.0 {
+        return 0.0;
+    }
+
+    let db = 20.0 * level.log10();
+    ((db - min_db) / (max_db - min_db)).max(0.0).min(1.0)
+}
+
+// Follows rising values immediately, and lets falling values decay.
+fn smooth(previous: f32, value: f32, smoothing: f32) -> f32 {
+    if value >= previous {
+        value
+    } else {
+        value + (previous - value) * smoothing
+    }
+}
+
+fn band_position(band: usize, num_bands: usize) -> f32 {
+    if num_bands > 1 {
+        band as f32 / (num_bands - 1) as f32
+    } else {
+        0.0
+    }
+}
+
+fn scale(color: Color, factor: f32) -> Color {
+    let scale = |value: u8| (f32::from(value) * factor).round() as u8;
+    Color::new(scale(color.red), scale(color.green), scale(color.blue))
+}
+
+// In-place iterative radix-2 FFT. The length of the slices needs to be a
+// power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    // Reorder the input in bit-reversed order.
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let angle = -2.0 * PI / len as f32;
+
+        for start in (0..n).step_by(len) {
+            for k in 0..half {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + half);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+
+        len <<= 1;
+    }
+}
@@ -0,0 +1,328 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Cellular automata effects.
+//!
+//! [`Elementary`] implements one-dimensional elementary cellular automata,
+//! such as rule 30 and rule 110, which are well suited for LED strips. Each
+//! call to `step()` calculates the next generation.
+//!
+//! [`Life`] implements Conway's Game of Life on a two-dimensional grid, for
+//! LED matrices.
+//!
+//! Living cells are colored through a [`Palette`], based on their position
+//! (`Elementary`) or their age (`Life`).
+//!
+//! [`Elementary`]: struct.Elementary.html
+//! [`Life`]: struct.Life.html
+//! [`Palette`]: ../struct.Palette.html
+
+use crate::frame::Frame;
+use crate::palette::Palette;
+use crate::rng::XorShift;
+
+/// A one-dimensional elementary cellular automaton.
+///
+/// The next state of each cell is determined by its current state and the
+/// state of its two neighbors, as described by the Wolfram code in `rule`.
+#[derive(Debug, Clone)]
+pub struct Elementary {
+    rule: u8,
+    cells: Vec<bool>,
+    next: Vec<bool>,
+    wrap: bool,
+}
+
+impl Elementary {
+    /// Constructs a new `Elementary` automaton using the specified rule,
+    /// containing `num_cells` dead cells.
+    ///
+    /// By default, cells beyond the edges are considered dead.
+    pub fn new(rule: u8, num_cells: usize) -> Elementary {
+        Elementary {
+            rule,
+            cells: vec![false; num_cells],
+            next: vec![false; num_cells],
+            wrap: false,
+        }
+    }
+
+    /// Returns the rule.
+    pub fn rule(&self) -> u8 {
+        self.rule
+    }
+
+    /// Sets the rule.
+    pub fn set_rule(&mut self, rule: u8) {
+        self.rule = rule;
+    }
+
+    /// Returns `true` if the edges wrap around.
+    pub fn wrap(&self) -> bool {
+        self.wrap
+    }
+
+    /// When enabled, the first and last cells are treated as neighbors.
+    /// Otherwise, cells beyond the edges are considered dead.
+    ///
+    /// By default, this is set to `false`.
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    /// Returns the state of all cells, where `true` indicates a living cell.
+    pub fn cells(&self) -> &[bool] {
+        &self.cells
+    }
+
+    /// Sets the state of a single cell.
+    pub fn set_cell(&mut self, cell: usize, alive: bool) {
+        if let Some(cell) = self.cells.get_mut(cell) {
+            *cell = alive;
+        }
+    }
+
+    /// Kills all cells, and brings the center cell to life.
+    pub fn seed_center(&mut self) {
+        let center = self.cells.len() / 2;
+
+        self.clear();
+        self.set_cell(center, true);
+    }
+
+    /// Randomly brings cells to life, using `seed` for the pseudo-random
+    /// number generator. `density` is specified as a floating point value
+    /// between `0.0` (no living cells) and `1.0` (all cells alive).
+    pub fn seed_random(&mut self, seed: u64, density: f32) {
+        let mut rng = XorShift::new(seed);
+        for cell in &mut self.cells {
+            *cell = rng.next_f32() < density;
+        }
+    }
+
+    /// Kills all cells.
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = false;
+        }
+    }
+
+    /// Calculates the next generation.
+    pub fn step(&mut self) {
+        let len = self.cells.len();
+
+        for idx in 0..len {
+            let left = if idx > 0 {
+                self.cells[idx - 1]
+            } else {
+                self.wrap && self.cells[len - 1]
+            };
+
+            let right = if idx + 1 < len {
+                self.cells[idx + 1]
+            } else {
+                self.wrap && self.cells[0]
+            };
+
+            let pattern = (left as u8) << 2 | (self.cells[idx] as u8) << 1 | (right as u8);
+            self.next[idx] = (self.rule >> pattern) & 1 == 1;
+        }
+
+        std::mem::swap(&mut self.cells, &mut self.next);
+    }
+
+    /// Renders all cells to `frame`. Living cells are colored based on their
+    /// position, evenly spread out across `palette`. Dead cells are set to
+    /// black.
+    pub fn render(&self, frame: &mut Frame, palette: &Palette) {
+        let last = self.cells.len().saturating_sub(1).max(1) as f32;
+
+        for (idx, alive) in self.cells.iter().enumerate() {
+            if *alive {
+                frame.set_pixel_color(idx, palette.color_at(idx as f32 / last));
+            } else {
+                frame.set_pixel(idx, 0, 0, 0);
+            }
+        }
+    }
+}
+
+/// Conway's Game of Life on a two-dimensional grid.
+///
+/// Cells are stored in row-major order, starting at the top left cell
+/// (`x` = `0`, `y` = `0`).
+#[derive(Debug, Clone)]
+pub struct Life {
+    width: usize,
+    height: usize,
+    ages: Vec<u16>,
+    next: Vec<u16>,
+    wrap: bool,
+}
+
+impl Life {
+    /// Constructs a new `Life` grid of the specified size, with all cells
+    /// dead.
+    ///
+    /// By default, the edges wrap around.
+    pub fn new(width: usize, height: usize) -> Life {
+        Life {
+            width,
+            height,
+            ages: vec![0; width * height],
+            next: vec![0; width * height],
+            wrap: true,
+        }
+    }
+
+    /// Returns the width of the grid.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the grid.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns `true` if the edges wrap around.
+    pub fn wrap(&self) -> bool {
+        self.wrap
+    }
+
+    /// When enabled, cells on opposite edges are treated as neighbors.
+    /// Otherwise, cells beyond the edges are considered dead.
+    ///
+    /// By default, this is set to `true`.
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    /// Returns `true` if the cell at the specified coordinates is alive.
+    pub fn is_alive(&self, x: usize, y: usize) -> bool {
+        self.age(x, y) > 0
+    }
+
+    /// Returns the number of generations the cell at the specified
+    /// coordinates has been alive, or `0` if it's dead.
+    pub fn age(&self, x: usize, y: usize) -> u16 {
+        if x < self.width && y < self.height {
+            self.ages[y * self.width + x]
+        } else {
+            0
+        }
+    }
+
+    /// Sets the state of a single cell.
+    pub fn set_cell(&mut self, x: usize, y: usize, alive: bool) {
+        if x < self.width && y < self.height {
+            self.ages[y * self.width + x] = alive as u16;
+        }
+    }
+
+    /// Randomly brings cells to life, using `seed` for the pseudo-random
+    /// number generator. `density` is specified as a floating point value
+    /// between `0.0` (no living cells) and `1.0` (all cells alive).
+    pub fn seed_random(&mut self, seed: u64, density: f32) {
+        let mut rng = XorShift::new(seed);
+        for age in &mut self.ages {
+            *age = (rng.next_f32() < density) as u16;
+        }
+    }
+
+    /// Kills all cells.
+    pub fn clear(&mut self) {
+        for age in &mut self.ages {
+            *age = 0;
+        }
+    }
+
+    /// Returns the number of living cells.
+    pub fn population(&self) -> usize {
+        self.ages.iter().filter(|age| **age > 0).count()
+    }
+
+    /// Calculates the next generation.
+    pub fn step(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let neighbors = self.neighbors(x, y);
+                let idx = y * self.width + x;
+                let age = self.ages[idx];
+
+                self.next[idx] = match (age > 0, neighbors) {
+                    (true, 2) | (true, 3) => age.saturating_add(1),
+                    (false, 3) => 1,
+                    _ => 0,
+                };
+            }
+        }
+
+        std::mem::swap(&mut self.ages, &mut self.next);
+    }
+
+    fn neighbors(&self, x: usize, y: usize) -> usize {
+        let mut count = 0;
+
+        for dy in [-1isize, 0, 1].iter() {
+            for dx in [-1isize, 0, 1].iter() {
+                if *dx == 0 && *dy == 0 {
+                    continue;
+                }
+
+                let nx = x as isize + dx;
+                let ny = y as isize + dy;
+
+                let (nx, ny) = if self.wrap {
+                    (
+                        ((nx + self.width as isize) % self.width as isize) as usize,
+                        ((ny + self.height as isize) % self.height as isize) as usize,
+                    )
+                } else if nx < 0 || ny < 0 {
+                    continue;
+                } else {
+                    (nx as usize, ny as usize)
+                };
+
+                if self.is_alive(nx, ny) {
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Renders all cells to `frame` in row-major order. Living cells are
+    /// colored based on their age, where newborn cells use the start of
+    /// `palette`, and cells that have been alive for `max_age` generations
+    /// or longer use the end. Dead cells are set to black.
+    pub fn render(&self, frame: &mut Frame, palette: &Palette, max_age: u16) {
+        let max_age = f32::from(max_age.max(2) - 1);
+
+        for (idx, age) in self.ages.iter().enumerate() {
+            if *age > 0 {
+                frame.set_pixel_color(idx, palette.color_at(f32::from(age - 1) / max_age));
+            } else {
+                frame.set_pixel(idx, 0, 0, 0);
+            }
+        }
+    }
+}
@@ -0,0 +1,319 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// blinkt-ctl - Sets the pixels from the command line, for scripting and quick
+// hardware checks.
+//
+// Usage: blinkt-ctl [--pixels NUM] [--config PATH] COMMAND
+//
+// Commands:
+//   set INDEX COLOR [--brightness B]     Sets a single pixel
+//   fill COLOR [--brightness B]          Sets all pixels
+//   clear                                Turns off all pixels
+//   effect NAME [--fps FPS] [--duration SECS]
+//                                        Plays rainbow, breathe or rotate
+//   fifo PATH [--fps FPS]                Shows raw RGB frames written to a FIFO
+//   stream [--fps FPS]                   Shows raw RGB frames read from stdin
+//
+// Colors are specified as rrggbb hex values, and brightness values range from
+// 0.0 to 1.0. Effects play until the process is stopped, unless a duration
+// is specified.
+//
+// The fifo command creates the FIFO at PATH if it doesn't exist, and shows
+// every frame of NUM * 3 bytes written to it, at most FPS times per second.
+// The stream command does the same for frames read from the standard input,
+// until it's closed, as in some-renderer | blinkt-ctl stream --fps 60.
+//
+// Without any options, blinkt-ctl drives a Blinkt! board. --pixels drives an
+// APA102 or SK9822 strip with NUM pixels through the SPI peripheral instead.
+// --config loads the settings from a TOML file, and requires the config
+// feature.
+//
+// Since the pixels can't be read back, the colors are stored in
+// blinkt-ctl.state after every command, so consecutive commands build on each
+// other. The file is kept in $XDG_RUNTIME_DIR, or in a blinkt directory in
+// $XDG_STATE_HOME or ~/.local/state, which is created with mode 0700. When
+// running as root without any of these, /run/blinkt is used instead. If no
+// private directory is available, the colors aren't stored at all.
+
+use std::env;
+use std::error::Error;
+use std::fs::DirBuilder;
+use std::io::Read;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use blinkt::animation::{Animation, Animator};
+use blinkt::effects::{Breathe, Rainbow, Rotate};
+use blinkt::stream::RawReader;
+use blinkt::{Blinkt, Color};
+
+const SPI_CLOCK_SPEED_HZ: u32 = 16_000_000;
+const DEFAULT_FPS: f32 = 30.0;
+
+enum Command {
+    Set(usize, Color),
+    Fill(Color),
+    Clear,
+    Effect(String),
+    Fifo(String),
+    Stream,
+}
+
+struct Options {
+    pixels: Option<usize>,
+    config: Option<String>,
+    brightness: Option<f32>,
+    fps: Option<f32>,
+    duration: Option<Duration>,
+    command: Command,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: blinkt-ctl [--pixels NUM] [--config PATH] COMMAND");
+    eprintln!();
+    eprintln!("Commands:");
+    eprintln!("  set INDEX COLOR [--brightness B]");
+    eprintln!("  fill COLOR [--brightness B]");
+    eprintln!("  clear");
+    eprintln!("  effect rainbow|breathe|rotate [--fps FPS] [--duration SECS]");
+    eprintln!("  fifo PATH [--fps FPS]");
+    eprintln!("  stream [--fps FPS]");
+    process::exit(2);
+}
+
+fn parse<T: std::str::FromStr>(value: Option<String>) -> T {
+    match value.map(|value| value.parse()) {
+        Some(Ok(value)) => value,
+        _ => usage(),
+    }
+}
+
+// Parses `rrggbb`, optionally preceded by `#`.
+fn parse_color(value: Option<String>) -> Color {
+    let value = match value {
+        Some(value) => value,
+        None => usage(),
+    };
+
+    let hex = value.trim_start_matches('#');
+    if hex.len() != 6 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        usage();
+    }
+
+    let value = u32::from_str_radix(hex, 16).unwrap_or_else(|_| usage());
+    Color::from(((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
+
+fn parse_args() -> Options {
+    let mut pixels = None;
+    let mut config = None;
+    let mut brightness = None;
+    let mut fps = None;
+    let mut duration = None;
+    let mut positional = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--pixels" => pixels = Some(parse(args.next())),
+            "--config" => config = Some(args.next().unwrap_or_else(|| usage())),
+            "--brightness" => brightness = Some(parse::<f32>(args.next()).max(0.0).min(1.0)),
+            "--fps" => fps = Some(parse(args.next())),
+            "--duration" => {
+                let secs: f64 = parse(args.next());
+                if secs < 0.0 {
+                    usage();
+                }
+
+                duration = Some(Duration::from_millis((secs * 1000.0) as u64));
+            }
+            _ if arg.starts_with("--") => usage(),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = match positional.next().as_ref().map(|command| &command[..]) {
+        Some("set") => {
+            let index = parse(positional.next());
+            Command::Set(index, parse_color(positional.next()))
+        }
+        Some("fill") => Command::Fill(parse_color(positional.next())),
+        Some("clear") => Command::Clear,
+        Some("effect") => Command::Effect(positional.next().unwrap_or_else(|| usage())),
+        Some("fifo") => Command::Fifo(positional.next().unwrap_or_else(|| usage())),
+        Some("stream") => Command::Stream,
+        _ => usage(),
+    };
+
+    if positional.next().is_some() {
+        usage();
+    }
+
+    Options {
+        pixels,
+        config,
+        brightness,
+        fps,
+        duration,
+        command,
+    }
+}
+
+fn open(options: &Options) -> blinkt::Result<Blinkt> {
+    if let Some(ref path) = options.config {
+        return load_config(path);
+    }
+
+    match options.pixels {
+        Some(pixels) => Blinkt::with_spi(SPI_CLOCK_SPEED_HZ, pixels),
+        None => Blinkt::new(),
+    }
+}
+
+#[cfg(feature = "config")]
+fn load_config(path: &str) -> blinkt::Result<Blinkt> {
+    Blinkt::from_config(path)
+}
+
+#[cfg(not(feature = "config"))]
+fn load_config(path: &str) -> blinkt::Result<Blinkt> {
+    eprintln!(
+        "blinkt-ctl: can't load {}, --config requires the config feature",
+        path
+    );
+    process::exit(2);
+}
+
+// Returns the path of the state file, or None if there's no directory only
+// the current user can write to.
+fn state_path() -> Option<PathBuf> {
+    let env_dir = |name| {
+        env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+    };
+
+    if let Some(dir) = env_dir("XDG_RUNTIME_DIR") {
+        return Some(dir.join("blinkt-ctl.state"));
+    }
+
+    let dir = if let Some(dir) = env_dir("XDG_STATE_HOME") {
+        dir.join("blinkt")
+    } else if let Some(home) = env_dir("HOME") {
+        home.join(".local/state/blinkt")
+    } else if unsafe { libc::getuid() } == 0 {
+        PathBuf::from("/run/blinkt")
+    } else {
+        return None;
+    };
+
+    DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .ok()?;
+
+    Some(dir.join("blinkt-ctl.state"))
+}
+
+fn play<A: Animation>(
+    blinkt: &mut Blinkt,
+    options: &Options,
+    mut animation: A,
+) -> blinkt::Result<()> {
+    let mut animator = Animator::new(options.fps.unwrap_or(DEFAULT_FPS));
+    if let Some(duration) = options.duration {
+        animator = animator.with_duration(duration);
+    }
+
+    animator.run(blinkt, &mut animation)
+}
+
+fn stream<R: Read>(
+    blinkt: &mut Blinkt,
+    options: &Options,
+    mut reader: RawReader<R>,
+) -> blinkt::Result<()> {
+    if let Some(fps) = options.fps {
+        reader = reader.with_fps(fps);
+    }
+
+    reader.run(blinkt)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_args();
+    let mut blinkt = open(&options)?;
+    blinkt.set_clear_on_drop(false);
+    // The state file doesn't exist yet on the first run, and any other
+    // errors only mean the previous colors are lost.
+    let state_path = state_path();
+    if let Some(ref path) = state_path {
+        let _ = blinkt.restore_state(path);
+    }
+
+    match options.command {
+        Command::Set(pixel, color) => {
+            if pixel >= blinkt.num_pixels() {
+                eprintln!(
+                    "blinkt-ctl: pixel {} is out of range (0-{})",
+                    pixel,
+                    blinkt.num_pixels().saturating_sub(1)
+                );
+                process::exit(1);
+            }
+
+            blinkt.set_pixel_color(pixel, color);
+            if let Some(brightness) = options.brightness {
+                blinkt.set_pixel_brightness(pixel, brightness);
+            }
+        }
+        Command::Fill(color) => {
+            blinkt.set_all_pixels_color(color);
+            if let Some(brightness) = options.brightness {
+                blinkt.set_all_pixels_brightness(brightness);
+            }
+        }
+        Command::Clear => blinkt.clear(),
+        Command::Effect(ref name) => match &name[..] {
+            "rainbow" => play(&mut blinkt, &options, Rainbow::new())?,
+            "breathe" => play(&mut blinkt, &options, Breathe::new())?,
+            "rotate" => play(&mut blinkt, &options, Rotate::new())?,
+            _ => {
+                eprintln!("blinkt-ctl: unknown effect {}", name);
+                process::exit(2);
+            }
+        },
+        Command::Fifo(ref path) => stream(&mut blinkt, &options, RawReader::open_fifo(path)?)?,
+        Command::Stream => stream(&mut blinkt, &options, RawReader::stdin())?,
+    }
+
+    blinkt.swap();
+    blinkt.show()?;
+    if let Some(ref path) = state_path {
+        blinkt.save_state(path)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// blinktd - Controls the pixels through a JSON REST API. See the
+// documentation of the blinkt::rest module for the available endpoints.
+//
+// Usage: blinktd [--bind ADDR] [--pixels NUM] [--config PATH] [--preview ADDR]
+//                [--name NAME]
+//
+// Without any options, blinktd drives a Blinkt! board, and listens on port
+// 8080 on all network interfaces. --pixels drives an APA102 or SK9822 strip
+// with NUM pixels through the SPI peripheral instead. --config loads the
+// settings from a TOML file, and requires the config feature. --preview
+// serves a live preview of the pixels on ADDR, which can be opened in a web
+// browser, and requires the websocket feature.
+//
+// When built with the mdns feature, blinktd advertises the REST API on the
+// local network as NAME, which defaults to the host name.
+//
+// SIGINT and SIGTERM stop the server, after which the pixels are cleared.
+
+use std::env;
+use std::error::Error;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use blinkt::effects::{Breathe, Rainbow, Rotate};
+#[cfg(feature = "mdns")]
+use blinkt::mdns::{self, Advertiser};
+use blinkt::rest::{RestServer, PORT};
+use blinkt::Blinkt;
+
+const SPI_CLOCK_SPEED_HZ: u32 = 16_000_000;
+
+// Set by the signal handler to stop the server.
+static STOP: AtomicBool = AtomicBool::new(false);
+
+struct Options {
+    bind: String,
+    pixels: Option<usize>,
+    config: Option<String>,
+    preview: Option<String>,
+    name: Option<String>,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: blinktd [--bind ADDR] [--pixels NUM] [--config PATH] [--preview ADDR] [--name NAME]"
+    );
+    process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut options = Options {
+        bind: format!("0.0.0.0:{}", PORT),
+        pixels: None,
+        config: None,
+        preview: None,
+        name: None,
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match args.next() {
+            Some(value) => value,
+            None => usage(),
+        };
+
+        match &arg[..] {
+            "--bind" => options.bind = value,
+            "--pixels" => match value.parse() {
+                Ok(pixels) => options.pixels = Some(pixels),
+                Err(_) => usage(),
+            },
+            "--config" => options.config = Some(value),
+            "--preview" => options.preview = Some(value),
+            "--name" => options.name = Some(value),
+            _ => usage(),
+        }
+    }
+
+    options
+}
+
+fn open(options: &Options) -> blinkt::Result<Blinkt> {
+    if let Some(ref path) = options.config {
+        return load_config(path);
+    }
+
+    match options.pixels {
+        Some(pixels) => Blinkt::with_spi(SPI_CLOCK_SPEED_HZ, pixels),
+        None => Blinkt::new(),
+    }
+}
+
+#[cfg(feature = "config")]
+fn load_config(path: &str) -> blinkt::Result<Blinkt> {
+    Blinkt::from_config(path)
+}
+
+#[cfg(not(feature = "config"))]
+fn load_config(path: &str) -> blinkt::Result<Blinkt> {
+    eprintln!(
+        "blinktd: can't load {}, --config requires the config feature",
+        path
+    );
+    process::exit(2);
+}
+
+#[cfg(feature = "websocket")]
+fn serve_preview(blinkt: &mut Blinkt, addr: &str) -> blinkt::Result<()> {
+    let addr = blinkt.serve_preview(addr)?;
+    println!("Preview available at http://{}/", addr);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "websocket"))]
+fn serve_preview(_: &mut Blinkt, _: &str) -> blinkt::Result<()> {
+    eprintln!("blinktd: --preview requires the websocket feature");
+    process::exit(2);
+}
+
+#[cfg(feature = "mdns")]
+fn advertise(server: &RestServer, name: &Option<String>) -> blinkt::Result<Advertiser> {
+    let name = match *name {
+        Some(ref name) => name.clone(),
+        None => mdns::host_name(),
+    };
+    let advertiser = server.advertise(&name)?;
+    println!("Advertising as {}", name);
+
+    Ok(advertiser)
+}
+
+// Stands in for mdns::Advertiser without the mdns feature.
+#[cfg(not(feature = "mdns"))]
+struct Advertiser;
+
+#[cfg(not(feature = "mdns"))]
+fn advertise(_: &RestServer, name: &Option<String>) -> blinkt::Result<Advertiser> {
+    if name.is_some() {
+        eprintln!("blinktd: --name requires the mdns feature");
+        process::exit(2);
+    }
+
+    Ok(Advertiser)
+}
+
+extern "C" fn request_stop(_: libc::c_int) {
+    STOP.store(true, Ordering::SeqCst);
+}
+
+// Makes SIGINT and SIGTERM stop the server, so main returns normally and
+// the pixels are cleared when blinkt is dropped.
+fn stop_on_signal() {
+    for &signal in &[libc::SIGINT, libc::SIGTERM] {
+        unsafe {
+            libc::signal(
+                signal,
+                request_stop as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            );
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_args();
+    let mut blinkt = open(&options)?;
+    if let Some(ref addr) = options.preview {
+        serve_preview(&mut blinkt, addr)?;
+    }
+
+    let mut server = RestServer::bind(&options.bind[..])?;
+
+    server.add_effect("rainbow", Rainbow::new());
+    server.add_effect("breathe", Breathe::new());
+    server.add_effect("rotate", Rotate::new());
+
+    stop_on_signal();
+
+    println!("Listening on {}", server.local_addr()?);
+    let _advertiser = advertise(&server, &options.name)?;
+    server.run_until(&mut blinkt, &STOP)?;
+
+    Ok(())
+}
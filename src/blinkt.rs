@@ -0,0 +1,2895 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// The std implementation of Blinkt, which drives the pixels through the GPIO
+// or SPI peripheral, or any other SerialOutput.
+
+use std::error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::mem;
+#[cfg(feature = "websocket")]
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::ops::Range;
+use std::os::unix::fs::OpenOptionsExt;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::result;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rppal::gpio::{Gpio, OutputPin};
+use rppal::spi;
+
+pub use rppal::gpio::Error as GpioError;
+pub use rppal::spi::Error as SpiError;
+
+use crate::animation::{Animator, Transition};
+use crate::apa102::{encoded_len, end_frame_len};
+use crate::color_space::ColorSpaceTransform;
+#[cfg(feature = "dithering")]
+use crate::dither::Dither;
+use crate::easing::Easing;
+use crate::event::{ResizeCallback, ShowCallback};
+use crate::filter::FilterChain;
+use crate::gif::GifRecorder;
+#[cfg(feature = "perceptual")]
+use crate::perceptual::PerceptualCurve;
+use crate::pipeline::Pipelines;
+use crate::pixel_map::PixelMap;
+use crate::recording::Recorder;
+use crate::refresh::{Refresher, SharedOutput};
+#[cfg(feature = "websocket")]
+use crate::websocket::PreviewServer;
+use crate::writer::Writer;
+use crate::{animation, apa102, capture, frame, pixel, png, refresh, rng};
+use crate::{
+    BrightnessPolicy, Color, ColorOrder, ColorSpace, Filter, Frame, Interpolation, Pipeline, Pixel,
+    ResizeEvent, Segment, ShowEvent, TeeOutput,
+};
+
+// Default values for the Pimoroni Blinkt! board using BCM GPIO pin numbers
+pub(crate) const DAT: u8 = 23;
+pub(crate) const CLK: u8 = 24;
+const NUM_PIXELS: usize = 8;
+const LEDS_PER_METER: f32 = 60.0;
+
+// Maximum number of bytes per SPI transfer, used when the spidev module
+// doesn't report its buffer size
+const SPI_BUFFER_BYTES: usize = 4096;
+const SPIDEV_BUFSIZ: &str = "/sys/module/spidev/parameters/bufsiz";
+
+// Minimum number of seconds between automatic saves of the pixel state
+const PERSIST_INTERVAL_SECS: u64 = 1;
+
+// Width and height in pixels of a single LED in a snapshot image
+pub(crate) const SNAPSHOT_CELL_SIZE: usize = 16;
+
+#[derive(Debug)]
+/// Errors that can occur while using Blinkt.
+pub enum Error {
+    /// Accessing the GPIO peripheral returned an error.
+    ///
+    /// Some of these errors can be fixed by changing file permissions, or upgrading
+    /// to a more recent version of Raspbian.
+    Gpio(GpioError),
+    /// Accessing the SPI peripheral returned an error.
+    Spi(SpiError),
+    /// An I/O operation returned an error.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::Gpio(ref err) => write!(f, "GPIO error: {}", err),
+            Error::Spi(ref err) => write!(f, "SPI error: {}", err),
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<GpioError> for Error {
+    fn from(err: GpioError) -> Error {
+        Error::Gpio(err)
+    }
+}
+
+impl From<SpiError> for Error {
+    fn from(err: SpiError) -> Error {
+        Error::Spi(err)
+    }
+}
+
+/// Result type returned from methods that can have `blinkt::Error`s.
+pub type Result<T> = result::Result<T, Error>;
+
+type ShutdownAnimation = Box<dyn FnMut(&mut Blinkt) -> Result<()> + Send>;
+
+/// An output that sends serialized frames to the pixels.
+///
+/// `Blinkt` uses bitbanging or hardware SPI by default. Implement
+/// `SerialOutput` and pass it to [`Blinkt::with_output`] to send the data
+/// elsewhere, such as to a different peripheral, or to a buffer for testing.
+///
+/// [`Blinkt::with_output`]: struct.Blinkt.html#method.with_output
+pub trait SerialOutput {
+    /// Writes `data` to the output.
+    fn write(&mut self, data: &[u8]) -> Result<()>;
+}
+
+impl<T: SerialOutput + ?Sized> SerialOutput for Box<T> {
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        (**self).write(data)
+    }
+}
+
+// Peripheral settings for a `Blinkt` that's constructed without accessing
+// the hardware.
+#[derive(Debug, Copy, Clone)]
+enum Device {
+    Gpio(u8, u8),
+    Spi(u32, Option<usize>),
+}
+
+impl Device {
+    fn open(self) -> Result<Box<dyn SerialOutput + Send>> {
+        Ok(match self {
+            Device::Gpio(pin_data, pin_clock) => {
+                Box::new(BlinktGpio::with_settings(pin_data, pin_clock)?)
+            }
+            Device::Spi(clock_speed_hz, chunk_size) => {
+                Box::new(BlinktSpi::with_settings(clock_speed_hz, chunk_size)?)
+            }
+        })
+    }
+}
+
+// Placeholder output until the peripheral for a lazily constructed `Blinkt`
+// is configured.
+struct Disconnected;
+
+impl SerialOutput for Disconnected {
+    fn write(&mut self, _data: &[u8]) -> Result<()> {
+        Err(Error::Io(io::Error::new(
+            io::ErrorKind::NotConnected,
+            "peripheral hasn't been configured",
+        )))
+    }
+}
+
+pub(crate) struct BlinktGpio {
+    pin_data: OutputPin,
+    pin_clock: OutputPin,
+}
+
+impl BlinktGpio {
+    pub fn with_settings(pin_data: u8, pin_clock: u8) -> Result<BlinktGpio> {
+        let gpio = Gpio::new()?;
+
+        let mut pin_data = gpio.get(pin_data)?.into_output();
+        let mut pin_clock = gpio.get(pin_clock)?.into_output();
+
+        pin_data.set_low();
+        pin_clock.set_low();
+
+        Ok(BlinktGpio {
+            pin_data,
+            pin_clock,
+        })
+    }
+}
+
+impl SerialOutput for BlinktGpio {
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        for byte in data {
+            for n in 0..8 {
+                if (byte & (1 << (7 - n))) > 0 {
+                    self.pin_data.set_high();
+                } else {
+                    self.pin_data.set_low();
+                }
+
+                self.pin_clock.set_high();
+                self.pin_clock.set_low();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) struct BlinktSpi {
+    spi: spi::Spi,
+    chunk_size: usize,
+}
+
+impl BlinktSpi {
+    pub fn with_settings(clock_speed_hz: u32, chunk_size: Option<usize>) -> Result<BlinktSpi> {
+        let chunk_size = chunk_size.unwrap_or_else(spidev_bufsiz);
+
+        Ok(BlinktSpi {
+            spi: spi::Spi::new(
+                spi::Bus::Spi0,
+                spi::SlaveSelect::Ss0,
+                clock_speed_hz,
+                spi::Mode::Mode0,
+            )?,
+            // Transfers are split on 4-byte boundaries, so pixels are never
+            // split between transfers.
+            chunk_size: (chunk_size - chunk_size % 4).max(4),
+        })
+    }
+}
+
+impl SerialOutput for BlinktSpi {
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        // spidev rejects transfers that exceed its buffer size.
+        for chunk in data.chunks(self.chunk_size) {
+            self.spi.write(chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Returns the maximum number of bytes per transfer supported by the spidev
+// module, which can be changed through the spidev.bufsiz kernel parameter.
+fn spidev_bufsiz() -> usize {
+    match fs::read_to_string(SPIDEV_BUFSIZ) {
+        Ok(bufsiz) => bufsiz.trim().parse().unwrap_or(SPI_BUFFER_BYTES),
+        Err(_) => SPI_BUFFER_BYTES,
+    }
+}
+
+// Hardware SPI through an arbitrary spidev device, for boards that aren't
+// supported by rppal.
+#[cfg(feature = "spidev")]
+struct BlinktSpidev {
+    spidev: spidev::Spidev,
+    chunk_size: usize,
+}
+
+#[cfg(feature = "spidev")]
+impl BlinktSpidev {
+    fn with_settings(path: &Path, clock_speed_hz: u32) -> Result<BlinktSpidev> {
+        let mut spidev = spidev::Spidev::open(path)?;
+        spidev.configure(
+            &spidev::SpidevOptions::new()
+                .bits_per_word(8)
+                .max_speed_hz(clock_speed_hz)
+                .mode(spidev::SpiModeFlags::SPI_MODE_0)
+                .build(),
+        )?;
+
+        let chunk_size = spidev_bufsiz();
+
+        Ok(BlinktSpidev {
+            spidev,
+            chunk_size: (chunk_size - chunk_size % 4).max(4),
+        })
+    }
+}
+
+#[cfg(feature = "spidev")]
+impl SerialOutput for BlinktSpidev {
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        for chunk in data.chunks(self.chunk_size) {
+            io::Write::write_all(&mut self.spidev, chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Interface for the Pimoroni Blinkt!, and any similar APA102 or SK9822 LED
+/// strips or boards.
+///
+/// By default, Blinkt is set up to communicate with an 8-pixel board through
+/// data pin GPIO 23 (physical pin 16) and clock pin GPIO 24 (physical pin 18).
+/// These settings can be changed to support alternate configurations.
+pub struct Blinkt {
+    serial_output: SharedOutput,
+    pending_device: Option<Device>,
+    refresher: Option<Refresher>,
+    writer: Option<Writer>,
+    last_frame: Option<Vec<u8>>,
+    encoded: Vec<u8>,
+    pub(crate) pixels: Vec<Pixel>,
+    front: Option<FrontBuffer>,
+    clear_on_drop: bool,
+    clear_on_drop_fade: Duration,
+    panic_hook_pixels: Option<Arc<AtomicUsize>>,
+    persist_path: Option<PathBuf>,
+    persisted_state: Option<(String, Instant)>,
+    end_frame: Vec<u8>,
+    leading_pixels: usize,
+    color_space: ColorSpace,
+    color_space_transform: Option<ColorSpaceTransform>,
+    color_order: ColorOrder,
+    gamma: f32,
+    gamma_curve: Option<[u8; 256]>,
+    filters: Vec<Filter>,
+    filter_chain: Option<FilterChain>,
+    pipelines: Pipelines,
+    pixel_map: Option<PixelMap>,
+    brightness_policy: BrightnessPolicy,
+    default_brightness: u8,
+    min_frame_gap: Duration,
+    target_frame_interval: Option<Duration>,
+    next_tick: Option<Instant>,
+    last_show: Option<Instant>,
+    shutdown_animation: Option<ShutdownAnimation>,
+    before_show: Vec<ShowCallback>,
+    after_show: Vec<ShowCallback>,
+    on_resize: Vec<ResizeCallback>,
+    frame_number: u64,
+    leds_per_meter: f32,
+    #[cfg(feature = "dithering")]
+    dither: Option<Dither>,
+    #[cfg(feature = "perceptual")]
+    perceptual: Option<PerceptualCurve>,
+}
+
+// The buffer that's sent to the pixels when double buffering is enabled.
+struct FrontBuffer {
+    pixels: Vec<Pixel>,
+    #[cfg(feature = "dithering")]
+    dither: Option<Dither>,
+}
+
+impl Blinkt {
+    /// Constructs a new `Blinkt` using the default settings for a Pimoroni
+    /// Blinkt! board.
+    ///
+    /// This sets the data pin to GPIO 23 (physical pin 16), the clock pin to
+    /// GPIO 24 (physical pin 18), and number of pixels to 8.
+    pub fn new() -> Result<Blinkt> {
+        Blinkt::with_settings(DAT, CLK, NUM_PIXELS)
+    }
+
+    /// Constructs a new `Blinkt` using bitbanging mode, with custom settings for
+    /// the data pin, clock pin, and number of pixels. Pins should be specified
+    /// by their BCM GPIO pin numbers.
+    pub fn with_settings(pin_data: u8, pin_clock: u8, num_pixels: usize) -> Result<Blinkt> {
+        Ok(Blinkt::with_serial_output(
+            Box::new(BlinktGpio::with_settings(pin_data, pin_clock)?),
+            num_pixels,
+        ))
+    }
+
+    /// Constructs a new `Blinkt` using hardware SPI, with custom settings for the
+    /// clock speed and number of pixels.
+    ///
+    /// This sets the data pin to GPIO 10 (physical pin 19) and the clock pin
+    /// to GPIO 11 (physical pin 23).
+    ///
+    /// The Raspberry Pi allows SPI clock speeds up to 125 MHz (125_000_000),
+    /// but the maximum speed supported by LED strips depends a lot on the
+    /// number of pixels and wire quality, and requires some experimentation.
+    /// 32 MHz (32_000_000) seems to be the maximum clock speed for a typical
+    /// short LED strip. Visit the [Raspberry Pi SPI Documentation](https://www.raspberrypi.org/documentation/hardware/raspberrypi/spi/)
+    /// page for a complete list of supported clock speeds.
+    ///
+    /// Frames are split into multiple transfers when they exceed the spidev
+    /// buffer size, which is read from `/sys/module/spidev/parameters/bufsiz`
+    /// and defaults to 4096 bytes. Use [`with_spi_chunk_size`] to override
+    /// the maximum transfer size.
+    ///
+    /// [`with_spi_chunk_size`]: #method.with_spi_chunk_size
+    pub fn with_spi(clock_speed_hz: u32, num_pixels: usize) -> Result<Blinkt> {
+        Ok(Blinkt::with_serial_output(
+            Box::new(BlinktSpi::with_settings(clock_speed_hz, None)?),
+            num_pixels,
+        ))
+    }
+
+    /// Constructs a new `Blinkt` using hardware SPI, with custom settings for
+    /// the clock speed, number of pixels, and maximum number of bytes per
+    /// SPI transfer.
+    ///
+    /// `chunk_size` is rounded down to a multiple of 4 bytes, so pixels
+    /// aren't split between transfers. It shouldn't exceed the spidev buffer
+    /// size, which can be increased by adding `spidev.bufsiz=<size>` to
+    /// `/boot/cmdline.txt`. See [`with_spi`] for more information on the
+    /// supported clock speeds.
+    ///
+    /// [`with_spi`]: #method.with_spi
+    pub fn with_spi_chunk_size(
+        clock_speed_hz: u32,
+        num_pixels: usize,
+        chunk_size: usize,
+    ) -> Result<Blinkt> {
+        Ok(Blinkt::with_serial_output(
+            Box::new(BlinktSpi::with_settings(clock_speed_hz, Some(chunk_size))?),
+            num_pixels,
+        ))
+    }
+
+    /// Constructs a new `Blinkt` using a Linux spidev device, with custom
+    /// settings for the device path, clock speed and number of pixels.
+    ///
+    /// This opens the device directly, for instance `/dev/spidev1.0`, which
+    /// supports single-board computers other than the Raspberry Pi, such as
+    /// the Orange Pi. The device is configured for SPI mode 0 with 8 bits per
+    /// word. Frames are split into multiple transfers in the same way as
+    /// [`with_spi`].
+    ///
+    /// This method requires the `spidev` feature.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::with_spidev("/dev/spidev1.0", 8_000_000, 144)?;
+    ///
+    /// blinkt.set_all_pixels(255, 0, 0);
+    /// blinkt.show()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`with_spi`]: #method.with_spi
+    #[cfg(feature = "spidev")]
+    pub fn with_spidev<P: AsRef<Path>>(
+        path: P,
+        clock_speed_hz: u32,
+        num_pixels: usize,
+    ) -> Result<Blinkt> {
+        Ok(Blinkt::with_serial_output(
+            Box::new(BlinktSpidev::with_settings(path.as_ref(), clock_speed_hz)?),
+            num_pixels,
+        ))
+    }
+
+    /// Constructs a new `Blinkt` using the settings stored in a TOML
+    /// configuration file.
+    ///
+    /// This allows the same binary to drive different strips, such as a
+    /// short strip during development and a long strip in production,
+    /// without recompiling. All settings are optional, and fall back to the
+    /// defaults used by [`new`]. When `interface` isn't set, SPI is selected
+    /// if `clock_speed_hz` or `spidev` is present, and bitbanging mode
+    /// otherwise. The SPI clock speed defaults to 8 MHz.
+    ///
+    /// ```toml
+    /// interface = "spi"       # "gpio" or "spi"
+    /// pin_data = 23           # bitbanging mode only
+    /// pin_clock = 24          # bitbanging mode only
+    /// clock_speed_hz = 16000000
+    /// spi_chunk_size = 4096
+    /// spidev = "/dev/spidev1.0" # requires the spidev feature
+    /// num_pixels = 144
+    /// color_order = "grb"     # see ColorOrder
+    /// gamma = 2.2
+    /// ```
+    ///
+    /// Files that can't be parsed, or that contain unknown settings, return
+    /// an `Error::Io` with `ErrorKind::InvalidData`.
+    ///
+    /// This method requires the `config` feature.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::from_config("/etc/blinkt.toml")?;
+    ///
+    /// blinkt.set_all_pixels(255, 0, 0);
+    /// blinkt.show()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`new`]: #method.new
+    #[cfg(feature = "config")]
+    pub fn from_config<P: AsRef<Path>>(path: P) -> Result<Blinkt> {
+        config::Config::load(path.as_ref())?.build()
+    }
+
+    /// Constructs a new `Blinkt` using bitbanging mode, with custom settings
+    /// for the data pin, clock pin, and number of pixels, without accessing
+    /// the GPIO peripheral.
+    ///
+    /// The GPIO pins are configured when [`connect`] is called, or on the
+    /// first call to [`show`]. This allows `Blinkt` to be constructed in
+    /// contexts where the hardware may not be present yet, such as during
+    /// configuration validation or in unit tests.
+    ///
+    /// [`connect`]: #method.connect
+    /// [`show`]: #method.show
+    pub fn with_settings_lazy(pin_data: u8, pin_clock: u8, num_pixels: usize) -> Blinkt {
+        Blinkt::with_pending_device(Device::Gpio(pin_data, pin_clock), num_pixels)
+    }
+
+    /// Constructs a new `Blinkt` using hardware SPI, with custom settings
+    /// for the clock speed and number of pixels, without accessing the SPI
+    /// peripheral.
+    ///
+    /// The SPI peripheral is configured when [`connect`] is called, or on the
+    /// first call to [`show`]. See [`with_spi`] for more information on the
+    /// supported clock speeds.
+    ///
+    /// [`connect`]: #method.connect
+    /// [`show`]: #method.show
+    /// [`with_spi`]: #method.with_spi
+    pub fn with_spi_lazy(clock_speed_hz: u32, num_pixels: usize) -> Blinkt {
+        Blinkt::with_pending_device(Device::Spi(clock_speed_hz, None), num_pixels)
+    }
+
+    /// Constructs a new `Blinkt` using a custom output, with the specified
+    /// number of pixels.
+    ///
+    /// This can be used to send the serialized frames to an output other than
+    /// the GPIO or SPI peripherals, such as a [`Capture`] for testing.
+    ///
+    /// [`Capture`]: capture/struct.Capture.html
+    pub fn with_output<T>(output: T, num_pixels: usize) -> Blinkt
+    where
+        T: SerialOutput + Send + 'static,
+    {
+        Blinkt::with_serial_output(Box::new(output), num_pixels)
+    }
+
+    fn with_pending_device(device: Device, num_pixels: usize) -> Blinkt {
+        let mut blinkt = Blinkt::with_serial_output(Box::new(Disconnected), num_pixels);
+        blinkt.pending_device = Some(device);
+
+        blinkt
+    }
+
+    fn with_serial_output(
+        serial_output: Box<dyn SerialOutput + Send>,
+        num_pixels: usize,
+    ) -> Blinkt {
+        Blinkt {
+            serial_output: Arc::new(Mutex::new(serial_output)),
+            pending_device: None,
+            refresher: None,
+            writer: None,
+            last_frame: None,
+            encoded: Vec::with_capacity(encoded_len(num_pixels)),
+            pixels: vec![Pixel::default(); num_pixels],
+            front: None,
+            clear_on_drop: true,
+            clear_on_drop_fade: Duration::from_secs(0),
+            panic_hook_pixels: None,
+            persist_path: None,
+            persisted_state: None,
+            end_frame: vec![0u8; end_frame_len(num_pixels)],
+            leading_pixels: 0,
+            color_space: ColorSpace::Srgb,
+            color_space_transform: None,
+            color_order: ColorOrder::Rgb,
+            gamma: 1.0,
+            gamma_curve: None,
+            filters: Vec::new(),
+            filter_chain: None,
+            pipelines: Pipelines::default(),
+            pixel_map: None,
+            brightness_policy: BrightnessPolicy::Preserve,
+            default_brightness: pixel::DEFAULT_BRIGHTNESS,
+            min_frame_gap: Duration::from_millis(0),
+            target_frame_interval: None,
+            next_tick: None,
+            last_show: None,
+            shutdown_animation: None,
+            before_show: Vec::new(),
+            after_show: Vec::new(),
+            on_resize: Vec::new(),
+            frame_number: 0,
+            leds_per_meter: LEDS_PER_METER,
+            #[cfg(feature = "dithering")]
+            dither: None,
+            #[cfg(feature = "perceptual")]
+            perceptual: None,
+        }
+    }
+
+    /// Configures the GPIO or SPI peripheral, if `Blinkt` was constructed
+    /// through [`with_settings_lazy`] or [`with_spi_lazy`] and hasn't been
+    /// connected yet. Otherwise, this does nothing.
+    ///
+    /// Returns an error if the peripheral couldn't be accessed. `connect` can
+    /// be called again to retry.
+    ///
+    /// [`with_settings_lazy`]: #method.with_settings_lazy
+    /// [`with_spi_lazy`]: #method.with_spi_lazy
+    pub fn connect(&mut self) -> Result<()> {
+        if let Some(ref device) = self.pending_device {
+            *refresh::lock_output(&self.serial_output) = device.open()?;
+        }
+
+        self.pending_device = None;
+
+        Ok(())
+    }
+
+    /// Returns `true` if the GPIO or SPI peripheral has been configured.
+    ///
+    /// This always returns `true`, unless `Blinkt` was constructed through
+    /// [`with_settings_lazy`] or [`with_spi_lazy`] and hasn't been connected
+    /// yet.
+    ///
+    /// [`with_settings_lazy`]: #method.with_settings_lazy
+    /// [`with_spi_lazy`]: #method.with_spi_lazy
+    pub fn is_connected(&self) -> bool {
+        self.pending_device.is_none()
+    }
+
+    /// Sends every frame to `output`, in addition to the current output.
+    ///
+    /// The current output, such as the GPIO or SPI peripheral, is combined
+    /// with `output` in a [`TeeOutput`], so the frames that are sent to the
+    /// hardware can be observed while the application runs, for instance
+    /// through a [`Capture`]. If `Blinkt` hasn't been connected yet, the
+    /// peripheral is configured first.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::capture::Capture;
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+    /// let capture = Capture::new();
+    /// blinkt.add_output(capture.clone())?;
+    ///
+    /// blinkt.set_all_pixels(255, 0, 0);
+    /// blinkt.show()?;
+    ///
+    /// println!("{:02x?}", capture.take());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`TeeOutput`]: struct.TeeOutput.html
+    /// [`Capture`]: capture/struct.Capture.html
+    pub fn add_output<T>(&mut self, output: T) -> Result<()>
+    where
+        T: SerialOutput + Send + 'static,
+    {
+        self.connect()?;
+
+        let mut serial_output = refresh::lock_output(&self.serial_output);
+        let current = mem::replace(&mut *serial_output, Box::new(Disconnected));
+        *serial_output = Box::new(TeeOutput::new().with_output(current).with_output(output));
+
+        Ok(())
+    }
+
+    /// Returns a mutable iterator over all `Pixel`s stored in `Blinkt`.
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            iter_mut: self.pixels.iter_mut(),
+        }
+    }
+
+    /// Returns a copy of the local buffer as a `Frame`.
+    pub fn frame(&self) -> Frame {
+        Frame::from_pixels(&self.pixels)
+    }
+
+    /// Copies the contents of `frame` to the local buffer.
+    ///
+    /// If `frame` contains more pixels than `Blinkt`, the remaining pixels are
+    /// ignored.
+    pub fn set_frame(&mut self, frame: &Frame) {
+        for (idx, pixel) in frame.iter().enumerate().take(self.pixels.len()) {
+            self.replace_pixel(idx, *pixel);
+        }
+    }
+
+    /// Crossfades from the current contents of the local buffer to `frame`,
+    /// and blocks until the transition has finished.
+    ///
+    /// The transition takes `duration` to complete, with its progress shaped
+    /// by `easing`. Intermediate frames are sent to the pixels at roughly 60
+    /// frames per second. Afterwards, the local buffer contains `frame`. To
+    /// run a transition in the background, pass an [`animation::Transition`]
+    /// to [`animation::Animator::spawn`].
+    ///
+    /// Returns an error if sending a frame to the pixels failed.
+    ///
+    /// [`animation::Transition`]: animation/struct.Transition.html
+    /// [`animation::Animator::spawn`]: animation/struct.Animator.html#method.spawn
+    pub fn transition_to(
+        &mut self,
+        frame: &Frame,
+        duration: Duration,
+        easing: Easing,
+    ) -> Result<()> {
+        let mut transition = Transition::new(self.frame(), frame.clone(), duration, easing);
+
+        Animator::new(60.0).run(self, &mut transition)
+    }
+
+    /// Blits multiple source frames into the specified destination ranges of
+    /// the local buffer.
+    ///
+    /// Each layer consists of a destination range, a source frame, and an
+    /// alpha value between `0.0` (fully transparent) and `1.0` (opaque). The
+    /// first pixel of the source frame is drawn at the start of the range.
+    /// Layers are drawn in order, so later layers are drawn on top of earlier
+    /// ones. Any pixels outside of the destination range or the source frame
+    /// are ignored.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::{Blinkt, Frame};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+    ///
+    /// let mut clock = Frame::new(48);
+    /// clock.set_all_pixels(0, 0, 255);
+    /// let mut weather = Frame::new(96);
+    /// weather.set_all_pixels(255, 128, 0);
+    ///
+    /// blinkt.compose(&[(0..48, &clock, 1.0), (48..144, &weather, 0.5)]);
+    /// blinkt.show()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compose(&mut self, layers: &[(Range<usize>, &Frame, f32)]) {
+        let mut frame = self.frame();
+        frame.compose(layers);
+        self.set_frame(&frame);
+    }
+
+    // Replaces a single pixel, and keeps any secondary buffers in sync.
+    pub(crate) fn replace_pixel(&mut self, idx: usize, pixel: Pixel) {
+        #[cfg(feature = "dithering")]
+        {
+            if let Some(ref mut dither) = self.dither {
+                let (red, green, blue) = pixel.rgb();
+                dither.set_rgb(idx, red, green, blue);
+            }
+        }
+
+        if let Some(dst) = self.pixels.get_mut(idx) {
+            *dst = pixel;
+        }
+    }
+
+    /// Returns a [`Segment`], which provides access to the pixels within
+    /// `range` as if they were an independent strip.
+    ///
+    /// Any part of `range` that's out of bounds is ignored.
+    ///
+    /// [`Segment`]: struct.Segment.html
+    pub fn segment(&mut self, range: Range<usize>) -> Segment<'_> {
+        Segment::new(self, range)
+    }
+
+    /// Sets the red, green and blue values for a single pixel in the local
+    /// buffer.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    pub fn set_pixel(&mut self, pixel: usize, red: u8, green: u8, blue: u8) {
+        #[cfg(feature = "dithering")]
+        if let Some(ref mut dither) = self.dither {
+            dither.set_rgb(pixel, red, green, blue);
+        }
+
+        let brightness = self.policy_brightness();
+        if let Some(pixel) = self.pixels.get_mut(pixel) {
+            pixel.set_rgb(red, green, blue);
+            if let Some(brightness) = brightness {
+                pixel.set_brightness_raw(brightness);
+            }
+        }
+    }
+
+    /// Sets the color for a single pixel in the local buffer.
+    ///
+    /// Pixels are numbered starting at `0`.
+    pub fn set_pixel_color<C: Into<Color>>(&mut self, pixel: usize, color: C) {
+        let color = color.into();
+        self.set_pixel(pixel, color.red, color.green, color.blue);
+    }
+
+    /// Sets the red, green and blue values for a single pixel in the local
+    /// buffer using 16-bit precision.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `red`, `green` and `blue` are specified as 16-bit values between `0` (0%) and `65535` (100%).
+    ///
+    /// The additional precision is only used when high resolution mode is
+    /// enabled through [`set_high_resolution`]. Otherwise, or when the
+    /// `dithering` feature is disabled, the values are truncated to 8 bits.
+    ///
+    /// [`set_high_resolution`]: #method.set_high_resolution
+    pub fn set_pixel_rgb16(&mut self, pixel: usize, red: u16, green: u16, blue: u16) {
+        #[cfg(feature = "dithering")]
+        if let Some(ref mut dither) = self.dither {
+            dither.set_rgb16(pixel, red, green, blue);
+        }
+
+        let brightness = self.policy_brightness();
+        if let Some(pixel) = self.pixels.get_mut(pixel) {
+            pixel.set_rgb((red >> 8) as u8, (green >> 8) as u8, (blue >> 8) as u8);
+            if let Some(brightness) = brightness {
+                pixel.set_brightness_raw(brightness);
+            }
+        }
+    }
+
+    /// Sets the red, green, blue and brightness values for a single pixel in
+    /// the local buffer.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_pixel_rgbb(&mut self, pixel: usize, red: u8, green: u8, blue: u8, brightness: f32) {
+        #[cfg(feature = "dithering")]
+        if let Some(ref mut dither) = self.dither {
+            dither.set_rgb(pixel, red, green, blue);
+        }
+
+        if let Some(pixel) = self.pixels.get_mut(pixel) {
+            pixel.set_rgbb(red, green, blue, brightness);
+        }
+    }
+
+    /// Sets the brightness value for a single pixel in the local buffer.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_pixel_brightness(&mut self, pixel: usize, brightness: f32) {
+        if let Some(pixel) = self.pixels.get_mut(pixel) {
+            pixel.set_brightness(brightness);
+        }
+    }
+
+    /// Returns the brightness policy.
+    pub fn brightness_policy(&self) -> BrightnessPolicy {
+        self.brightness_policy
+    }
+
+    /// Sets the brightness policy, which determines how setters that only
+    /// change the color, such as `set_pixel()`, `set_pixel_color()` and
+    /// `set_all_pixels()`, affect the brightness value of the pixels they
+    /// modify.
+    ///
+    /// Setters that explicitly specify a brightness value aren't affected.
+    ///
+    /// By default, this is set to `BrightnessPolicy::Preserve`.
+    pub fn set_brightness_policy(&mut self, brightness_policy: BrightnessPolicy) {
+        self.brightness_policy = brightness_policy;
+    }
+
+    /// Returns the global default brightness.
+    pub fn default_brightness(&self) -> f32 {
+        f32::from(self.default_brightness) / 31.0
+    }
+
+    /// Sets the global default brightness, which is applied by color setters
+    /// when the brightness policy is set to `BrightnessPolicy::Global`.
+    ///
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    ///
+    /// By default, this is set to 7/31 (roughly 22%).
+    pub fn set_default_brightness(&mut self, brightness: f32) {
+        self.default_brightness = (31.0 * brightness.max(0.0).min(1.0)) as u8;
+    }
+
+    // Returns the 5-bit brightness value color setters should apply, based on
+    // the brightness policy.
+    fn policy_brightness(&self) -> Option<u8> {
+        match self.brightness_policy {
+            BrightnessPolicy::Preserve => None,
+            BrightnessPolicy::Reset => Some(pixel::DEFAULT_BRIGHTNESS),
+            BrightnessPolicy::Global => Some(self.default_brightness),
+        }
+    }
+
+    /// Sets the red, green and blue values for consecutive pixels in the local
+    /// buffer, starting at the first pixel.
+    ///
+    /// If `colors` contains more values than the number of pixels, the
+    /// remaining values are ignored.
+    #[cfg(feature = "rgb")]
+    pub fn set_pixels_rgb8(&mut self, colors: &[rgb::RGB8]) {
+        for (idx, color) in colors.iter().enumerate().take(self.pixels.len()) {
+            self.set_pixel(idx, color.r, color.g, color.b);
+        }
+    }
+
+    /// Sets the red, green and blue values for all pixels in the local buffer.
+    ///
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    pub fn set_all_pixels(&mut self, red: u8, green: u8, blue: u8) {
+        #[cfg(feature = "dithering")]
+        if let Some(ref mut dither) = self.dither {
+            dither.set_all_rgb(red, green, blue);
+        }
+
+        let brightness = self.policy_brightness();
+        for pixel in &mut self.pixels {
+            pixel.set_rgb(red, green, blue);
+            if let Some(brightness) = brightness {
+                pixel.set_brightness_raw(brightness);
+            }
+        }
+    }
+
+    /// Sets the color for all pixels in the local buffer.
+    pub fn set_all_pixels_color<C: Into<Color>>(&mut self, color: C) {
+        let color = color.into();
+        self.set_all_pixels(color.red, color.green, color.blue);
+    }
+
+    /// Sets the red, green and blue values for all pixels in the local buffer
+    /// using 16-bit precision.
+    ///
+    /// `red`, `green` and `blue` are specified as 16-bit values between `0` (0%) and `65535` (100%).
+    ///
+    /// The additional precision is only used when high resolution mode is
+    /// enabled through [`set_high_resolution`]. Otherwise, or when the
+    /// `dithering` feature is disabled, the values are truncated to 8 bits.
+    ///
+    /// [`set_high_resolution`]: #method.set_high_resolution
+    pub fn set_all_pixels_rgb16(&mut self, red: u16, green: u16, blue: u16) {
+        #[cfg(feature = "dithering")]
+        if let Some(ref mut dither) = self.dither {
+            dither.set_all_rgb16(red, green, blue);
+        }
+
+        let brightness = self.policy_brightness();
+        for pixel in &mut self.pixels {
+            pixel.set_rgb((red >> 8) as u8, (green >> 8) as u8, (blue >> 8) as u8);
+            if let Some(brightness) = brightness {
+                pixel.set_brightness_raw(brightness);
+            }
+        }
+    }
+
+    /// Sets the red, green, blue and brightness values for all pixels in the
+    /// local buffer.
+    ///
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_all_pixels_rgbb(&mut self, red: u8, green: u8, blue: u8, brightness: f32) {
+        #[cfg(feature = "dithering")]
+        if let Some(ref mut dither) = self.dither {
+            dither.set_all_rgb(red, green, blue);
+        }
+
+        for pixel in &mut self.pixels {
+            pixel.set_rgbb(red, green, blue, brightness);
+        }
+    }
+
+    /// Sets the brightness value for all pixels.
+    ///
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_all_pixels_brightness(&mut self, brightness: f32) {
+        for pixel in &mut self.pixels {
+            pixel.set_brightness(brightness);
+        }
+    }
+
+    /// Fills all pixels in the local buffer with a gradient from `start` to
+    /// `end`.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::{Blinkt, Color, Interpolation};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::new()?;
+    ///
+    /// blinkt.fill_gradient(Color::new(255, 0, 0), Color::new(0, 0, 255), Interpolation::Hsv);
+    /// blinkt.show()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fill_gradient<C: Into<Color>>(
+        &mut self,
+        start: C,
+        end: C,
+        interpolation: Interpolation,
+    ) {
+        let len = self.pixels.len();
+        self.fill_gradient_range(0..len, start, end, interpolation);
+    }
+
+    /// Fills the pixels within `range` in the local buffer with a gradient from
+    /// `start` to `end`.
+    ///
+    /// Any part of `range` that's out of bounds is ignored, but still
+    /// included when calculating the gradient.
+    pub fn fill_gradient_range<C: Into<Color>>(
+        &mut self,
+        range: Range<usize>,
+        start: C,
+        end: C,
+        interpolation: Interpolation,
+    ) {
+        let (start, end) = (start.into(), end.into());
+        for (idx, color) in frame::gradient(range, start, end, interpolation) {
+            self.set_pixel_color(idx, color);
+        }
+    }
+
+    /// Returns the pixel density used for physical addressing.
+    pub fn leds_per_meter(&self) -> f32 {
+        self.leds_per_meter
+    }
+
+    /// Sets the pixel density used for physical addressing through
+    /// [`set_at_mm`] and [`fill_mm`].
+    ///
+    /// Common LED strips have a density of 30, 60 or 144 LEDs per meter.
+    ///
+    /// By default, this is set to `60.0`.
+    ///
+    /// [`set_at_mm`]: #method.set_at_mm
+    /// [`fill_mm`]: #method.fill_mm
+    pub fn set_leds_per_meter(&mut self, leds_per_meter: f32) {
+        self.leds_per_meter = leds_per_meter;
+    }
+
+    /// Sets the color for the pixel located at `pos_mm` millimeters from the
+    /// start of the strip.
+    ///
+    /// Positions are converted to pixel indices based on the pixel density
+    /// set through [`set_leds_per_meter`]. Positions beyond the end of the
+    /// strip are ignored.
+    ///
+    /// [`set_leds_per_meter`]: #method.set_leds_per_meter
+    pub fn set_at_mm<C: Into<Color>>(&mut self, pos_mm: f32, color: C) {
+        if pos_mm < 0.0 || self.leds_per_meter <= 0.0 {
+            return;
+        }
+
+        let pitch_mm = 1000.0 / self.leds_per_meter;
+        self.set_pixel_color((pos_mm / pitch_mm) as usize, color);
+    }
+
+    /// Sets the color for all pixels located between `range_mm.start` and
+    /// `range_mm.end` millimeters from the start of the strip.
+    ///
+    /// A pixel is considered part of the range if its center lies within
+    /// `range_mm`. Positions are converted to pixel indices based on the
+    /// pixel density set through [`set_leds_per_meter`].
+    ///
+    /// [`set_leds_per_meter`]: #method.set_leds_per_meter
+    pub fn fill_mm<C: Into<Color>>(&mut self, range_mm: Range<f32>, color: C) {
+        if self.leds_per_meter <= 0.0 {
+            return;
+        }
+
+        let color = color.into();
+        let pitch_mm = 1000.0 / self.leds_per_meter;
+        let first = ((range_mm.start / pitch_mm) - 0.5).ceil().max(0.0) as usize;
+        let last = ((range_mm.end / pitch_mm) - 0.5).ceil().max(0.0) as usize;
+
+        for idx in first..last.min(self.pixels.len()) {
+            self.set_pixel_color(idx, color);
+        }
+    }
+
+    /// Multiplies the red, green and blue values of all pixels in the local
+    /// buffer by `factor`.
+    ///
+    /// `factor` is specified as a floating point value between `0.0` and `1.0`.
+    /// Calling `fade_all()` repeatedly makes pixels decay towards black, which
+    /// is the basis for comet trails and similar effects.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+    ///
+    /// for head in 0..144 {
+    ///     blinkt.fade_all(0.8);
+    ///     blinkt.set_pixel(head, 255, 255, 255);
+    ///     blinkt.show()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fade_all(&mut self, factor: f32) {
+        self.fade_all_with_floor(factor, 0);
+    }
+
+    /// Multiplies the red, green and blue values of all pixels in the local
+    /// buffer by `factor`, without letting any values drop below `floor`.
+    ///
+    /// Values that are already at or below `floor` are left unchanged.
+    pub fn fade_all_with_floor(&mut self, factor: f32, floor: u8) {
+        for pixel in &mut self.pixels {
+            pixel.fade(factor, floor);
+        }
+
+        #[cfg(feature = "dithering")]
+        {
+            if let Some(ref mut dither) = self.dither {
+                dither.fade(factor, floor);
+            }
+        }
+    }
+
+    /// Blurs all pixels in the local buffer by blending each pixel with its
+    /// neighbors.
+    ///
+    /// `amount` is specified as a floating point value between `0.0` (no
+    /// blur) and `1.0`, and determines the share of each pixel's color that's
+    /// spread out evenly to its neighbors. The first and last pixels are
+    /// treated as their own neighbors beyond the ends of the strip.
+    pub fn blur(&mut self, amount: f32) {
+        frame::blur(&mut self.pixels, amount);
+        self.sync_secondary_buffers();
+    }
+
+    // Copies the color values of all pixels to any secondary buffers.
+    fn sync_secondary_buffers(&mut self) {
+        #[cfg(feature = "dithering")]
+        {
+            if let Some(ref mut dither) = self.dither {
+                for (idx, pixel) in self.pixels.iter().enumerate() {
+                    let (red, green, blue) = pixel.rgb();
+                    dither.set_rgb(idx, red, green, blue);
+                }
+            }
+        }
+    }
+
+    /// Rotates all pixels in the local buffer `n` places towards the first
+    /// pixel. Pixels that move past the first pixel wrap around to the end.
+    ///
+    /// Combined with `show()`, this makes it easy to create chase and marquee
+    /// effects without recalculating every pixel for each frame.
+    pub fn rotate_left(&mut self, n: usize) {
+        frame::rotate_left(&mut self.pixels, n);
+
+        #[cfg(feature = "dithering")]
+        {
+            if let Some(ref mut dither) = self.dither {
+                frame::rotate_left(dither.values_mut(), n);
+            }
+        }
+    }
+
+    /// Rotates all pixels in the local buffer `n` places towards the last
+    /// pixel. Pixels that move past the last pixel wrap around to the start.
+    pub fn rotate_right(&mut self, n: usize) {
+        frame::rotate_right(&mut self.pixels, n);
+
+        #[cfg(feature = "dithering")]
+        {
+            if let Some(ref mut dither) = self.dither {
+                frame::rotate_right(dither.values_mut(), n);
+            }
+        }
+    }
+
+    /// Shifts all pixels in the local buffer `n` places towards the last
+    /// pixel, or towards the first pixel if `n` is negative. Pixels that move
+    /// out of bounds are discarded, and vacated pixels are set to `fill`.
+    ///
+    /// The brightness of vacated pixels is left unchanged.
+    pub fn shift<C: Into<Color>>(&mut self, n: isize, fill: C) {
+        let vacated = frame::shift(&mut self.pixels, n);
+
+        #[cfg(feature = "dithering")]
+        {
+            if let Some(ref mut dither) = self.dither {
+                frame::shift(dither.values_mut(), n);
+            }
+        }
+
+        let fill = fill.into();
+        for idx in vacated {
+            self.set_pixel_color(idx, fill);
+        }
+    }
+
+    /// Sets the red, green and blue values for all pixels to `0`.
+    pub fn clear(&mut self) {
+        self.set_all_pixels(0, 0, 0);
+    }
+
+    /// Sends the contents of the local buffer to the pixels, updating their
+    /// LED colors and brightness.
+    pub fn show(&mut self) -> Result<()> {
+        self.connect()?;
+
+        // Make sure frames handed off by show_nonblocking() are sent first.
+        self.wait_for_vsync()?;
+        self.wait_frame_gap();
+
+        let started = self.begin_show();
+
+        self.encode();
+        self.write_encoded()?;
+
+        self.last_show = Some(Instant::now());
+        self.finish_show(started);
+
+        Ok(())
+    }
+
+    /// Sends the contents of the local buffer to the pixels, unless the
+    /// frame is identical to the one that was sent previously.
+    ///
+    /// Returns `Ok(true)` if the frame was sent, or `Ok(false)` if nothing
+    /// changed since the last transmission. Skipping redundant frames saves
+    /// bandwidth and CPU time for displays that rarely change. Changes are
+    /// detected by comparing the encoded frames, so adjustments applied
+    /// during transmission, such as dithering or pipeline crossfades, are
+    /// taken into account. When a frame is skipped, no callbacks are called.
+    ///
+    /// The first call always sends the frame, and enables change tracking
+    /// for subsequent calls to `show()` and `show_nonblocking()`, which keeps
+    /// a copy of the last frame in memory.
+    pub fn show_changed(&mut self) -> Result<bool> {
+        self.connect()?;
+
+        self.encode();
+        let unchanged = match self.last_frame {
+            Some(ref last_frame) => *last_frame == self.encoded,
+            None => {
+                self.last_frame = Some(Vec::new());
+                false
+            }
+        };
+
+        // Skipping a frame still counts as a call to show() for the watchdog,
+        // unless it already turned off the pixels.
+        if unchanged {
+            match self.refresher {
+                Some(ref refresher) if !refresher.feed() => {}
+                _ => return Ok(false),
+            }
+        }
+
+        self.wait_for_vsync()?;
+        self.wait_frame_gap();
+
+        let started = self.begin_show();
+
+        self.write_encoded()?;
+
+        self.last_show = Some(Instant::now());
+        self.finish_show(started);
+
+        Ok(true)
+    }
+
+    /// Hands off the contents of the local buffer to a background thread,
+    /// which sends it to the pixels, and returns immediately.
+    ///
+    /// `show()` blocks for the duration of the transfer, which can take a
+    /// significant amount of time on long strips. `show_nonblocking()` only
+    /// encodes the frame, so the next frame can be rendered while the
+    /// previous one is still being sent. If the background thread hasn't
+    /// started sending the previously handed off frame yet, that frame is
+    /// replaced by the new one. Call [`wait_for_vsync`] to wait until the
+    /// transfer has finished.
+    ///
+    /// The background thread is started on the first call. Errors that
+    /// occur while sending a frame are returned by the next call to
+    /// `show_nonblocking()`, `wait_for_vsync()` or `show()`. The
+    /// `on_after_show()` callbacks are called once the frame has been handed
+    /// off, rather than after the transfer has finished.
+    ///
+    /// [`wait_for_vsync`]: #method.wait_for_vsync
+    pub fn show_nonblocking(&mut self) -> Result<()> {
+        self.connect()?;
+
+        let started = self.begin_show();
+
+        self.encode();
+
+        if let Some(ref refresher) = self.refresher {
+            refresher.update(&self.encoded);
+        }
+
+        if let Some(ref mut last_frame) = self.last_frame {
+            last_frame.clone_from(&self.encoded);
+        }
+
+        if self.writer.is_none() {
+            self.writer = Some(Writer::new(self.serial_output.clone()));
+        }
+
+        let result = match self.writer {
+            Some(ref writer) => writer.submit(&self.encoded, self.min_frame_gap),
+            None => Ok(()),
+        };
+
+        self.last_show = Some(Instant::now());
+        self.finish_show(started);
+
+        result
+    }
+
+    /// Sends the contents of the local buffer to the pixels without blocking
+    /// the current thread.
+    ///
+    /// The frame is handed off to the same background thread used by
+    /// [`show_nonblocking`], and the returned future completes once it has
+    /// been sent. The future doesn't depend on a specific runtime, so it can
+    /// be awaited from a tokio application without spawning a blocking task.
+    ///
+    /// This method requires the `async` feature, and Rust 1.39 or later.
+    ///
+    /// [`show_nonblocking`]: #method.show_nonblocking
+    #[cfg(feature = "async")]
+    pub async fn show_async(&mut self) -> Result<()> {
+        self.show_nonblocking()?;
+
+        match self.writer {
+            Some(ref writer) => writer.wait_async().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Blocks until all frames handed off through [`show_nonblocking`] have
+    /// been sent to the pixels.
+    ///
+    /// Returns an error if sending a previous frame failed. If
+    /// `show_nonblocking()` hasn't been called, this returns immediately.
+    ///
+    /// [`show_nonblocking`]: #method.show_nonblocking
+    pub fn wait_for_vsync(&mut self) -> Result<()> {
+        match self.writer {
+            Some(ref writer) => writer.wait(),
+            None => Ok(()),
+        }
+    }
+
+    // Gives the pixels enough time to latch the previous frame.
+    fn wait_frame_gap(&self) {
+        if let Some(last_show) = self.last_show {
+            let elapsed = last_show.elapsed();
+            if elapsed < self.min_frame_gap {
+                thread::sleep(self.min_frame_gap - elapsed);
+            }
+        }
+    }
+
+    // Calls the before_show callbacks, and returns the start time.
+    fn begin_show(&mut self) -> Instant {
+        let started = Instant::now();
+        let pixels = match self.front {
+            Some(ref front) => &front.pixels,
+            None => &self.pixels,
+        };
+
+        for callback in &mut self.before_show {
+            callback(&ShowEvent {
+                pixels,
+                frame_number: self.frame_number,
+                started,
+                duration: None,
+            });
+        }
+
+        started
+    }
+
+    // Calls the after_show callbacks, and advances the frame number.
+    fn finish_show(&mut self, started: Instant) {
+        let pixels = match self.front {
+            Some(ref front) => &front.pixels,
+            None => &self.pixels,
+        };
+
+        for callback in &mut self.after_show {
+            callback(&ShowEvent {
+                pixels,
+                frame_number: self.frame_number,
+                started,
+                duration: Some(started.elapsed()),
+            });
+        }
+
+        self.frame_number = self.frame_number.wrapping_add(1);
+
+        self.persist(false);
+    }
+
+    // Sends the encoded frame to the pixels, and keeps a copy when it needs
+    // to be re-transmitted or compared later.
+    fn write_encoded(&mut self) -> Result<()> {
+        let mut output = refresh::lock_output(&self.serial_output);
+        output.write(&self.encoded)?;
+
+        if let Some(ref refresher) = self.refresher {
+            refresher.update(&self.encoded);
+        }
+
+        drop(output);
+
+        if let Some(ref mut last_frame) = self.last_frame {
+            last_frame.clone_from(&self.encoded);
+        }
+
+        Ok(())
+    }
+
+    // Copies packed 8-bit red, green and blue values, as received by the
+    // network and streaming modules, into the local buffer starting at
+    // `offset`. An incomplete pixel at the end is ignored.
+    pub(crate) fn set_pixels_rgb(&mut self, offset: usize, data: &[u8]) {
+        for (pixel, rgb) in data.chunks_exact(3).enumerate() {
+            self.set_pixel(offset + pixel, rgb[0], rgb[1], rgb[2]);
+        }
+    }
+
+    // Copies packed 8-bit red, green and blue values into the local buffer,
+    // and sends it to the pixels.
+    pub(crate) fn show_rgb(&mut self, data: &[u8]) -> Result<()> {
+        self.set_pixels_rgb(0, data);
+        self.swap();
+        self.show()
+    }
+
+    // Sends a frame that was serialized elsewhere, such as by a remote
+    // client, bypassing the local buffer. No callbacks are called.
+    pub(crate) fn show_encoded(&mut self, data: &[u8]) -> Result<()> {
+        self.connect()?;
+
+        self.wait_for_vsync()?;
+        self.wait_frame_gap();
+
+        self.encoded.clear();
+        self.encoded.extend_from_slice(data);
+        self.write_encoded()?;
+
+        self.last_show = Some(Instant::now());
+
+        Ok(())
+    }
+
+    // Makes sure the transmit buffer can hold an entire frame, so encoding
+    // doesn't allocate.
+    fn reserve_encoded(&mut self) {
+        let len = encoded_len(self.pixels.len() + self.leading_pixels);
+        self.encoded.reserve(len.saturating_sub(self.encoded.len()));
+    }
+
+    // Encodes the local buffer, including the start and end frames, into a
+    // single contiguous buffer, so the output receives the entire frame in
+    // one call. The buffer is reused between frames.
+    fn encode(&mut self) {
+        let buffer = &mut self.encoded;
+        buffer.clear();
+
+        buffer.extend_from_slice(&apa102::START_FRAME);
+
+        // Sacrificial pixels are always turned off.
+        for _ in 0..self.leading_pixels {
+            buffer.extend_from_slice(&apa102::OFF_FRAME);
+        }
+
+        self.pipelines.prepare();
+        let apply_pipelines = !self.pipelines.is_empty();
+
+        let pixels = match self.front {
+            Some(FrontBuffer { ref pixels, .. }) => pixels,
+            None => &self.pixels,
+        };
+
+        #[cfg(feature = "dithering")]
+        let mut dither = match self.front {
+            Some(FrontBuffer { ref mut dither, .. }) => dither.as_mut(),
+            None => self.dither.as_mut(),
+        };
+
+        // LED frames (3*1, 5*brightness, 8*blue, 8*green, 8*red).
+        for position in 0..pixels.len() {
+            let idx = match self.pixel_map {
+                Some(ref pixel_map) => match pixel_map.source(position) {
+                    Some(idx) => idx,
+                    None => {
+                        // Unmapped positions are turned off.
+                        buffer.extend_from_slice(Pixel::default().bytes());
+                        continue;
+                    }
+                },
+                None => position,
+            };
+
+            let mut pixel = pixels[idx];
+
+            #[cfg(feature = "dithering")]
+            {
+                if let Some(ref mut dither) = dither {
+                    // Replace the 8-bit color values with the next set of
+                    // temporally dithered values from the 16-bit buffer.
+                    if let Some((red, green, blue)) = dither.next_rgb(idx) {
+                        pixel.set_rgb(red, green, blue);
+                    }
+                }
+            }
+
+            if let Some(ref filter_chain) = self.filter_chain {
+                filter_chain.apply(&mut pixel);
+            }
+
+            if apply_pipelines {
+                self.pipelines.apply(&mut pixel);
+            }
+
+            if let Some(ref transform) = self.color_space_transform {
+                transform.apply(&mut pixel);
+            }
+
+            if let Some(ref curve) = self.gamma_curve {
+                let (red, green, blue) = pixel.rgb();
+                pixel.set_rgb(
+                    curve[red as usize],
+                    curve[green as usize],
+                    curve[blue as usize],
+                );
+            }
+
+            #[cfg(feature = "perceptual")]
+            {
+                if let Some(ref perceptual) = self.perceptual {
+                    perceptual.apply(&mut pixel);
+                }
+            }
+
+            self.color_order.apply(&mut pixel);
+
+            buffer.extend_from_slice(pixel.bytes());
+        }
+
+        // End frame (8*0 for every 16 pixels, 32*0 SK9822 reset frame).
+        // The SK9822 won't update any pixels until it receives the next
+        // start frame (32*0). The APA102 doesn't care if we send zeroes
+        // instead of ones as the end frame. This workaround is
+        // compatible with both the APA102 and SK9822.
+        buffer.extend_from_slice(&self.end_frame);
+    }
+
+    /// Returns the color space used to interpret the color values stored in
+    /// the local buffer.
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Sets the color space used to interpret the color values stored in the
+    /// local buffer.
+    ///
+    /// When set to anything other than `ColorSpace::Srgb`, color values are
+    /// converted to the LEDs' native primaries before they're sent to the
+    /// pixels. The values stored in the local buffer aren't modified.
+    ///
+    /// By default, this is set to `ColorSpace::Srgb`.
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.color_space = color_space;
+        self.color_space_transform = if color_space == ColorSpace::Srgb {
+            None
+        } else {
+            Some(ColorSpaceTransform::new(color_space))
+        };
+    }
+
+    /// Returns the order in which the strip wires its red, green and blue
+    /// channels.
+    pub fn color_order(&self) -> ColorOrder {
+        self.color_order
+    }
+
+    /// Sets the order in which the strip wires its red, green and blue
+    /// channels.
+    ///
+    /// The color values are rearranged to match the strip right before
+    /// they're sent to the pixels. The values stored in the local buffer
+    /// aren't modified.
+    ///
+    /// By default, this is set to `ColorOrder::Rgb`.
+    pub fn set_color_order(&mut self, color_order: ColorOrder) {
+        self.color_order = color_order;
+    }
+
+    /// Returns the gamma exponent applied to the red, green and blue values.
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Sets the gamma exponent applied to the red, green and blue values.
+    ///
+    /// Gamma correction is applied to all pixels after any filters,
+    /// pipelines and color space conversions. The values stored in the local
+    /// buffer aren't modified. Values below 0.1 are clamped to 0.1.
+    ///
+    /// By default, this is set to 1.0, which disables gamma correction.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.gamma = gamma.max(0.1);
+        self.gamma_curve = if self.gamma == 1.0 {
+            None
+        } else {
+            let mut curve = [0u8; 256];
+            for (value, entry) in curve.iter_mut().enumerate() {
+                *entry = ((value as f32 / 255.0).powf(self.gamma) * 255.0).round() as u8;
+            }
+
+            Some(curve)
+        };
+    }
+
+    /// Returns the color filters that are currently active, in the order
+    /// they're applied.
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    /// Adds a color filter to the top of the filter stack.
+    ///
+    /// Filters are applied to all pixels, in the order they were added,
+    /// before the color values are sent to the pixels. The values stored in
+    /// the local buffer aren't modified.
+    pub fn push_filter(&mut self, filter: Filter) {
+        self.filters.push(filter);
+        self.update_filter_chain();
+    }
+
+    /// Removes the most recently added color filter from the filter stack,
+    /// and returns it, or `None` if no filters are active.
+    pub fn pop_filter(&mut self) -> Option<Filter> {
+        let filter = self.filters.pop();
+        self.update_filter_chain();
+
+        filter
+    }
+
+    /// Removes all color filters.
+    pub fn clear_filters(&mut self) {
+        self.filters.clear();
+        self.update_filter_chain();
+    }
+
+    /// Registers an output pipeline under the specified name, replacing any
+    /// pipeline previously registered under the same name.
+    ///
+    /// If the replaced pipeline is currently active, the new pipeline takes
+    /// effect immediately.
+    pub fn add_pipeline(&mut self, name: &str, pipeline: Pipeline) {
+        self.pipelines.add(name, pipeline);
+    }
+
+    /// Removes the output pipeline registered under the specified name, and
+    /// returns it, or `None` if no such pipeline exists.
+    ///
+    /// If the pipeline is currently active, output continues without any
+    /// pipeline.
+    pub fn remove_pipeline(&mut self, name: &str) -> Option<Pipeline> {
+        self.pipelines.remove(name)
+    }
+
+    /// Returns the output pipeline registered under the specified name.
+    pub fn pipeline(&self, name: &str) -> Option<&Pipeline> {
+        self.pipelines.get(name)
+    }
+
+    /// Returns the name of the active output pipeline, or `None` if no
+    /// pipeline is active.
+    pub fn active_pipeline(&self) -> Option<&str> {
+        self.pipelines.active_name()
+    }
+
+    /// Switches to the output pipeline registered under the specified name,
+    /// or disables the active pipeline when `name` is set to `None`.
+    ///
+    /// When `crossfade` is longer than `0`, the output gradually transitions
+    /// from the previous pipeline to the new one. The transition progresses
+    /// every time the local buffer is sent to the pixels, so `show()` needs
+    /// to be called regularly during the crossfade. Any active color
+    /// filters are applied before the pipeline.
+    ///
+    /// Returns `false` if no pipeline is registered under `name`, in which
+    /// case the active pipeline remains unchanged.
+    pub fn switch_pipeline(&mut self, name: Option<&str>, crossfade: Duration) -> bool {
+        self.pipelines.switch(name, crossfade)
+    }
+
+    fn update_filter_chain(&mut self) {
+        self.filter_chain = if self.filters.is_empty() {
+            None
+        } else {
+            Some(FilterChain::new(&self.filters))
+        };
+    }
+
+    /// Sets a function that translates pixel indices to physical positions
+    /// on the strip.
+    ///
+    /// `map` is called with the index of each pixel in the local buffer, and
+    /// returns the position of that pixel on the strip, where the first
+    /// pixel is at position `0`. This covers unusual wiring, such as rings
+    /// wired in a zigzag pattern, split runs, or skipped dead pixels, without
+    /// translating the indices for every setter.
+    ///
+    /// The mapping is applied when the local buffer is sent to the pixels.
+    /// Positions beyond the end of the strip are ignored. Positions that no
+    /// pixel maps to are turned off. When multiple pixels map to the same
+    /// position, the pixel with the highest index is sent.
+    ///
+    /// `map` is only called when the pixel map is set, or when the number of
+    /// pixels changes.
+    pub fn set_pixel_map<F>(&mut self, map: F)
+    where
+        F: Fn(usize) -> usize + Send + 'static,
+    {
+        self.pixel_map = Some(PixelMap::new(Box::new(map), self.pixels.len()));
+    }
+
+    /// Removes the pixel map set through [`set_pixel_map`], so each pixel is
+    /// sent to the physical position matching its index.
+    ///
+    /// [`set_pixel_map`]: #method.set_pixel_map
+    pub fn clear_pixel_map(&mut self) {
+        self.pixel_map = None;
+    }
+
+    /// Returns `true` if double buffering is enabled.
+    pub fn double_buffering(&self) -> bool {
+        self.front.is_some()
+    }
+
+    /// Enables or disables double buffering.
+    ///
+    /// When double buffering is enabled, the local buffer acts as a back
+    /// buffer. All setters continue to update the local buffer, but `show()`
+    /// sends a separate front buffer to the pixels, which only changes when
+    /// [`swap()`] is called. This prevents partially updated frames from
+    /// being displayed when multiple threads update the pixels, for instance
+    /// an animation thread and a control thread that share a `Blinkt`
+    /// through an `Arc<Mutex<Blinkt>>`.
+    ///
+    /// Enabling double buffering copies the current contents of the local
+    /// buffer to the front buffer. Helpers that render complete frames, such
+    /// as [`Animator`] and [`transition_to()`], call `swap()` themselves
+    /// before each frame is sent. Custom shutdown animations need to call
+    /// `swap()` explicitly.
+    ///
+    /// By default, double buffering is disabled.
+    ///
+    /// [`swap()`]: #method.swap
+    /// [`Animator`]: animation/struct.Animator.html
+    /// [`transition_to()`]: #method.transition_to
+    pub fn set_double_buffering(&mut self, double_buffering: bool) {
+        if !double_buffering {
+            self.front = None;
+        } else if self.front.is_none() {
+            self.front = Some(FrontBuffer {
+                pixels: self.pixels.clone(),
+                #[cfg(feature = "dithering")]
+                dither: self.dither.clone(),
+            });
+        }
+    }
+
+    /// Copies the contents of the local buffer to the front buffer, so
+    /// they're sent to the pixels on the next call to `show()`.
+    ///
+    /// The local buffer keeps its contents, so the next frame can be built
+    /// by updating only the pixels that change. Does nothing if double
+    /// buffering is disabled.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::new()?;
+    /// blinkt.set_double_buffering(true);
+    ///
+    /// blinkt.set_all_pixels(255, 0, 0);
+    /// blinkt.set_pixel(0, 0, 0, 255);
+    ///
+    /// blinkt.swap();
+    /// blinkt.show()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn swap(&mut self) {
+        if let Some(ref mut front) = self.front {
+            front.pixels.clone_from(&self.pixels);
+
+            #[cfg(feature = "dithering")]
+            {
+                match (&mut front.dither, &self.dither) {
+                    (Some(front), Some(back)) => front.copy_values_from(back),
+                    (front, back) => front.clone_from(back),
+                }
+            }
+        }
+    }
+
+    /// Returns the number of pixels in the local buffer.
+    pub fn num_pixels(&self) -> usize {
+        self.pixels.len()
+    }
+
+    /// Changes the number of pixels.
+    ///
+    /// Pixels are added or removed at the end of the local buffer. Added
+    /// pixels are turned off. This allows a `Blinkt` to be created before the
+    /// length of the strip is known, for instance with `0` pixels, and
+    /// resized once the length has been determined, or when a strip is
+    /// swapped for one with a different length. Any callbacks registered
+    /// through [`on_resize`] are called when the number of pixels changes.
+    ///
+    /// Pixels that are removed aren't turned off automatically. Call
+    /// `clear()` and `show()` first if they're still connected.
+    ///
+    /// [`on_resize`]: #method.on_resize
+    pub fn resize(&mut self, num_pixels: usize) {
+        let old_len = self.pixels.len();
+        if old_len == num_pixels {
+            return;
+        }
+
+        self.pixels.resize(num_pixels, Pixel::default());
+        if let Some(ref mut front) = self.front {
+            front.pixels.resize(num_pixels, Pixel::default());
+
+            #[cfg(feature = "dithering")]
+            {
+                if let Some(ref mut dither) = front.dither {
+                    dither.resize(num_pixels);
+                }
+            }
+        }
+
+        self.end_frame = vec![0u8; end_frame_len(num_pixels + self.leading_pixels)];
+        self.reserve_encoded();
+        self.update_panic_hook();
+
+        if let Some(ref mut pixel_map) = self.pixel_map {
+            pixel_map.resize(num_pixels);
+        }
+
+        #[cfg(feature = "dithering")]
+        {
+            if let Some(ref mut dither) = self.dither {
+                dither.resize(num_pixels);
+            }
+        }
+
+        let event = ResizeEvent {
+            old_len,
+            new_len: num_pixels,
+        };
+
+        for callback in &mut self.on_resize {
+            callback(&event);
+        }
+    }
+
+    /// Registers a callback that's called by `resize()` after the number of
+    /// pixels has changed.
+    ///
+    /// Callbacks receive a [`ResizeEvent`], which contains the number of
+    /// pixels before and after the change.
+    ///
+    /// [`ResizeEvent`]: struct.ResizeEvent.html
+    pub fn on_resize<F>(&mut self, callback: F)
+    where
+        F: FnMut(&ResizeEvent) + Send + 'static,
+    {
+        self.on_resize.push(Box::new(callback));
+    }
+
+    /// Returns the number of sacrificial pixels that are sent before the
+    /// first pixel.
+    pub fn leading_pixels(&self) -> usize {
+        self.leading_pixels
+    }
+
+    /// Sets the number of sacrificial pixels that are sent before the first
+    /// pixel.
+    ///
+    /// Some LED strips interpret the start frame differently, which causes
+    /// the first pixel to light up brightly at random. Physically skipping
+    /// those pixels by leaving them dark hides the artifact. Sacrificial
+    /// pixels are always turned off, and aren't part of the local buffer, so
+    /// pixel indices used by your application remain the same.
+    ///
+    /// By default, this is set to `0`.
+    pub fn set_leading_pixels(&mut self, leading_pixels: usize) {
+        self.leading_pixels = leading_pixels;
+        self.end_frame = vec![0u8; end_frame_len(self.pixels.len() + leading_pixels)];
+        self.reserve_encoded();
+        self.update_panic_hook();
+    }
+
+    /// Registers a callback that's called by `show()` right before the local
+    /// buffer is sent to the pixels.
+    ///
+    /// Callbacks receive a [`ShowEvent`], which provides read-only access to
+    /// the local buffer, and timing information. Callbacks should return
+    /// quickly, since they delay the transmission.
+    ///
+    /// [`ShowEvent`]: struct.ShowEvent.html
+    pub fn on_before_show<F>(&mut self, callback: F)
+    where
+        F: FnMut(&ShowEvent<'_>) + Send + 'static,
+    {
+        self.before_show.push(Box::new(callback));
+    }
+
+    /// Registers a callback that's called by `show()` after the local buffer
+    /// has been sent to the pixels successfully.
+    ///
+    /// Callbacks receive a [`ShowEvent`], which provides read-only access to
+    /// the local buffer, and timing information including the duration of
+    /// the transmission.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+    ///
+    /// blinkt.on_after_show(|event| {
+    ///     if let Some(duration) = event.duration() {
+    ///         println!("Frame {} took {:?}", event.frame_number(), duration);
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ShowEvent`]: struct.ShowEvent.html
+    pub fn on_after_show<F>(&mut self, callback: F)
+    where
+        F: FnMut(&ShowEvent<'_>) + Send + 'static,
+    {
+        self.after_show.push(Box::new(callback));
+    }
+
+    /// Mirrors the output to a second `Blinkt`.
+    ///
+    /// After each frame is sent to the pixels, a copy of the local buffer is
+    /// passed to `transform`, which can modify it, for instance by reversing,
+    /// dimming or color-shifting the pixels. The result is copied to the
+    /// local buffer of `destination`, and sent to its pixels. This allows a
+    /// second strip to display a transformed copy of the first one, without
+    /// rendering every frame twice.
+    ///
+    /// Errors returned while sending a frame to `destination` are ignored.
+    /// The mirror is implemented as a callback, and is removed by
+    /// `clear_show_callbacks()`.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// # use std::sync::{Arc, Mutex};
+    /// #
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::with_spi(16_000_000, 60)?;
+    /// let mirror = Arc::new(Mutex::new(Blinkt::with_settings(5, 6, 60)?));
+    ///
+    /// // Show a reversed copy at half the intensity.
+    /// blinkt.mirror_output(mirror.clone(), |frame| {
+    ///     frame.reverse();
+    ///     frame.fade_all(0.5);
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn mirror_output<F>(&mut self, destination: Arc<Mutex<Blinkt>>, mut transform: F)
+    where
+        F: FnMut(&mut Frame) + Send + 'static,
+    {
+        self.on_after_show(move |event| {
+            let mut frame = Frame::from_pixels(event.pixels());
+            transform(&mut frame);
+
+            let mut destination = animation::lock(&destination);
+            destination.set_frame(&frame);
+            destination.swap();
+            let _ = destination.show();
+        });
+    }
+
+    /// Records every frame sent to the pixels to a recording file at `path`.
+    ///
+    /// Each line of the file contains the time at which the frame was sent,
+    /// followed by the color and brightness values of the local buffer. Use
+    /// [`Recording`] to replay the frames onto any `Blinkt` later. An
+    /// existing file at `path` is overwritten.
+    ///
+    /// Errors returned while writing a frame to the file are ignored. The
+    /// recorder is implemented as a callback, and is removed by
+    /// `clear_show_callbacks()`.
+    ///
+    /// [`Recording`]: recording/struct.Recording.html
+    pub fn record<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let mut recorder = Recorder::create(path.as_ref())?;
+        self.on_after_show(move |event| {
+            let _ = recorder.record(event);
+        });
+
+        Ok(())
+    }
+
+    /// Records every frame sent to the pixels to an animated GIF at `path`.
+    ///
+    /// Each pixel is rendered as a 16x16 square in the same way as
+    /// [`save_snapshot`], and each frame is shown for the time until the
+    /// next frame was sent. Frames sent less than 20 ms apart are merged,
+    /// since most viewers don't support shorter delays. The last frame is
+    /// shown for one second before the animation loops. The image width is
+    /// based on the current number of pixels. An existing file at `path` is
+    /// overwritten.
+    ///
+    /// The GIF is completed when the recorder is removed, either by
+    /// `clear_show_callbacks()` or when `Blinkt` goes out of scope. In the
+    /// latter case, the final frame sent by [`set_clear_on_drop`] is
+    /// included. Errors returned while writing a frame are ignored.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::capture::Capture;
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// // Render the effect without any hardware.
+    /// let mut blinkt = Blinkt::with_output(Capture::new(), 16);
+    /// blinkt.record_gif("rainbow.gif")?;
+    ///
+    /// // Run the effect ...
+    ///
+    /// blinkt.clear_show_callbacks();
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`save_snapshot`]: #method.save_snapshot
+    /// [`set_clear_on_drop`]: #method.set_clear_on_drop
+    pub fn record_gif<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let mut recorder = GifRecorder::create(path.as_ref(), self.pixels.len())?;
+        self.on_after_show(move |event| {
+            let _ = recorder.record(event);
+        });
+
+        Ok(())
+    }
+
+    /// Removes all callbacks registered through `on_before_show()` and
+    /// `on_after_show()`.
+    pub fn clear_show_callbacks(&mut self) {
+        self.before_show.clear();
+        self.after_show.clear();
+    }
+
+    /// Returns the minimum amount of time between consecutive transmissions.
+    pub fn min_frame_gap(&self) -> Duration {
+        self.min_frame_gap
+    }
+
+    /// Sets the minimum amount of time between consecutive transmissions.
+    ///
+    /// Some APA102 and SK9822 clones need a short quiet period after each
+    /// frame to latch the new values, and drop updates when frames are sent
+    /// back-to-back. If the previous call to `show()` finished less than
+    /// `min_frame_gap` ago, `show()` sleeps for the remaining time before
+    /// sending the next frame.
+    ///
+    /// By default, this is set to `0`.
+    pub fn set_min_frame_gap(&mut self, min_frame_gap: Duration) {
+        self.min_frame_gap = min_frame_gap;
+    }
+
+    /// Returns the target frame rate used by `tick()` and `show_at_fps()`,
+    /// or `0.0` if the frame rate isn't limited.
+    pub fn target_fps(&self) -> f32 {
+        match self.target_frame_interval {
+            Some(interval) => 1.0 / animation::as_secs_f32(interval),
+            None => 0.0,
+        }
+    }
+
+    /// Sets the target frame rate used by [`tick()`] and [`show_at_fps()`].
+    ///
+    /// `fps` is limited to the range `0.1` to `1000.0`. Setting `fps` to `0.0`
+    /// disables the frame rate limiter.
+    ///
+    /// By default, the frame rate isn't limited.
+    ///
+    /// [`tick()`]: #method.tick
+    /// [`show_at_fps()`]: #method.show_at_fps
+    pub fn set_target_fps(&mut self, fps: f32) {
+        self.target_frame_interval = if fps > 0.0 {
+            let fps = fps.max(0.1).min(1000.0);
+            Some(Duration::from_micros((1_000_000.0 / fps) as u64))
+        } else {
+            None
+        };
+
+        self.next_tick = None;
+    }
+
+    /// Sleeps until the next frame is due, based on the target frame rate.
+    ///
+    /// Frames are scheduled at fixed intervals rather than relative to the
+    /// previous call, so the time spent rendering and sending a frame doesn't
+    /// affect the frame rate, as long as it's shorter than the frame
+    /// interval. If a loop falls behind by more than a frame, the missed
+    /// frames are skipped instead of being rushed out back-to-back.
+    ///
+    /// Returns immediately if the target frame rate hasn't been set through
+    /// [`set_target_fps()`].
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::{Blinkt, Color};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::new()?;
+    /// blinkt.set_target_fps(60.0);
+    ///
+    /// let mut hue = 0.0;
+    /// loop {
+    ///     hue = (hue + 1.0) % 360.0;
+    ///     blinkt.set_all_pixels_color(Color::from_hsv(hue, 1.0, 1.0));
+    ///
+    ///     blinkt.tick();
+    ///     blinkt.show()?;
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`set_target_fps()`]: #method.set_target_fps
+    pub fn tick(&mut self) {
+        let interval = match self.target_frame_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        let now = Instant::now();
+        let deadline = self.next_tick.unwrap_or(now);
+        if deadline > now {
+            thread::sleep(deadline - now);
+        }
+
+        let next_tick = deadline + interval;
+        self.next_tick = Some(if next_tick < now {
+            now + interval
+        } else {
+            next_tick
+        });
+    }
+
+    /// Waits for the next frame through [`tick()`], and sends the contents
+    /// of the local buffer to the pixels.
+    ///
+    /// [`tick()`]: #method.tick
+    pub fn show_at_fps(&mut self) -> Result<()> {
+        self.tick();
+        self.show()
+    }
+
+    /// Returns the interval at which the last frame is re-transmitted, or
+    /// `None` if persistent display mode is disabled.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        self.refresher
+            .as_ref()
+            .and_then(|refresher| refresher.interval())
+    }
+
+    /// Enables or disables persistent display mode.
+    ///
+    /// When set to `Some(interval)`, a background thread re-transmits the last
+    /// frame sent by `show()` whenever no new frame has been sent for
+    /// `interval`. This automatically restores the pixels on strips that lose
+    /// their state after a brief power interruption, which can happen on long
+    /// cable runs. Errors during re-transmission are ignored, and the frame
+    /// is sent again after the next interval.
+    ///
+    /// Setting `refresh_interval` to `None` stops the background thread.
+    ///
+    /// By default, this is set to `None`.
+    pub fn set_refresh_interval(&mut self, refresh_interval: Option<Duration>) {
+        let watchdog_timeout = self.watchdog_timeout();
+        self.update_refresher(refresh_interval, watchdog_timeout);
+    }
+
+    /// Returns the watchdog timeout, or `None` if the watchdog is disabled.
+    pub fn watchdog_timeout(&self) -> Option<Duration> {
+        self.refresher
+            .as_ref()
+            .and_then(|refresher| refresher.timeout())
+    }
+
+    /// Enables or disables the watchdog.
+    ///
+    /// When set to `Some(timeout)`, a background thread turns off all pixels
+    /// if no frame has been sent through `show()` or one of its variants for
+    /// `timeout`. This prevents unattended installations from displaying a
+    /// frozen frame indefinitely when the application hangs, deadlocks, or a
+    /// controller thread crashes. The pixels are turned back on by the next
+    /// call to `show()`. When persistent display mode is enabled, the
+    /// turned off frame is re-transmitted instead of the last frame.
+    ///
+    /// The watchdog only starts counting down after the first frame has been
+    /// sent. Setting `watchdog_timeout` to `None` disables the watchdog.
+    ///
+    /// By default, this is set to `None`.
+    pub fn set_watchdog_timeout(&mut self, watchdog_timeout: Option<Duration>) {
+        let refresh_interval = self.refresh_interval();
+        self.update_refresher(refresh_interval, watchdog_timeout);
+    }
+
+    // Starts, updates or stops the background thread used by persistent
+    // display mode and the watchdog.
+    fn update_refresher(&mut self, interval: Option<Duration>, timeout: Option<Duration>) {
+        if interval.is_none() && timeout.is_none() {
+            self.refresher = None;
+        } else if let Some(ref refresher) = self.refresher {
+            refresher.set_interval(interval);
+            if refresher.timeout() != timeout {
+                refresher.set_timeout(timeout);
+            }
+        } else {
+            self.refresher = Some(Refresher::new(
+                self.serial_output.clone(),
+                interval,
+                timeout,
+            ));
+        }
+    }
+
+    /// Saves the contents of the local buffer to `path`.
+    ///
+    /// The state is stored as a text file, with one line per pixel containing
+    /// the red, green, blue and 5-bit brightness values as hexadecimal bytes.
+    /// The file is written to a temporary file first, and then renamed, so
+    /// an existing file isn't corrupted when power is lost halfway through.
+    pub fn save_state<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_state(path.as_ref(), &state_text(&self.pixels))
+    }
+
+    /// Restores the contents of the local buffer from a file created by
+    /// [`save_state`].
+    ///
+    /// If the file contains fewer pixels than the local buffer, the remaining
+    /// pixels are left unchanged. Any additional pixels in the file are
+    /// ignored. Call `show()` to send the restored state to the pixels.
+    ///
+    /// [`save_state`]: #method.save_state
+    pub fn restore_state<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let bytes = capture::load_hex(path)?;
+        if bytes.len() % 4 != 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "state file contains an incomplete pixel",
+            )));
+        }
+
+        for (idx, values) in bytes.chunks(4).enumerate().take(self.pixels.len()) {
+            let mut pixel = Pixel::default();
+            pixel.set_rgb(values[0], values[1], values[2]);
+            pixel.set_brightness_raw(values[3]);
+
+            self.replace_pixel(idx, pixel);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the path the pixel state is automatically saved to, or `None`
+    /// if automatic saving is disabled.
+    pub fn auto_persist(&self) -> Option<&Path> {
+        self.persist_path.as_ref().map(AsRef::as_ref)
+    }
+
+    /// Enables or disables automatically saving the pixel state.
+    ///
+    /// When set to `Some(path)`, the frame that was sent to the pixels is
+    /// saved to `path` through [`save_state`] whenever it changes, at most
+    /// once per second, and once more when `Blinkt` is dropped or shut down.
+    /// Combined with [`restore_state`] at startup, this allows a lamp to come
+    /// back at its previous color after a reboot. Errors that occur while
+    /// saving the state are ignored.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::new()?;
+    ///
+    /// // The state file doesn't exist on the first run.
+    /// let _ = blinkt.restore_state("/var/lib/lamp/state");
+    /// blinkt.set_auto_persist(Some("/var/lib/lamp/state".into()));
+    /// blinkt.show()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// By default, this is set to `None`.
+    ///
+    /// [`save_state`]: #method.save_state
+    /// [`restore_state`]: #method.restore_state
+    pub fn set_auto_persist(&mut self, path: Option<PathBuf>) {
+        self.persist_path = path;
+        self.persisted_state = None;
+    }
+
+    // Saves the pixel state if automatic saving is enabled and the state has
+    // changed. Unless force is set, the state is saved at most once every
+    // PERSIST_INTERVAL_SECS.
+    fn persist(&mut self, force: bool) {
+        let path = match self.persist_path {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        if let Some((_, saved)) = self.persisted_state {
+            if !force && saved.elapsed() < Duration::from_secs(PERSIST_INTERVAL_SECS) {
+                return;
+            }
+        }
+
+        let pixels = match self.front {
+            Some(ref front) => &front.pixels,
+            None => &self.pixels,
+        };
+
+        let text = state_text(pixels);
+        match self.persisted_state {
+            Some((ref saved, _)) if *saved == text => return,
+            _ => {}
+        }
+
+        if write_state(path, &text).is_ok() {
+            self.persisted_state = Some((text, Instant::now()));
+        }
+    }
+
+    /// Saves a PNG image of the local buffer to `path`.
+    ///
+    /// Each pixel is rendered as a 16x16 square, with its red, green and blue
+    /// values scaled by its brightness, to approximate what the LEDs look
+    /// like. Note that the local buffer may contain changes that haven't
+    /// been sent to the pixels yet through `show()`.
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        write_snapshot(path.as_ref(), &self.pixels)
+    }
+
+    /// Saves a PNG image of every frame sent to the pixels to the directory
+    /// at `dir`.
+    ///
+    /// Frames are rendered in the same way as [`save_snapshot`], and named
+    /// after their sequence number since this method was called, starting at
+    /// `000000.png`. The directory is created if it doesn't exist, and
+    /// existing images are overwritten. Combined with deterministic effect
+    /// code, such as animations driven by a fixed random seed, the images
+    /// can be compared against known good images in regression tests.
+    ///
+    /// Errors returned while writing an image are ignored. The dump is
+    /// implemented as a callback, and is removed by
+    /// `clear_show_callbacks()`.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::capture::Capture;
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::with_output(Capture::new(), 16);
+    /// blinkt.dump_frames("target/frames")?;
+    ///
+    /// for step in 0..16 {
+    ///     blinkt.clear();
+    ///     blinkt.set_pixel(step, 255, 0, 0);
+    ///     blinkt.show()?;
+    /// }
+    ///
+    /// // Compare target/frames/000000.png through 000015.png against the
+    /// // known good images.
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`save_snapshot`]: #method.save_snapshot
+    pub fn dump_frames<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut sequence: u64 = 0;
+        self.on_after_show(move |event| {
+            let path = dir.join(format!("{:06}.png", sequence));
+            let _ = write_snapshot(&path, event.pixels());
+            sequence += 1;
+        });
+
+        Ok(())
+    }
+
+    /// Streams every frame sent to the pixels to web browsers, for remote
+    /// monitoring.
+    ///
+    /// Starts an HTTP server on `addr` in the background, and returns the
+    /// address it's listening on. Opening the server's address in a browser
+    /// shows a live virtual copy of the pixels. The frames are sent over a
+    /// WebSocket as binary messages with four bytes per pixel: red, green,
+    /// blue and the brightness scaled to `0`-`255`, which makes it easy to
+    /// build custom viewers. Clients that can't keep up skip frames.
+    ///
+    /// The preview is implemented as a callback. The server stops, and all
+    /// clients are disconnected, when the callback is removed by
+    /// `clear_show_callbacks()`.
+    ///
+    /// This method requires the `websocket` feature.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// #
+    /// # use blinkt::Blinkt;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut blinkt = Blinkt::new()?;
+    /// let addr = blinkt.serve_preview("0.0.0.0:8081")?;
+    ///
+    /// println!("Preview available at http://{}/", addr);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "websocket")]
+    pub fn serve_preview<A: ToSocketAddrs>(&mut self, addr: A) -> Result<SocketAddr> {
+        let server = PreviewServer::spawn(addr)?;
+        let local_addr = server.local_addr();
+        self.on_after_show(move |event| server.publish(event));
+
+        Ok(local_addr)
+    }
+
+    /// Returns `true` if high resolution mode is enabled.
+    #[cfg(feature = "dithering")]
+    pub fn high_resolution(&self) -> bool {
+        self.dither.is_some()
+    }
+
+    /// When enabled, stores red, green and blue values with 16-bit precision,
+    /// and applies temporal dithering to simulate the additional color depth.
+    ///
+    /// By default, this is set to `false`.
+    ///
+    /// Temporal dithering rapidly alternates between the nearest 8-bit values
+    /// on successive calls to `show()`, which smooths out visible steps in
+    /// slow fades at low brightness levels. For the effect to be
+    /// convincing, `show()` should be called at a high, steady rate.
+    ///
+    /// When high resolution mode is enabled, the 16-bit buffer is initialized
+    /// with the current color values. Changes made through `iter_mut()`
+    /// aren't reflected in the 16-bit buffer. Use the `set_` methods instead.
+    ///
+    /// Only available when the `dithering` feature is enabled (default).
+    #[cfg(feature = "dithering")]
+    pub fn set_high_resolution(&mut self, high_resolution: bool) {
+        if high_resolution {
+            if self.dither.is_none() {
+                self.dither = Some(Dither::new(&self.pixels));
+            }
+        } else {
+            self.dither = None;
+        }
+    }
+
+    /// Returns `true` if perceptual brightness mapping is enabled.
+    #[cfg(feature = "perceptual")]
+    pub fn perceptual_brightness(&self) -> bool {
+        self.perceptual.is_some()
+    }
+
+    /// When enabled, maps brightness values to perceived lightness using the
+    /// CIE 1931 lightness formula before sending them to the pixels.
+    ///
+    /// By default, this is set to `false`, and brightness values are converted
+    /// linearly, which causes a brightness of `0.5` to appear considerably
+    /// brighter than half.
+    ///
+    /// Because the 5-bit brightness value is too coarse for the low end of the
+    /// curve, the red, green and blue values are scaled down as well to reach
+    /// the intended luminance. The values stored in the local buffer aren't
+    /// modified.
+    ///
+    /// Only available when the `perceptual` feature is enabled (default).
+    #[cfg(feature = "perceptual")]
+    pub fn set_perceptual_brightness(&mut self, perceptual_brightness: bool) {
+        if perceptual_brightness {
+            if self.perceptual.is_none() {
+                self.perceptual = Some(PerceptualCurve::new());
+            }
+        } else {
+            self.perceptual = None;
+        }
+    }
+
+    /// Sets an animation that's played when `shutdown()` is called, before
+    /// all pixels are cleared.
+    ///
+    /// `animation` is called once, and is expected to update the local buffer
+    /// and call `show()` as many times as needed before returning.
+    pub fn set_shutdown_animation<F>(&mut self, animation: F)
+    where
+        F: FnMut(&mut Blinkt) -> Result<()> + Send + 'static,
+    {
+        self.shutdown_animation = Some(Box::new(animation));
+    }
+
+    /// Shuts down `Blinkt` in an orderly fashion.
+    ///
+    /// `shutdown()` plays the shutdown animation if one was set through
+    /// [`set_shutdown_animation`], clears all pixels, and releases the GPIO or
+    /// SPI peripheral. Unlike the automatic cleanup when `Blinkt` goes out of
+    /// scope, any errors that occur during shutdown are returned, which
+    /// allows long-running services to report a clean exit.
+    ///
+    /// Pixels are cleared regardless of the value of `clear_on_drop`, and
+    /// fade out first if a fade duration was set through
+    /// [`set_clear_on_drop_fade`].
+    ///
+    /// [`set_shutdown_animation`]: #method.set_shutdown_animation
+    /// [`set_clear_on_drop_fade`]: #method.set_clear_on_drop_fade
+    pub fn shutdown(mut self) -> Result<()> {
+        // Make sure Drop doesn't send another frame
+        self.clear_on_drop = false;
+
+        // Save the last state before the pixels are cleared, and make sure
+        // the cleared state isn't saved.
+        self.persist(true);
+        self.persist_path = None;
+
+        if let Some(mut animation) = self.shutdown_animation.take() {
+            animation(&mut self)?;
+        }
+
+        self.fade_out()?;
+
+        self.clear();
+        self.swap();
+        self.show()
+    }
+
+    /// Returns the value of `clear_on_drop`.
+    pub fn clear_on_drop(&self) -> bool {
+        self.clear_on_drop
+    }
+
+    /// When enabled, clears all pixels when `Blinkt` goes out of scope.
+    ///
+    /// By default, this is set to `true`.
+    ///
+    /// ## Note
+    ///
+    /// Drop methods aren't called when a process is abnormally terminated, for
+    /// instance when a user presses <kbd>Ctrl</kbd> + <kbd>C</kbd>, and the `SIGINT` signal
+    /// isn't caught. You can catch those using crates such as [`simple_signal`],
+    /// or through [`signals::clear_on_signal`] when the `signals` feature is
+    /// enabled.
+    ///
+    /// [`simple_signal`]: https://crates.io/crates/simple-signal
+    /// [`signals::clear_on_signal`]: signals/fn.clear_on_signal.html
+    pub fn set_clear_on_drop(&mut self, clear_on_drop: bool) {
+        self.clear_on_drop = clear_on_drop;
+    }
+
+    /// Returns the duration of the fade to black when pixels are cleared on
+    /// drop or shutdown.
+    pub fn clear_on_drop_fade(&self) -> Duration {
+        self.clear_on_drop_fade
+    }
+
+    /// Sets the duration of the fade to black when pixels are cleared because
+    /// `Blinkt` goes out of scope, or when `shutdown()` is called.
+    ///
+    /// Instead of turning off instantly, the pixels fade out smoothly over
+    /// `fade`, which is more pleasant for ambient lighting. Dropping or
+    /// shutting down `Blinkt` blocks until the fade has finished.
+    ///
+    /// By default, this is set to `0`, and pixels are cleared instantly.
+    pub fn set_clear_on_drop_fade(&mut self, fade: Duration) {
+        self.clear_on_drop_fade = fade;
+    }
+
+    // Clears all pixels, and releases the peripheral. Used when the process
+    // is about to exit without running destructors.
+    #[cfg(feature = "signals")]
+    pub(crate) fn release(&mut self) {
+        if self.is_connected() {
+            let _ = self.fade_out();
+
+            self.clear();
+            self.swap();
+            let _ = self.show();
+        }
+
+        // Stop the background threads before dropping the output, so they
+        // can't reconfigure the pins.
+        self.refresher = None;
+        self.writer = None;
+        *refresh::lock_output(&self.serial_output) = Box::new(Disconnected);
+    }
+
+    /// Installs a panic hook that turns off all pixels when any thread
+    /// panics.
+    ///
+    /// `Drop` isn't always reached after a panic, for instance when another
+    /// thread panics while holding a lock on `Blinkt`, or when the process
+    /// is built with `panic = "abort"`. A frozen frame at full brightness can
+    /// draw a lot of power and generate a lot of heat, so the hook sends a
+    /// frame that turns off all pixels directly to the output, without
+    /// locking `Blinkt`. Afterwards, the GPIO or SPI peripheral is released,
+    /// and any subsequent calls to `show()` return an error. The previously
+    /// installed panic hook is called afterwards, so panic messages are still
+    /// printed.
+    ///
+    /// The hook is triggered by every panic in the process, including
+    /// panics that are caught through `std::panic::catch_unwind`.
+    ///
+    /// The hook is only installed once for each `Blinkt`, so calling this
+    /// method again has no effect. It only holds a weak reference to the
+    /// output, and does nothing once `Blinkt` is dropped, so the output is
+    /// closed as usual and later panics can't affect pixels that are driven
+    /// by another `Blinkt` by then.
+    pub fn install_panic_hook(&mut self) {
+        if self.panic_hook_pixels.is_some() {
+            return;
+        }
+
+        let num_pixels = Arc::new(AtomicUsize::new(self.pixels.len() + self.leading_pixels));
+        self.panic_hook_pixels = Some(num_pixels.clone());
+
+        let output = Arc::downgrade(&self.serial_output);
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if let Some(output) = output.upgrade() {
+                blank_output(&output, num_pixels.load(Ordering::SeqCst));
+            }
+            previous(info);
+        }));
+    }
+
+    // Keeps the number of pixels used by the panic hook up to date.
+    fn update_panic_hook(&self) {
+        if let Some(ref num_pixels) = self.panic_hook_pixels {
+            num_pixels.store(self.pixels.len() + self.leading_pixels, Ordering::SeqCst);
+        }
+    }
+
+    // Fades all pixels to black over clear_on_drop_fade.
+    fn fade_out(&mut self) -> Result<()> {
+        if self.clear_on_drop_fade == Duration::from_secs(0) {
+            return Ok(());
+        }
+
+        let mut black = self.frame();
+        black.clear();
+
+        self.transition_to(&black, self.clear_on_drop_fade, Easing::EaseOutQuad)
+    }
+}
+
+impl Drop for Blinkt {
+    /// Clears all pixels if [`clear_on_drop`] is set to `true` (default),
+    /// fading out first if [`set_clear_on_drop_fade`] was used.
+    ///
+    /// Nothing is sent if the peripheral was never configured.
+    ///
+    /// [`clear_on_drop`]: #method.clear_on_drop
+    /// [`set_clear_on_drop_fade`]: #method.set_clear_on_drop_fade
+    fn drop(&mut self) {
+        // Save the last state before the pixels are cleared, and make sure
+        // the cleared state isn't saved.
+        self.persist(true);
+        self.persist_path = None;
+
+        if self.clear_on_drop && self.is_connected() {
+            let _ = self.fade_out();
+
+            self.clear();
+            self.swap();
+            let _ = self.show();
+        }
+    }
+}
+
+// Renders each pixel as a square of SNAPSHOT_CELL_SIZE by SNAPSHOT_CELL_SIZE
+// image pixels, with its color values scaled by its brightness. The image is
+// exactly `width` pixels wide. Missing pixels are rendered as black.
+pub(crate) fn snapshot_rgb(pixels: &[Pixel], width: usize) -> Vec<u8> {
+    let mut row = Vec::with_capacity(width * SNAPSHOT_CELL_SIZE * 3);
+    for idx in 0..width {
+        let (red, green, blue) = match pixels.get(idx) {
+            Some(pixel) => pixel.displayed_rgb(),
+            None => (0, 0, 0),
+        };
+
+        for _ in 0..SNAPSHOT_CELL_SIZE {
+            row.extend_from_slice(&[red, green, blue]);
+        }
+    }
+
+    let mut rgb = Vec::with_capacity(row.len() * SNAPSHOT_CELL_SIZE);
+    for _ in 0..SNAPSHOT_CELL_SIZE {
+        rgb.extend_from_slice(&row);
+    }
+
+    rgb
+}
+
+fn write_snapshot(path: &Path, pixels: &[Pixel]) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_snapshot_png(&mut writer, pixels)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+// Writes a PNG image of `pixels`, rendered by snapshot_rgb(), to `writer`.
+pub(crate) fn write_snapshot_png<W: Write>(writer: &mut W, pixels: &[Pixel]) -> io::Result<()> {
+    let width = pixels.len() * SNAPSHOT_CELL_SIZE;
+    let rgb = snapshot_rgb(pixels, pixels.len());
+
+    png::write_png(writer, width as u32, SNAPSHOT_CELL_SIZE as u32, &rgb)
+}
+
+// Formats pixels for save_state().
+fn state_text(pixels: &[Pixel]) -> String {
+    let mut text = String::from("# red, green, blue, brightness (0-31)\n");
+    for pixel in pixels {
+        let (red, green, blue) = pixel.rgb();
+        text.push_str(&format!(
+            "{:02x} {:02x} {:02x} {:02x}\n",
+            red,
+            green,
+            blue,
+            pixel.brightness_raw()
+        ));
+    }
+
+    text
+}
+
+// Writes a state file through a temporary file, so the existing file is
+// replaced atomically. The temporary file gets a random name and is created
+// exclusively, with mode 0600, so a file or symlink planted at a predictable
+// path can't be written through.
+fn write_state(path: &Path, text: &str) -> Result<()> {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos() as u64 ^ elapsed.as_secs())
+        .unwrap_or(0);
+    let mut rng = rng::XorShift::new(seed ^ u64::from(process::id()));
+
+    let mut attempts = 0;
+    let (temp_path, mut file) = loop {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(format!(".{:016x}.tmp", rng.next_u64()));
+
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&temp_path)
+        {
+            Ok(file) => break (temp_path, file),
+            Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists && attempts < 8 => {
+                attempts += 1
+            }
+            Err(err) => return Err(Error::Io(err)),
+        }
+    };
+
+    let result = file
+        .write_all(text.as_bytes())
+        .and_then(|_| file.sync_all())
+        .and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result.map_err(Error::Io)
+}
+
+// Sends a frame that turns off num_pixels pixels, and releases the output.
+// The output may be locked by the panicking thread, so this gives up after a
+// short while instead of blocking.
+fn blank_output(output: &SharedOutput, num_pixels: usize) {
+    for _ in 0..100 {
+        let mut output = match output.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+        };
+
+        let leds = vec![apa102::OFF_FRAME; num_pixels];
+        let mut frame = vec![0u8; encoded_len(num_pixels)];
+        if apa102::encode_into(&leds, &mut frame).is_some() {
+            let _ = output.write(&frame);
+        }
+
+        // Dropping the output releases the peripheral, and makes sure the
+        // persistent display mode can't send the previous frame again.
+        *output = Box::new(Disconnected);
+        return;
+    }
+}
+
+/// A mutable iterator over all `Pixel`s stored in `Blinkt`.
+pub struct IterMut<'a> {
+    pub(crate) iter_mut: slice::IterMut<'a, Pixel>,
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = &'a mut Pixel;
+
+    fn next(&mut self) -> Option<&'a mut Pixel> {
+        self.iter_mut.next()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Blinkt {
+    type Item = &'a mut Pixel;
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
@@ -0,0 +1,317 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Bias lighting driven by a camera.
+//!
+//! A [`Camera`] captures frames from a V4L2 camera, such as a cheap USB
+//! webcam pointed at a TV, and shows the colors along the edges of the
+//! picture on a strip placed around the screen. This works with any video
+//! source, including devices that don't run on the Raspberry Pi, where the
+//! [`ambilight`] module can't read the screen directly.
+//!
+//! Strips are placed along the edges in the same way as for an
+//! [`Ambilight`]. When the camera sees more than just the screen,
+//! [`Camera::with_crop`] limits the sampled area to the screen itself.
+//!
+//! The camera needs to support the YUYV or RGB3 pixel format, which most
+//! webcams do.
+//!
+//! This module requires the `camera` feature.
+//!
+//! ```rust,no_run
+//! use blinkt::ambilight::{ColorMode, Edge};
+//! use blinkt::camera::Camera;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 2 * (32 + 18))?;
+//!
+//! let mut camera = Camera::new()?
+//!     .with_crop(40, 30, 240, 135)
+//!     .with_mode(ColorMode::Dominant)
+//!     .with_smoothing(0.5)
+//!     .with_edge(Edge::Left, 18)
+//!     .with_edge(Edge::Top, 32)
+//!     .with_edge(Edge::Right, 18)
+//!     .with_edge(Edge::Bottom, 32);
+//!
+//! camera.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Camera`]: struct.Camera.html
+//! [`Camera::with_crop`]: struct.Camera.html#method.with_crop
+//! [`ambilight`]: ../ambilight/index.html
+//! [`Ambilight`]: ../ambilight/struct.Ambilight.html
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use v4l::buffer::Type;
+use v4l::io::mmap::Stream;
+use v4l::io::traits::CaptureStream;
+use v4l::video::Capture;
+use v4l::{Device, FourCC};
+
+use crate::ambilight::{ColorMode, Edge, Zones};
+use crate::{Blinkt, Error, Result};
+
+/// The default camera device.
+pub const DEVICE: &str = "/dev/video0";
+
+// Requested capture resolution. Bias lighting only needs a rough picture,
+// and small frames keep the USB bandwidth and conversion costs down.
+const CAPTURE_WIDTH: u32 = 320;
+const CAPTURE_HEIGHT: u32 = 240;
+const BUFFER_COUNT: u32 = 4;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PixelFormat {
+    Yuyv,
+    Rgb3,
+}
+
+/// Mirrors the colors along the edges of a camera picture onto a strip.
+pub struct Camera {
+    stream: Stream<'static>,
+    width: usize,
+    height: usize,
+    stride: usize,
+    format: PixelFormat,
+    crop: Option<(usize, usize, usize, usize)>,
+    zones: Zones,
+}
+
+impl fmt::Debug for Camera {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Camera")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("format", &self.format)
+            .field("crop", &self.crop)
+            .field("zones", &self.zones)
+            .finish()
+    }
+}
+
+impl Camera {
+    /// Constructs a new `Camera` that captures frames from the default
+    /// camera device.
+    pub fn new() -> Result<Camera> {
+        Camera::open(DEVICE)
+    }
+
+    /// Constructs a new `Camera` that captures frames from the V4L2 device
+    /// at `path`.
+    ///
+    /// Frames are captured at 320x240 pixels, or the closest resolution the
+    /// camera supports. By default, no edges are placed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Camera> {
+        let device = Device::with_path(path)?;
+
+        let mut format = device.format()?;
+        format.width = CAPTURE_WIDTH;
+        format.height = CAPTURE_HEIGHT;
+        format.fourcc = FourCC::new(b"YUYV");
+        let mut format = device.set_format(&format)?;
+
+        if format.fourcc != FourCC::new(b"YUYV") {
+            format.fourcc = FourCC::new(b"RGB3");
+            format = device.set_format(&format)?;
+        }
+
+        let pixel_format = if format.fourcc == FourCC::new(b"YUYV") {
+            PixelFormat::Yuyv
+        } else if format.fourcc == FourCC::new(b"RGB3") {
+            PixelFormat::Rgb3
+        } else {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unsupported camera pixel format",
+            )));
+        };
+
+        let stream = Stream::with_buffers(&device, Type::VideoCapture, BUFFER_COUNT)?;
+
+        Ok(Camera {
+            stream,
+            width: format.width as usize,
+            height: format.height as usize,
+            stride: format.stride as usize,
+            format: pixel_format,
+            crop: None,
+            zones: Zones::new(),
+        })
+    }
+
+    /// Adds `num_pixels` pixels along `edge`, following the pixels that were
+    /// added previously.
+    pub fn with_edge(mut self, edge: Edge, num_pixels: usize) -> Camera {
+        self.zones.push(Some(edge), num_pixels, false);
+        self
+    }
+
+    /// Adds `num_pixels` pixels along `edge` running counterclockwise,
+    /// following the pixels that were added previously.
+    pub fn with_edge_reversed(mut self, edge: Edge, num_pixels: usize) -> Camera {
+        self.zones.push(Some(edge), num_pixels, true);
+        self
+    }
+
+    /// Skips `num_pixels` pixels, for instance where the strip runs around a
+    /// corner or behind a stand. Skipped pixels are turned off.
+    pub fn with_gap(mut self, num_pixels: usize) -> Camera {
+        self.zones.push(None, num_pixels, false);
+        self
+    }
+
+    /// Only samples the area of the picture that's `width` by `height`
+    /// pixels, starting at `x`, `y`.
+    ///
+    /// The area is limited to the size of the captured frames, which can be
+    /// checked with [`frame_size`].
+    ///
+    /// [`frame_size`]: #method.frame_size
+    pub fn with_crop(mut self, x: usize, y: usize, width: usize, height: usize) -> Camera {
+        self.crop = Some((x, y, width, height));
+        self
+    }
+
+    /// Sets how far the sampled areas reach into the picture, as a fraction
+    /// of its width or height.
+    ///
+    /// `depth` is limited to a range of `0.01` to `0.5`. Defaults to `0.1`.
+    pub fn with_depth(mut self, depth: f32) -> Camera {
+        self.zones.set_depth(depth);
+        self
+    }
+
+    /// Sets how the color of each area is determined. Defaults to
+    /// `ColorMode::Average`.
+    pub fn with_mode(mut self, mode: ColorMode) -> Camera {
+        self.zones.set_mode(mode);
+        self
+    }
+
+    /// Smooths out sudden changes, by blending each new color with the
+    /// previous one.
+    ///
+    /// `smoothing` ranges from `0.0`, which shows the new colors as they
+    /// are, to `1.0`, which never changes the colors. Defaults to `0.0`.
+    pub fn with_smoothing(mut self, smoothing: f32) -> Camera {
+        self.zones.set_smoothing(smoothing);
+        self
+    }
+
+    /// Returns the width and height of the captured frames.
+    pub fn frame_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the total number of pixels placed along the edges, including
+    /// any gaps.
+    pub fn num_pixels(&self) -> usize {
+        self.zones.num_pixels()
+    }
+
+    /// Captures frames, and sends the colors to the pixels, until an error
+    /// occurs.
+    ///
+    /// The pixels are updated at the frame rate of the camera.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        loop {
+            self.update(blinkt)?;
+        }
+    }
+
+    /// Waits for the next frame, and sends the colors to the pixels.
+    pub fn update(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        let (data, _) = self.stream.next()?;
+
+        let (crop_x, crop_y, width, height) = crop_area(self.crop, self.width, self.height);
+        let (stride, format) = (self.stride, self.format);
+
+        self.zones.show(blinkt, width, height, |x, y| {
+            pixel(data, stride, format, crop_x + x, crop_y + y)
+        })
+    }
+}
+
+// Limits the crop area to the frame, or returns the entire frame.
+fn crop_area(
+    crop: Option<(usize, usize, usize, usize)>,
+    frame_width: usize,
+    frame_height: usize,
+) -> (usize, usize, usize, usize) {
+    match crop {
+        Some((x, y, width, height)) => {
+            let x = x.min(frame_width);
+            let y = y.min(frame_height);
+            (
+                x,
+                y,
+                width.min(frame_width - x),
+                height.min(frame_height - y),
+            )
+        }
+        None => (0, 0, frame_width, frame_height),
+    }
+}
+
+// Returns the red, green and blue values of a pixel.
+fn pixel(data: &[u8], stride: usize, format: PixelFormat, x: usize, y: usize) -> [u32; 3] {
+    match format {
+        PixelFormat::Rgb3 => {
+            let offset = y * stride + x * 3;
+            match data.get(offset..offset + 3) {
+                Some(rgb) => [u32::from(rgb[0]), u32::from(rgb[1]), u32::from(rgb[2])],
+                None => [0; 3],
+            }
+        }
+        PixelFormat::Yuyv => {
+            // Two horizontally adjacent pixels share their U and V values.
+            let offset = y * stride + (x / 2) * 4;
+            let yuyv = match data.get(offset..offset + 4) {
+                Some(yuyv) => yuyv,
+                None => return [0; 3],
+            };
+
+            let luma = if x & 1 == 0 { yuyv[0] } else { yuyv[2] };
+            yuv_to_rgb(luma, yuyv[1], yuyv[3])
+        }
+    }
+}
+
+// Converts limited range BT.601 YUV values to RGB.
+fn yuv_to_rgb(y: u8, u: u8, v: u8) -> [u32; 3] {
+    let c = i32::from(y) - 16;
+    let d = i32::from(u) - 128;
+    let e = i32::from(v) - 128;
+
+    let channel = |value: i32| ((value + 128) >> 8).max(0).min(255) as u32;
+
+    [
+        channel(298 * c + 409 * e),
+        channel(298 * c - 100 * d - 208 * e),
+        channel(298 * c + 516 * d),
+    ]
+}
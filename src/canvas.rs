@@ -0,0 +1,214 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::color::{Color, Interpolation};
+use crate::matrix::Matrix;
+
+/// A virtual drawing surface that can be larger than the physical matrix.
+///
+/// Content is drawn onto the canvas once, and displayed on a [`Matrix`] by
+/// moving the viewport, which determines the part of the canvas that's
+/// visible. The viewport position is specified as floating point
+/// coordinates. Fractional positions are rendered by interpolating between
+/// neighboring pixels, which results in smooth sub-pixel scrolling.
+///
+/// Pixels are stored in row-major order, starting at the top left pixel.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// # use std::thread;
+/// # use std::time::Duration;
+/// #
+/// # use blinkt::{Blinkt, Canvas, Color, Layout, Matrix};
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let blinkt = Blinkt::with_spi(16_000_000, 16 * 8)?;
+/// let mut matrix = Matrix::new(blinkt, 16, 8, Layout::Serpentine);
+///
+/// let mut canvas = Canvas::new(64, 8);
+/// canvas.set_wrap(true);
+/// for x in (0..64).step_by(4) {
+///     canvas.set_xy(x, 4, Color::new(0, 0, 255));
+/// }
+///
+/// let mut x = 0.0;
+/// loop {
+///     canvas.set_viewport(x, 0.0);
+///     canvas.render(&mut matrix);
+///     matrix.show()?;
+///
+///     x += 0.25;
+///     thread::sleep(Duration::from_millis(20));
+/// }
+/// # }
+/// ```
+///
+/// [`Matrix`]: struct.Matrix.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    viewport: (f32, f32),
+    wrap: bool,
+}
+
+impl Canvas {
+    /// Constructs a new `Canvas` with the specified dimensions, where all
+    /// pixels are set to black.
+    pub fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![Color::new(0, 0, 0); width * height],
+            viewport: (0.0, 0.0),
+            wrap: false,
+        }
+    }
+
+    /// Returns the width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the color of the pixel at the specified coordinates, or
+    /// `None` if the coordinates are out of bounds.
+    pub fn pixel_xy(&self, x: usize, y: usize) -> Option<Color> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Sets the color for the pixel at the specified coordinates.
+    ///
+    /// Coordinates that are out of bounds are ignored.
+    pub fn set_xy<C: Into<Color>>(&mut self, x: usize, y: usize, color: C) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color.into();
+        }
+    }
+
+    /// Sets the color for all pixels.
+    pub fn fill<C: Into<Color>>(&mut self, color: C) {
+        let color = color.into();
+        for pixel in &mut self.pixels {
+            *pixel = color;
+        }
+    }
+
+    /// Sets all pixels to black.
+    pub fn clear(&mut self) {
+        self.fill(Color::new(0, 0, 0));
+    }
+
+    /// Returns the position of the top left corner of the viewport.
+    pub fn viewport(&self) -> (f32, f32) {
+        self.viewport
+    }
+
+    /// Moves the top left corner of the viewport to the specified position
+    /// on the canvas.
+    ///
+    /// `x` and `y` can be fractional, negative, or extend beyond the edges of
+    /// the canvas. Areas outside the canvas are rendered as black, unless
+    /// wrapping is enabled.
+    pub fn set_viewport(&mut self, x: f32, y: f32) {
+        self.viewport = (x, y);
+    }
+
+    /// Returns `true` if the edges of the canvas wrap around.
+    pub fn wrap(&self) -> bool {
+        self.wrap
+    }
+
+    /// When enabled, the edges of the canvas wrap around, so the content
+    /// repeats in all directions. This is useful for tickers that scroll
+    /// continuously.
+    ///
+    /// By default, this is set to `false`.
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    /// Copies the area of the canvas covered by the viewport to `matrix`.
+    ///
+    /// The viewport has the same dimensions as the matrix.
+    pub fn render(&self, matrix: &mut Matrix) {
+        let (viewport_x, viewport_y) = self.viewport;
+        let (base_x, base_y) = (viewport_x.floor(), viewport_y.floor());
+        let (fraction_x, fraction_y) = (viewport_x - base_x, viewport_y - base_y);
+
+        for y in 0..matrix.height() {
+            for x in 0..matrix.width() {
+                let source_x = base_x as isize + x as isize;
+                let source_y = base_y as isize + y as isize;
+
+                let top = self.sample(source_x, source_y).interpolate(
+                    self.sample(source_x + 1, source_y),
+                    fraction_x,
+                    Interpolation::Rgb,
+                );
+                let bottom = self.sample(source_x, source_y + 1).interpolate(
+                    self.sample(source_x + 1, source_y + 1),
+                    fraction_x,
+                    Interpolation::Rgb,
+                );
+
+                matrix.set_xy(
+                    x,
+                    y,
+                    top.interpolate(bottom, fraction_y, Interpolation::Rgb),
+                );
+            }
+        }
+    }
+
+    // Returns the pixel at the specified coordinates, taking wrapping into
+    // account. Pixels outside the canvas are black.
+    fn sample(&self, x: isize, y: isize) -> Color {
+        if self.width == 0 || self.height == 0 {
+            return Color::new(0, 0, 0);
+        }
+
+        let (x, y) = if self.wrap {
+            let (width, height) = (self.width as isize, self.height as isize);
+            (
+                ((x % width) + width) % width,
+                ((y % height) + height) % height,
+            )
+        } else {
+            (x, y)
+        };
+
+        if x < 0 || y < 0 {
+            return Color::new(0, 0, 0);
+        }
+
+        self.pixel_xy(x as usize, y as usize)
+            .unwrap_or_else(|| Color::new(0, 0, 0))
+    }
+}
@@ -0,0 +1,259 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Capturing and comparing the serialized byte stream.
+//!
+//! [`Capture`] is a [`SerialOutput`] that records every byte sent by `Blinkt`
+//! instead of writing it to a peripheral. Combined with [`load_hex`], it
+//! allows you to verify that the serialized frames are identical to a known
+//! good byte stream, such as one recorded with a logic analyzer, without
+//! requiring any hardware.
+//!
+//! [`FaultInjector`] wraps any output, and simulates wiring faults such as
+//! write errors, truncated frames and latency spikes, so applications can
+//! verify their retry and degradation logic without real hardware.
+//!
+//! Capture files contain whitespace-separated hexadecimal byte values.
+//! Anything following a `#` on a line is treated as a comment.
+//!
+//! ```rust
+//! use blinkt::capture::{self, Capture};
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let capture = Capture::new();
+//! let mut blinkt = Blinkt::with_output(capture.clone(), 1);
+//!
+//! blinkt.set_pixel(0, 255, 0, 0);
+//! blinkt.show()?;
+//!
+//! let expected = capture::parse_hex("00 00 00 00  e7 00 00 ff  00 00 00 00 00")?;
+//! assert_eq!(capture.take(), expected);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Capture`]: struct.Capture.html
+//! [`SerialOutput`]: ../trait.SerialOutput.html
+//! [`load_hex`]: fn.load_hex.html
+//! [`FaultInjector`]: struct.FaultInjector.html
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::rng::XorShift;
+use crate::{Error, Result, SerialOutput};
+
+/// A [`SerialOutput`] that records all bytes written to it.
+///
+/// Clones share the same buffer, so a clone can be kept to inspect the bytes
+/// after the original has been moved into `Blinkt`.
+///
+/// [`SerialOutput`]: ../trait.SerialOutput.html
+#[derive(Debug, Clone, Default)]
+pub struct Capture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Capture {
+    /// Constructs a new, empty `Capture`.
+    pub fn new() -> Capture {
+        Capture::default()
+    }
+
+    /// Returns a copy of all bytes recorded so far.
+    pub fn bytes(&self) -> Vec<u8> {
+        match self.buffer.lock() {
+            Ok(buffer) => buffer.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Returns all bytes recorded so far, and clears the buffer.
+    pub fn take(&self) -> Vec<u8> {
+        match self.buffer.lock() {
+            Ok(mut buffer) => buffer.split_off(0),
+            Err(poisoned) => poisoned.into_inner().split_off(0),
+        }
+    }
+}
+
+impl SerialOutput for Capture {
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        match self.buffer.lock() {
+            Ok(mut buffer) => buffer.extend_from_slice(data),
+            Err(poisoned) => poisoned.into_inner().extend_from_slice(data),
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`SerialOutput`] wrapper that randomly injects faults.
+///
+/// Each call to `write` can independently fail with an error, send only part
+/// of the data, or be delayed, based on the configured rates. Faults are
+/// generated by a pseudo-random number generator initialized with a fixed
+/// seed, so test runs are reproducible.
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use blinkt::capture::{Capture, FaultInjector};
+/// use blinkt::Blinkt;
+///
+/// let capture = Capture::new();
+/// let output = FaultInjector::new(capture.clone(), 42)
+///     .with_error_rate(0.1)
+///     .with_truncation_rate(0.05)
+///     .with_latency(0.01, Duration::from_millis(50));
+///
+/// let mut blinkt = Blinkt::with_output(output, 8);
+/// blinkt.set_clear_on_drop(false);
+///
+/// for _ in 0..10 {
+///     if blinkt.show().is_err() {
+///         // Retry, or degrade gracefully.
+///     }
+/// }
+/// ```
+///
+/// [`SerialOutput`]: ../trait.SerialOutput.html
+#[derive(Debug)]
+pub struct FaultInjector<T> {
+    output: T,
+    rng: XorShift,
+    error_rate: f32,
+    truncation_rate: f32,
+    latency_rate: f32,
+    latency: Duration,
+}
+
+impl<T: SerialOutput> FaultInjector<T> {
+    /// Constructs a new `FaultInjector` wrapping `output`, using `seed` to
+    /// initialize the pseudo-random number generator.
+    ///
+    /// By default, no faults are injected.
+    pub fn new(output: T, seed: u64) -> FaultInjector<T> {
+        FaultInjector {
+            output,
+            rng: XorShift::new(seed),
+            error_rate: 0.0,
+            truncation_rate: 0.0,
+            latency_rate: 0.0,
+            latency: Duration::from_millis(0),
+        }
+    }
+
+    /// Sets the probability that a write fails without sending any data.
+    ///
+    /// `rate` is specified as a floating point value between `0.0` (never)
+    /// and `1.0` (always).
+    pub fn with_error_rate(mut self, rate: f32) -> FaultInjector<T> {
+        self.error_rate = rate;
+        self
+    }
+
+    /// Sets the probability that a write only sends a random part of the
+    /// data, while reporting success.
+    ///
+    /// `rate` is specified as a floating point value between `0.0` (never)
+    /// and `1.0` (always).
+    pub fn with_truncation_rate(mut self, rate: f32) -> FaultInjector<T> {
+        self.truncation_rate = rate;
+        self
+    }
+
+    /// Sets the probability that a write is delayed by `latency` before the
+    /// data is sent.
+    ///
+    /// `rate` is specified as a floating point value between `0.0` (never)
+    /// and `1.0` (always).
+    pub fn with_latency(mut self, rate: f32, latency: Duration) -> FaultInjector<T> {
+        self.latency_rate = rate;
+        self.latency = latency;
+        self
+    }
+
+    /// Returns a reference to the wrapped output.
+    pub fn get_ref(&self) -> &T {
+        &self.output
+    }
+
+    /// Consumes the `FaultInjector`, returning the wrapped output.
+    pub fn into_inner(self) -> T {
+        self.output
+    }
+}
+
+impl<T: SerialOutput> SerialOutput for FaultInjector<T> {
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        if self.rng.next_f32() < self.latency_rate {
+            thread::sleep(self.latency);
+        }
+
+        if self.rng.next_f32() < self.error_rate {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "injected write error",
+            )));
+        }
+
+        if !data.is_empty() && self.rng.next_f32() < self.truncation_rate {
+            let len = (self.rng.next_u64() % data.len() as u64) as usize;
+            return self.output.write(&data[..len]);
+        }
+
+        self.output.write(data)
+    }
+}
+
+/// Parses whitespace-separated hexadecimal byte values. Anything following a
+/// `#` on a line is ignored.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("");
+        for token in line.split_whitespace() {
+            let token = token.trim_start_matches("0x");
+            let byte = u8::from_str_radix(token, 16).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid hexadecimal byte value: {}", token),
+                )
+            })?;
+
+            bytes.push(byte);
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Loads a capture file containing whitespace-separated hexadecimal byte
+/// values. Anything following a `#` on a line is ignored.
+pub fn load_hex<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+    parse_hex(&fs::read_to_string(path)?)
+}
@@ -0,0 +1,347 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+const MIN_KELVIN: u32 = 1000;
+#[cfg(feature = "std")]
+const MAX_KELVIN: u32 = 12_000;
+
+/// Color space used to interpolate between two colors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Interpolation {
+    /// Interpolates the red, green and blue values separately.
+    Rgb,
+    /// Interpolates hue, saturation and value, following the shortest path
+    /// around the color wheel. Produces more saturated intermediate colors
+    /// than `Rgb`.
+    Hsv,
+}
+
+/// An RGB color.
+///
+/// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Color {
+    /// The red value.
+    pub red: u8,
+    /// The green value.
+    pub green: u8,
+    /// The blue value.
+    pub blue: u8,
+}
+
+impl Color {
+    /// Constructs a new `Color` with the specified red, green and blue values.
+    pub const fn new(red: u8, green: u8, blue: u8) -> Color {
+        Color { red, green, blue }
+    }
+
+    /// Constructs a new `Color` that approximates the color of a black body
+    /// radiator at the specified color temperature.
+    ///
+    /// `kelvin` is clamped to a value between `1000` and `12000`. Typical
+    /// values are `2700` for warm white, `4000` for neutral white, and `6500`
+    /// for daylight.
+    ///
+    /// The conversion is based on a curve fit of the CIE 1964 10-degree color
+    /// matching functions, and is accurate enough for ambient lighting. Use
+    /// the pixel brightness to control the intensity.
+    ///
+    /// This function requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn from_kelvin(kelvin: u32) -> Color {
+        let temperature = kelvin.max(MIN_KELVIN).min(MAX_KELVIN) as f64 / 100.0;
+
+        let red = if temperature <= 66.0 {
+            255.0
+        } else {
+            329.698_727_446 * (temperature - 60.0).powf(-0.133_204_759_2)
+        };
+
+        let green = if temperature <= 66.0 {
+            99.470_802_586_1 * temperature.ln() - 161.119_568_166_1
+        } else {
+            288.122_169_528_3 * (temperature - 60.0).powf(-0.075_514_849_2)
+        };
+
+        let blue = if temperature >= 66.0 {
+            255.0
+        } else if temperature <= 19.0 {
+            0.0
+        } else {
+            138.517_731_223_1 * (temperature - 10.0).ln() - 305.044_792_730_7
+        };
+
+        Color::new(clamp_u8(red), clamp_u8(green), clamp_u8(blue))
+    }
+
+    /// Constructs a new `Color` from hue, saturation and value.
+    ///
+    /// `hue` is specified in degrees, and wraps around at `360.0`.
+    /// `saturation` and `value` are specified as floating point values between `0.0` (0%) and `1.0` (100%).
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let hue = ((hue % 360.0) + 360.0) % 360.0;
+        let saturation = saturation.max(0.0).min(1.0);
+        let value = value.max(0.0).min(1.0);
+
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - abs((hue / 60.0) % 2.0 - 1.0));
+        let m = value - chroma;
+
+        let (red, green, blue) = match (hue / 60.0) as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        Color::new(
+            clamp_u8(f64::from((red + m) * 255.0)),
+            clamp_u8(f64::from((green + m) * 255.0)),
+            clamp_u8(f64::from((blue + m) * 255.0)),
+        )
+    }
+
+    /// Returns a tuple containing the values for red, green and blue.
+    #[inline]
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        (self.red, self.green, self.blue)
+    }
+
+    /// Returns a tuple containing the values for hue (in degrees, between
+    /// `0.0` and `360.0`), saturation and value (between `0.0` and `1.0`).
+    pub fn hsv(&self) -> (f32, f32, f32) {
+        let red = f32::from(self.red) / 255.0;
+        let green = f32::from(self.green) / 255.0;
+        let blue = f32::from(self.blue) / 255.0;
+
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == red {
+            60.0 * (((green - blue) / delta) % 6.0)
+        } else if max == green {
+            60.0 * (((blue - red) / delta) + 2.0)
+        } else {
+            60.0 * (((red - green) / delta) + 4.0)
+        };
+
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+        (((hue % 360.0) + 360.0) % 360.0, saturation, max)
+    }
+
+    /// Returns a color between `self` and `other`.
+    ///
+    /// `t` is specified as a floating point value between `0.0` (`self`) and
+    /// `1.0` (`other`).
+    pub fn interpolate(&self, other: Color, t: f32, interpolation: Interpolation) -> Color {
+        let t = t.max(0.0).min(1.0);
+
+        match interpolation {
+            Interpolation::Rgb => Color::new(
+                lerp_u8(self.red, other.red, t),
+                lerp_u8(self.green, other.green, t),
+                lerp_u8(self.blue, other.blue, t),
+            ),
+            Interpolation::Hsv => {
+                let (mut h1, s1, v1) = self.hsv();
+                let (mut h2, s2, v2) = other.hsv();
+
+                // Achromatic colors don't have a meaningful hue, so borrow the
+                // hue from the other color to avoid a detour around the wheel.
+                if s1 == 0.0 {
+                    h1 = h2;
+                } else if s2 == 0.0 {
+                    h2 = h1;
+                }
+
+                let mut delta = h2 - h1;
+                if delta > 180.0 {
+                    delta -= 360.0;
+                } else if delta < -180.0 {
+                    delta += 360.0;
+                }
+
+                Color::from_hsv(h1 + delta * t, s1 + (s2 - s1) * t, v1 + (v2 - v1) * t)
+            }
+        }
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from(rgb: (u8, u8, u8)) -> Color {
+        Color::new(rgb.0, rgb.1, rgb.2)
+    }
+}
+
+impl From<Color> for (u8, u8, u8) {
+    fn from(color: Color) -> (u8, u8, u8) {
+        color.rgb()
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<palette::Srgb<u8>> for Color {
+    fn from(color: palette::Srgb<u8>) -> Color {
+        Color::new(color.red, color.green, color.blue)
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<palette::Srgb> for Color {
+    fn from(color: palette::Srgb) -> Color {
+        Color::from(color.into_format::<u8>())
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<palette::LinSrgb> for Color {
+    fn from(color: palette::LinSrgb) -> Color {
+        Color::from(palette::Srgb::from_linear(color))
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<palette::Hsv> for Color {
+    fn from(color: palette::Hsv) -> Color {
+        Color::from(palette::Srgb::from(color))
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<Color> for palette::Srgb<u8> {
+    fn from(color: Color) -> palette::Srgb<u8> {
+        palette::Srgb::new(color.red, color.green, color.blue)
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<Color> for palette::Srgb {
+    fn from(color: Color) -> palette::Srgb {
+        palette::Srgb::<u8>::from(color).into_format()
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<Color> for palette::LinSrgb {
+    fn from(color: Color) -> palette::LinSrgb {
+        palette::Srgb::from(color).into_linear()
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<Color> for palette::Hsv {
+    fn from(color: Color) -> palette::Hsv {
+        palette::Hsv::from(palette::Srgb::from(color))
+    }
+}
+
+#[cfg(feature = "rgb")]
+impl From<rgb::RGB8> for Color {
+    fn from(color: rgb::RGB8) -> Color {
+        Color::new(color.r, color.g, color.b)
+    }
+}
+
+/// The alpha value is ignored.
+#[cfg(feature = "rgb")]
+impl From<rgb::RGBA8> for Color {
+    fn from(color: rgb::RGBA8) -> Color {
+        Color::new(color.r, color.g, color.b)
+    }
+}
+
+#[cfg(feature = "rgb")]
+impl From<Color> for rgb::RGB8 {
+    fn from(color: Color) -> rgb::RGB8 {
+        rgb::RGB8::new(color.red, color.green, color.blue)
+    }
+}
+
+/// The alpha value is set to `255` (opaque).
+#[cfg(feature = "rgb")]
+impl From<Color> for rgb::RGBA8 {
+    fn from(color: Color) -> rgb::RGBA8 {
+        rgb::RGBA8::new(color.red, color.green, color.blue, 255)
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl From<embedded_graphics::pixelcolor::Rgb888> for Color {
+    fn from(color: embedded_graphics::pixelcolor::Rgb888) -> Color {
+        use embedded_graphics::pixelcolor::RgbColor;
+
+        Color::new(color.r(), color.g(), color.b())
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl From<Color> for embedded_graphics::pixelcolor::Rgb888 {
+    fn from(color: Color) -> embedded_graphics::pixelcolor::Rgb888 {
+        embedded_graphics::pixelcolor::Rgb888::new(color.red, color.green, color.blue)
+    }
+}
+
+#[inline]
+fn lerp_u8(a: u8, b: u8, t: f32) -> u8 {
+    round(f64::from(f32::from(a) + (f32::from(b) - f32::from(a)) * t)) as u8
+}
+
+#[inline]
+fn clamp_u8(value: f64) -> u8 {
+    round(value).max(0.0).min(255.0) as u8
+}
+
+// The rounding and absolute value functions of f32 and f64 aren't available
+// in core on every supported compiler.
+#[inline]
+fn abs(value: f32) -> f32 {
+    if value < 0.0 {
+        -value
+    } else {
+        value
+    }
+}
+
+#[cfg(feature = "std")]
+#[inline]
+fn round(value: f64) -> f64 {
+    value.round()
+}
+
+#[cfg(not(feature = "std"))]
+#[inline]
+fn round(value: f64) -> f64 {
+    if value < 0.0 {
+        -round(-value)
+    } else {
+        (value + 0.5) as u64 as f64
+    }
+}
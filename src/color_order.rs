@@ -0,0 +1,68 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::pixel::Pixel;
+
+/// The order in which a strip wires its red, green and blue channels.
+///
+/// Most APA102 and SK9822 strips use the standard channel order, but some
+/// clones swap two or more channels. Selecting the matching color order
+/// rearranges the color values before they're sent to the pixels, so the
+/// same code produces the same colors on every strip.
+///
+/// Each variant lists the channels in the order the strip actually displays
+/// them in the red, green and blue positions of a standard APA102 frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ColorOrder {
+    /// Standard channel order. No rearrangement is applied.
+    Rgb,
+    /// Green and blue swapped.
+    Rbg,
+    /// Red and green swapped.
+    Grb,
+    /// Green, blue, red.
+    Gbr,
+    /// Blue, red, green.
+    Brg,
+    /// Red and blue swapped.
+    Bgr,
+}
+
+impl ColorOrder {
+    #[inline]
+    pub(crate) fn apply(self, pixel: &mut Pixel) {
+        let (red, green, blue) = pixel.rgb();
+        let (red, green, blue) = match self {
+            ColorOrder::Rgb => return,
+            ColorOrder::Rbg => (red, blue, green),
+            ColorOrder::Grb => (green, red, blue),
+            ColorOrder::Gbr => (green, blue, red),
+            ColorOrder::Brg => (blue, red, green),
+            ColorOrder::Bgr => (blue, green, red),
+        };
+
+        pixel.set_rgb(red, green, blue);
+    }
+}
@@ -0,0 +1,128 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::pixel::Pixel;
+
+/// Color spaces used to interpret the color values stored in the local
+/// buffer.
+///
+/// APA102 and SK9822 LEDs have primaries that are roughly comparable to sRGB.
+/// When the color values come from a wide gamut source, such as a camera or a
+/// video frame, selecting the matching color space converts the values to the
+/// strip's native primaries, so colors appear less oversaturated.
+///
+/// The conversions use approximate matrices, and assume all color spaces use
+/// the sRGB transfer curve and a D65 white point.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// sRGB, which matches the LEDs' native primaries. No conversion is
+    /// applied.
+    Srgb,
+    /// Display P3, a variant of DCI-P3 with a D65 white point, used by many
+    /// recent displays and phone cameras.
+    DisplayP3,
+    /// Adobe RGB (1998).
+    AdobeRgb,
+    /// ITU-R BT.2020, used for UHD video content.
+    Rec2020,
+}
+
+impl ColorSpace {
+    // Returns the linear conversion matrix to sRGB primaries.
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorSpace::Srgb => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            ColorSpace::DisplayP3 => [
+                [1.2249, -0.2247, 0.0],
+                [-0.0420, 1.0419, 0.0],
+                [-0.0197, -0.0786, 1.0979],
+            ],
+            ColorSpace::AdobeRgb => [
+                [1.3982, -0.3982, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, -0.0429, 1.0429],
+            ],
+            ColorSpace::Rec2020 => [
+                [1.6605, -0.5876, -0.0728],
+                [-0.1246, 1.1329, -0.0083],
+                [-0.0182, -0.1006, 1.1187],
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ColorSpaceTransform {
+    matrix: [[f32; 3]; 3],
+    linear: [f32; 256],
+}
+
+impl ColorSpaceTransform {
+    pub(crate) fn new(color_space: ColorSpace) -> ColorSpaceTransform {
+        let mut linear = [0f32; 256];
+        for (value, entry) in linear.iter_mut().enumerate() {
+            *entry = to_linear(value as f32 / 255.0);
+        }
+
+        ColorSpaceTransform {
+            matrix: color_space.matrix(),
+            linear,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn apply(&self, pixel: &mut Pixel) {
+        let (red, green, blue) = pixel.rgb();
+        let input = [
+            self.linear[red as usize],
+            self.linear[green as usize],
+            self.linear[blue as usize],
+        ];
+
+        let convert = |row: &[f32; 3]| {
+            let value = row[0] * input[0] + row[1] * input[1] + row[2] * input[2];
+            (from_linear(value.max(0.0).min(1.0)) * 255.0).round() as u8
+        };
+
+        pixel.set_rgb(
+            convert(&self.matrix[0]),
+            convert(&self.matrix[1]),
+            convert(&self.matrix[2]),
+        );
+    }
+}
+
+// sRGB transfer curve (electro-optical).
+fn to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Inverse sRGB transfer curve.
+fn from_linear(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// Strip settings loaded from a TOML configuration file by
+// `Blinkt::from_config`.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{Blinkt, ColorOrder, Error, Result, CLK, DAT, NUM_PIXELS};
+
+// Clock speed used when the configuration selects SPI without specifying
+// a clock speed.
+const SPI_CLOCK_SPEED_HZ: u32 = 8_000_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Interface {
+    Gpio,
+    Spi,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    interface: Option<Interface>,
+    pin_data: Option<u8>,
+    pin_clock: Option<u8>,
+    clock_speed_hz: Option<u32>,
+    spi_chunk_size: Option<usize>,
+    spidev: Option<String>,
+    num_pixels: Option<usize>,
+    color_order: Option<ColorOrder>,
+    gamma: Option<f32>,
+}
+
+impl Config {
+    pub(crate) fn load(path: &Path) -> Result<Config> {
+        Config::parse(&fs::read_to_string(path)?)
+    }
+
+    pub(crate) fn parse(config: &str) -> Result<Config> {
+        toml::from_str(config).map_err(|err| invalid_data(err.to_string()))
+    }
+
+    pub(crate) fn build(&self) -> Result<Blinkt> {
+        let num_pixels = self.num_pixels.unwrap_or(NUM_PIXELS);
+        let interface = match self.interface {
+            Some(interface) => interface,
+            None if self.clock_speed_hz.is_some() || self.spidev.is_some() => Interface::Spi,
+            None => Interface::Gpio,
+        };
+
+        let mut blinkt = match interface {
+            Interface::Gpio => Blinkt::with_settings(
+                self.pin_data.unwrap_or(DAT),
+                self.pin_clock.unwrap_or(CLK),
+                num_pixels,
+            )?,
+            Interface::Spi => self.build_spi(num_pixels)?,
+        };
+
+        if let Some(color_order) = self.color_order {
+            blinkt.set_color_order(color_order);
+        }
+
+        if let Some(gamma) = self.gamma {
+            blinkt.set_gamma(gamma);
+        }
+
+        Ok(blinkt)
+    }
+
+    fn build_spi(&self, num_pixels: usize) -> Result<Blinkt> {
+        let clock_speed_hz = self.clock_speed_hz.unwrap_or(SPI_CLOCK_SPEED_HZ);
+
+        if let Some(ref path) = self.spidev {
+            return spidev(path, clock_speed_hz, num_pixels);
+        }
+
+        match self.spi_chunk_size {
+            Some(chunk_size) => Blinkt::with_spi_chunk_size(clock_speed_hz, num_pixels, chunk_size),
+            None => Blinkt::with_spi(clock_speed_hz, num_pixels),
+        }
+    }
+}
+
+#[cfg(feature = "spidev")]
+fn spidev(path: &str, clock_speed_hz: u32, num_pixels: usize) -> Result<Blinkt> {
+    Blinkt::with_spidev(path, clock_speed_hz, num_pixels)
+}
+
+#[cfg(not(feature = "spidev"))]
+fn spidev(_path: &str, _clock_speed_hz: u32, _num_pixels: usize) -> Result<Blinkt> {
+    Err(invalid_data(
+        "the spidev setting requires the spidev feature".to_owned(),
+    ))
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
@@ -0,0 +1,240 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// Helpers shared by the network control interfaces.
+
+#[cfg(any(feature = "mqtt", feature = "rest", feature = "wled"))]
+use std::fmt;
+#[cfg(any(feature = "mqtt", feature = "rest", feature = "wled"))]
+use std::time::{Duration, Instant};
+
+#[cfg(any(feature = "ipc", feature = "rest"))]
+use serde_json::json;
+#[cfg(any(feature = "ipc", feature = "mqtt", feature = "rest"))]
+use serde_json::Value;
+
+#[cfg(any(feature = "mqtt", feature = "rest", feature = "wled"))]
+use crate::animation::Animation;
+#[cfg(any(feature = "ipc", feature = "mqtt", feature = "rest", feature = "wled"))]
+use crate::Blinkt;
+use crate::Color;
+#[cfg(any(feature = "mqtt", feature = "rest", feature = "wled"))]
+use crate::{Frame, Result};
+
+// Roughly 30 frames per second
+#[cfg(any(feature = "mqtt", feature = "rest", feature = "wled"))]
+const EFFECT_FRAME_INTERVAL_MS: u64 = 33;
+
+#[cfg(any(feature = "mqtt", feature = "rest", feature = "wled"))]
+struct Effect {
+    name: String,
+    animation: Box<dyn Animation + Send>,
+}
+
+#[cfg(any(feature = "mqtt", feature = "rest", feature = "wled"))]
+impl fmt::Debug for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Effect").field("name", &self.name).finish()
+    }
+}
+
+// A list of named effects, of which at most one is selected and rendered.
+#[cfg(any(feature = "mqtt", feature = "rest", feature = "wled"))]
+#[derive(Debug)]
+pub(crate) struct Effects {
+    effects: Vec<Effect>,
+    selected: Option<usize>,
+    start: Instant,
+    next_frame: Instant,
+    // Rendered frame, or `None` when the selected effect needs to (re)start
+    // from the current contents of the local buffer.
+    frame: Option<Frame>,
+}
+
+#[cfg(any(feature = "mqtt", feature = "rest", feature = "wled"))]
+impl Effects {
+    pub(crate) fn new() -> Effects {
+        let now = Instant::now();
+
+        Effects {
+            effects: Vec::new(),
+            selected: None,
+            start: now,
+            next_frame: now,
+            frame: None,
+        }
+    }
+
+    // Adds an effect, or replaces an existing effect with the same name.
+    pub(crate) fn add(&mut self, name: &str, animation: Box<dyn Animation + Send>) {
+        match self.find(name) {
+            Some(index) => {
+                self.effects[index].animation = animation;
+                if self.selected == Some(index) {
+                    self.restart();
+                }
+            }
+            None => self.effects.push(Effect {
+                name: name.to_owned(),
+                animation,
+            }),
+        }
+    }
+
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.effects.iter().map(|effect| &effect.name[..])
+    }
+
+    pub(crate) fn find(&self, name: &str) -> Option<usize> {
+        self.effects.iter().position(|effect| effect.name == name)
+    }
+
+    pub(crate) fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    #[cfg(any(feature = "mqtt", feature = "rest"))]
+    pub(crate) fn selected_name(&self) -> Option<&str> {
+        self.selected.map(|index| &self.effects[index].name[..])
+    }
+
+    // Selects an effect by index, or deselects the current effect. A newly
+    // selected effect starts from the beginning.
+    pub(crate) fn select(&mut self, index: Option<usize>) {
+        if index != self.selected {
+            self.selected = index;
+            self.restart();
+        }
+    }
+
+    pub(crate) fn restart(&mut self) {
+        self.frame = None;
+    }
+
+    // Returns the time left until the next frame of the selected effect is
+    // due.
+    pub(crate) fn until_next_frame(&self) -> Duration {
+        let now = Instant::now();
+        if self.next_frame > now {
+            self.next_frame - now
+        } else {
+            Duration::from_millis(0)
+        }
+    }
+
+    // Renders the next frame of the selected effect, and sends it to the
+    // pixels. `brightness` overrides the brightness of the rendered frame.
+    pub(crate) fn render(&mut self, blinkt: &mut Blinkt, brightness: Option<f32>) -> Result<()> {
+        let effect = match self.selected {
+            Some(index) => &mut self.effects[index],
+            None => return Ok(()),
+        };
+
+        if self.frame.is_none() {
+            self.start = Instant::now();
+        }
+
+        let frame = self.frame.get_or_insert_with(|| blinkt.frame());
+        let t = self.start.elapsed();
+        if !effect.animation.is_finished(t) {
+            effect.animation.render(t, frame);
+        }
+
+        self.next_frame = Instant::now() + Duration::from_millis(EFFECT_FRAME_INTERVAL_MS);
+
+        blinkt.set_frame(frame);
+        if let Some(brightness) = brightness {
+            blinkt.set_all_pixels_brightness(brightness);
+        }
+
+        blinkt.swap();
+        blinkt.show()
+    }
+}
+
+// Parses `#rrggbb`.
+pub(crate) fn parse_hex(text: &str) -> Option<Color> {
+    if text.len() != 7 || !text.starts_with('#') {
+        return None;
+    }
+
+    let hex = &text[1..];
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let value = u32::from_str_radix(hex, 16).ok()?;
+
+    Some(Color::from((
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    )))
+}
+
+// Parses a JSON color, either as `"#rrggbb"` or `[r, g, b]`.
+#[cfg(any(feature = "ipc", feature = "mqtt", feature = "rest"))]
+pub(crate) fn json_color(value: &Value) -> Option<Color> {
+    match *value {
+        Value::String(ref text) => parse_hex(text),
+        Value::Array(ref values) if values.len() == 3 => {
+            let mut rgb = [0u8; 3];
+            for (channel, value) in rgb.iter_mut().zip(values) {
+                let value = value.as_u64()?;
+                if value > 255 {
+                    return None;
+                }
+
+                *channel = value as u8;
+            }
+
+            Some(Color::from((rgb[0], rgb[1], rgb[2])))
+        }
+        _ => None,
+    }
+}
+
+// Returns the local buffer as a JSON array, with the color and brightness of
+// each pixel, such as `[{"color":"#ff0000","brightness":0.5}]`.
+#[cfg(any(feature = "ipc", feature = "rest"))]
+pub(crate) fn pixels_json(blinkt: &Blinkt) -> Value {
+    blinkt
+        .frame()
+        .iter()
+        .map(|pixel| {
+            let (red, green, blue) = pixel.rgb();
+            json!({
+                "color": format!("#{:02x}{:02x}{:02x}", red, green, blue),
+                "brightness": json_f32(pixel.brightness()),
+            })
+        })
+        .collect()
+}
+
+// Converts a brightness value to a JSON number with the shortest
+// representation of the f32 value, so 0.7 isn't sent as 0.699999988079071.
+// Values JSON can't represent are replaced by 0.
+#[cfg(any(feature = "ipc", feature = "rest"))]
+pub(crate) fn json_f32(value: f32) -> Value {
+    match value.to_string().parse::<f64>() {
+        Ok(value) if value.is_finite() => json!(value),
+        _ => json!(0),
+    }
+}
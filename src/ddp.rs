@@ -0,0 +1,152 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Receiving realtime pixel data through DDP (Distributed Display Protocol).
+//!
+//! A [`DdpReceiver`] listens for DDP packets, such as those sent by LedFx
+//! or xLights, and copies their RGB data to the pixels. Senders split long
+//! strips over multiple packets, each with a byte offset into the frame,
+//! and set the push flag on the last packet of a frame, at which point the
+//! frame is sent to the pixels.
+//!
+//! Only packets for the default output device (ID 1) or all devices
+//! (ID 255) are used, and the data is interpreted as 8-bit red, green and
+//! blue values. Query packets aren't answered.
+//!
+//! This module requires the `ddp` feature.
+//!
+//! ```rust,no_run
+//! use blinkt::ddp::DdpReceiver;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 300)?;
+//! let mut receiver = DdpReceiver::new()?;
+//!
+//! receiver.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`DdpReceiver`]: struct.DdpReceiver.html
+
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::{Blinkt, Result};
+
+/// The UDP port used by DDP.
+pub const PORT: u16 = 4048;
+
+const HEADER_LEN: usize = 10;
+const TIMECODE_LEN: usize = 4;
+const VERSION_MASK: u8 = 0xc0;
+const VERSION_1: u8 = 0x40;
+const FLAG_TIMECODE: u8 = 0x10;
+const FLAG_QUERY: u8 = 0x02;
+const FLAG_PUSH: u8 = 0x01;
+const ID_DISPLAY: u8 = 1;
+const ID_ALL: u8 = 255;
+// Maximum packet size: a 14-byte header and 1440 bytes of data.
+const MAX_PACKET_LEN: usize = 1454;
+
+/// A DDP receiver that drives the pixels of a `Blinkt`.
+#[derive(Debug)]
+pub struct DdpReceiver {
+    socket: UdpSocket,
+    frame: Vec<u8>,
+    buffer: Vec<u8>,
+}
+
+impl DdpReceiver {
+    /// Constructs a new `DdpReceiver` listening on the standard DDP port on
+    /// all network interfaces.
+    pub fn new() -> Result<DdpReceiver> {
+        DdpReceiver::bind(("0.0.0.0", PORT))
+    }
+
+    /// Constructs a new `DdpReceiver` listening on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<DdpReceiver> {
+        Ok(DdpReceiver {
+            socket: UdpSocket::bind(addr)?,
+            frame: Vec::new(),
+            buffer: vec![0u8; MAX_PACKET_LEN],
+        })
+    }
+
+    /// Returns the address the receiver is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Receives and processes packets until an error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Waits for a single packet, and processes it.
+    ///
+    /// Returns `Ok(true)` if a frame was sent to the pixels. Data for pixels
+    /// beyond the end of the strip is ignored, as are packets that aren't
+    /// valid DDP packets.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        let (len, _) = self.socket.recv_from(&mut self.buffer)?;
+        let packet = &self.buffer[..len];
+
+        if len < HEADER_LEN || packet[0] & VERSION_MASK != VERSION_1 || packet[0] & FLAG_QUERY != 0
+        {
+            return Ok(false);
+        }
+
+        if packet[3] != ID_DISPLAY && packet[3] != ID_ALL {
+            return Ok(false);
+        }
+
+        let offset = (usize::from(packet[4]) << 24)
+            | (usize::from(packet[5]) << 16)
+            | (usize::from(packet[6]) << 8)
+            | usize::from(packet[7]);
+        let data_len = (usize::from(packet[8]) << 8) | usize::from(packet[9]);
+
+        let start = if packet[0] & FLAG_TIMECODE != 0 {
+            HEADER_LEN + TIMECODE_LEN
+        } else {
+            HEADER_LEN
+        };
+        let data = &packet[start.min(len)..(start + data_len).min(len)];
+
+        // The frame is kept separately from the local buffer, since packets
+        // don't necessarily start or end on a pixel boundary.
+        self.frame.resize(blinkt.num_pixels() * 3, 0);
+        if offset < self.frame.len() {
+            let end = (offset + data.len()).min(self.frame.len());
+            self.frame[offset..end].copy_from_slice(&data[..end - offset]);
+        }
+
+        if packet[0] & FLAG_PUSH == 0 {
+            return Ok(false);
+        }
+
+        blinkt.show_rgb(&self.frame)?;
+
+        Ok(true)
+    }
+}
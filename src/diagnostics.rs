@@ -0,0 +1,301 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Tools for verifying long-term reliability.
+//!
+//! [`soak_test`] continuously drives the pixels for an extended period of
+//! time, while recording transmission errors, timing jitter and the system
+//! temperature. The resulting [`SoakReport`] can be used to sign off a
+//! permanent installation.
+//!
+//! ```rust,no_run
+//! # use std::error::Error;
+//! # use std::time::Duration;
+//! #
+//! # use blinkt::diagnostics;
+//! # use blinkt::{Blinkt, Frame};
+//! #
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 300)?;
+//!
+//! // Drive all pixels at full white for 8 hours.
+//! let mut pattern = |_t: Duration, frame: &mut Frame| frame.set_all_pixels(255, 255, 255);
+//! let report = diagnostics::soak_test(&mut blinkt, Duration::from_secs(8 * 3600), &mut pattern, 30.0);
+//!
+//! println!("{}", report);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`soak_test`]: fn.soak_test.html
+//! [`SoakReport`]: struct.SoakReport.html
+
+use std::fmt;
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::animation::{as_secs_f32, Animation};
+use crate::Blinkt;
+
+// Reports the SoC temperature in millidegrees Celsius on a Raspberry Pi.
+pub(crate) const THERMAL_ZONE: &str = "/sys/class/thermal/thermal_zone0/temp";
+// Temperature readings are relatively slow, so only sample once per second.
+const TEMPERATURE_INTERVAL_MS: u64 = 1000;
+
+/// The results of a [`soak_test`].
+///
+/// `SoakReport` implements `Display`, which formats the results as a
+/// human-readable report.
+///
+/// [`soak_test`]: fn.soak_test.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoakReport {
+    elapsed: Duration,
+    target_interval: Duration,
+    frames: u64,
+    errors: u64,
+    last_error: Option<String>,
+    mean_interval: Duration,
+    max_interval: Duration,
+    jitter: Duration,
+    min_temperature: Option<f32>,
+    max_temperature: Option<f32>,
+}
+
+impl SoakReport {
+    /// Returns the total duration of the test.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the number of frames that were sent.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Returns the number of frames that failed to transmit.
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Returns the fraction of frames that failed to transmit, as a value
+    /// between `0.0` and `1.0`.
+    pub fn error_rate(&self) -> f32 {
+        if self.frames == 0 {
+            0.0
+        } else {
+            self.errors as f32 / self.frames as f32
+        }
+    }
+
+    /// Returns the description of the most recent transmission error.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_ref().map(AsRef::as_ref)
+    }
+
+    /// Returns the average time between the start of two consecutive frames.
+    pub fn mean_interval(&self) -> Duration {
+        self.mean_interval
+    }
+
+    /// Returns the longest time between the start of two consecutive frames.
+    pub fn max_interval(&self) -> Duration {
+        self.max_interval
+    }
+
+    /// Returns the timing jitter, calculated as the standard deviation of
+    /// the time between the start of two consecutive frames.
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    /// Returns the lowest system temperature in degrees Celsius, or `None` if
+    /// the temperature couldn't be read.
+    pub fn min_temperature(&self) -> Option<f32> {
+        self.min_temperature
+    }
+
+    /// Returns the highest system temperature in degrees Celsius, or `None`
+    /// if the temperature couldn't be read.
+    pub fn max_temperature(&self) -> Option<f32> {
+        self.max_temperature
+    }
+}
+
+impl fmt::Display for SoakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Duration: {:.1} s", as_secs_f32(self.elapsed))?;
+        writeln!(
+            f,
+            "Frames: {} ({} failed, {:.3}%)",
+            self.frames,
+            self.errors,
+            self.error_rate() * 100.0
+        )?;
+
+        if let Some(ref err) = self.last_error {
+            writeln!(f, "Last error: {}", err)?;
+        }
+
+        writeln!(
+            f,
+            "Frame interval: {:.2} ms target, {:.2} ms mean, {:.2} ms max, {:.2} ms jitter",
+            as_secs_f32(self.target_interval) * 1000.0,
+            as_secs_f32(self.mean_interval) * 1000.0,
+            as_secs_f32(self.max_interval) * 1000.0,
+            as_secs_f32(self.jitter) * 1000.0
+        )?;
+
+        match (self.min_temperature, self.max_temperature) {
+            (Some(min), Some(max)) => {
+                write!(f, "Temperature: {:.1} °C min, {:.1} °C max", min, max)
+            }
+            _ => write!(f, "Temperature: unavailable"),
+        }
+    }
+}
+
+/// Drives the pixels with `pattern` at `fps` frames per second for the
+/// specified duration, and returns a report.
+///
+/// Unlike an [`Animator`], the test continues when a frame fails to
+/// transmit, so intermittent errors are counted instead of ending the test.
+/// The system temperature is sampled once per second, if available.
+///
+/// The frame rate is limited to a range of 0.1 to 1000 frames per second.
+///
+/// [`Animator`]: ../animation/struct.Animator.html
+pub fn soak_test<A>(
+    blinkt: &mut Blinkt,
+    duration: Duration,
+    pattern: &mut A,
+    fps: f32,
+) -> SoakReport
+where
+    A: Animation + ?Sized,
+{
+    let fps = fps.max(0.1).min(1000.0);
+    let target_interval = Duration::from_micros((1_000_000.0 / fps) as u64);
+
+    let mut frame = blinkt.frame();
+    let mut frames = 0u64;
+    let mut errors = 0u64;
+    let mut last_error = None;
+
+    // Running sums of the frame intervals in seconds, used to calculate the
+    // mean and standard deviation.
+    let mut interval_sum = 0f64;
+    let mut interval_sum_squares = 0f64;
+    let mut max_interval = Duration::from_millis(0);
+
+    let mut min_temperature: Option<f32> = None;
+    let mut max_temperature: Option<f32> = None;
+    let mut last_temperature: Option<Instant> = None;
+
+    let start = Instant::now();
+    let mut previous_frame: Option<Instant> = None;
+    let mut next_frame = Duration::from_millis(0);
+
+    while start.elapsed() < duration {
+        let now = Instant::now();
+        if let Some(previous_frame) = previous_frame {
+            let interval = now - previous_frame;
+            let seconds = f64::from(as_secs_f32(interval));
+
+            interval_sum += seconds;
+            interval_sum_squares += seconds * seconds;
+            max_interval = max_interval.max(interval);
+        }
+        previous_frame = Some(now);
+
+        let sample_temperature = match last_temperature {
+            Some(last) => last.elapsed() >= Duration::from_millis(TEMPERATURE_INTERVAL_MS),
+            None => true,
+        };
+
+        if sample_temperature {
+            if let Some(temperature) = read_temperature() {
+                min_temperature =
+                    Some(min_temperature.map_or(temperature, |min| min.min(temperature)));
+                max_temperature =
+                    Some(max_temperature.map_or(temperature, |max| max.max(temperature)));
+            }
+
+            last_temperature = Some(Instant::now());
+        }
+
+        pattern.render(start.elapsed(), &mut frame);
+        blinkt.set_frame(&frame);
+        blinkt.swap();
+
+        frames += 1;
+        if let Err(err) = blinkt.show() {
+            errors += 1;
+            last_error = Some(err.to_string());
+        }
+
+        // Skip any frames we've missed.
+        let elapsed = start.elapsed();
+        while next_frame <= elapsed {
+            next_frame += target_interval;
+        }
+
+        let elapsed = start.elapsed();
+        if next_frame > elapsed {
+            thread::sleep(next_frame - elapsed);
+        }
+    }
+
+    let intervals = frames.saturating_sub(1) as f64;
+    let (mean, variance) = if intervals > 0.0 {
+        let mean = interval_sum / intervals;
+        (
+            mean,
+            (interval_sum_squares / intervals - mean * mean).max(0.0),
+        )
+    } else {
+        (0.0, 0.0)
+    };
+
+    SoakReport {
+        elapsed: start.elapsed(),
+        target_interval,
+        frames,
+        errors,
+        last_error,
+        mean_interval: duration_from_secs(mean),
+        max_interval,
+        jitter: duration_from_secs(variance.sqrt()),
+        min_temperature,
+        max_temperature,
+    }
+}
+
+fn duration_from_secs(seconds: f64) -> Duration {
+    Duration::from_nanos((seconds * 1_000_000_000.0) as u64)
+}
+
+// Returns the current system temperature in degrees Celsius.
+fn read_temperature() -> Option<f32> {
+    let millidegrees: f32 = fs::read_to_string(THERMAL_ZONE).ok()?.trim().parse().ok()?;
+
+    Some(millidegrees / 1000.0)
+}
@@ -0,0 +1,128 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::pixel::Pixel;
+
+// Temporal dithering for 16-bit color values. Each channel is treated as an
+// 8.8 fixed-point value. The fractional part that can't be displayed is
+// carried over to the next frame, so the average output over a number of
+// frames approximates the full 16-bit value.
+#[derive(Debug, Clone)]
+pub(crate) struct Dither {
+    values: Vec<[u16; 3]>, // Red, green, blue
+    error: Vec<[u16; 3]>,
+}
+
+impl Dither {
+    pub(crate) fn new(pixels: &[Pixel]) -> Dither {
+        Dither {
+            values: pixels
+                .iter()
+                .map(|pixel| {
+                    let (red, green, blue) = pixel.rgb();
+                    [to_u16(red), to_u16(green), to_u16(blue)]
+                })
+                .collect(),
+            error: vec![[0u16; 3]; pixels.len()],
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set_rgb16(&mut self, pixel: usize, red: u16, green: u16, blue: u16) {
+        if let Some(value) = self.values.get_mut(pixel) {
+            *value = [red, green, blue];
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set_rgb(&mut self, pixel: usize, red: u8, green: u8, blue: u8) {
+        self.set_rgb16(pixel, to_u16(red), to_u16(green), to_u16(blue));
+    }
+
+    pub(crate) fn set_all_rgb16(&mut self, red: u16, green: u16, blue: u16) {
+        for value in &mut self.values {
+            *value = [red, green, blue];
+        }
+    }
+
+    pub(crate) fn set_all_rgb(&mut self, red: u8, green: u8, blue: u8) {
+        self.set_all_rgb16(to_u16(red), to_u16(green), to_u16(blue));
+    }
+
+    pub(crate) fn fade(&mut self, factor: f32, floor: u8) {
+        let factor = factor.max(0.0).min(1.0);
+        let floor = to_u16(floor);
+
+        for value in &mut self.values {
+            for channel in value.iter_mut() {
+                if *channel > floor {
+                    let mut faded = (f32::from(*channel) * factor).round() as u16;
+                    if faded >= *channel && factor < 1.0 {
+                        faded = *channel - 1;
+                    }
+
+                    *channel = faded.max(floor);
+                }
+            }
+        }
+    }
+
+    // Adds or removes pixels at the end. Added pixels are turned off.
+    pub(crate) fn resize(&mut self, num_pixels: usize) {
+        self.values.resize(num_pixels, [0u16; 3]);
+        self.error.resize(num_pixels, [0u16; 3]);
+    }
+
+    // Copies the 16-bit values from other, while keeping the carried over
+    // error for each pixel.
+    pub(crate) fn copy_values_from(&mut self, other: &Dither) {
+        self.values.clone_from(&other.values);
+        self.error.resize(self.values.len(), [0u16; 3]);
+    }
+
+    #[inline]
+    pub(crate) fn values_mut(&mut self) -> &mut [[u16; 3]] {
+        &mut self.values
+    }
+
+    /// Returns the dithered 8-bit red, green and blue values for the next
+    /// frame, and updates the carried over error.
+    pub(crate) fn next_rgb(&mut self, pixel: usize) -> Option<(u8, u8, u8)> {
+        let value = self.values.get(pixel)?;
+        let error = self.error.get_mut(pixel)?;
+
+        let mut rgb = [0u8; 3];
+        for channel in 0..3 {
+            let total = u32::from(value[channel]) + u32::from(error[channel]);
+            let output = (total >> 8).min(255);
+
+            rgb[channel] = output as u8;
+            error[channel] = (total - (output << 8)).min(255) as u16;
+        }
+
+        Some((rgb[0], rgb[1], rgb[2]))
+    }
+}
+
+// Converts an 8-bit value to 8.8 fixed-point.
+#[inline]
+fn to_u16(value: u8) -> u16 {
+    u16::from(value) << 8
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// DMX universe handling shared by the network lighting protocol receivers.
+
+use crate::Blinkt;
+
+// Number of RGB pixels that fit in a single 512-channel DMX universe.
+pub(crate) const PIXELS_PER_UNIVERSE: usize = 170;
+
+/// Determines when frames received over the network are sent to the
+/// pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FlushPolicy {
+    /// Sends a frame after every DMX packet for a mapped universe.
+    Immediate,
+    /// Sends a frame once every mapped universe has been received since the
+    /// previous frame. This avoids tearing when the pixels span multiple
+    /// universes.
+    AllUniverses,
+    /// Sends a frame when a synchronization packet is received, which lets
+    /// the controller update multiple nodes at the same time.
+    Sync,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    universe: u16,
+    start_pixel: usize,
+    // Number of pixels covered by the most recent packet.
+    num_pixels: usize,
+    received: bool,
+}
+
+// Maps DMX universes onto ranges of pixels, and keeps track of which
+// universes were received since the last frame. Each universe contains the
+// red, green and blue values for up to 170 consecutive pixels.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UniverseMap {
+    entries: Vec<Entry>,
+}
+
+impl UniverseMap {
+    pub(crate) fn map(&mut self, universe: u16, start_pixel: usize) {
+        match self.entry_mut(universe) {
+            Some(entry) => entry.start_pixel = start_pixel,
+            None => self.entries.push(Entry {
+                universe,
+                start_pixel,
+                num_pixels: 0,
+                received: false,
+            }),
+        }
+    }
+
+    pub(crate) fn unmap(&mut self, universe: u16) {
+        self.entries.retain(|entry| entry.universe != universe);
+    }
+
+    #[cfg(feature = "sacn")]
+    pub(crate) fn contains(&self, universe: u16) -> bool {
+        self.entries.iter().any(|entry| entry.universe == universe)
+    }
+
+    #[cfg(feature = "artnet")]
+    pub(crate) fn universes(&self) -> impl Iterator<Item = u16> + '_ {
+        self.entries.iter().map(|entry| entry.universe)
+    }
+
+    // Copies the channel values for `universe` to the local buffer. Returns
+    // `false` if the universe isn't mapped.
+    pub(crate) fn apply(&mut self, blinkt: &mut Blinkt, universe: u16, channels: &[u8]) -> bool {
+        let entry = match self.entry_mut(universe) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        let num_pixels = (channels.len() / 3).min(PIXELS_PER_UNIVERSE);
+        for (offset, rgb) in channels.chunks(3).take(num_pixels).enumerate() {
+            blinkt.set_pixel(entry.start_pixel + offset, rgb[0], rgb[1], rgb[2]);
+        }
+
+        entry.num_pixels = num_pixels;
+        entry.received = true;
+
+        true
+    }
+
+    // Turns off the pixels covered by the most recent packet for `universe`.
+    #[cfg(feature = "sacn")]
+    pub(crate) fn blank(&mut self, blinkt: &mut Blinkt, universe: u16) {
+        if let Some(entry) = self.entry_mut(universe) {
+            for pixel in entry.start_pixel..entry.start_pixel + entry.num_pixels {
+                blinkt.set_pixel(pixel, 0, 0, 0);
+            }
+        }
+    }
+
+    pub(crate) fn all_received(&self) -> bool {
+        !self.entries.is_empty() && self.entries.iter().all(|entry| entry.received)
+    }
+
+    pub(crate) fn reset_received(&mut self) {
+        for entry in &mut self.entries {
+            entry.received = false;
+        }
+    }
+
+    fn entry_mut(&mut self, universe: u16) -> Option<&mut Entry> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.universe == universe)
+    }
+}
+
+// Sends the local buffer to the pixels, and starts a new frame.
+pub(crate) fn flush(blinkt: &mut Blinkt, universes: &mut UniverseMap) -> crate::Result<()> {
+    universes.reset_received();
+    blinkt.swap();
+    blinkt.show()
+}
@@ -0,0 +1,209 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Easing functions for smoother fades and movements.
+//!
+//! Each function takes the linear progress of an animation as a floating
+//! point value between `0.0` and `1.0`, and returns the eased progress.
+//! Values outside that range are clamped. All functions return `0.0` at the
+//! start and `1.0` at the end, although the elastic functions overshoot in
+//! between.
+//!
+//! The eased progress can be passed directly to interpolation helpers such
+//! as [`Color::interpolate`], or used to calculate positions in an
+//! [`Animation`].
+//!
+//! ```rust
+//! use blinkt::easing::{self, Easing};
+//! use blinkt::{Color, Interpolation};
+//!
+//! let black = Color::new(0, 0, 0);
+//! let white = Color::new(255, 255, 255);
+//!
+//! let color = black.interpolate(white, easing::ease_in_quad(0.5), Interpolation::Rgb);
+//! assert_eq!(color, Color::new(64, 64, 64));
+//!
+//! assert_eq!(Easing::EaseInOutCubic.apply(1.0), 1.0);
+//! ```
+//!
+//! [`Color::interpolate`]: ../struct.Color.html#method.interpolate
+//! [`Animation`]: ../animation/trait.Animation.html
+
+use std::f32::consts::PI;
+
+/// Easing functions that can be selected at runtime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Easing {
+    /// See [`linear`](fn.linear.html).
+    Linear,
+    /// See [`ease_in_quad`](fn.ease_in_quad.html).
+    EaseInQuad,
+    /// See [`ease_out_quad`](fn.ease_out_quad.html).
+    EaseOutQuad,
+    /// See [`ease_in_out_quad`](fn.ease_in_out_quad.html).
+    EaseInOutQuad,
+    /// See [`ease_in_cubic`](fn.ease_in_cubic.html).
+    EaseInCubic,
+    /// See [`ease_out_cubic`](fn.ease_out_cubic.html).
+    EaseOutCubic,
+    /// See [`ease_in_out_cubic`](fn.ease_in_out_cubic.html).
+    EaseInOutCubic,
+    /// See [`ease_in_sine`](fn.ease_in_sine.html).
+    EaseInSine,
+    /// See [`ease_out_sine`](fn.ease_out_sine.html).
+    EaseOutSine,
+    /// See [`ease_in_out_sine`](fn.ease_in_out_sine.html).
+    EaseInOutSine,
+    /// See [`ease_in_elastic`](fn.ease_in_elastic.html).
+    EaseInElastic,
+    /// See [`ease_out_elastic`](fn.ease_out_elastic.html).
+    EaseOutElastic,
+    /// See [`ease_in_out_elastic`](fn.ease_in_out_elastic.html).
+    EaseInOutElastic,
+}
+
+impl Easing {
+    /// Applies the easing function to `t`.
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => linear(t),
+            Easing::EaseInQuad => ease_in_quad(t),
+            Easing::EaseOutQuad => ease_out_quad(t),
+            Easing::EaseInOutQuad => ease_in_out_quad(t),
+            Easing::EaseInCubic => ease_in_cubic(t),
+            Easing::EaseOutCubic => ease_out_cubic(t),
+            Easing::EaseInOutCubic => ease_in_out_cubic(t),
+            Easing::EaseInSine => ease_in_sine(t),
+            Easing::EaseOutSine => ease_out_sine(t),
+            Easing::EaseInOutSine => ease_in_out_sine(t),
+            Easing::EaseInElastic => ease_in_elastic(t),
+            Easing::EaseOutElastic => ease_out_elastic(t),
+            Easing::EaseInOutElastic => ease_in_out_elastic(t),
+        }
+    }
+}
+
+#[inline]
+fn clamp(t: f32) -> f32 {
+    t.max(0.0).min(1.0)
+}
+
+/// Returns `t` unchanged, resulting in a constant speed.
+pub fn linear(t: f32) -> f32 {
+    clamp(t)
+}
+
+/// Starts slowly and accelerates (quadratic).
+pub fn ease_in_quad(t: f32) -> f32 {
+    let t = clamp(t);
+    t * t
+}
+
+/// Starts quickly and decelerates (quadratic).
+pub fn ease_out_quad(t: f32) -> f32 {
+    let t = clamp(t);
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+/// Accelerates until halfway, then decelerates (quadratic).
+pub fn ease_in_out_quad(t: f32) -> f32 {
+    let t = clamp(t);
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+    }
+}
+
+/// Starts slowly and accelerates (cubic).
+pub fn ease_in_cubic(t: f32) -> f32 {
+    let t = clamp(t);
+    t * t * t
+}
+
+/// Starts quickly and decelerates (cubic).
+pub fn ease_out_cubic(t: f32) -> f32 {
+    let t = clamp(t);
+    1.0 - (1.0 - t).powi(3)
+}
+
+/// Accelerates until halfway, then decelerates (cubic).
+pub fn ease_in_out_cubic(t: f32) -> f32 {
+    let t = clamp(t);
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+/// Starts slowly and accelerates (sinusoidal).
+pub fn ease_in_sine(t: f32) -> f32 {
+    1.0 - (clamp(t) * PI / 2.0).cos()
+}
+
+/// Starts quickly and decelerates (sinusoidal).
+pub fn ease_out_sine(t: f32) -> f32 {
+    (clamp(t) * PI / 2.0).sin()
+}
+
+/// Accelerates until halfway, then decelerates (sinusoidal).
+pub fn ease_in_out_sine(t: f32) -> f32 {
+    -((clamp(t) * PI).cos() - 1.0) / 2.0
+}
+
+/// Winds up with a growing oscillation before accelerating to the end.
+pub fn ease_in_elastic(t: f32) -> f32 {
+    let t = clamp(t);
+    if t <= 0.0 || t >= 1.0 {
+        return t;
+    }
+
+    -(2f32).powf(10.0 * t - 10.0) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+}
+
+/// Overshoots the end, and settles with a decaying oscillation.
+pub fn ease_out_elastic(t: f32) -> f32 {
+    let t = clamp(t);
+    if t <= 0.0 || t >= 1.0 {
+        return t;
+    }
+
+    (2f32).powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+}
+
+/// Combines [`ease_in_elastic`] and [`ease_out_elastic`], oscillating at
+/// both the start and the end.
+///
+/// [`ease_in_elastic`]: fn.ease_in_elastic.html
+/// [`ease_out_elastic`]: fn.ease_out_elastic.html
+pub fn ease_in_out_elastic(t: f32) -> f32 {
+    let t = clamp(t);
+    if t <= 0.0 || t >= 1.0 {
+        return t;
+    }
+
+    let wave = ((20.0 * t - 11.125) * (2.0 * PI / 4.5)).sin();
+    if t < 0.5 {
+        -((2f32).powf(20.0 * t - 10.0) * wave) / 2.0
+    } else {
+        (2f32).powf(-20.0 * t + 10.0) * wave / 2.0 + 1.0
+    }
+}
@@ -0,0 +1,222 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Ready-made effects.
+//!
+//! Each effect implements [`Animation`], so it can be played with an
+//! [`Animator`], or offered by the network control interfaces, such as the
+//! `rest` and `wled` servers.
+//!
+//! ```rust,no_run
+//! # use std::error::Error;
+//! # use std::time::Duration;
+//! #
+//! # use blinkt::animation::Animator;
+//! # use blinkt::effects::Rainbow;
+//! # use blinkt::Blinkt;
+//! #
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let mut blinkt = Blinkt::new()?;
+//!
+//! Animator::new(30.0)
+//!     .with_duration(Duration::from_secs(10))
+//!     .run(&mut blinkt, &mut Rainbow::new())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Animation`]: ../animation/trait.Animation.html
+//! [`Animator`]: ../animation/struct.Animator.html
+
+use std::f32::consts::PI;
+use std::time::Duration;
+
+use crate::animation::{as_secs_f32, Animation};
+use crate::frame::Frame;
+use crate::palette::Palette;
+
+const DEFAULT_RAINBOW_PERIOD_SECS: u64 = 5;
+const DEFAULT_BREATHE_PERIOD_SECS: u64 = 4;
+const DEFAULT_ROTATE_SPEED: f32 = 10.0;
+
+/// Scrolls a rainbow along the pixels.
+#[derive(Debug, Clone)]
+pub struct Rainbow {
+    palette: Palette,
+    period: Duration,
+}
+
+impl Rainbow {
+    /// Constructs a new `Rainbow` that scrolls along the entire strip once
+    /// every 5 seconds.
+    pub fn new() -> Rainbow {
+        Rainbow {
+            palette: Palette::rainbow(),
+            period: Duration::from_secs(DEFAULT_RAINBOW_PERIOD_SECS),
+        }
+    }
+
+    /// Sets the time it takes for the rainbow to scroll along the entire
+    /// strip.
+    pub fn with_period(mut self, period: Duration) -> Rainbow {
+        self.period = period;
+        self
+    }
+}
+
+impl Default for Rainbow {
+    fn default() -> Rainbow {
+        Rainbow::new()
+    }
+}
+
+impl Animation for Rainbow {
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        let len = frame.len();
+        let offset = as_secs_f32(t) / as_secs_f32(self.period).max(0.001);
+
+        for pixel in 0..len {
+            let position = offset + pixel as f32 / len as f32;
+            frame.set_pixel_color(pixel, self.palette.color_at_wrapping(position));
+        }
+    }
+}
+
+// The contents of the pixels when an effect started. A new start is
+// detected when the elapsed time goes backwards.
+#[derive(Debug, Clone, Default)]
+struct Start {
+    frame: Option<Frame>,
+    previous: Duration,
+}
+
+impl Start {
+    fn update(&mut self, t: Duration, frame: &Frame) -> &Frame {
+        if t < self.previous {
+            self.frame = None;
+        }
+
+        self.previous = t;
+        self.frame.get_or_insert_with(|| frame.clone())
+    }
+}
+
+/// Slowly fades the colors the pixels had when the effect started in and
+/// out.
+#[derive(Debug, Clone)]
+pub struct Breathe {
+    period: Duration,
+    start: Start,
+}
+
+impl Breathe {
+    /// Constructs a new `Breathe` that fades in and out once every 4
+    /// seconds.
+    pub fn new() -> Breathe {
+        Breathe {
+            period: Duration::from_secs(DEFAULT_BREATHE_PERIOD_SECS),
+            start: Start::default(),
+        }
+    }
+
+    /// Sets the time it takes to fade in and out.
+    pub fn with_period(mut self, period: Duration) -> Breathe {
+        self.period = period;
+        self
+    }
+}
+
+impl Default for Breathe {
+    fn default() -> Breathe {
+        Breathe::new()
+    }
+}
+
+impl Animation for Breathe {
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        let phase = as_secs_f32(t) / as_secs_f32(self.period).max(0.001);
+        let scale = 0.5 - 0.5 * (phase * 2.0 * PI).cos();
+        let start = self.start.update(t, frame);
+
+        for (pixel, start) in frame.iter_mut().zip(start.iter()) {
+            let (red, green, blue) = start.rgb();
+            pixel.set_rgb(
+                (f32::from(red) * scale) as u8,
+                (f32::from(green) * scale) as u8,
+                (f32::from(blue) * scale) as u8,
+            );
+        }
+    }
+}
+
+/// Rotates the colors the pixels had when the effect started along the
+/// strip.
+#[derive(Debug, Clone)]
+pub struct Rotate {
+    speed: f32,
+    start: Start,
+}
+
+impl Rotate {
+    /// Constructs a new `Rotate` that moves the colors by 10 pixels per
+    /// second.
+    pub fn new() -> Rotate {
+        Rotate {
+            speed: DEFAULT_ROTATE_SPEED,
+            start: Start::default(),
+        }
+    }
+
+    /// Sets the speed in pixels per second. Negative values rotate the
+    /// colors towards the start of the strip.
+    pub fn with_speed(mut self, speed: f32) -> Rotate {
+        self.speed = speed;
+        self
+    }
+}
+
+impl Default for Rotate {
+    fn default() -> Rotate {
+        Rotate::new()
+    }
+}
+
+impl Animation for Rotate {
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        let len = frame.len();
+        if len == 0 {
+            return;
+        }
+
+        let shift = (as_secs_f32(t) * self.speed) as i64 % len as i64;
+        let offset = if shift < 0 {
+            (shift + len as i64) as usize
+        } else {
+            shift as usize
+        };
+
+        let start = self.start.update(t, frame);
+        for (pixel, start) in start.iter().enumerate() {
+            if let Some(target) = frame.pixel_mut((pixel + offset) % len) {
+                target.set_color(start.color());
+            }
+        }
+    }
+}
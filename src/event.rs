@@ -0,0 +1,84 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::time::{Duration, Instant};
+
+use crate::pixel::Pixel;
+
+pub(crate) type ShowCallback = Box<dyn FnMut(&ShowEvent<'_>) + Send>;
+pub(crate) type ResizeCallback = Box<dyn FnMut(&ResizeEvent) + Send>;
+
+/// Information about a transmission, passed to the callbacks registered
+/// through `Blinkt::on_before_show()` and `Blinkt::on_after_show()`.
+#[derive(Debug)]
+pub struct ShowEvent<'a> {
+    pub(crate) pixels: &'a [Pixel],
+    pub(crate) frame_number: u64,
+    pub(crate) started: Instant,
+    pub(crate) duration: Option<Duration>,
+}
+
+impl<'a> ShowEvent<'a> {
+    /// Returns the contents of the local buffer that are being sent to the
+    /// pixels.
+    ///
+    /// The color and brightness values don't include any adjustments that are
+    /// applied during transmission, such as dithering or color space
+    /// conversion.
+    pub fn pixels(&self) -> &'a [Pixel] {
+        self.pixels
+    }
+
+    /// Returns the sequence number of this transmission, starting at `0`.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+
+    /// Returns the time at which the transmission started.
+    pub fn started(&self) -> Instant {
+        self.started
+    }
+
+    /// Returns the time it took to send the frame to the pixels, or `None`
+    /// when the transmission hasn't finished yet.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+}
+
+/// Information about a change in the number of pixels, passed to the
+/// callbacks registered through `Blinkt::on_resize()`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ResizeEvent {
+    pub(crate) old_len: usize,
+    pub(crate) new_len: usize,
+}
+
+impl ResizeEvent {
+    /// Returns the number of pixels before the resize.
+    pub fn old_len(&self) -> usize {
+        self.old_len
+    }
+
+    /// Returns the number of pixels after the resize.
+    pub fn new_len(&self) -> usize {
+        self.new_len
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! C interface.
+//!
+//! Exposes a `Blinkt` to C, C++ and any other language that can call C
+//! functions, when blinkt is built as a shared or static library. The
+//! functions are declared in `include/blinkt.h`, which is generated with
+//! [cbindgen]:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/blinkt.h
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! A `Blinkt` is created with [`blinkt_new`] or [`blinkt_with_spi`], and
+//! must be released with [`blinkt_destroy`]. Functions that can fail return
+//! `0` on success, or `-1` on failure, in which case
+//! [`blinkt_last_error`] describes the error. Pixels that are out of range
+//! are ignored, as they are by the corresponding `Blinkt` methods.
+//!
+//! ```c
+//! #include "blinkt.h"
+//!
+//! int main(void) {
+//!     Blinkt *blinkt = blinkt_with_spi(16000000, 144);
+//!     if (!blinkt) {
+//!         fprintf(stderr, "%s\n", blinkt_last_error());
+//!         return 1;
+//!     }
+//!
+//!     blinkt_set_all_pixels(blinkt, 255, 128, 0);
+//!     blinkt_show(blinkt);
+//!     blinkt_destroy(blinkt);
+//!     return 0;
+//! }
+//! ```
+//!
+//! This module requires the `ffi` feature.
+//!
+//! [cbindgen]: https://github.com/eqrion/cbindgen
+//! [`blinkt_new`]: fn.blinkt_new.html
+//! [`blinkt_with_spi`]: fn.blinkt_with_spi.html
+//! [`blinkt_destroy`]: fn.blinkt_destroy.html
+//! [`blinkt_last_error`]: fn.blinkt_last_error.html
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use crate::{Blinkt, Result};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', ""))
+        .unwrap_or_else(|_| CString::new("unknown error").unwrap());
+
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+fn into_raw(result: Result<Blinkt>) -> *mut Blinkt {
+    match result {
+        Ok(blinkt) => Box::into_raw(Box::new(blinkt)),
+        Err(err) => {
+            set_last_error(err.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Returns a description of the last error that occurred on the calling
+/// thread, or `NULL` if no error has occurred.
+///
+/// The string remains valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn blinkt_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| match *last_error.borrow() {
+        Some(ref message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Creates a `Blinkt` that drives a Blinkt! board, as `Blinkt::new()`.
+///
+/// Returns `NULL` on failure.
+#[no_mangle]
+pub extern "C" fn blinkt_new() -> *mut Blinkt {
+    into_raw(Blinkt::new())
+}
+
+/// Creates a `Blinkt` that drives a strip with `num_pixels` pixels through
+/// the SPI peripheral, as `Blinkt::with_spi()`.
+///
+/// Returns `NULL` on failure.
+#[no_mangle]
+pub extern "C" fn blinkt_with_spi(clock_speed_hz: u32, num_pixels: usize) -> *mut Blinkt {
+    into_raw(Blinkt::with_spi(clock_speed_hz, num_pixels))
+}
+
+/// Releases a `Blinkt`. Does nothing if `blinkt` is `NULL`.
+///
+/// # Safety
+///
+/// `blinkt` must have been returned by `blinkt_new()` or
+/// `blinkt_with_spi()`, and can't be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_destroy(blinkt: *mut Blinkt) {
+    if !blinkt.is_null() {
+        drop(Box::from_raw(blinkt));
+    }
+}
+
+/// Returns the number of pixels, or `0` if `blinkt` is `NULL`.
+///
+/// # Safety
+///
+/// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+/// `blinkt_with_spi()`.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_num_pixels(blinkt: *const Blinkt) -> usize {
+    match blinkt.as_ref() {
+        Some(blinkt) => blinkt.num_pixels(),
+        None => 0,
+    }
+}
+
+/// Sets the red, green and blue values of a single pixel.
+///
+/// # Safety
+///
+/// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+/// `blinkt_with_spi()`.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_set_pixel(
+    blinkt: *mut Blinkt,
+    pixel: usize,
+    red: u8,
+    green: u8,
+    blue: u8,
+) {
+    if let Some(blinkt) = blinkt.as_mut() {
+        blinkt.set_pixel(pixel, red, green, blue);
+    }
+}
+
+/// Sets the red, green, blue and brightness values of a single pixel.
+///
+/// # Safety
+///
+/// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+/// `blinkt_with_spi()`.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_set_pixel_rgbb(
+    blinkt: *mut Blinkt,
+    pixel: usize,
+    red: u8,
+    green: u8,
+    blue: u8,
+    brightness: f32,
+) {
+    if let Some(blinkt) = blinkt.as_mut() {
+        blinkt.set_pixel_rgbb(pixel, red, green, blue, brightness);
+    }
+}
+
+/// Sets the brightness of a single pixel, from `0.0` to `1.0`.
+///
+/// # Safety
+///
+/// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+/// `blinkt_with_spi()`.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_set_pixel_brightness(
+    blinkt: *mut Blinkt,
+    pixel: usize,
+    brightness: f32,
+) {
+    if let Some(blinkt) = blinkt.as_mut() {
+        blinkt.set_pixel_brightness(pixel, brightness);
+    }
+}
+
+/// Sets the red, green and blue values of all pixels.
+///
+/// # Safety
+///
+/// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+/// `blinkt_with_spi()`.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_set_all_pixels(blinkt: *mut Blinkt, red: u8, green: u8, blue: u8) {
+    if let Some(blinkt) = blinkt.as_mut() {
+        blinkt.set_all_pixels(red, green, blue);
+    }
+}
+
+/// Sets the brightness of all pixels, from `0.0` to `1.0`.
+///
+/// # Safety
+///
+/// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+/// `blinkt_with_spi()`.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_set_all_pixels_brightness(blinkt: *mut Blinkt, brightness: f32) {
+    if let Some(blinkt) = blinkt.as_mut() {
+        blinkt.set_all_pixels_brightness(brightness);
+    }
+}
+
+/// Turns off all pixels.
+///
+/// # Safety
+///
+/// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+/// `blinkt_with_spi()`.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_clear(blinkt: *mut Blinkt) {
+    if let Some(blinkt) = blinkt.as_mut() {
+        blinkt.clear();
+    }
+}
+
+/// Sets whether all pixels are turned off when the `Blinkt` is destroyed.
+///
+/// # Safety
+///
+/// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+/// `blinkt_with_spi()`.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_set_clear_on_drop(blinkt: *mut Blinkt, clear_on_drop: bool) {
+    if let Some(blinkt) = blinkt.as_mut() {
+        blinkt.set_clear_on_drop(clear_on_drop);
+    }
+}
+
+/// Sends the color and brightness values to the pixels.
+///
+/// Returns `0` on success, or `-1` on failure.
+///
+/// # Safety
+///
+/// `blinkt` must be `NULL` or a valid pointer returned by `blinkt_new()` or
+/// `blinkt_with_spi()`.
+#[no_mangle]
+pub unsafe extern "C" fn blinkt_show(blinkt: *mut Blinkt) -> c_int {
+    let blinkt = match blinkt.as_mut() {
+        Some(blinkt) => blinkt,
+        None => {
+            set_last_error("blinkt is NULL".to_owned());
+            return -1;
+        }
+    };
+
+    blinkt.swap();
+    match blinkt.show() {
+        Ok(()) => 0,
+        Err(err) => {
+            set_last_error(err.to_string());
+            -1
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::pixel::Pixel;
+
+/// Color filters that can be applied to all pixels when the local buffer is
+/// sent to the pixels.
+///
+/// Filters are pushed onto a stack through [`Blinkt::push_filter`], and are
+/// applied in the order they were added. This changes the overall look
+/// without having to modify the code that sets the pixel values.
+///
+/// The filters use the same matrices as their CSS counterparts.
+///
+/// [`Blinkt::push_filter`]: struct.Blinkt.html#method.push_filter
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Filter {
+    /// Rotates the hue by the specified number of degrees.
+    HueRotate(f32),
+    /// Scales the saturation. `0.0` removes all color, `1.0` leaves the
+    /// colors unchanged, and values above `1.0` oversaturate.
+    Saturate(f32),
+    /// Converts all colors to shades of gray.
+    Grayscale,
+    /// Applies a warm, brownish tone.
+    Sepia,
+}
+
+impl Filter {
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Filter::HueRotate(degrees) => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+
+                [
+                    [
+                        0.213 + cos * 0.787 - sin * 0.213,
+                        0.715 - cos * 0.715 - sin * 0.715,
+                        0.072 - cos * 0.072 + sin * 0.928,
+                    ],
+                    [
+                        0.213 - cos * 0.213 + sin * 0.143,
+                        0.715 + cos * 0.285 + sin * 0.140,
+                        0.072 - cos * 0.072 - sin * 0.283,
+                    ],
+                    [
+                        0.213 - cos * 0.213 - sin * 0.787,
+                        0.715 - cos * 0.715 + sin * 0.715,
+                        0.072 + cos * 0.928 + sin * 0.072,
+                    ],
+                ]
+            }
+            Filter::Saturate(amount) => {
+                let amount = amount.max(0.0);
+
+                [
+                    [
+                        0.213 + 0.787 * amount,
+                        0.715 - 0.715 * amount,
+                        0.072 - 0.072 * amount,
+                    ],
+                    [
+                        0.213 - 0.213 * amount,
+                        0.715 + 0.285 * amount,
+                        0.072 - 0.072 * amount,
+                    ],
+                    [
+                        0.213 - 0.213 * amount,
+                        0.715 - 0.715 * amount,
+                        0.072 + 0.928 * amount,
+                    ],
+                ]
+            }
+            Filter::Grayscale => Filter::Saturate(0.0).matrix(),
+            Filter::Sepia => [
+                [0.393, 0.769, 0.189],
+                [0.349, 0.686, 0.168],
+                [0.272, 0.534, 0.131],
+            ],
+        }
+    }
+}
+
+// All filters are linear, so the stack is combined into a single matrix.
+#[derive(Debug, Clone)]
+pub(crate) struct FilterChain {
+    matrix: [[f32; 3]; 3],
+}
+
+impl FilterChain {
+    pub(crate) fn new(filters: &[Filter]) -> FilterChain {
+        let mut matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        for filter in filters {
+            matrix = multiply(&filter.matrix(), &matrix);
+        }
+
+        FilterChain { matrix }
+    }
+
+    #[inline]
+    pub(crate) fn apply(&self, pixel: &mut Pixel) {
+        let (red, green, blue) = pixel.rgb();
+        let input = [f32::from(red), f32::from(green), f32::from(blue)];
+
+        let convert = |row: &[f32; 3]| {
+            let value = row[0] * input[0] + row[1] * input[1] + row[2] * input[2];
+            value.max(0.0).min(255.0).round() as u8
+        };
+
+        pixel.set_rgb(
+            convert(&self.matrix[0]),
+            convert(&self.matrix[1]),
+            convert(&self.matrix[2]),
+        );
+    }
+}
+
+fn multiply(a: &[[f32; 3]; 3], b: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut result = [[0f32; 3]; 3];
+    for (row, result_row) in result.iter_mut().enumerate() {
+        for (column, value) in result_row.iter_mut().enumerate() {
+            *value = (0..3).map(|idx| a[row][idx] * b[idx][column]).sum();
+        }
+    }
+
+    result
+}
@@ -0,0 +1,206 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::apa102;
+use crate::blinkt::{BlinktGpio, BlinktSpi, CLK, DAT};
+use crate::pixel::Pixel;
+use crate::{Result, SerialOutput};
+
+/// A fixed-size alternative to `Blinkt` for small boards, with the pixel
+/// buffer stored inline instead of on the heap.
+///
+/// `FixedBlinkt` only supports the basic setters and `show()`. Frames are
+/// encoded straight from the pixel buffer to the output, so sending a frame
+/// never allocates, and the number of pixels is known at compile time. Any
+/// [`SerialOutput`] can be used as the output.
+///
+/// This type requires the `fixed` feature, and Rust 1.51 or later.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # use blinkt::FixedBlinkt;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mut blinkt = FixedBlinkt::new()?;
+///
+/// blinkt.set_all_pixels(255, 0, 0);
+/// blinkt.show()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`SerialOutput`]: trait.SerialOutput.html
+pub struct FixedBlinkt<O: SerialOutput, const N: usize> {
+    output: O,
+    pixels: [Pixel; N],
+    clear_on_drop: bool,
+}
+
+impl FixedBlinkt<Box<dyn SerialOutput + Send>, 8> {
+    /// Constructs a new `FixedBlinkt` using the default settings for a
+    /// Pimoroni Blinkt! board.
+    ///
+    /// This sets the data pin to GPIO 23 (physical pin 16), and the clock
+    /// pin to GPIO 24 (physical pin 18).
+    pub fn new() -> Result<Self> {
+        FixedBlinkt::with_settings(DAT, CLK)
+    }
+}
+
+impl<const N: usize> FixedBlinkt<Box<dyn SerialOutput + Send>, N> {
+    /// Constructs a new `FixedBlinkt` using bitbanging mode, with custom
+    /// settings for the data pin and clock pin.
+    ///
+    /// The GPIO pins are specified by their BCM GPIO pin numbers.
+    pub fn with_settings(pin_data: u8, pin_clock: u8) -> Result<Self> {
+        Ok(FixedBlinkt::with_output(Box::new(
+            BlinktGpio::with_settings(pin_data, pin_clock)?,
+        )))
+    }
+
+    /// Constructs a new `FixedBlinkt` using hardware SPI, with a custom
+    /// setting for the clock speed.
+    ///
+    /// See `Blinkt::with_spi()` for more information on the supported clock
+    /// speeds.
+    pub fn with_spi(clock_speed_hz: u32) -> Result<Self> {
+        Ok(FixedBlinkt::with_output(Box::new(
+            BlinktSpi::with_settings(clock_speed_hz, None)?,
+        )))
+    }
+}
+
+impl<O: SerialOutput, const N: usize> FixedBlinkt<O, N> {
+    /// Constructs a new `FixedBlinkt` using a custom output.
+    pub fn with_output(output: O) -> Self {
+        FixedBlinkt {
+            output,
+            pixels: [Pixel::default(); N],
+            clear_on_drop: true,
+        }
+    }
+
+    /// Returns a reference to the output.
+    pub fn get_ref(&self) -> &O {
+        &self.output
+    }
+
+    /// Returns the pixel buffer.
+    pub fn pixels(&self) -> &[Pixel; N] {
+        &self.pixels
+    }
+
+    /// Returns a mutable reference to the pixel buffer.
+    pub fn pixels_mut(&mut self) -> &mut [Pixel; N] {
+        &mut self.pixels
+    }
+
+    /// Sets the red, green and blue values for a single pixel in the local
+    /// buffer.
+    ///
+    /// Pixels are numbered starting at `0`. Out of bounds pixels are
+    /// ignored.
+    pub fn set_pixel(&mut self, pixel: usize, red: u8, green: u8, blue: u8) {
+        if let Some(pixel) = self.pixels.get_mut(pixel) {
+            pixel.set_rgb(red, green, blue);
+        }
+    }
+
+    /// Sets the red, green, blue and brightness values for a single pixel in
+    /// the local buffer.
+    ///
+    /// `brightness` is specified as a floating point value between `0.0`
+    /// (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_pixel_rgbb(&mut self, pixel: usize, red: u8, green: u8, blue: u8, brightness: f32) {
+        if let Some(pixel) = self.pixels.get_mut(pixel) {
+            pixel.set_rgbb(red, green, blue, brightness);
+        }
+    }
+
+    /// Sets the red, green and blue values for all pixels in the local
+    /// buffer.
+    pub fn set_all_pixels(&mut self, red: u8, green: u8, blue: u8) {
+        for pixel in self.pixels.iter_mut() {
+            pixel.set_rgb(red, green, blue);
+        }
+    }
+
+    /// Sets the red, green, blue and brightness values for all pixels in the
+    /// local buffer.
+    pub fn set_all_pixels_rgbb(&mut self, red: u8, green: u8, blue: u8, brightness: f32) {
+        for pixel in self.pixels.iter_mut() {
+            pixel.set_rgbb(red, green, blue, brightness);
+        }
+    }
+
+    /// Sets the brightness value for all pixels.
+    pub fn set_all_pixels_brightness(&mut self, brightness: f32) {
+        for pixel in self.pixels.iter_mut() {
+            pixel.set_brightness(brightness);
+        }
+    }
+
+    /// Sets the red, green and blue values for all pixels to `0`.
+    pub fn clear(&mut self) {
+        self.set_all_pixels(0, 0, 0);
+    }
+
+    /// Sends the contents of the local buffer to the pixels.
+    pub fn show(&mut self) -> Result<()> {
+        self.output.write(&apa102::START_FRAME)?;
+
+        // LED frames (3*1, 5*brightness, 8*blue, 8*green, 8*red).
+        for pixel in self.pixels.iter() {
+            self.output.write(pixel.bytes())?;
+        }
+
+        // End frame, see Blinkt::show() for details.
+        let mut remaining = apa102::end_frame_len(N);
+        while remaining > 0 {
+            let len = remaining.min(4);
+            self.output.write(&[0u8; 4][..len])?;
+            remaining -= len;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of `clear_on_drop`.
+    pub fn clear_on_drop(&self) -> bool {
+        self.clear_on_drop
+    }
+
+    /// When enabled, clears all pixels when `FixedBlinkt` goes out of scope.
+    ///
+    /// By default, this is set to `true`.
+    pub fn set_clear_on_drop(&mut self, clear_on_drop: bool) {
+        self.clear_on_drop = clear_on_drop;
+    }
+}
+
+impl<O: SerialOutput, const N: usize> Drop for FixedBlinkt<O, N> {
+    fn drop(&mut self) {
+        if self.clear_on_drop {
+            self.clear();
+            let _ = self.show();
+        }
+    }
+}
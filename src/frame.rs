@@ -0,0 +1,464 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::ops::Range;
+use std::slice;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::color::{Color, Interpolation};
+use crate::pixel::Pixel;
+
+/// Blend modes used by [`Frame::blend`].
+///
+/// [`Frame::blend`]: struct.Frame.html#method.blend
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlendMode {
+    /// Adds the color values, saturating at `255`.
+    Add,
+    /// Multiplies the color values, which darkens the result.
+    Multiply,
+    /// Inverts, multiplies and inverts the color values again, which
+    /// brightens the result.
+    Screen,
+    /// Uses the highest of the two color values.
+    Max,
+    /// Draws the overlay on top with the specified opacity, between `0.0`
+    /// (transparent) and `1.0` (opaque). Also blends brightness values.
+    AlphaOver(f32),
+}
+
+/// A buffer of pixels that's independent of `Blinkt`.
+///
+/// A `Frame` can be rendered separately, for instance on a different thread,
+/// and then copied to the local buffer of `Blinkt` through `Blinkt::set_frame()`
+/// or `Blinkt::compose()`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Frame {
+    pixels: Vec<Pixel>,
+}
+
+impl Frame {
+    /// Constructs a new `Frame` containing `num_pixels` pixels, with all red,
+    /// green and blue values set to `0`.
+    pub fn new(num_pixels: usize) -> Frame {
+        Frame {
+            pixels: vec![Pixel::default(); num_pixels],
+        }
+    }
+
+    /// Constructs a new `Frame` containing a copy of `pixels`.
+    pub fn from_pixels(pixels: &[Pixel]) -> Frame {
+        Frame {
+            pixels: pixels.to_vec(),
+        }
+    }
+
+    /// Returns the number of pixels.
+    pub fn len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    /// Returns `true` if the frame doesn't contain any pixels.
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Returns a reference to a single pixel, or `None` if `pixel` is out of
+    /// bounds.
+    ///
+    /// Pixels are numbered starting at `0`.
+    pub fn pixel(&self, pixel: usize) -> Option<&Pixel> {
+        self.pixels.get(pixel)
+    }
+
+    /// Returns a mutable reference to a single pixel, or `None` if `pixel` is
+    /// out of bounds.
+    ///
+    /// Pixels are numbered starting at `0`.
+    pub fn pixel_mut(&mut self, pixel: usize) -> Option<&mut Pixel> {
+        self.pixels.get_mut(pixel)
+    }
+
+    /// Returns a slice containing all pixels.
+    pub fn pixels(&self) -> &[Pixel] {
+        &self.pixels
+    }
+
+    /// Returns an iterator over all pixels.
+    pub fn iter(&self) -> slice::Iter<'_, Pixel> {
+        self.pixels.iter()
+    }
+
+    /// Returns a mutable iterator over all pixels.
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, Pixel> {
+        self.pixels.iter_mut()
+    }
+
+    /// Sets the red, green and blue values for a single pixel.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    pub fn set_pixel(&mut self, pixel: usize, red: u8, green: u8, blue: u8) {
+        if let Some(pixel) = self.pixels.get_mut(pixel) {
+            pixel.set_rgb(red, green, blue);
+        }
+    }
+
+    /// Sets the red, green, blue and brightness values for a single pixel.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_pixel_rgbb(&mut self, pixel: usize, red: u8, green: u8, blue: u8, brightness: f32) {
+        if let Some(pixel) = self.pixels.get_mut(pixel) {
+            pixel.set_rgbb(red, green, blue, brightness);
+        }
+    }
+
+    /// Sets the color for a single pixel.
+    ///
+    /// Pixels are numbered starting at `0`.
+    pub fn set_pixel_color<C: Into<Color>>(&mut self, pixel: usize, color: C) {
+        if let Some(pixel) = self.pixels.get_mut(pixel) {
+            pixel.set_color(color);
+        }
+    }
+
+    /// Sets the red, green and blue values for consecutive pixels, starting
+    /// at the first pixel.
+    ///
+    /// If `colors` contains more values than the number of pixels, the
+    /// remaining values are ignored.
+    #[cfg(feature = "rgb")]
+    pub fn set_pixels_rgb8(&mut self, colors: &[rgb::RGB8]) {
+        for (pixel, color) in self.pixels.iter_mut().zip(colors) {
+            pixel.set_rgb(color.r, color.g, color.b);
+        }
+    }
+
+    /// Sets the red, green and blue values for all pixels.
+    ///
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    pub fn set_all_pixels(&mut self, red: u8, green: u8, blue: u8) {
+        for pixel in &mut self.pixels {
+            pixel.set_rgb(red, green, blue);
+        }
+    }
+
+    /// Sets the color for all pixels.
+    pub fn set_all_pixels_color<C: Into<Color>>(&mut self, color: C) {
+        let color = color.into();
+        self.set_all_pixels(color.red, color.green, color.blue);
+    }
+
+    /// Sets the brightness value for all pixels.
+    ///
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_all_pixels_brightness(&mut self, brightness: f32) {
+        for pixel in &mut self.pixels {
+            pixel.set_brightness(brightness);
+        }
+    }
+
+    /// Fills all pixels with a gradient from `start` to `end`.
+    pub fn fill_gradient<C: Into<Color>>(
+        &mut self,
+        start: C,
+        end: C,
+        interpolation: Interpolation,
+    ) {
+        let len = self.pixels.len();
+        self.fill_gradient_range(0..len, start, end, interpolation);
+    }
+
+    /// Fills the pixels within `range` with a gradient from `start` to `end`.
+    ///
+    /// Any part of `range` that's out of bounds is ignored, but still
+    /// included when calculating the gradient.
+    pub fn fill_gradient_range<C: Into<Color>>(
+        &mut self,
+        range: Range<usize>,
+        start: C,
+        end: C,
+        interpolation: Interpolation,
+    ) {
+        let (start, end) = (start.into(), end.into());
+        for (idx, color) in gradient(range, start, end, interpolation) {
+            self.set_pixel_color(idx, color);
+        }
+    }
+
+    /// Multiplies the red, green and blue values of all pixels by `factor`.
+    ///
+    /// `factor` is specified as a floating point value between `0.0` and `1.0`.
+    /// Calling `fade_all()` repeatedly makes pixels decay towards black, which
+    /// is the basis for comet trails and similar effects.
+    pub fn fade_all(&mut self, factor: f32) {
+        self.fade_all_with_floor(factor, 0);
+    }
+
+    /// Multiplies the red, green and blue values of all pixels by `factor`,
+    /// without letting any values drop below `floor`.
+    ///
+    /// Values that are already at or below `floor` are left unchanged.
+    pub fn fade_all_with_floor(&mut self, factor: f32, floor: u8) {
+        for pixel in &mut self.pixels {
+            pixel.fade(factor, floor);
+        }
+    }
+
+    /// Blurs all pixels by blending each pixel with its neighbors.
+    ///
+    /// `amount` is specified as a floating point value between `0.0` (no
+    /// blur) and `1.0`, and determines the share of each pixel's color that's
+    /// spread out evenly to its neighbors.
+    pub fn blur(&mut self, amount: f32) {
+        blur(&mut self.pixels, amount);
+    }
+
+    /// Returns the index and new color of every pixel that differs between
+    /// this frame and `other`, in ascending order.
+    ///
+    /// Applying the returned changes to this frame through
+    /// [`set_pixel_color`] results in the colors of `other`. Pixels in
+    /// `other` that are beyond the end of this frame are always included.
+    /// Only the red, green and blue values are compared, so brightness
+    /// changes aren't reported.
+    ///
+    /// [`set_pixel_color`]: #method.set_pixel_color
+    pub fn diff(&self, other: &Frame) -> Vec<(usize, Color)> {
+        other
+            .pixels
+            .iter()
+            .enumerate()
+            .filter(|&(idx, pixel)| match self.pixels.get(idx) {
+                Some(current) => current.rgb() != pixel.rgb(),
+                None => true,
+            })
+            .map(|(idx, pixel)| (idx, pixel.color()))
+            .collect()
+    }
+
+    /// Reverses the order of all pixels.
+    pub fn reverse(&mut self) {
+        self.pixels.reverse();
+    }
+
+    /// Rotates all pixels `n` places towards the start of the frame. Pixels
+    /// that move past the first pixel wrap around to the end.
+    pub fn rotate_left(&mut self, n: usize) {
+        rotate_left(&mut self.pixels, n);
+    }
+
+    /// Rotates all pixels `n` places towards the end of the frame. Pixels
+    /// that move past the last pixel wrap around to the start.
+    pub fn rotate_right(&mut self, n: usize) {
+        rotate_right(&mut self.pixels, n);
+    }
+
+    /// Shifts all pixels `n` places towards the end of the frame, or towards
+    /// the start if `n` is negative. Pixels that move out of bounds are
+    /// discarded, and vacated pixels are set to `fill`.
+    ///
+    /// The brightness of vacated pixels is left unchanged.
+    pub fn shift<C: Into<Color>>(&mut self, n: isize, fill: C) {
+        let fill = fill.into();
+        for idx in shift(&mut self.pixels, n) {
+            self.pixels[idx].set_color(fill);
+        }
+    }
+
+    /// Sets the red, green and blue values for all pixels to `0`.
+    pub fn clear(&mut self) {
+        self.set_all_pixels(0, 0, 0);
+    }
+
+    /// Blends `overlay` on top of this frame, using the specified blend mode.
+    ///
+    /// Pixels are matched by index. If `overlay` contains fewer pixels, the
+    /// remaining pixels are left unchanged. Except for
+    /// `BlendMode::AlphaOver`, brightness values of this frame are left
+    /// unchanged.
+    ///
+    /// ```rust
+    /// use blinkt::{BlendMode, Frame};
+    ///
+    /// let mut background = Frame::new(8);
+    /// background.set_all_pixels(0, 0, 64);
+    ///
+    /// let mut notification = Frame::new(8);
+    /// notification.set_pixel(0, 255, 0, 0);
+    ///
+    /// background.blend(&notification, BlendMode::Add);
+    /// assert_eq!(background.pixel(0).unwrap().rgb(), (255, 0, 64));
+    /// ```
+    pub fn blend(&mut self, overlay: &Frame, mode: BlendMode) {
+        for (pixel, overlay) in self.pixels.iter_mut().zip(&overlay.pixels) {
+            if let BlendMode::AlphaOver(alpha) = mode {
+                *pixel = pixel.mix(overlay, alpha);
+                continue;
+            }
+
+            let blend = |base: u8, top: u8| -> u8 {
+                let (base, top) = (u16::from(base), u16::from(top));
+
+                (match mode {
+                    BlendMode::Add => (base + top).min(255),
+                    BlendMode::Multiply => (base * top + 127) / 255,
+                    BlendMode::Screen => 255 - ((255 - base) * (255 - top) + 127) / 255,
+                    BlendMode::Max | BlendMode::AlphaOver(_) => base.max(top),
+                }) as u8
+            };
+
+            let (red, green, blue) = pixel.rgb();
+            let (top_red, top_green, top_blue) = overlay.rgb();
+
+            pixel.set_rgb(
+                blend(red, top_red),
+                blend(green, top_green),
+                blend(blue, top_blue),
+            );
+        }
+    }
+
+    /// Blits multiple source frames into the specified destination ranges.
+    ///
+    /// Each layer consists of a destination range, a source frame, and an
+    /// alpha value between `0.0` (fully transparent) and `1.0` (opaque). The
+    /// first pixel of the source frame is drawn at the start of the range.
+    /// Layers are drawn in order, so later layers are drawn on top of earlier
+    /// ones. Any pixels outside of the destination range or the source frame
+    /// are ignored.
+    pub fn compose(&mut self, layers: &[(Range<usize>, &Frame, f32)]) {
+        compose(&mut self.pixels, layers);
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Frame {
+    type Item = &'a mut Pixel;
+    type IntoIter = slice::IterMut<'a, Pixel>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pixels.iter_mut()
+    }
+}
+
+// Convolves all pixels with a [amount / 2, 1 - amount, amount / 2] kernel.
+// The first and last pixels are treated as their own neighbor beyond the edge
+// of the strip, so no light is lost at the ends. Runs in a single pass.
+pub(crate) fn blur(pixels: &mut [Pixel], amount: f32) {
+    if pixels.len() < 2 {
+        return;
+    }
+
+    let share = (amount.max(0.0).min(1.0) * 256.0).round() as u32;
+    let keep = 256 - share;
+
+    let mut previous = pixels[0].rgb();
+    for idx in 0..pixels.len() {
+        let current = pixels[idx].rgb();
+        let next = pixels.get(idx + 1).map_or(current, |pixel| pixel.rgb());
+
+        let blend = |left: u8, center: u8, right: u8| {
+            let total = keep * u32::from(center) + share * (u32::from(left) + u32::from(right)) / 2;
+            ((total + 128) >> 8).min(255) as u8
+        };
+
+        pixels[idx].set_rgb(
+            blend(previous.0, current.0, next.0),
+            blend(previous.1, current.1, next.1),
+            blend(previous.2, current.2, next.2),
+        );
+
+        previous = current;
+    }
+}
+
+pub(crate) fn rotate_left<T>(values: &mut [T], n: usize) {
+    if !values.is_empty() {
+        let len = values.len();
+        values.rotate_left(n % len);
+    }
+}
+
+pub(crate) fn rotate_right<T>(values: &mut [T], n: usize) {
+    if !values.is_empty() {
+        let len = values.len();
+        values.rotate_right(n % len);
+    }
+}
+
+// Shifts all values n places towards the end (or start, if n is negative),
+// and returns the range of vacated indices. Vacated values are left unchanged.
+pub(crate) fn shift<T: Copy>(values: &mut [T], n: isize) -> Range<usize> {
+    let len = values.len();
+    let count = (n.wrapping_abs() as usize).min(len);
+
+    if n >= 0 {
+        for idx in (count..len).rev() {
+            values[idx] = values[idx - count];
+        }
+
+        0..count
+    } else {
+        for idx in 0..(len - count) {
+            values[idx] = values[idx + count];
+        }
+
+        (len - count)..len
+    }
+}
+
+// Returns an iterator over pixel indices and colors for a gradient.
+pub(crate) fn gradient(
+    range: Range<usize>,
+    start: Color,
+    end: Color,
+    interpolation: Interpolation,
+) -> impl Iterator<Item = (usize, Color)> {
+    let first = range.start;
+    let steps = range.end.saturating_sub(range.start).saturating_sub(1);
+
+    range.map(move |idx| {
+        let t = if steps == 0 {
+            0.0
+        } else {
+            (idx - first) as f32 / steps as f32
+        };
+
+        (idx, start.interpolate(end, t, interpolation))
+    })
+}
+
+pub(crate) fn compose(pixels: &mut [Pixel], layers: &[(Range<usize>, &Frame, f32)]) {
+    for (range, frame, alpha) in layers {
+        let end = range.end.min(pixels.len());
+        if range.start >= end {
+            continue;
+        }
+
+        for (pixel, source) in pixels[range.start..end].iter_mut().zip(&frame.pixels) {
+            *pixel = pixel.mix(source, *alpha);
+        }
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// A minimal animated GIF encoder. Each frame gets its own color table,
+// which holds every color exactly as long as a frame contains 256 colors or
+// less, and falls back to a fixed 3-3-2 palette otherwise. Image data is
+// LZW compressed.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::blinkt::{snapshot_rgb, SNAPSHOT_CELL_SIZE};
+use crate::event::ShowEvent;
+use crate::Result;
+
+const MAX_CODES: u16 = 4096;
+const MAX_CODE_SIZE: u8 = 12;
+// Minimum frame delay in hundredths of a second.
+const MIN_DELAY: u16 = 2;
+// Time the last frame is shown before the animation loops.
+const LAST_FRAME_DELAY: u16 = 100;
+
+pub(crate) struct GifWriter<W: Write> {
+    writer: W,
+    width: u16,
+    height: u16,
+}
+
+impl<W: Write> GifWriter<W> {
+    // Writes the header, and marks the animation to loop forever.
+    pub(crate) fn new(mut writer: W, width: u16, height: u16) -> io::Result<GifWriter<W>> {
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid image dimensions",
+            ));
+        }
+
+        writer.write_all(b"GIF89a")?;
+        writer.write_all(&le_bytes(width))?;
+        writer.write_all(&le_bytes(height))?;
+        // No global color table, background color 0, square pixels.
+        writer.write_all(&[0, 0, 0])?;
+
+        // NETSCAPE2.0 application extension, loop count 0 (forever).
+        writer.write_all(&[0x21, 0xff, 0x0b])?;
+        writer.write_all(b"NETSCAPE2.0")?;
+        writer.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        Ok(GifWriter {
+            writer,
+            width,
+            height,
+        })
+    }
+
+    /// Writes `rgb` as the next frame, shown for `delay` hundredths of a
+    /// second. `rgb` contains 3 bytes (red, green, blue) for each pixel, in
+    /// row-major order.
+    pub(crate) fn write_frame(&mut self, rgb: &[u8], delay: u16) -> io::Result<()> {
+        if rgb.len() != self.width as usize * self.height as usize * 3 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid image dimensions",
+            ));
+        }
+
+        let (palette, indices) = quantize(rgb);
+
+        // The color table holds 2^(size + 1) entries.
+        let mut size = 0;
+        while (2 << size) < palette.len() {
+            size += 1;
+        }
+
+        // Graphic control extension, disposal method 1 (leave in place).
+        self.writer.write_all(&[0x21, 0xf9, 0x04, 0x04])?;
+        self.writer.write_all(&le_bytes(delay))?;
+        self.writer.write_all(&[0x00, 0x00])?;
+
+        // Image descriptor with a local color table.
+        self.writer.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.writer.write_all(&le_bytes(self.width))?;
+        self.writer.write_all(&le_bytes(self.height))?;
+        self.writer.write_all(&[0x80 | size])?;
+
+        for &(red, green, blue) in &palette {
+            self.writer.write_all(&[red, green, blue])?;
+        }
+        for _ in palette.len()..(2 << size) {
+            self.writer.write_all(&[0, 0, 0])?;
+        }
+
+        let min_code_size = (size + 1).max(2);
+        self.writer.write_all(&[min_code_size])?;
+        for block in lzw_encode(min_code_size, &indices).chunks(255) {
+            self.writer.write_all(&[block.len() as u8])?;
+            self.writer.write_all(block)?;
+        }
+        self.writer.write_all(&[0x00])?;
+
+        Ok(())
+    }
+
+    // Writes the trailer, and flushes the underlying writer.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        self.writer.write_all(&[0x3b])?;
+        self.writer.flush()
+    }
+}
+
+// Returns the color table and the color table index for every pixel.
+fn quantize(rgb: &[u8]) -> (Vec<(u8, u8, u8)>, Vec<u8>) {
+    let mut palette = Vec::new();
+    let mut lookup = HashMap::new();
+    let mut indices = Vec::with_capacity(rgb.len() / 3);
+
+    for values in rgb.chunks(3) {
+        let color = (values[0], values[1], values[2]);
+        let next = palette.len();
+        let index = *lookup.entry(color).or_insert(next);
+        if index == next {
+            if next == 256 {
+                return quantize_332(rgb);
+            }
+
+            palette.push(color);
+        }
+
+        indices.push(index as u8);
+    }
+
+    (palette, indices)
+}
+
+// Maps every pixel to a fixed palette with 3 bits for red and green, and 2
+// bits for blue.
+fn quantize_332(rgb: &[u8]) -> (Vec<(u8, u8, u8)>, Vec<u8>) {
+    let palette = (0..=255u8)
+        .map(|index| {
+            (
+                ((index >> 5) as u32 * 255 / 7) as u8,
+                (((index >> 2) & 0x07) as u32 * 255 / 7) as u8,
+                ((index & 0x03) as u32 * 255 / 3) as u8,
+            )
+        })
+        .collect();
+
+    let indices = rgb
+        .chunks(3)
+        .map(|values| {
+            let red = (values[0] as u32 * 7 + 127) / 255;
+            let green = (values[1] as u32 * 7 + 127) / 255;
+            let blue = (values[2] as u32 * 3 + 127) / 255;
+            ((red << 5) | (green << 2) | blue) as u8
+        })
+        .collect();
+
+    (palette, indices)
+}
+
+// Packs variable-width codes into bytes, least significant bit first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u8) {
+        self.buffer |= u32::from(code) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+
+        self.bytes
+    }
+}
+
+fn lzw_encode(min_code_size: u8, indices: &[u8]) -> Vec<u8> {
+    let clear_code = 1u16 << min_code_size;
+    let end_code = clear_code + 1;
+
+    let mut output = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        bits: 0,
+    };
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = min_code_size + 1;
+
+    output.write(clear_code, code_size);
+
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let current = match prefix {
+            Some(current) => current,
+            None => {
+                prefix = Some(u16::from(index));
+                continue;
+            }
+        };
+
+        if let Some(&code) = dictionary.get(&(current, index)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        output.write(current, code_size);
+        if next_code >= (1 << code_size) && code_size < MAX_CODE_SIZE {
+            code_size += 1;
+        }
+
+        if next_code < MAX_CODES {
+            dictionary.insert((current, index), next_code);
+            next_code += 1;
+        } else {
+            // The dictionary is full, so start over.
+            output.write(clear_code, code_size);
+            dictionary.clear();
+            next_code = end_code + 1;
+            code_size = min_code_size + 1;
+        }
+
+        prefix = Some(u16::from(index));
+    }
+
+    if let Some(current) = prefix {
+        output.write(current, code_size);
+        if next_code >= (1 << code_size) && code_size < MAX_CODE_SIZE {
+            code_size += 1;
+        }
+    }
+    output.write(end_code, code_size);
+
+    output.finish()
+}
+
+fn le_bytes(value: u16) -> [u8; 2] {
+    [value as u8, (value >> 8) as u8]
+}
+
+// Writes the frames passed to an `after_show` callback to an animated GIF.
+// Frames are delayed by one, since a frame's delay is only known once the
+// next frame arrives. The file is completed when the recorder is dropped.
+pub(crate) struct GifRecorder {
+    gif: GifWriter<BufWriter<File>>,
+    num_pixels: usize,
+    pending: Option<(Vec<u8>, Instant)>,
+}
+
+impl GifRecorder {
+    pub(crate) fn create(path: &Path, num_pixels: usize) -> Result<GifRecorder> {
+        let width = (num_pixels * SNAPSHOT_CELL_SIZE).min(u16::MAX as usize);
+        let writer = BufWriter::new(File::create(path)?);
+
+        Ok(GifRecorder {
+            gif: GifWriter::new(writer, width as u16, SNAPSHOT_CELL_SIZE as u16)?,
+            num_pixels: width / SNAPSHOT_CELL_SIZE,
+            pending: None,
+        })
+    }
+
+    pub(crate) fn record(&mut self, event: &ShowEvent<'_>) -> Result<()> {
+        let rgb = snapshot_rgb(event.pixels(), self.num_pixels);
+
+        if let Some((ref mut pending, started)) = self.pending {
+            let delay = centis(event.started().duration_since(started));
+            if delay < MIN_DELAY {
+                // Most viewers don't support shorter delays, so the pending
+                // frame is replaced instead.
+                *pending = rgb;
+                return Ok(());
+            }
+
+            self.gif.write_frame(pending, delay)?;
+        }
+
+        self.pending = Some((rgb, event.started()));
+
+        Ok(())
+    }
+}
+
+impl Drop for GifRecorder {
+    fn drop(&mut self) {
+        if let Some((ref pending, _)) = self.pending {
+            let _ = self.gif.write_frame(pending, LAST_FRAME_DELAY);
+        }
+
+        let _ = self.gif.finish();
+    }
+}
+
+fn centis(duration: Duration) -> u16 {
+    let centis = duration.as_secs() * 100 + u64::from(duration.subsec_millis() / 10);
+
+    centis.min(u64::from(u16::MAX)) as u16
+}
@@ -0,0 +1,413 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Home Assistant integration through MQTT discovery.
+//!
+//! A [`HomeAssistantLight`] announces itself to [Home Assistant] through
+//! MQTT discovery, so the pixels show up as a light entity without any
+//! manual configuration. The light uses the JSON schema, and supports
+//! turning the pixels on and off, changing their brightness and color, and
+//! selecting one of the effects added with [`add_effect`].
+//!
+//! With the default `homeassistant` discovery prefix and an object ID of
+//! `desk`, the following topics are used.
+//!
+//! | Topic | Payload |
+//! |---|---|
+//! | `homeassistant/light/desk/config` | The discovery config, published by the light |
+//! | `blinkt/desk/set` | A JSON command sent by Home Assistant |
+//! | `blinkt/desk/state` | The current state, published by the light |
+//! | `blinkt/desk/availability` | `online` or `offline` |
+//!
+//! The broker publishes `offline` to the availability topic when the
+//! connection is lost unexpectedly.
+//!
+//! This module requires the `mqtt` feature.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use blinkt::home_assistant::{DiscoveryOptions, HomeAssistantLight};
+//! use blinkt::mqtt::MqttOptions;
+//! use blinkt::{Blinkt, Color, Frame};
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 60)?;
+//!
+//! let options = MqttOptions::new("homeassistant.local:1883")
+//!     .with_client_id("blinkt-desk")
+//!     .with_credentials("blinkt", "secret");
+//! let discovery = DiscoveryOptions::new("desk").with_name("Desk strip");
+//! let mut light = HomeAssistantLight::connect(options, discovery)?;
+//!
+//! light.add_effect("rainbow", |t: Duration, frame: &mut Frame| {
+//!     let len = frame.len();
+//!     for pixel in 0..len {
+//!         let offset = t.as_secs() as f32 * 50.0 + t.subsec_millis() as f32 / 20.0;
+//!         let hue = (offset + pixel as f32 * 360.0 / len as f32) % 360.0;
+//!         frame.set_pixel_color(pixel, Color::from_hsv(hue, 1.0, 1.0));
+//!     }
+//! });
+//!
+//! light.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [Home Assistant]: https://www.home-assistant.io/
+//! [`HomeAssistantLight`]: struct.HomeAssistantLight.html
+//! [`add_effect`]: struct.HomeAssistantLight.html#method.add_effect
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::animation::Animation;
+use crate::control::Effects;
+use crate::mqtt::{Connection, LightState, MqttOptions};
+use crate::{Blinkt, Color, Result};
+
+/// Discovery settings for a [`HomeAssistantLight`].
+///
+/// [`HomeAssistantLight`]: struct.HomeAssistantLight.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveryOptions {
+    object_id: String,
+    name: String,
+    discovery_prefix: String,
+    topic_prefix: String,
+}
+
+impl DiscoveryOptions {
+    /// Constructs a new `DiscoveryOptions` for the light identified by
+    /// `object_id`.
+    ///
+    /// `object_id` is used as the unique ID of the entity in Home Assistant,
+    /// and should only contain letters, digits, underscores and hyphens.
+    /// The name defaults to `object_id`, the discovery prefix to
+    /// `homeassistant`, and the topic prefix to `blinkt/<object_id>`.
+    pub fn new(object_id: &str) -> DiscoveryOptions {
+        DiscoveryOptions {
+            object_id: object_id.to_owned(),
+            name: object_id.to_owned(),
+            discovery_prefix: "homeassistant".to_owned(),
+            topic_prefix: format!("blinkt/{}", object_id),
+        }
+    }
+
+    /// Sets the name shown in Home Assistant.
+    pub fn with_name(mut self, name: &str) -> DiscoveryOptions {
+        self.name = name.to_owned();
+        self
+    }
+
+    /// Sets the discovery prefix configured in Home Assistant.
+    pub fn with_discovery_prefix(mut self, prefix: &str) -> DiscoveryOptions {
+        self.discovery_prefix = prefix.to_owned();
+        self
+    }
+
+    /// Sets the prefix of the command, state and availability topics.
+    pub fn with_topic_prefix(mut self, prefix: &str) -> DiscoveryOptions {
+        self.topic_prefix = prefix.to_owned();
+        self
+    }
+
+    fn config_topic(&self) -> String {
+        format!("{}/light/{}/config", self.discovery_prefix, self.object_id)
+    }
+
+    fn command_topic(&self) -> String {
+        format!("{}/set", self.topic_prefix)
+    }
+
+    fn state_topic(&self) -> String {
+        format!("{}/state", self.topic_prefix)
+    }
+
+    fn availability_topic(&self) -> String {
+        format!("{}/availability", self.topic_prefix)
+    }
+}
+
+/// A light entity in Home Assistant that controls the pixels of a `Blinkt`.
+#[derive(Debug)]
+pub struct HomeAssistantLight {
+    connection: Connection,
+    discovery: DiscoveryOptions,
+    command_topic: String,
+    state_topic: String,
+    state: LightState,
+    effects: Effects,
+}
+
+impl HomeAssistantLight {
+    /// Connects to the broker, subscribes to the command topic, and marks
+    /// the light as available.
+    ///
+    /// The command and state topics set in `options` are not used. The
+    /// discovery config is published by [`run`] or [`publish_discovery`].
+    ///
+    /// [`run`]: #method.run
+    /// [`publish_discovery`]: #method.publish_discovery
+    pub fn connect(
+        options: MqttOptions,
+        discovery: DiscoveryOptions,
+    ) -> Result<HomeAssistantLight> {
+        let availability_topic = discovery.availability_topic();
+        let mut connection = Connection::open(
+            &options.addr,
+            &options.client_id,
+            options.credentials.as_ref(),
+            options.keep_alive,
+            Some((&availability_topic, b"offline")),
+        )?;
+
+        let command_topic = discovery.command_topic();
+        connection.subscribe(&[&command_topic])?;
+        connection.publish(&availability_topic, b"online", true)?;
+
+        Ok(HomeAssistantLight {
+            connection,
+            state_topic: discovery.state_topic(),
+            command_topic,
+            discovery,
+            state: LightState::default(),
+            effects: Effects::new(),
+        })
+    }
+
+    /// Adds an effect that can be selected in Home Assistant.
+    ///
+    /// Effects are rendered at roughly 30 frames per second while they're
+    /// selected, and the light is turned on. Effects added after the
+    /// discovery config was published only show up in Home Assistant once
+    /// it's published again.
+    pub fn add_effect<A>(&mut self, name: &str, animation: A)
+    where
+        A: Animation + Send + 'static,
+    {
+        self.effects.add(name, Box::new(animation));
+    }
+
+    /// Returns the name of the selected effect, if any.
+    pub fn effect(&self) -> Option<&str> {
+        self.effects.selected_name()
+    }
+
+    /// Publishes the discovery config, the current state, and then receives
+    /// and processes commands until an error occurs, such as a lost
+    /// connection.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        self.publish_discovery()?;
+        self.state.show(blinkt)?;
+        self.publish_state()?;
+
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Waits for a single command, processes it, and renders the next frame
+    /// of the selected effect when it's due.
+    ///
+    /// Returns `Ok(true)` if the command changed the state, in which case
+    /// the pixels are updated, and the new state is published. Commands with
+    /// invalid payloads and unknown effects are ignored.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        let interval = if self.effect_active() {
+            Some(self.effects.until_next_frame())
+        } else {
+            None
+        };
+        self.connection.set_poll_interval(interval)?;
+
+        let changed = match self.connection.poll()? {
+            Some((ref topic, ref payload)) if *topic == self.command_topic => {
+                self.handle_command(blinkt, payload)?
+            }
+            _ => false,
+        };
+
+        if self.effect_active() && self.effects.until_next_frame() == Duration::from_millis(0) {
+            self.effects.render(blinkt, Some(self.state.brightness))?;
+        }
+
+        Ok(changed)
+    }
+
+    /// Publishes the discovery config, which adds the light to Home
+    /// Assistant, or updates its settings.
+    pub fn publish_discovery(&mut self) -> Result<()> {
+        let config = self.config_json().to_string();
+        self.connection
+            .publish(&self.discovery.config_topic(), config.as_bytes(), true)
+    }
+
+    /// Removes the light from Home Assistant by publishing an empty
+    /// discovery config.
+    pub fn remove_discovery(&mut self) -> Result<()> {
+        self.connection
+            .publish(&self.discovery.config_topic(), &[], true)
+    }
+
+    /// Publishes the current state to the state topic.
+    pub fn publish_state(&mut self) -> Result<()> {
+        let json = self.state_json().to_string();
+        self.connection
+            .publish(&self.state_topic, json.as_bytes(), true)
+    }
+
+    /// Marks the light as unavailable, and disconnects from the broker.
+    pub fn disconnect(mut self) -> Result<()> {
+        self.connection
+            .publish(&self.discovery.availability_topic(), b"offline", true)?;
+        self.connection.disconnect()
+    }
+
+    fn effect_active(&self) -> bool {
+        self.state.on && self.effects.selected().is_some()
+    }
+
+    fn handle_command(&mut self, blinkt: &mut Blinkt, payload: &[u8]) -> Result<bool> {
+        let command = match serde_json::from_str::<Value>(&String::from_utf8_lossy(payload)) {
+            Ok(command) => command,
+            Err(_) => return Ok(false),
+        };
+
+        let mut state = self.state.clone();
+        let mut effect = self.effects.selected();
+
+        match command.get("state").map(Value::as_str) {
+            Some(Some("ON")) => state.on = true,
+            Some(Some("OFF")) => state.on = false,
+            Some(_) => return Ok(false),
+            None => {}
+        }
+
+        if let Some(brightness) = command.get("brightness") {
+            match brightness.as_f64() {
+                Some(brightness) => {
+                    state.brightness = (brightness / 255.0).max(0.0).min(1.0) as f32
+                }
+                None => return Ok(false),
+            }
+        }
+
+        if let Some(color) = command.get("color") {
+            match rgb_color(color) {
+                Some(color) => {
+                    state.color = Some(color);
+                    state.frame = None;
+                    effect = None;
+                }
+                None => return Ok(false),
+            }
+        }
+
+        if let Some(name) = command.get("effect") {
+            let index = name.as_str().and_then(|name| self.effects.find(name));
+
+            match index {
+                Some(index) => effect = Some(index),
+                None => return Ok(false),
+            }
+        }
+
+        if state == self.state && effect == self.effects.selected() {
+            return Ok(false);
+        }
+
+        if state.on && !self.state.on {
+            self.effects.restart();
+        }
+
+        self.state = state;
+        self.effects.select(effect);
+
+        if self.effect_active() {
+            self.effects.render(blinkt, Some(self.state.brightness))?;
+        } else {
+            self.state.show(blinkt)?;
+        }
+
+        self.publish_state()?;
+
+        Ok(true)
+    }
+
+    fn state_json(&self) -> Value {
+        let mut json = json!({
+            "state": if self.state.on { "ON" } else { "OFF" },
+            "brightness": (self.state.brightness * 255.0).round() as u8,
+        });
+
+        if let Some(color) = self.state.color {
+            let (red, green, blue) = color.rgb();
+            json["color_mode"] = json!("rgb");
+            json["color"] = json!({ "r": red, "g": green, "b": blue });
+        }
+
+        if let Some(name) = self.effect() {
+            json["effect"] = json!(name);
+        }
+
+        json
+    }
+
+    fn config_json(&self) -> Value {
+        let discovery = &self.discovery;
+        let effects: Vec<&str> = self.effects.names().collect();
+
+        json!({
+            "name": discovery.name,
+            "unique_id": discovery.object_id,
+            "schema": "json",
+            "command_topic": self.command_topic,
+            "state_topic": self.state_topic,
+            "availability_topic": discovery.availability_topic(),
+            "brightness": true,
+            "brightness_scale": 255,
+            "supported_color_modes": ["rgb"],
+            "effect": !effects.is_empty(),
+            "effect_list": effects,
+            "device": {
+                "identifiers": [discovery.object_id],
+                "name": discovery.name,
+                "model": "APA102",
+                "sw_version": env!("CARGO_PKG_VERSION"),
+            },
+        })
+    }
+}
+
+// Parses a JSON schema color, `{"r": 255, "g": 0, "b": 0}`.
+fn rgb_color(value: &Value) -> Option<Color> {
+    let channel = |name: &str| -> Option<u8> {
+        let value = value.get(name)?.as_u64()?;
+        if value > 255 {
+            return None;
+        }
+
+        Some(value as u8)
+    };
+
+    Some(Color::from((channel("r")?, channel("g")?, channel("b")?)))
+}
@@ -0,0 +1,291 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// A minimal HTTP/1.1 implementation for the built-in web APIs. Every
+// connection handles a single request.
+
+use std::io::{self, BufRead, Read, Write};
+#[cfg(any(feature = "rest", feature = "wled"))]
+use std::net::{TcpListener, TcpStream};
+#[cfg(any(feature = "rest", feature = "wled"))]
+use std::time::{Duration, Instant};
+
+#[cfg(any(feature = "rest", feature = "wled"))]
+use serde_json::{json, Value};
+
+#[cfg(any(feature = "rest", feature = "wled"))]
+use crate::Result;
+
+// Maximum accepted length of the request line and headers combined.
+const MAX_HEADER_LEN: usize = 8 * 1024;
+// Maximum accepted request body length.
+const MAX_BODY_LEN: usize = 1 << 20;
+// Maximum time a client may take to send the whole request, and to receive
+// the whole response.
+#[cfg(any(feature = "rest", feature = "wled"))]
+const CLIENT_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) query: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Request {
+    pub(crate) fn read<R: BufRead>(reader: &mut R) -> io::Result<Request> {
+        let mut header_len = 0;
+        let request_line = read_line(reader, &mut header_len)?;
+
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+                (method.to_owned(), target)
+            }
+            _ => return Err(invalid_data("invalid request line")),
+        };
+
+        let (path, query) = match target.find('?') {
+            Some(pos) => (&target[..pos], &target[pos + 1..]),
+            None => (target, ""),
+        };
+
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(reader, &mut header_len)?;
+            if line.is_empty() {
+                break;
+            }
+
+            match line.find(':') {
+                Some(pos) => headers.push((
+                    line[..pos].trim().to_owned(),
+                    line[pos + 1..].trim().to_owned(),
+                )),
+                None => return Err(invalid_data("invalid header")),
+            }
+        }
+
+        let mut request = Request {
+            method,
+            path: path.to_owned(),
+            query: query.to_owned(),
+            headers,
+            body: Vec::new(),
+        };
+
+        let body_len = match request.header("Content-Length") {
+            Some(len) => len
+                .parse::<usize>()
+                .map_err(|_| invalid_data("invalid Content-Length"))?,
+            None => 0,
+        };
+
+        if body_len > MAX_BODY_LEN {
+            return Err(invalid_data("request body exceeds the maximum length"));
+        }
+
+        request.body = vec![0u8; body_len];
+        reader.read_exact(&mut request.body)?;
+
+        Ok(request)
+    }
+
+    // Returns the value of the first header called `name`, ignoring case.
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| &value[..])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Response {
+    pub(crate) status: u16,
+    pub(crate) content_type: &'static str,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Response {
+    #[cfg(any(feature = "rest", feature = "wled"))]
+    pub(crate) fn json(status: u16, json: &Value) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body: json.to_string().into_bytes(),
+        }
+    }
+
+    // Returns a JSON response containing an error message.
+    #[cfg(any(feature = "rest", feature = "wled"))]
+    pub(crate) fn error(status: u16, message: &str) -> Response {
+        Response::json(status, &json!({ "error": message }))
+    }
+
+    pub(crate) fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len()
+        )?;
+
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+// Waits for the next connection, or returns `None` if `wait` is `false` and
+// no client is waiting.
+#[cfg(any(feature = "rest", feature = "wled"))]
+pub(crate) fn accept(listener: &TcpListener, wait: bool) -> Result<Option<TcpStream>> {
+    listener.set_nonblocking(!wait)?;
+
+    let stream = match listener.accept() {
+        Ok((stream, _)) => stream,
+        Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    if stream.set_nonblocking(false).is_err() {
+        return Ok(None);
+    }
+
+    Ok(Some(stream))
+}
+
+// Reads a single request from `stream`, and sends the response returned by
+// `handle`. Invalid requests are answered with an error, and errors that
+// only affect the connection with the client are ignored. Reading the
+// request and writing the response are each limited to CLIENT_TIMEOUT_SECS
+// in total, so a client that trickles bytes can't hold up the caller.
+//
+// Returns `Ok(true)` if a request was handled.
+#[cfg(any(feature = "rest", feature = "wled"))]
+pub(crate) fn serve<F>(stream: &TcpStream, handle: F) -> Result<bool>
+where
+    F: FnOnce(&Request) -> Result<Response>,
+{
+    let mut reader = io::BufReader::new(DeadlineStream::new(stream));
+    let response = match Request::read(&mut reader) {
+        Ok(request) => handle(&request)?,
+        Err(ref err) if err.kind() == io::ErrorKind::InvalidData => {
+            Response::error(400, &err.to_string())
+        }
+        Err(_) => return Ok(false),
+    };
+
+    let _ = response.write_to(&mut DeadlineStream::new(stream));
+
+    Ok(true)
+}
+
+// A TcpStream wrapper that fails all reads and writes once CLIENT_TIMEOUT_SECS
+// have passed since it was created. Each call only blocks for the remaining
+// time.
+#[cfg(any(feature = "rest", feature = "wled"))]
+struct DeadlineStream<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+#[cfg(any(feature = "rest", feature = "wled"))]
+impl<'a> DeadlineStream<'a> {
+    fn new(stream: &'a TcpStream) -> DeadlineStream<'a> {
+        DeadlineStream {
+            stream,
+            deadline: Instant::now() + Duration::from_secs(CLIENT_TIMEOUT_SECS),
+        }
+    }
+
+    fn remaining(&self) -> io::Result<Option<Duration>> {
+        let now = Instant::now();
+        if now >= self.deadline {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "client took too long",
+            ));
+        }
+
+        Ok(Some(self.deadline - now))
+    }
+}
+
+#[cfg(any(feature = "rest", feature = "wled"))]
+impl<'a> Read for DeadlineStream<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.set_read_timeout(self.remaining()?)?;
+        (&mut &*self.stream).read(buf)
+    }
+}
+
+#[cfg(any(feature = "rest", feature = "wled"))]
+impl<'a> Write for DeadlineStream<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.set_write_timeout(self.remaining()?)?;
+        (&mut &*self.stream).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&mut &*self.stream).flush()
+    }
+}
+
+fn read_line<R: BufRead>(reader: &mut R, header_len: &mut usize) -> io::Result<String> {
+    let mut line = Vec::new();
+    let limit = (MAX_HEADER_LEN - *header_len) as u64;
+    let len = (&mut *reader).take(limit).read_until(b'\n', &mut line)?;
+    *header_len += len;
+
+    if line.last() != Some(&b'\n') {
+        return Err(if len == 0 {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed")
+        } else {
+            invalid_data("request headers exceed the maximum length")
+        });
+    }
+
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+
+    String::from_utf8(line).map_err(|_| invalid_data("invalid request headers"))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
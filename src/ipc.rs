@@ -0,0 +1,514 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Sharing the pixels between local processes through a unix domain socket.
+//!
+//! Only one process can drive the pixels at a time. An [`IpcServer`] runs in
+//! the process that owns the `Blinkt`, and lets any number of other
+//! processes on the same machine update the pixels by sending it commands,
+//! either through an [`IpcClient`] or with tools like `socat`. Commands are
+//! handled one at a time, so clients never interfere with each other
+//! halfway through an update.
+//!
+//! Each command is a JSON object on a single line. Colors are specified as
+//! `"#rrggbb"` or `[r, g, b]`, and brightness values range from `0.0` to
+//! `1.0`.
+//!
+//! | Command | Description |
+//! |---|---|
+//! | `{"cmd": "set", "pixel": 0, "color": "#ff0000", "brightness": 0.5}` | Sets a single pixel. `color` and `brightness` are optional |
+//! | `{"cmd": "fill", "color": "#ff0000", "brightness": 0.5}` | Sets all pixels. `color` and `brightness` are optional |
+//! | `{"cmd": "clear"}` | Turns off all pixels |
+//! | `{"cmd": "show"}` | Sends the local buffer to the pixels |
+//! | `{"cmd": "get"}` | Returns the contents of the local buffer |
+//!
+//! `set`, `fill` and `clear` only update the local buffer, so a frame only
+//! becomes visible after a `show`. Several commands can be sent as a JSON
+//! array on a single line. The commands in an array are executed together,
+//! without any commands from other clients in between, and none of them are
+//! executed if any of them is invalid.
+//!
+//! Every line is answered with a single line, either `{"ok":true}` or
+//! `{"ok":false,"error":"..."}`. The `get` command adds the pixels to the
+//! response, for instance
+//! `{"ok":true,"pixels":[{"color":"#ff0000","brightness":0.5}]}`.
+//!
+//! This module requires the `ipc` feature.
+//!
+//! In the process that owns the pixels:
+//!
+//! ```rust,no_run
+//! use blinkt::ipc::IpcServer;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::new()?;
+//! let server = IpcServer::new()?;
+//!
+//! server.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! In any other process:
+//!
+//! ```rust,no_run
+//! use blinkt::ipc::IpcClient;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut client = IpcClient::connect("/tmp/blinkt.sock")?;
+//!
+//! client.set_all_pixels_color((255, 128, 0))?;
+//! client.show()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ```text
+//! echo '[{"cmd": "fill", "color": "#ff8000"}, {"cmd": "show"}]' | socat - UNIX-CONNECT:/tmp/blinkt.sock
+//! ```
+//!
+//! [`IpcServer`]: struct.IpcServer.html
+//! [`IpcClient`]: struct.IpcClient.html
+
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::{json, Value};
+
+use crate::control::{json_color, json_f32, pixels_json};
+use crate::{Blinkt, Color, Error, Result};
+
+/// The default socket path.
+pub const PATH: &str = "/tmp/blinkt.sock";
+
+// Maximum accepted line length. Longer lines close the connection, to avoid
+// allocating arbitrary amounts of memory.
+const MAX_LINE_LEN: usize = 1 << 16;
+
+#[derive(Debug, Clone)]
+enum Command {
+    Set(usize, Option<Color>, Option<f32>),
+    Fill(Option<Color>, Option<f32>),
+    Clear,
+    Show,
+    Get,
+}
+
+// A line received from a client, together with the channel the response
+// should be sent back on.
+#[derive(Debug)]
+struct Request {
+    line: String,
+    response: Sender<String>,
+}
+
+/// A server that executes the commands sent by local processes through a
+/// unix domain socket.
+///
+/// Every client is read by a separate thread, but all commands are executed
+/// by the thread that calls [`run`] or [`receive`].
+///
+/// The socket file is removed when the `IpcServer` is dropped.
+///
+/// [`run`]: #method.run
+/// [`receive`]: #method.receive
+#[derive(Debug)]
+pub struct IpcServer {
+    path: PathBuf,
+    requests: Receiver<Request>,
+    closed: Arc<AtomicBool>,
+}
+
+impl IpcServer {
+    /// Constructs a new `IpcServer` listening on the default socket path.
+    pub fn new() -> Result<IpcServer> {
+        IpcServer::bind(PATH)
+    }
+
+    /// Constructs a new `IpcServer` listening on the socket at `path`.
+    ///
+    /// A socket file left behind by a process that didn't exit cleanly is
+    /// replaced. Returns an error if another server is still listening on
+    /// `path`.
+    pub fn bind<P: AsRef<Path>>(path: P) -> Result<IpcServer> {
+        let path = path.as_ref().to_path_buf();
+
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => {
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(Error::Io(io::Error::new(
+                        io::ErrorKind::AddrInUse,
+                        "another server is listening on the socket",
+                    )));
+                }
+
+                fs::remove_file(&path)?;
+                UnixListener::bind(&path)?
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let (sender, requests) = mpsc::channel();
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let accept_closed = closed.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_closed.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                if let Ok(stream) = stream {
+                    let sender = sender.clone();
+                    thread::spawn(move || {
+                        let _ = serve(stream, &sender);
+                    });
+                }
+            }
+        });
+
+        Ok(IpcServer {
+            path,
+            requests,
+            closed,
+        })
+    }
+
+    /// Returns the path of the socket the server is listening on.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Executes commands until an error occurs.
+    pub fn run(&self, blinkt: &mut Blinkt) -> Result<()> {
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Waits for a single line from any of the clients, and executes its
+    /// commands.
+    ///
+    /// Invalid commands are reported to the client, and otherwise ignored.
+    /// Returns an error if sending a frame to the pixels failed.
+    pub fn receive(&self, blinkt: &mut Blinkt) -> Result<()> {
+        let request = match self.requests.recv() {
+            Ok(request) => request,
+            // The accept thread only exits after the server is dropped.
+            Err(_) => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "the socket was closed",
+                )))
+            }
+        };
+
+        let (response, result) = match parse_line(&request.line, blinkt.num_pixels()) {
+            Ok(commands) => {
+                let mut pixels = None;
+                let mut result = Ok(());
+                for command in commands {
+                    if let Err(err) = execute(blinkt, &command, &mut pixels) {
+                        result = Err(err);
+                        break;
+                    }
+                }
+
+                let response = match (&result, pixels) {
+                    (Err(err), _) => error_response(&err.to_string()),
+                    (Ok(()), Some(pixels)) => json!({ "ok": true, "pixels": pixels }).to_string(),
+                    (Ok(()), None) => json!({ "ok": true }).to_string(),
+                };
+
+                (response, result)
+            }
+            Err(message) => (error_response(message), Ok(())),
+        };
+
+        // The client may have disconnected in the meantime.
+        let _ = request.response.send(response);
+
+        result
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+
+        // Wake up the accept loop, so it notices the server was closed.
+        let _ = UnixStream::connect(&self.path);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Reads lines from the client, and forwards them to the server, until the
+// client disconnects or the server is dropped.
+fn serve(stream: UnixStream, requests: &Sender<Request>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let (sender, responses) = mpsc::channel();
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let len = reader
+            .by_ref()
+            .take(MAX_LINE_LEN as u64)
+            .read_line(&mut line)?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        if !line.ends_with('\n') && len == MAX_LINE_LEN {
+            writer.write_all(error_response("line too long").as_bytes())?;
+            writer.write_all(b"\n")?;
+            return Ok(());
+        }
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = Request {
+            line: line.clone(),
+            response: sender.clone(),
+        };
+
+        if requests.send(request).is_err() {
+            return Ok(());
+        }
+
+        match responses.recv() {
+            Ok(response) => {
+                writer.write_all(response.as_bytes())?;
+                writer.write_all(b"\n")?;
+            }
+            Err(_) => return Ok(()),
+        }
+    }
+}
+
+// Parses a single command, or an array of commands.
+fn parse_line(line: &str, num_pixels: usize) -> std::result::Result<Vec<Command>, &'static str> {
+    let value = match serde_json::from_str::<Value>(line) {
+        Ok(value) => value,
+        Err(_) => return Err("invalid JSON"),
+    };
+
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .map(|value| parse_command(value, num_pixels))
+            .collect(),
+        value => Ok(vec![parse_command(&value, num_pixels)?]),
+    }
+}
+
+fn parse_command(value: &Value, num_pixels: usize) -> std::result::Result<Command, &'static str> {
+    if !value.is_object() {
+        return Err("expected a JSON object");
+    }
+
+    let color = match value.get("color").map(json_color) {
+        Some(None) => return Err("invalid color"),
+        color => color.and_then(|color| color),
+    };
+
+    let brightness = match value.get("brightness").map(Value::as_f64) {
+        Some(None) => return Err("invalid brightness"),
+        brightness => {
+            brightness.and_then(|brightness| brightness.map(|brightness| brightness as f32))
+        }
+    };
+
+    match value.get("cmd").and_then(Value::as_str) {
+        Some("set") => match value.get("pixel").and_then(Value::as_u64) {
+            Some(pixel) if (pixel as usize) < num_pixels => {
+                Ok(Command::Set(pixel as usize, color, brightness))
+            }
+            Some(_) => Err("pixel out of range"),
+            None => Err("expected a pixel"),
+        },
+        Some("fill") => Ok(Command::Fill(color, brightness)),
+        Some("clear") => Ok(Command::Clear),
+        Some("show") => Ok(Command::Show),
+        Some("get") => Ok(Command::Get),
+        Some(_) => Err("unknown command"),
+        None => Err("expected a command"),
+    }
+}
+
+fn execute(blinkt: &mut Blinkt, command: &Command, pixels: &mut Option<Value>) -> Result<()> {
+    match *command {
+        Command::Set(pixel, color, brightness) => {
+            if let Some(color) = color {
+                blinkt.set_pixel_color(pixel, color);
+            }
+
+            if let Some(brightness) = brightness {
+                blinkt.set_pixel_brightness(pixel, brightness);
+            }
+        }
+        Command::Fill(color, brightness) => {
+            if let Some(color) = color {
+                blinkt.set_all_pixels_color(color);
+            }
+
+            if let Some(brightness) = brightness {
+                blinkt.set_all_pixels_brightness(brightness);
+            }
+        }
+        Command::Clear => blinkt.clear(),
+        Command::Show => {
+            blinkt.swap();
+            blinkt.show()?;
+        }
+        Command::Get => *pixels = Some(pixels_json(blinkt)),
+    }
+
+    Ok(())
+}
+
+fn error_response(message: &str) -> String {
+    json!({ "ok": false, "error": message }).to_string()
+}
+
+/// A client that sends commands to an [`IpcServer`].
+///
+/// Like the corresponding `Blinkt` methods, `set_pixel_color`,
+/// `set_all_pixels_color` and `clear` only update the server's local
+/// buffer. Call [`show`] to send it to the pixels.
+///
+/// [`IpcServer`]: struct.IpcServer.html
+/// [`show`]: #method.show
+#[derive(Debug)]
+pub struct IpcClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl IpcClient {
+    /// Connects to the `IpcServer` listening on the socket at `path`.
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<IpcClient> {
+        let writer = UnixStream::connect(path)?;
+
+        Ok(IpcClient {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    /// Sets the color of the specified pixel.
+    pub fn set_pixel_color<C: Into<Color>>(&mut self, pixel: usize, color: C) -> Result<()> {
+        let (red, green, blue) = color.into().rgb();
+        self.command(&json!({ "cmd": "set", "pixel": pixel, "color": [red, green, blue] }))
+    }
+
+    /// Sets the brightness of the specified pixel.
+    pub fn set_pixel_brightness(&mut self, pixel: usize, brightness: f32) -> Result<()> {
+        self.command(&json!({
+            "cmd": "set",
+            "pixel": pixel,
+            "brightness": json_f32(brightness),
+        }))
+    }
+
+    /// Sets the color of all pixels.
+    pub fn set_all_pixels_color<C: Into<Color>>(&mut self, color: C) -> Result<()> {
+        let (red, green, blue) = color.into().rgb();
+        self.command(&json!({ "cmd": "fill", "color": [red, green, blue] }))
+    }
+
+    /// Sets the brightness of all pixels.
+    pub fn set_all_pixels_brightness(&mut self, brightness: f32) -> Result<()> {
+        self.command(&json!({ "cmd": "fill", "brightness": json_f32(brightness) }))
+    }
+
+    /// Turns off all pixels.
+    pub fn clear(&mut self) -> Result<()> {
+        self.command(&json!({ "cmd": "clear" }))
+    }
+
+    /// Sends the server's local buffer to the pixels.
+    pub fn show(&mut self) -> Result<()> {
+        self.command(&json!({ "cmd": "show" }))
+    }
+
+    /// Sends a raw line containing a single command or an array of commands,
+    /// and returns the server's response.
+    ///
+    /// Returns an error if the server couldn't be reached. Commands rejected
+    /// by the server are reported in the response.
+    pub fn send(&mut self, line: &str) -> Result<String> {
+        if line.contains('\n') {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "commands can't contain line breaks",
+            )));
+        }
+
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+
+        let mut response = String::new();
+        if self.reader.read_line(&mut response)? == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the server closed the connection",
+            )));
+        }
+
+        Ok(response.trim_end().to_owned())
+    }
+
+    // Sends a command, and turns a rejected command into an error.
+    fn command(&mut self, command: &Value) -> Result<()> {
+        let response = self.send(&command.to_string())?;
+        let response: Value = match serde_json::from_str(&response) {
+            Ok(response) => response,
+            Err(_) => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid response",
+                )))
+            }
+        };
+
+        if response.get("ok").and_then(Value::as_bool) == Some(true) {
+            return Ok(());
+        }
+
+        let message = response
+            .get("error")
+            .and_then(Value::as_str)
+            .unwrap_or("command failed");
+
+        Err(Error::Io(io::Error::other(message)))
+    }
+}
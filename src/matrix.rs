@@ -0,0 +1,421 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#[cfg(feature = "embedded-graphics")]
+use embedded_graphics::draw_target::DrawTarget;
+#[cfg(feature = "embedded-graphics")]
+use embedded_graphics::geometry::{OriginDimensions, Size};
+#[cfg(feature = "embedded-graphics")]
+use embedded_graphics::pixelcolor::Rgb888;
+
+use crate::color::{Color, Interpolation};
+use crate::frame::Frame;
+use crate::pixel::Pixel;
+use crate::sprite::Sprite;
+use crate::{Blinkt, Result};
+
+/// The order in which the pixels of a [`Matrix`] are wired.
+///
+/// [`Matrix`]: struct.Matrix.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Every row starts on the left. The last pixel of a row is connected to
+    /// the first pixel of the next row.
+    Progressive,
+    /// Rows alternate direction. Even rows (starting at `0`) run from left to
+    /// right, odd rows run from right to left.
+    Serpentine,
+}
+
+/// The orientation of the content displayed on a [`Matrix`], relative to
+/// the physical panel.
+///
+/// [`Matrix`]: struct.Matrix.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    /// The content isn't rotated.
+    Rotate0,
+    /// The content is rotated 90 degrees clockwise.
+    Rotate90,
+    /// The content is rotated 180 degrees.
+    Rotate180,
+    /// The content is rotated 270 degrees clockwise (90 degrees
+    /// counterclockwise).
+    Rotate270,
+}
+
+/// A two-dimensional grid of pixels, arranged from one or more LED strips.
+///
+/// `Matrix` wraps a `Blinkt` instance, and translates `x` and `y` coordinates
+/// to pixel indices based on the configured [`Layout`]. The top left pixel is
+/// located at `x` = `0`, `y` = `0`, and is the first pixel on the strip.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # use blinkt::{Blinkt, Color, Layout, Matrix};
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let blinkt = Blinkt::with_spi(16_000_000, 16 * 16)?;
+/// let mut matrix = Matrix::new(blinkt, 16, 16, Layout::Serpentine);
+///
+/// matrix.set_xy(3, 5, Color::new(255, 0, 0));
+/// matrix.show()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Layout`]: enum.Layout.html
+pub struct Matrix {
+    blinkt: Blinkt,
+    width: usize,
+    height: usize,
+    layout: Layout,
+    rotation: Rotation,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+}
+
+impl Matrix {
+    /// Constructs a new `Matrix` with the specified dimensions and layout.
+    ///
+    /// `width` and `height` describe the physical panel, before any rotation
+    /// is applied.
+    ///
+    /// Pixels on the strip beyond `width` * `height` aren't accessible
+    /// through the matrix.
+    pub fn new(blinkt: Blinkt, width: usize, height: usize, layout: Layout) -> Matrix {
+        Matrix {
+            blinkt,
+            width,
+            height,
+            layout,
+            rotation: Rotation::Rotate0,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }
+    }
+
+    /// Returns the width in pixels, taking the rotation into account.
+    pub fn width(&self) -> usize {
+        match self.rotation {
+            Rotation::Rotate90 | Rotation::Rotate270 => self.height,
+            _ => self.width,
+        }
+    }
+
+    /// Returns the height in pixels, taking the rotation into account.
+    pub fn height(&self) -> usize {
+        match self.rotation {
+            Rotation::Rotate90 | Rotation::Rotate270 => self.width,
+            _ => self.height,
+        }
+    }
+
+    /// Returns the layout.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Returns the rotation.
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Sets the rotation of the content relative to the physical panel.
+    ///
+    /// This corrects for panels that are mounted sideways or upside down,
+    /// so all drawing calls can use the same coordinates as the viewer. With
+    /// `Rotation::Rotate90` or `Rotation::Rotate270`, the width and height
+    /// are swapped.
+    ///
+    /// By default, this is set to `Rotation::Rotate0`.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
+    }
+
+    /// Returns `true` if the content is mirrored horizontally.
+    pub fn flip_horizontal(&self) -> bool {
+        self.flip_horizontal
+    }
+
+    /// When enabled, mirrors the content horizontally.
+    ///
+    /// Flipping is applied before the rotation.
+    ///
+    /// By default, this is set to `false`.
+    pub fn set_flip_horizontal(&mut self, flip_horizontal: bool) {
+        self.flip_horizontal = flip_horizontal;
+    }
+
+    /// Returns `true` if the content is mirrored vertically.
+    pub fn flip_vertical(&self) -> bool {
+        self.flip_vertical
+    }
+
+    /// When enabled, mirrors the content vertically.
+    ///
+    /// Flipping is applied before the rotation.
+    ///
+    /// By default, this is set to `false`.
+    pub fn set_flip_vertical(&mut self, flip_vertical: bool) {
+        self.flip_vertical = flip_vertical;
+    }
+
+    /// Returns the pixel index for the specified coordinates, or `None` if
+    /// the coordinates are out of bounds.
+    ///
+    /// The coordinates are translated based on the flip settings, rotation
+    /// and layout.
+    pub fn index(&self, x: usize, y: usize) -> Option<usize> {
+        let (width, height) = (self.width(), self.height());
+        if x >= width || y >= height {
+            return None;
+        }
+
+        let x = if self.flip_horizontal {
+            width - 1 - x
+        } else {
+            x
+        };
+        let y = if self.flip_vertical {
+            height - 1 - y
+        } else {
+            y
+        };
+
+        let (x, y) = match self.rotation {
+            Rotation::Rotate0 => (x, y),
+            Rotation::Rotate90 => (self.width - 1 - y, x),
+            Rotation::Rotate180 => (self.width - 1 - x, self.height - 1 - y),
+            Rotation::Rotate270 => (y, self.height - 1 - x),
+        };
+
+        let x = match self.layout {
+            Layout::Serpentine if y % 2 == 1 => self.width - 1 - x,
+            _ => x,
+        };
+
+        Some(y * self.width + x)
+    }
+
+    /// Returns the pixel at the specified coordinates, or `None` if the
+    /// coordinates are out of bounds.
+    pub fn pixel_xy(&self, x: usize, y: usize) -> Option<Pixel> {
+        self.index(x, y)
+            .and_then(|idx| self.blinkt.pixels.get(idx).cloned())
+    }
+
+    /// Sets the color for the pixel at the specified coordinates.
+    ///
+    /// Coordinates that are out of bounds are ignored.
+    pub fn set_xy<C: Into<Color>>(&mut self, x: usize, y: usize, color: C) {
+        if let Some(idx) = self.index(x, y) {
+            self.blinkt.set_pixel_color(idx, color);
+        }
+    }
+
+    /// Sets the brightness value for the pixel at the specified coordinates.
+    ///
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_xy_brightness(&mut self, x: usize, y: usize, brightness: f32) {
+        if let Some(idx) = self.index(x, y) {
+            self.blinkt.set_pixel_brightness(idx, brightness);
+        }
+    }
+
+    /// Sets the color for all pixels.
+    pub fn fill<C: Into<Color>>(&mut self, color: C) {
+        self.blinkt.set_all_pixels_color(color);
+    }
+
+    /// Copies the contents of `frame` to the matrix, where `frame` stores the
+    /// pixels in row-major order, starting at the top left pixel.
+    ///
+    /// This is the order used by the effects in the [`automata`] module.
+    ///
+    /// [`automata`]: automata/index.html
+    pub fn draw_frame(&mut self, frame: &Frame) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if let (Some(idx), Some(pixel)) =
+                    (self.index(x, y), frame.pixel(y * self.width() + x))
+                {
+                    self.blinkt.replace_pixel(idx, *pixel);
+                }
+            }
+        }
+    }
+
+    /// Draws `image` onto the matrix.
+    ///
+    /// The image is resized to fill the entire matrix while preserving its
+    /// aspect ratio, and any parts that extend beyond the edges are cropped.
+    /// Transparent areas are blended with black.
+    ///
+    /// The color values are stored with 16-bit precision. When high
+    /// resolution mode is enabled through [`Blinkt::set_high_resolution`],
+    /// the additional precision is used for temporal dithering, which
+    /// improves smooth gradients and dark areas. Otherwise, the values are
+    /// truncated to 8 bits.
+    ///
+    /// [`Blinkt::set_high_resolution`]: struct.Blinkt.html#method.set_high_resolution
+    #[cfg(feature = "image")]
+    pub fn draw_image(&mut self, image: &image::DynamicImage) {
+        if self.width() == 0 || self.height() == 0 {
+            return;
+        }
+
+        let resized = image
+            .resize_to_fill(
+                self.width() as u32,
+                self.height() as u32,
+                image::imageops::FilterType::Triangle,
+            )
+            .to_rgba16();
+
+        for (x, y, pixel) in resized.enumerate_pixels() {
+            let [red, green, blue, alpha] = pixel.0;
+            let blend = |value: u16| (u32::from(value) * u32::from(alpha) / 65535) as u16;
+
+            if let Some(idx) = self.index(x as usize, y as usize) {
+                self.blinkt
+                    .set_pixel_rgb16(idx, blend(red), blend(green), blend(blue));
+            }
+        }
+    }
+
+    /// Draws `sprite` onto the matrix, with the top left corner of the
+    /// sprite located at `x`, `y`.
+    ///
+    /// Fully opaque pixels replace the color on the matrix, partially
+    /// transparent pixels are blended with it, and fully transparent pixels
+    /// or pixels matching the sprite's transparent color are skipped. Pixels
+    /// that fall outside the matrix are ignored, so `x` and `y` can be
+    /// negative to draw partially visible sprites.
+    pub fn blit(&mut self, sprite: &Sprite, x: isize, y: isize) {
+        for sprite_y in 0..sprite.height() {
+            for sprite_x in 0..sprite.width() {
+                let (matrix_x, matrix_y) = (x + sprite_x as isize, y + sprite_y as isize);
+                if matrix_x < 0 || matrix_y < 0 {
+                    continue;
+                }
+
+                let idx = match self.index(matrix_x as usize, matrix_y as usize) {
+                    Some(idx) => idx,
+                    None => continue,
+                };
+
+                if let Some((color, alpha)) = sprite.visible_pixel(sprite_x, sprite_y) {
+                    let color = if alpha < 1.0 {
+                        self.blinkt.pixels[idx].color().interpolate(
+                            color,
+                            alpha,
+                            Interpolation::Rgb,
+                        )
+                    } else {
+                        color
+                    };
+
+                    self.blinkt.set_pixel_color(idx, color);
+                }
+            }
+        }
+    }
+
+    /// Sets the red, green and blue values for all pixels to `0`.
+    pub fn clear(&mut self) {
+        self.blinkt.clear();
+    }
+
+    /// Sends the contents of the local buffer to the pixels.
+    pub fn show(&mut self) -> Result<()> {
+        self.blinkt.show()
+    }
+
+    // Copies packed 8-bit red, green and blue values for an image that's
+    // `width` pixels wide into the local buffer, and sends it to the pixels.
+    // Parts of the image outside of the matrix are cropped.
+    pub(crate) fn show_rgb(&mut self, width: usize, data: &[u8]) -> Result<()> {
+        for (offset, rgb) in data.chunks_exact(3).enumerate() {
+            if let Some(pixel) = self.index(offset % width, offset / width) {
+                self.blinkt.set_pixel(pixel, rgb[0], rgb[1], rgb[2]);
+            }
+        }
+
+        self.blinkt.swap();
+        self.blinkt.show()
+    }
+
+    /// Returns a reference to the wrapped `Blinkt`.
+    pub fn blinkt(&self) -> &Blinkt {
+        &self.blinkt
+    }
+
+    /// Returns a mutable reference to the wrapped `Blinkt`.
+    pub fn blinkt_mut(&mut self) -> &mut Blinkt {
+        &mut self.blinkt
+    }
+
+    /// Consumes the `Matrix`, returning the wrapped `Blinkt`.
+    pub fn into_inner(self) -> Blinkt {
+        self.blinkt
+    }
+}
+
+/// Allows the primitives, fonts and images from the [`embedded-graphics`]
+/// crate to be drawn directly onto the matrix.
+///
+/// Pixels outside the bounds of the matrix are ignored. Call [`show`] to send
+/// the results to the pixels.
+///
+/// [`embedded-graphics`]: https://crates.io/crates/embedded-graphics
+/// [`show`]: #method.show
+#[cfg(feature = "embedded-graphics")]
+impl DrawTarget for Matrix {
+    type Color = Rgb888;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> core::result::Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
+    {
+        for embedded_graphics::Pixel(point, color) in pixels {
+            if point.x >= 0 && point.y >= 0 {
+                self.set_xy(point.x as usize, point.y as usize, color);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> core::result::Result<(), Self::Error> {
+        self.fill(color);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-graphics")]
+impl OriginDimensions for Matrix {
+    fn size(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
+    }
+}
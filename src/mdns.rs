@@ -0,0 +1,626 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Advertising and discovering Blinkt nodes on the local network through
+//! mDNS.
+//!
+//! An [`Advertiser`] announces a service, such as a [`RemoteServer`] or the
+//! REST API of `blinktd`, through multicast DNS (DNS-SD), and answers
+//! queries for it in a background thread. [`discover`] sends a query and
+//! collects the answers, so control apps and other machines can find every
+//! Blinkt node on the network without hardcoding IP addresses.
+//!
+//! Only IPv4 is supported. The advertiser shares the mDNS port with other
+//! responders on the same machine, such as Avahi, so the nodes can also be
+//! found with tools like `avahi-browse _blinkt-remote._tcp`.
+//!
+//! This module requires the `mdns` feature.
+//!
+//! On the machine connected to the pixels:
+//!
+//! ```rust,no_run
+//! use blinkt::remote::RemoteServer;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+//! let server = RemoteServer::bind("0.0.0.0:7000")?;
+//! let _advertiser = server.advertise("Living room")?;
+//!
+//! server.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! On any other machine:
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use blinkt::mdns::{self, SERVICE_REMOTE};
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! for node in mdns::discover(SERVICE_REMOTE, Duration::from_secs(1))? {
+//!     println!("{} at {}", node.name(), node.addr());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Advertiser`]: struct.Advertiser.html
+//! [`RemoteServer`]: ../remote/struct.RemoteServer.html
+//! [`discover`]: fn.discover.html
+
+use std::io;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::os::unix::io::FromRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Error, Result};
+
+/// The service type of a [`RemoteServer`].
+///
+/// [`RemoteServer`]: ../remote/struct.RemoteServer.html
+pub const SERVICE_REMOTE: &str = "_blinkt-remote._tcp.local";
+/// The service type of the REST API served by a [`RestServer`].
+///
+/// [`RestServer`]: ../rest/struct.RestServer.html
+pub const SERVICE_REST: &str = "_blinkt._tcp.local";
+
+const PORT: u16 = 5353;
+const MAX_PACKET_LEN: usize = 9000;
+const MAX_LABEL_LEN: usize = 63;
+// Time to live of the records in seconds, and the maximum for responses to
+// one-shot queries from other ports than 5353 (RFC 6762, section 6.7).
+const TTL: u32 = 120;
+const LEGACY_UNICAST_TTL: u32 = 10;
+// How often the advertiser thread checks whether it should stop.
+const POLL_INTERVAL_MS: u64 = 250;
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+const CLASS_CACHE_FLUSH: u16 = 0x8000;
+const FLAGS_RESPONSE: u16 = 0x8400;
+
+/// A Blinkt node found by [`discover`].
+///
+/// [`discover`]: fn.discover.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    name: String,
+    host: String,
+    addr: SocketAddr,
+}
+
+impl Node {
+    /// Returns the instance name of the service, such as `Living room`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the host name of the node, such as `raspberrypi.local`.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the address and port the service is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+/// Advertises a service through mDNS until it's dropped.
+///
+/// The service is announced when the `Advertiser` is created, and queries
+/// for its service type are answered in a background thread. When the
+/// `Advertiser` is dropped, the thread sends a goodbye packet so other
+/// machines remove the service from their caches, and stops.
+#[derive(Debug)]
+pub struct Advertiser {
+    closed: Arc<AtomicBool>,
+}
+
+impl Advertiser {
+    /// Starts advertising an instance called `name` of `service`, for
+    /// instance [`SERVICE_REMOTE`], listening on `port`.
+    ///
+    /// `name` is shown to users, and can contain spaces and any other
+    /// characters, up to 63 bytes. The host name of the machine is used to
+    /// point other machines to its address.
+    ///
+    /// [`SERVICE_REMOTE`]: constant.SERVICE_REMOTE.html
+    pub fn spawn(service: &str, name: &str, port: u16) -> Result<Advertiser> {
+        if name.is_empty() || name.len() > MAX_LABEL_LEN {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "service instance names must be 1 to 63 bytes long",
+            )));
+        }
+
+        let socket = bind_shared(PORT)?;
+        socket.join_multicast_v4(&Ipv4Addr::new(224, 0, 0, 251), &Ipv4Addr::UNSPECIFIED)?;
+        socket.set_read_timeout(Some(Duration::from_millis(POLL_INTERVAL_MS)))?;
+
+        let record = Record {
+            service: service.to_owned(),
+            name: name.to_owned(),
+            host: format!("{}.local", host_name()),
+            port,
+        };
+
+        let closed = Arc::new(AtomicBool::new(false));
+        let thread_closed = closed.clone();
+        thread::spawn(move || {
+            let _ = record.announce(&socket, TTL);
+            let _ = record.serve(&socket, &thread_closed);
+            let _ = record.announce(&socket, 0);
+        });
+
+        Ok(Advertiser { closed })
+    }
+}
+
+impl Drop for Advertiser {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Sends an mDNS query for `service`, for instance [`SERVICE_REMOTE`], and
+/// returns the nodes that answered within `timeout`.
+///
+/// Nodes that are advertised with the same name more than once are only
+/// returned once.
+///
+/// [`SERVICE_REMOTE`]: constant.SERVICE_REMOTE.html
+pub fn discover(service: &str, timeout: Duration) -> Result<Vec<Node>> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    socket.send_to(&query(service), multicast_group())?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buffer = vec![0u8; MAX_PACKET_LEN];
+    let mut nodes: Vec<Node> = Vec::new();
+
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+
+        socket.set_read_timeout(Some(deadline - now))?;
+        let (len, src) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(ref e)
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut =>
+            {
+                break
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        for node in parse_response(&buffer[..len], service, src.ip()) {
+            if !nodes.iter().any(|other| other.name == node.name) {
+                nodes.push(node);
+            }
+        }
+    }
+
+    Ok(nodes)
+}
+
+// The records of an advertised service.
+#[derive(Debug)]
+struct Record {
+    service: String,
+    name: String,
+    host: String,
+    port: u16,
+}
+
+impl Record {
+    // Answers queries until the advertiser is dropped.
+    fn serve(&self, socket: &UdpSocket, closed: &AtomicBool) -> io::Result<()> {
+        let mut buffer = vec![0u8; MAX_PACKET_LEN];
+
+        while !closed.load(Ordering::SeqCst) {
+            let (len, src) = match socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(ref e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::Interrupted =>
+                {
+                    continue
+                }
+                Err(e) => return Err(e),
+            };
+
+            let packet = &buffer[..len];
+            let unicast = match is_query_for(packet, &self.service) {
+                Some(unicast) => unicast,
+                None => continue,
+            };
+
+            // Answer with the address of the interface the query arrived on.
+            let ip = match local_ip(src) {
+                Ok(ip) => ip,
+                Err(_) => continue,
+            };
+
+            let (response, dest) = if src.port() != PORT {
+                // One-shot queries expect the query ID and question back.
+                let id = be_u16(packet);
+                (self.response(id, ip, LEGACY_UNICAST_TTL, true), src)
+            } else if unicast {
+                (self.response(0, ip, TTL, false), src)
+            } else {
+                (self.response(0, ip, TTL, false), multicast_group())
+            };
+
+            // A failed answer doesn't stop the advertiser.
+            let _ = socket.send_to(&response, dest);
+        }
+
+        Ok(())
+    }
+
+    // Sends the records to the multicast group. A `ttl` of 0 removes them
+    // from the caches of other machines.
+    fn announce(&self, socket: &UdpSocket, ttl: u32) -> io::Result<()> {
+        let group = multicast_group();
+        let response = self.response(0, local_ip(group)?, ttl, false);
+        socket.send_to(&response, group)?;
+
+        Ok(())
+    }
+
+    fn response(&self, id: u16, ip: Ipv4Addr, ttl: u32, legacy: bool) -> Vec<u8> {
+        let service: Vec<&str> = self.service.split('.').collect();
+        let mut instance = vec![&self.name[..]];
+        instance.extend_from_slice(&service);
+        let host: Vec<&str> = self.host.split('.').collect();
+
+        // Legacy unicast responses must not set the cache flush bit.
+        let unique = if legacy {
+            CLASS_IN
+        } else {
+            CLASS_IN | CLASS_CACHE_FLUSH
+        };
+
+        let mut packet = Vec::with_capacity(512);
+        push_u16(&mut packet, id);
+        push_u16(&mut packet, FLAGS_RESPONSE);
+        push_u16(&mut packet, if legacy { 1 } else { 0 });
+        push_u16(&mut packet, 1);
+        push_u16(&mut packet, 0);
+        push_u16(&mut packet, 3);
+
+        if legacy {
+            push_name(&mut packet, &service);
+            push_u16(&mut packet, TYPE_PTR);
+            push_u16(&mut packet, CLASS_IN);
+        }
+
+        push_record(&mut packet, &service, TYPE_PTR, CLASS_IN, ttl, |data| {
+            push_name(data, &instance)
+        });
+        push_record(&mut packet, &instance, TYPE_SRV, unique, ttl, |data| {
+            push_u16(data, 0);
+            push_u16(data, 0);
+            push_u16(data, self.port);
+            push_name(data, &host);
+        });
+        push_record(&mut packet, &instance, TYPE_TXT, unique, ttl, |data| {
+            data.push(0)
+        });
+        push_record(&mut packet, &host, TYPE_A, unique, ttl, |data| {
+            data.extend_from_slice(&ip.octets())
+        });
+
+        packet
+    }
+}
+
+// A resource record in a response.
+struct Answer<'a> {
+    name: String,
+    kind: u16,
+    data: &'a [u8],
+    // Offset of the data in the packet, needed to follow compressed names.
+    offset: usize,
+}
+
+fn query(service: &str) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(64);
+    packet.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+    push_name(&mut packet, &service.split('.').collect::<Vec<_>>());
+    push_u16(&mut packet, TYPE_PTR);
+    push_u16(&mut packet, CLASS_IN);
+
+    packet
+}
+
+// Returns `Some(unicast)` if `packet` is a query that asks for `service`,
+// where `unicast` is `true` if the querier asked for a unicast response.
+fn is_query_for(packet: &[u8], service: &str) -> Option<bool> {
+    if packet.len() < 12 || packet[2] & 0x80 != 0 {
+        return None;
+    }
+
+    let mut offset = 12;
+    for _ in 0..be_u16(&packet[4..]) {
+        let (name, end) = read_name(packet, offset)?;
+        let question = packet.get(end..end + 4)?;
+        let kind = be_u16(question);
+        let class = be_u16(&question[2..]);
+        offset = end + 4;
+
+        if name.eq_ignore_ascii_case(service) && (kind == TYPE_PTR || kind == TYPE_ANY) {
+            return Some(class & CLASS_CACHE_FLUSH != 0);
+        }
+    }
+
+    None
+}
+
+fn parse_response(packet: &[u8], service: &str, src: IpAddr) -> Vec<Node> {
+    let answers = match parse_answers(packet) {
+        Some(answers) => answers,
+        None => return Vec::new(),
+    };
+
+    let suffix = format!(".{}", service.to_ascii_lowercase());
+    let mut nodes = Vec::new();
+
+    for answer in &answers {
+        if answer.kind != TYPE_PTR || !answer.name.eq_ignore_ascii_case(service) {
+            continue;
+        }
+
+        let instance = match read_name(packet, answer.offset) {
+            Some((instance, _)) => instance,
+            None => continue,
+        };
+
+        if !instance.to_ascii_lowercase().ends_with(&suffix) {
+            continue;
+        }
+
+        let srv = answers
+            .iter()
+            .find(|srv| srv.kind == TYPE_SRV && srv.name.eq_ignore_ascii_case(&instance));
+        let (port, host) = match srv {
+            Some(srv) if srv.data.len() > 6 => match read_name(packet, srv.offset + 6) {
+                Some((host, _)) => (be_u16(&srv.data[4..]), host),
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        // Fall back to the sender's address if the address record is
+        // missing.
+        let ip = answers
+            .iter()
+            .find(|a| a.kind == TYPE_A && a.data.len() == 4 && a.name.eq_ignore_ascii_case(&host))
+            .map(|a| IpAddr::V4(Ipv4Addr::new(a.data[0], a.data[1], a.data[2], a.data[3])))
+            .unwrap_or(src);
+
+        nodes.push(Node {
+            name: instance[..instance.len() - suffix.len()].to_owned(),
+            host,
+            addr: SocketAddr::new(ip, port),
+        });
+    }
+
+    nodes
+}
+
+// Returns all answer, authority and additional records in a response.
+fn parse_answers(packet: &[u8]) -> Option<Vec<Answer<'_>>> {
+    if packet.len() < 12 || packet[2] & 0x80 == 0 {
+        return None;
+    }
+
+    let mut offset = 12;
+    for _ in 0..be_u16(&packet[4..]) {
+        let (_, end) = read_name(packet, offset)?;
+        offset = end + 4;
+    }
+
+    let count = be_u16(&packet[6..]) as usize
+        + be_u16(&packet[8..]) as usize
+        + be_u16(&packet[10..]) as usize;
+
+    let mut answers = Vec::with_capacity(count);
+    for _ in 0..count {
+        let (name, end) = read_name(packet, offset)?;
+        let header = packet.get(end..end + 10)?;
+        let len = be_u16(&header[8..]) as usize;
+        let data = packet.get(end + 10..end + 10 + len)?;
+
+        answers.push(Answer {
+            name,
+            kind: be_u16(header),
+            data,
+            offset: end + 10,
+        });
+        offset = end + 10 + len;
+    }
+
+    Some(answers)
+}
+
+// Reads a possibly compressed name starting at `offset`. Returns the name,
+// with its labels separated by dots, and the offset of the first byte after
+// the name.
+fn read_name(packet: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut name = String::new();
+    let mut end = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *packet.get(offset)? as usize;
+        if len & 0xc0 == 0xc0 {
+            // Limit the number of pointers to reject loops.
+            jumps += 1;
+            if jumps > 16 {
+                return None;
+            }
+
+            end = end.or(Some(offset + 2));
+            offset = ((len & 0x3f) << 8) | *packet.get(offset + 1)? as usize;
+        } else if len == 0 {
+            return Some((name, end.unwrap_or(offset + 1)));
+        } else {
+            let label = packet.get(offset + 1..offset + 1 + len)?;
+            if !name.is_empty() {
+                name.push('.');
+            }
+
+            name.push_str(&String::from_utf8_lossy(label));
+            offset += 1 + len;
+        }
+    }
+}
+
+fn push_name(packet: &mut Vec<u8>, labels: &[&str]) {
+    for label in labels {
+        let label = &label.as_bytes()[..label.len().min(MAX_LABEL_LEN)];
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label);
+    }
+
+    packet.push(0);
+}
+
+fn push_record<F>(packet: &mut Vec<u8>, name: &[&str], kind: u16, class: u16, ttl: u32, data: F)
+where
+    F: FnOnce(&mut Vec<u8>),
+{
+    push_name(packet, name);
+    push_u16(packet, kind);
+    push_u16(packet, class);
+    push_u16(packet, (ttl >> 16) as u16);
+    push_u16(packet, ttl as u16);
+
+    let len_offset = packet.len();
+    push_u16(packet, 0);
+    data(packet);
+
+    let len = (packet.len() - len_offset - 2) as u16;
+    packet[len_offset] = (len >> 8) as u8;
+    packet[len_offset + 1] = len as u8;
+}
+
+fn push_u16(packet: &mut Vec<u8>, value: u16) {
+    packet.push((value >> 8) as u8);
+    packet.push(value as u8);
+}
+
+fn be_u16(bytes: &[u8]) -> u16 {
+    (u16::from(bytes[0]) << 8) | u16::from(bytes[1])
+}
+
+fn multicast_group() -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)), PORT)
+}
+
+// Returns the local address used to reach `addr`, to answer with an address
+// on the interface the query arrived on.
+fn local_ip(addr: SocketAddr) -> io::Result<Ipv4Addr> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+    socket.connect(addr)?;
+
+    match socket.local_addr()?.ip() {
+        IpAddr::V4(ip) => Ok(ip),
+        IpAddr::V6(_) => Err(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            "no IPv4 address",
+        )),
+    }
+}
+
+/// Returns the host name of this machine, without its domain, as it's
+/// advertised to other machines with a `.local` suffix.
+pub fn host_name() -> String {
+    let mut buffer = [0u8; 256];
+    let result =
+        unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+    if result != 0 {
+        return String::from("blinkt");
+    }
+
+    let len = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    match String::from_utf8_lossy(&buffer[..len]).split('.').next() {
+        Some(name) if !name.is_empty() => name.to_owned(),
+        _ => String::from("blinkt"),
+    }
+}
+
+// Binds a UDP socket to `port` on all interfaces with SO_REUSEADDR, so it
+// can share the port with other mDNS responders on the same machine.
+fn bind_shared(port: u16) -> io::Result<UdpSocket> {
+    unsafe {
+        let fd = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // The socket takes ownership of the file descriptor, and closes it
+        // if any of the following calls fail.
+        let socket = UdpSocket::from_raw_fd(fd);
+
+        let enable: libc::c_int = 1;
+        if libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_REUSEADDR,
+            &enable as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        ) != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut addr: libc::sockaddr_in = mem::zeroed();
+        addr.sin_family = libc::AF_INET as libc::sa_family_t;
+        addr.sin_port = port.to_be();
+        addr.sin_addr.s_addr = u32::from(Ipv4Addr::UNSPECIFIED).to_be();
+
+        if libc::bind(
+            fd,
+            &addr as *const libc::sockaddr_in as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        ) != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(socket)
+    }
+}
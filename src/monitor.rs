@@ -0,0 +1,801 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! System monitors.
+//!
+//! A [`SystemMonitor`] shows the CPU usage, memory use and load average of
+//! the Raspberry Pi as bars on the pixels, which turns a Blinkt! into an
+//! at-a-glance status display for headless systems. A [`NetworkMonitor`]
+//! shows the network traffic, which is useful for routers and NAS boxes, and
+//! a [`TemperatureMonitor`] warns when the Raspberry Pi runs hot.
+//!
+//! The monitors implement [`Animation`], so they're played with an
+//! [`Animator`]. They sample the system at a fixed interval, independently
+//! of the frame rate.
+//!
+//! This module requires the `monitor` feature.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use blinkt::animation::Animator;
+//! use blinkt::monitor::{Metric, SystemMonitor};
+//! use blinkt::{Blinkt, Color};
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::new()?;
+//!
+//! let mut monitor = SystemMonitor::new()
+//!     .with_metrics(&[Metric::Cpu, Metric::Memory])
+//!     .with_color(Metric::Memory, Color::new(255, 0, 255))
+//!     .with_interval(Duration::from_secs(2));
+//!
+//! Animator::new(10.0).run(&mut blinkt, &mut monitor)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//!
+//! ```rust,no_run
+//! use blinkt::animation::Animator;
+//! use blinkt::monitor::{NetDev, NetworkMonitor, NetworkStyle};
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::new()?;
+//!
+//! let mut monitor = NetworkMonitor::with_sampler(NetDev::with_interface("eth0"))
+//!     .with_style(NetworkStyle::Pulses)
+//!     .with_max_rate(125_000_000.0);
+//!
+//! Animator::new(60.0).run(&mut blinkt, &mut monitor)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SystemMonitor`]: struct.SystemMonitor.html
+//! [`NetworkMonitor`]: struct.NetworkMonitor.html
+//! [`TemperatureMonitor`]: struct.TemperatureMonitor.html
+//! [`Animation`]: ../animation/trait.Animation.html
+//! [`Animator`]: ../animation/struct.Animator.html
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use sysinfo::System;
+
+use crate::animation::{as_secs_f32, Animation};
+use crate::diagnostics::THERMAL_ZONE;
+use crate::{Color, Frame, Interpolation, Palette};
+
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+const PROC_NET_DEV: &str = "/proc/net/dev";
+// 100 Mbit/s.
+const DEFAULT_MAX_RATE: f64 = 12_500_000.0;
+// Rates below 1 KB/s don't show up.
+const MIN_RATE: f64 = 1024.0;
+// Pulses per second at the maximum rate.
+const MAX_PULSE_RATE: f32 = 4.0;
+// Time it takes a pulse to travel along the entire strip.
+const PULSE_TRAVEL_SECS: f32 = 1.0;
+const DEFAULT_MIN_TEMPERATURE: f32 = 30.0;
+const DEFAULT_MAX_TEMPERATURE: f32 = 85.0;
+const DEFAULT_WARNING_TEMPERATURE: f32 = 70.0;
+const DEFAULT_CRITICAL_TEMPERATURE: f32 = 80.0;
+const WARNING_FLASH_PERIOD_MS: u64 = 1000;
+const CRITICAL_FLASH_PERIOD_MS: u64 = 250;
+
+/// A statistic shown by a [`SystemMonitor`].
+///
+/// [`SystemMonitor`]: struct.SystemMonitor.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Metric {
+    /// The CPU usage, averaged over all cores.
+    Cpu,
+    /// The fraction of memory in use.
+    Memory,
+    /// The one-minute load average, relative to the number of cores. The bar
+    /// is full when every core has a task waiting for it on average.
+    LoadAverage,
+}
+
+impl Metric {
+    fn index(self) -> usize {
+        match self {
+            Metric::Cpu => 0,
+            Metric::Memory => 1,
+            Metric::LoadAverage => 2,
+        }
+    }
+}
+
+/// Shows system statistics as bars on the pixels.
+///
+/// The pixels are divided evenly between the statistics, and each one is
+/// shown as a bar that fills its part of the strip from start to end. The
+/// last pixel of a bar is dimmed to show the remainder, so even a Blinkt!
+/// with 8 pixels shows small changes.
+pub struct SystemMonitor {
+    system: System,
+    metrics: Vec<Metric>,
+    colors: [Color; 3],
+    values: [f32; 3],
+    interval: Duration,
+    last_sample: Option<Duration>,
+}
+
+impl fmt::Debug for SystemMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SystemMonitor")
+            .field("metrics", &self.metrics)
+            .field("colors", &self.colors)
+            .field("values", &self.values)
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+impl SystemMonitor {
+    /// Constructs a new `SystemMonitor` that shows the CPU usage in green,
+    /// memory use in blue and the load average in orange, sampled once per
+    /// second.
+    pub fn new() -> SystemMonitor {
+        let mut system = System::new();
+        // CPU usage is calculated from the difference between two samples.
+        system.refresh_cpu();
+
+        SystemMonitor {
+            system,
+            metrics: vec![Metric::Cpu, Metric::Memory, Metric::LoadAverage],
+            colors: [
+                Color::new(0, 255, 0),
+                Color::new(0, 0, 255),
+                Color::new(255, 128, 0),
+            ],
+            values: [0.0; 3],
+            interval: Duration::from_millis(DEFAULT_INTERVAL_MS),
+            last_sample: None,
+        }
+    }
+
+    /// Sets the statistics to show, and their order along the strip.
+    pub fn with_metrics(mut self, metrics: &[Metric]) -> SystemMonitor {
+        self.metrics = metrics.to_vec();
+        self
+    }
+
+    /// Sets the color of the bar for `metric`.
+    pub fn with_color<C: Into<Color>>(mut self, metric: Metric, color: C) -> SystemMonitor {
+        self.colors[metric.index()] = color.into();
+        self
+    }
+
+    /// Sets the time between samples.
+    ///
+    /// The interval is limited to a minimum of 200 ms, because shorter
+    /// intervals don't give an accurate CPU usage.
+    pub fn with_interval(mut self, interval: Duration) -> SystemMonitor {
+        self.interval = interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        self
+    }
+
+    /// Returns the most recently sampled value for `metric`, between `0.0`
+    /// and `1.0`.
+    pub fn value(&self, metric: Metric) -> f32 {
+        self.values[metric.index()]
+    }
+
+    fn sample(&mut self) {
+        self.system.refresh_cpu();
+        self.system.refresh_memory();
+
+        let cpu = self.system.global_cpu_info().cpu_usage() / 100.0;
+
+        let total = self.system.total_memory();
+        let memory = if total > 0 {
+            self.system.used_memory() as f32 / total as f32
+        } else {
+            0.0
+        };
+
+        let cores = self.system.cpus().len().max(1);
+        let load = System::load_average().one as f32 / cores as f32;
+
+        for (value, sample) in self.values.iter_mut().zip(&[cpu, memory, load]) {
+            *value = sample.max(0.0).min(1.0);
+        }
+    }
+}
+
+impl Default for SystemMonitor {
+    fn default() -> SystemMonitor {
+        SystemMonitor::new()
+    }
+}
+
+impl Animation for SystemMonitor {
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        // Sample immediately when the animation (re)starts.
+        let due = match self.last_sample {
+            Some(last) => t < last || t - last >= self.interval,
+            None => true,
+        };
+
+        if due {
+            self.sample();
+            self.last_sample = Some(t);
+        }
+
+        let len = frame.len();
+        let count = self.metrics.len();
+
+        frame.clear();
+        for (idx, metric) in self.metrics.iter().enumerate() {
+            draw_bar(
+                frame,
+                idx * len / count..(idx + 1) * len / count,
+                self.values[metric.index()],
+                self.colors[metric.index()],
+            );
+        }
+    }
+}
+
+/// A source of network traffic counters for a [`NetworkMonitor`].
+///
+/// `Sampler` is implemented for any closure with a matching signature, which
+/// makes it easy to monitor traffic that isn't listed in `/proc/net/dev`,
+/// such as the counters of a router queried over SNMP.
+///
+/// [`NetworkMonitor`]: struct.NetworkMonitor.html
+pub trait Sampler {
+    /// Returns the total number of bytes received and transmitted so far.
+    fn sample(&mut self) -> io::Result<(u64, u64)>;
+}
+
+impl<F> Sampler for F
+where
+    F: FnMut() -> io::Result<(u64, u64)>,
+{
+    fn sample(&mut self) -> io::Result<(u64, u64)> {
+        self()
+    }
+}
+
+/// Reads the traffic counters of the network interfaces from
+/// `/proc/net/dev`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NetDev {
+    interface: Option<String>,
+}
+
+impl NetDev {
+    /// Constructs a new `NetDev` that adds up the traffic of all interfaces,
+    /// except for the loopback interface.
+    pub fn new() -> NetDev {
+        NetDev { interface: None }
+    }
+
+    /// Constructs a new `NetDev` that only reads the traffic of the
+    /// interface called `name`, such as `eth0` or `wlan0`.
+    pub fn with_interface(name: &str) -> NetDev {
+        NetDev {
+            interface: Some(name.to_owned()),
+        }
+    }
+}
+
+impl Sampler for NetDev {
+    fn sample(&mut self) -> io::Result<(u64, u64)> {
+        let contents = fs::read_to_string(PROC_NET_DEV)?;
+        let mut found = false;
+        let (mut rx, mut tx) = (0, 0);
+
+        // The first two lines contain the column headers.
+        for line in contents.lines().skip(2) {
+            let mut parts = line.splitn(2, ':');
+            let name = parts.next().unwrap_or("").trim();
+            let fields: Vec<&str> = parts.next().unwrap_or("").split_whitespace().collect();
+
+            let selected = match self.interface {
+                Some(ref interface) => name == interface,
+                None => name != "lo",
+            };
+
+            if !selected || fields.len() < 9 {
+                continue;
+            }
+
+            let parse = |field: &str| {
+                field
+                    .parse::<u64>()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            };
+
+            rx += parse(fields[0])?;
+            tx += parse(fields[8])?;
+            found = true;
+        }
+
+        if self.interface.is_some() && !found {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "network interface not found",
+            ));
+        }
+
+        Ok((rx, tx))
+    }
+}
+
+/// The way a [`NetworkMonitor`] shows the traffic.
+///
+/// [`NetworkMonitor`]: struct.NetworkMonitor.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NetworkStyle {
+    /// The first half of the pixels shows the receive rate as a bar, and the
+    /// second half the transmit rate.
+    Bars,
+    /// Pulses travel along the pixels, towards the first pixel for received
+    /// traffic and towards the last pixel for transmitted traffic. Busier
+    /// links send out more pulses.
+    Pulses,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Pulse {
+    transmit: bool,
+    distance: f32,
+}
+
+/// Shows the network receive and transmit rates on the pixels.
+///
+/// Rates are shown on a logarithmic scale, from 1 KB/s up to the maximum
+/// rate set with [`with_max_rate`], so both a trickle of background traffic
+/// and a large download remain visible.
+///
+/// If sampling fails, for instance because the interface went away, both
+/// rates drop to zero until sampling succeeds again.
+///
+/// [`with_max_rate`]: #method.with_max_rate
+pub struct NetworkMonitor {
+    sampler: Box<dyn Sampler + Send>,
+    style: NetworkStyle,
+    colors: [Color; 2],
+    max_rate: f64,
+    interval: Duration,
+    last_sample: Option<(Duration, u64, u64)>,
+    rates: [f64; 2],
+    pulses: Vec<Pulse>,
+    credits: [f32; 2],
+    last_render: Option<Duration>,
+}
+
+impl fmt::Debug for NetworkMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetworkMonitor")
+            .field("style", &self.style)
+            .field("colors", &self.colors)
+            .field("max_rate", &self.max_rate)
+            .field("interval", &self.interval)
+            .field("rates", &self.rates)
+            .finish()
+    }
+}
+
+impl NetworkMonitor {
+    /// Constructs a new `NetworkMonitor` that shows the traffic of all
+    /// network interfaces as bars, with received traffic in green and
+    /// transmitted traffic in blue, up to 100 Mbit/s.
+    pub fn new() -> NetworkMonitor {
+        NetworkMonitor::with_sampler(NetDev::new())
+    }
+
+    /// Constructs a new `NetworkMonitor` that reads the traffic counters
+    /// from `sampler`.
+    pub fn with_sampler<S>(sampler: S) -> NetworkMonitor
+    where
+        S: Sampler + Send + 'static,
+    {
+        NetworkMonitor {
+            sampler: Box::new(sampler),
+            style: NetworkStyle::Bars,
+            colors: [Color::new(0, 255, 0), Color::new(0, 0, 255)],
+            max_rate: DEFAULT_MAX_RATE,
+            interval: Duration::from_millis(DEFAULT_INTERVAL_MS),
+            last_sample: None,
+            rates: [0.0; 2],
+            pulses: Vec::new(),
+            credits: [0.0; 2],
+            last_render: None,
+        }
+    }
+
+    /// Sets the way the traffic is shown.
+    pub fn with_style(mut self, style: NetworkStyle) -> NetworkMonitor {
+        self.style = style;
+        self
+    }
+
+    /// Sets the colors of the received and transmitted traffic.
+    pub fn with_colors<C: Into<Color>>(mut self, receive: C, transmit: C) -> NetworkMonitor {
+        self.colors = [receive.into(), transmit.into()];
+        self
+    }
+
+    /// Sets the rate in bytes per second that fills a bar, or sends out
+    /// pulses at the highest frequency.
+    pub fn with_max_rate(mut self, bytes_per_sec: f64) -> NetworkMonitor {
+        self.max_rate = bytes_per_sec.max(MIN_RATE * 2.0);
+        self
+    }
+
+    /// Sets the time between samples.
+    pub fn with_interval(mut self, interval: Duration) -> NetworkMonitor {
+        self.interval = interval.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Returns the most recently measured receive and transmit rates, in
+    /// bytes per second.
+    pub fn rates(&self) -> (f64, f64) {
+        (self.rates[0], self.rates[1])
+    }
+
+    fn sample(&mut self, t: Duration) {
+        match self.sampler.sample() {
+            Ok((rx, tx)) => {
+                if let Some((last, last_rx, last_tx)) = self.last_sample {
+                    let secs = as_secs_f32(t - last).max(0.001) as f64;
+                    // Counters restart at zero when an interface is
+                    // recreated.
+                    self.rates = [
+                        rx.saturating_sub(last_rx) as f64 / secs,
+                        tx.saturating_sub(last_tx) as f64 / secs,
+                    ];
+                }
+
+                self.last_sample = Some((t, rx, tx));
+            }
+            Err(_) => {
+                self.rates = [0.0; 2];
+                self.last_sample = None;
+            }
+        }
+    }
+
+    // Converts a rate to a position on a logarithmic scale between 0.0 and
+    // 1.0.
+    fn level(&self, rate: f64) -> f32 {
+        if rate <= MIN_RATE {
+            return 0.0;
+        }
+
+        ((rate / MIN_RATE).ln() / (self.max_rate / MIN_RATE).ln()).min(1.0) as f32
+    }
+
+    fn render_pulses(&mut self, elapsed: f32, frame: &mut Frame) {
+        let len = frame.len();
+        let speed = len as f32 / PULSE_TRAVEL_SECS;
+
+        for pulse in &mut self.pulses {
+            pulse.distance += speed * elapsed;
+        }
+        self.pulses
+            .retain(|pulse| pulse.distance < len as f32 + 1.0);
+
+        for direction in 0..2 {
+            let level = self.level(self.rates[direction]);
+            self.credits[direction] += level * MAX_PULSE_RATE * elapsed;
+
+            while self.credits[direction] >= 1.0 {
+                self.credits[direction] -= 1.0;
+                self.pulses.push(Pulse {
+                    transmit: direction == 1,
+                    distance: 0.0,
+                });
+            }
+        }
+
+        frame.clear();
+        for pulse in &self.pulses {
+            let color = self.colors[pulse.transmit as usize];
+            // Spread each pulse over the two nearest pixels, so it moves
+            // smoothly.
+            let whole = pulse.distance.floor();
+            let fraction = pulse.distance - whole;
+
+            for &(offset, weight) in &[(0, 1.0 - fraction), (1, fraction)] {
+                let distance = whole as isize + offset - 1;
+                if distance < 0 || distance >= len as isize {
+                    continue;
+                }
+
+                let pixel = if pulse.transmit {
+                    distance as usize
+                } else {
+                    len - 1 - distance as usize
+                };
+
+                add_pixel_color(frame, pixel, scale(color, weight));
+            }
+        }
+    }
+}
+
+impl Default for NetworkMonitor {
+    fn default() -> NetworkMonitor {
+        NetworkMonitor::new()
+    }
+}
+
+impl Animation for NetworkMonitor {
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        // Start over when the animation restarts.
+        if self.last_render.map(|last| t < last).unwrap_or(false) {
+            self.last_sample = None;
+            self.rates = [0.0; 2];
+            self.pulses.clear();
+        }
+
+        let elapsed = as_secs_f32(t - self.last_render.unwrap_or(t).min(t));
+        self.last_render = Some(t);
+
+        let due = match self.last_sample {
+            Some((last, _, _)) => t - last >= self.interval,
+            None => true,
+        };
+
+        if due {
+            self.sample(t);
+        }
+
+        match self.style {
+            NetworkStyle::Bars => {
+                let len = frame.len();
+                let (receive, transmit) = (self.level(self.rates[0]), self.level(self.rates[1]));
+
+                frame.clear();
+                draw_bar(frame, 0..len / 2, receive, self.colors[0]);
+                draw_bar(frame, len / 2..len, transmit, self.colors[1]);
+            }
+            NetworkStyle::Pulses => self.render_pulses(elapsed, frame),
+        }
+    }
+}
+
+/// The way a [`TemperatureMonitor`] shows the temperature.
+///
+/// [`TemperatureMonitor`]: struct.TemperatureMonitor.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TemperatureStyle {
+    /// All pixels show the color for the current temperature.
+    Color,
+    /// A bar that grows with the temperature, like a thermometer. Every pixel
+    /// shows the color for the temperature it represents.
+    Bar,
+}
+
+/// Shows the CPU temperature on the pixels.
+///
+/// The temperature is looked up in a color gradient that runs from blue at
+/// the low end of the range, through green and yellow, to red at the high
+/// end. Above the warning threshold the pixels flash slowly, and above the
+/// critical threshold they flash quickly.
+///
+/// If the temperature can't be read, the pixels are turned off.
+#[derive(Debug, Clone)]
+pub struct TemperatureMonitor {
+    path: PathBuf,
+    style: TemperatureStyle,
+    min: f32,
+    max: f32,
+    warning: f32,
+    critical: f32,
+    palette: Palette,
+    interval: Duration,
+    temperature: Option<f32>,
+    last_sample: Option<Duration>,
+}
+
+impl TemperatureMonitor {
+    /// Constructs a new `TemperatureMonitor` that reads the temperature of
+    /// the Raspberry Pi's SoC from `/sys/class/thermal/thermal_zone0/temp`,
+    /// once per second.
+    ///
+    /// The gradient runs from 30 to 85 °C. The pixels start flashing at
+    /// 70 °C, and flash faster from 80 °C, where the Raspberry Pi starts to
+    /// throttle.
+    pub fn new() -> TemperatureMonitor {
+        TemperatureMonitor::with_path(THERMAL_ZONE)
+    }
+
+    /// Constructs a new `TemperatureMonitor` that reads the temperature from
+    /// the file at `path`, which contains the temperature in thousandths of
+    /// a degree Celsius, like the other thermal zones in
+    /// `/sys/class/thermal`.
+    pub fn with_path<P: AsRef<Path>>(path: P) -> TemperatureMonitor {
+        let mut palette = Palette::new(&[
+            Color::new(0, 0, 255),
+            Color::new(0, 255, 0),
+            Color::new(255, 0, 0),
+        ]);
+        palette.set_interpolation(Some(Interpolation::Hsv));
+
+        TemperatureMonitor {
+            path: path.as_ref().to_path_buf(),
+            style: TemperatureStyle::Color,
+            min: DEFAULT_MIN_TEMPERATURE,
+            max: DEFAULT_MAX_TEMPERATURE,
+            warning: DEFAULT_WARNING_TEMPERATURE,
+            critical: DEFAULT_CRITICAL_TEMPERATURE,
+            palette,
+            interval: Duration::from_millis(DEFAULT_INTERVAL_MS),
+            temperature: None,
+            last_sample: None,
+        }
+    }
+
+    /// Sets the way the temperature is shown.
+    pub fn with_style(mut self, style: TemperatureStyle) -> TemperatureMonitor {
+        self.style = style;
+        self
+    }
+
+    /// Sets the temperatures in °C at the start and end of the gradient.
+    pub fn with_range(mut self, min: f32, max: f32) -> TemperatureMonitor {
+        self.min = min;
+        self.max = max.max(min + 1.0);
+        self
+    }
+
+    /// Sets the temperatures in °C above which the pixels flash slowly
+    /// (`warning`) and quickly (`critical`).
+    ///
+    /// Set a threshold to `f32::INFINITY` to disable it.
+    pub fn with_thresholds(mut self, warning: f32, critical: f32) -> TemperatureMonitor {
+        self.warning = warning;
+        self.critical = critical;
+        self
+    }
+
+    /// Replaces the default blue to red gradient.
+    pub fn with_palette(mut self, palette: Palette) -> TemperatureMonitor {
+        self.palette = palette;
+        self
+    }
+
+    /// Sets the time between samples.
+    pub fn with_interval(mut self, interval: Duration) -> TemperatureMonitor {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns the most recently read temperature in °C, or `None` if it
+    /// couldn't be read.
+    pub fn temperature(&self) -> Option<f32> {
+        self.temperature
+    }
+
+    fn sample(&mut self) {
+        self.temperature = fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| contents.trim().parse::<f32>().ok())
+            .map(|millidegrees| millidegrees / 1000.0);
+    }
+}
+
+impl Default for TemperatureMonitor {
+    fn default() -> TemperatureMonitor {
+        TemperatureMonitor::new()
+    }
+}
+
+impl Animation for TemperatureMonitor {
+    fn render(&mut self, t: Duration, frame: &mut Frame) {
+        // Sample immediately when the animation (re)starts.
+        let due = match self.last_sample {
+            Some(last) => t < last || t - last >= self.interval,
+            None => true,
+        };
+
+        if due {
+            self.sample();
+            self.last_sample = Some(t);
+        }
+
+        frame.clear();
+
+        let temperature = match self.temperature {
+            Some(temperature) => temperature,
+            None => return,
+        };
+
+        let flash_period = if temperature >= self.critical {
+            Some(CRITICAL_FLASH_PERIOD_MS)
+        } else if temperature >= self.warning {
+            Some(WARNING_FLASH_PERIOD_MS)
+        } else {
+            None
+        };
+
+        if let Some(period) = flash_period {
+            let ms = t.as_secs() * 1000 + u64::from(t.subsec_millis());
+            if ms % period >= period / 2 {
+                return;
+            }
+        }
+
+        let position = (temperature - self.min) / (self.max - self.min);
+
+        match self.style {
+            TemperatureStyle::Color => {
+                frame.set_all_pixels_color(self.palette.color_at(position));
+            }
+            TemperatureStyle::Bar => {
+                let len = frame.len();
+                let lit = position.max(0.0).min(1.0) * len as f32;
+
+                for pixel in 0..len {
+                    let fill = (lit - pixel as f32).max(0.0).min(1.0);
+                    if fill > 0.0 {
+                        let color = self.palette.color_at((pixel as f32 + 0.5) / len as f32);
+                        frame.set_pixel_color(pixel, scale(color, fill));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Fills `range` from the start for `value` between 0.0 and 1.0, with the
+// last pixel dimmed to show the remainder.
+fn draw_bar(frame: &mut Frame, range: Range<usize>, value: f32, color: Color) {
+    let lit = value * range.len() as f32;
+
+    for (idx, pixel) in range.enumerate() {
+        let fill = (lit - idx as f32).max(0.0).min(1.0);
+        if fill > 0.0 {
+            frame.set_pixel_color(pixel, scale(color, fill));
+        }
+    }
+}
+
+fn add_pixel_color(frame: &mut Frame, pixel: usize, color: Color) {
+    let (red, green, blue) = frame.pixel(pixel).map(|p| p.rgb()).unwrap_or((0, 0, 0));
+
+    frame.set_pixel_color(
+        pixel,
+        Color::new(
+            red.saturating_add(color.red),
+            green.saturating_add(color.green),
+            blue.saturating_add(color.blue),
+        ),
+    );
+}
+
+fn scale(color: Color, factor: f32) -> Color {
+    let scale = |value: u8| (f32::from(value) * factor).round() as u8;
+    Color::new(scale(color.red), scale(color.green), scale(color.blue))
+}
@@ -0,0 +1,661 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Controlling the pixels through MQTT.
+//!
+//! An [`MqttClient`] connects to an MQTT broker, subscribes to a set of
+//! command topics, and publishes the resulting state, which is the usual way
+//! to integrate LED hardware with home automation systems. The client
+//! implements MQTT 3.1.1, and only uses QoS 0.
+//!
+//! With the default `blinkt` topic prefix, the following topics are used.
+//!
+//! | Topic | Payload |
+//! |---|---|
+//! | `blinkt/color/set` | `#rrggbb`, `r,g,b` or a JSON array `[r, g, b]` |
+//! | `blinkt/brightness/set` | A value between `0.0` and `1.0` |
+//! | `blinkt/power/set` | `ON` or `OFF` |
+//! | `blinkt/frame/set` | A JSON array with a color for every pixel, either as `[r, g, b]` or `"#rrggbb"` |
+//! | `blinkt/state` | The current state, published by the client |
+//!
+//! A color replaces any frame set earlier, and vice versa. The state is
+//! published as a retained JSON object after every change, for instance
+//! `{"state":"ON","color":[255,0,0],"brightness":0.5}`. When a frame is
+//! active, `color` is `null`.
+//!
+//! This module requires the `mqtt` feature.
+//!
+//! ```rust,no_run
+//! use blinkt::mqtt::{MqttClient, MqttOptions};
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 60)?;
+//!
+//! let options = MqttOptions::new("broker.local:1883")
+//!     .with_client_id("living-room")
+//!     .with_topic_prefix("home/living-room/strip");
+//! let mut client = MqttClient::connect(options)?;
+//!
+//! client.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`MqttClient`]: struct.MqttClient.html
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::control::{json_color, parse_hex};
+use crate::{Blinkt, Color, Error, Result};
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PUBACK: u8 = 0x40;
+const SUBSCRIBE: u8 = 0x82;
+const PINGREQ: u8 = 0xc0;
+const DISCONNECT: u8 = 0xe0;
+const PROTOCOL_LEVEL: u8 = 4;
+const DEFAULT_KEEP_ALIVE_SECS: u64 = 30;
+// Maximum accepted packet length. Larger packets are rejected to avoid
+// allocating arbitrary amounts of memory.
+const MAX_PACKET_LEN: usize = 1 << 20;
+
+/// Connection settings and topics for an [`MqttClient`].
+///
+/// [`MqttClient`]: struct.MqttClient.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttOptions {
+    pub(crate) addr: String,
+    pub(crate) client_id: String,
+    pub(crate) credentials: Option<(String, String)>,
+    pub(crate) keep_alive: Duration,
+    color_topic: String,
+    brightness_topic: String,
+    power_topic: String,
+    frame_topic: String,
+    state_topic: String,
+}
+
+impl MqttOptions {
+    /// Constructs a new `MqttOptions` for the broker at `addr`, for
+    /// instance `localhost:1883`.
+    ///
+    /// The client ID defaults to `blinkt`, the keep alive interval to 30
+    /// seconds, and the topic prefix to `blinkt`.
+    pub fn new(addr: &str) -> MqttOptions {
+        MqttOptions {
+            addr: addr.to_owned(),
+            client_id: "blinkt".to_owned(),
+            credentials: None,
+            keep_alive: Duration::from_secs(DEFAULT_KEEP_ALIVE_SECS),
+            color_topic: String::new(),
+            brightness_topic: String::new(),
+            power_topic: String::new(),
+            frame_topic: String::new(),
+            state_topic: String::new(),
+        }
+        .with_topic_prefix("blinkt")
+    }
+
+    /// Sets the client ID, which needs to be unique for each client
+    /// connected to the broker.
+    pub fn with_client_id(mut self, client_id: &str) -> MqttOptions {
+        self.client_id = client_id.to_owned();
+        self
+    }
+
+    /// Sets the username and password used to log in to the broker.
+    pub fn with_credentials(mut self, username: &str, password: &str) -> MqttOptions {
+        self.credentials = Some((username.to_owned(), password.to_owned()));
+        self
+    }
+
+    /// Sets the keep alive interval. The broker disconnects the client when
+    /// it doesn't receive any packets for 1.5 times this interval.
+    ///
+    /// An interval of zero disables keep alive, so no pings are sent, and
+    /// the broker never disconnects the client because it's idle.
+    pub fn with_keep_alive(mut self, keep_alive: Duration) -> MqttOptions {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Sets all topics to their default names, below `prefix`.
+    pub fn with_topic_prefix(mut self, prefix: &str) -> MqttOptions {
+        self.color_topic = format!("{}/color/set", prefix);
+        self.brightness_topic = format!("{}/brightness/set", prefix);
+        self.power_topic = format!("{}/power/set", prefix);
+        self.frame_topic = format!("{}/frame/set", prefix);
+        self.state_topic = format!("{}/state", prefix);
+        self
+    }
+
+    /// Sets the topic used to set the color of all pixels.
+    pub fn with_color_topic(mut self, topic: &str) -> MqttOptions {
+        self.color_topic = topic.to_owned();
+        self
+    }
+
+    /// Sets the topic used to set the brightness of all pixels.
+    pub fn with_brightness_topic(mut self, topic: &str) -> MqttOptions {
+        self.brightness_topic = topic.to_owned();
+        self
+    }
+
+    /// Sets the topic used to turn the pixels on or off.
+    pub fn with_power_topic(mut self, topic: &str) -> MqttOptions {
+        self.power_topic = topic.to_owned();
+        self
+    }
+
+    /// Sets the topic used to set the color of every pixel individually.
+    pub fn with_frame_topic(mut self, topic: &str) -> MqttOptions {
+        self.frame_topic = topic.to_owned();
+        self
+    }
+
+    /// Sets the topic the state is published to.
+    pub fn with_state_topic(mut self, topic: &str) -> MqttOptions {
+        self.state_topic = topic.to_owned();
+        self
+    }
+}
+
+// The state controlled through MQTT.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LightState {
+    pub(crate) on: bool,
+    pub(crate) color: Option<Color>,
+    pub(crate) frame: Option<Vec<Color>>,
+    pub(crate) brightness: f32,
+}
+
+impl Default for LightState {
+    fn default() -> LightState {
+        LightState {
+            on: false,
+            color: Some(Color::from((255, 255, 255))),
+            frame: None,
+            brightness: 1.0,
+        }
+    }
+}
+
+impl LightState {
+    // Copies the state to the local buffer, and sends it to the pixels.
+    pub(crate) fn show(&self, blinkt: &mut Blinkt) -> Result<()> {
+        if !self.on {
+            blinkt.clear();
+        } else if let Some(ref frame) = self.frame {
+            blinkt.clear();
+            for (pixel, &color) in frame.iter().enumerate() {
+                blinkt.set_pixel_color(pixel, color);
+            }
+        } else if let Some(color) = self.color {
+            blinkt.set_all_pixels_color(color);
+        }
+
+        blinkt.set_all_pixels_brightness(self.brightness);
+        blinkt.swap();
+        blinkt.show()
+    }
+
+    fn to_json(&self) -> String {
+        let color = match self.color {
+            Some(color) if self.frame.is_none() => {
+                let (red, green, blue) = color.rgb();
+                format!("[{},{},{}]", red, green, blue)
+            }
+            _ => "null".to_owned(),
+        };
+
+        format!(
+            r#"{{"state":"{}","color":{},"brightness":{}}}"#,
+            if self.on { "ON" } else { "OFF" },
+            color,
+            self.brightness
+        )
+    }
+}
+
+/// An MQTT client that controls the pixels of a `Blinkt`.
+#[derive(Debug)]
+pub struct MqttClient {
+    connection: Connection,
+    options: MqttOptions,
+    state: LightState,
+}
+
+impl MqttClient {
+    /// Connects to the broker, and subscribes to the command topics.
+    pub fn connect(options: MqttOptions) -> Result<MqttClient> {
+        let mut connection = Connection::open(
+            &options.addr,
+            &options.client_id,
+            options.credentials.as_ref(),
+            options.keep_alive,
+            None,
+        )?;
+
+        connection.subscribe(&[
+            &options.color_topic,
+            &options.brightness_topic,
+            &options.power_topic,
+            &options.frame_topic,
+        ])?;
+
+        Ok(MqttClient {
+            connection,
+            options,
+            state: LightState::default(),
+        })
+    }
+
+    /// Returns the options the client was connected with.
+    pub fn options(&self) -> &MqttOptions {
+        &self.options
+    }
+
+    /// Receives and processes messages until an error occurs, such as a
+    /// lost connection.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        self.state.show(blinkt)?;
+        self.publish_state()?;
+
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Waits for a single message, and processes it.
+    ///
+    /// Returns `Ok(true)` if the message changed the state, in which case
+    /// the pixels are updated, and the new state is published. To keep the
+    /// connection alive, this returns early when no message arrives within
+    /// half the keep alive interval, unless keep alive is disabled. Messages
+    /// with invalid payloads are ignored.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        let (topic, payload) = match self.connection.poll()? {
+            Some(message) => message,
+            None => return Ok(false),
+        };
+
+        let payload = String::from_utf8_lossy(&payload);
+        let payload = payload.trim();
+        let options = &self.options;
+        let mut state = self.state.clone();
+
+        if topic == options.color_topic {
+            match parse_color(payload) {
+                Some(color) => {
+                    state.color = Some(color);
+                    state.frame = None;
+                    state.on = true;
+                }
+                None => return Ok(false),
+            }
+        } else if topic == options.brightness_topic {
+            match payload.parse::<f32>() {
+                Ok(brightness) => state.brightness = brightness.max(0.0).min(1.0),
+                Err(_) => return Ok(false),
+            }
+        } else if topic == options.power_topic {
+            match payload {
+                "ON" | "on" => state.on = true,
+                "OFF" | "off" => state.on = false,
+                _ => return Ok(false),
+            }
+        } else if topic == options.frame_topic {
+            match parse_frame(payload) {
+                Some(frame) => {
+                    state.frame = Some(frame);
+                    state.on = true;
+                }
+                None => return Ok(false),
+            }
+        } else {
+            return Ok(false);
+        }
+
+        if state == self.state {
+            return Ok(false);
+        }
+
+        self.state = state;
+        self.state.show(blinkt)?;
+        self.publish_state()?;
+
+        Ok(true)
+    }
+
+    /// Publishes the current state to the state topic.
+    pub fn publish_state(&mut self) -> Result<()> {
+        let json = self.state.to_json();
+        self.connection
+            .publish(&self.options.state_topic, json.as_bytes(), true)
+    }
+
+    /// Disconnects from the broker.
+    pub fn disconnect(mut self) -> Result<()> {
+        self.connection.disconnect()
+    }
+}
+
+// Parses `#rrggbb`, `r,g,b` or a JSON array `[r, g, b]`.
+pub(crate) fn parse_color(text: &str) -> Option<Color> {
+    if text.starts_with('#') {
+        return parse_hex(text);
+    }
+
+    if text.starts_with('[') {
+        return json_color(&serde_json::from_str::<Value>(text).ok()?);
+    }
+
+    let mut values = text.split(',').map(|value| value.trim().parse::<u8>());
+    match (values.next(), values.next(), values.next(), values.next()) {
+        (Some(Ok(red)), Some(Ok(green)), Some(Ok(blue)), None) => {
+            Some(Color::from((red, green, blue)))
+        }
+        _ => None,
+    }
+}
+
+fn parse_frame(text: &str) -> Option<Vec<Color>> {
+    match serde_json::from_str::<Value>(text).ok()? {
+        Value::Array(values) => values.iter().map(json_color).collect(),
+        _ => None,
+    }
+}
+
+// A minimal MQTT 3.1.1 client connection, limited to QoS 0.
+#[derive(Debug)]
+pub(crate) struct Connection {
+    stream: TcpStream,
+    keep_alive: Duration,
+    last_sent: Instant,
+    next_packet_id: u16,
+}
+
+impl Connection {
+    pub(crate) fn open(
+        addr: &str,
+        client_id: &str,
+        credentials: Option<&(String, String)>,
+        keep_alive: Duration,
+        will: Option<(&str, &[u8])>,
+    ) -> Result<Connection> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+
+        let mut connection = Connection {
+            stream,
+            keep_alive,
+            last_sent: Instant::now(),
+            next_packet_id: 1,
+        };
+
+        // Clean session.
+        let mut flags = 0x02;
+        if credentials.is_some() {
+            flags |= 0xc0;
+        }
+
+        // Retained last will, published by the broker when the connection
+        // is lost.
+        if will.is_some() {
+            flags |= 0x24;
+        }
+
+        let mut body = Vec::new();
+        write_string(&mut body, "MQTT");
+        body.push(PROTOCOL_LEVEL);
+        body.push(flags);
+        body.extend_from_slice(&be_u16(keep_alive.as_secs().min(0xffff) as u16));
+        write_string(&mut body, client_id);
+        if let Some((topic, message)) = will {
+            write_string(&mut body, topic);
+            body.extend_from_slice(&be_u16(message.len() as u16));
+            body.extend_from_slice(message);
+        }
+
+        if let Some((username, password)) = credentials {
+            write_string(&mut body, username);
+            write_string(&mut body, password);
+        }
+
+        connection.send(CONNECT, &body)?;
+
+        match connection.read_packet()? {
+            (CONNACK, ref body) if body.len() == 2 && body[1] == 0 => {}
+            (CONNACK, ref body) if body.len() == 2 => {
+                return Err(protocol_error(&format!(
+                    "connection refused with return code {}",
+                    body[1]
+                )))
+            }
+            _ => return Err(protocol_error("expected CONNACK")),
+        }
+
+        // Wake up regularly to send keep alive pings.
+        connection.set_poll_interval(None)?;
+
+        Ok(connection)
+    }
+
+    // Sets the maximum time `poll` waits for a message, or only waits for
+    // keep alive pings if `interval` is `None`. Unless keep alive is
+    // disabled, the interval is capped at half the keep alive interval, or
+    // 100 ms, whichever is longer.
+    pub(crate) fn set_poll_interval(&mut self, interval: Option<Duration>) -> Result<()> {
+        let interval = if self.keep_alive == Duration::from_secs(0) {
+            interval
+        } else {
+            let max_interval = (self.keep_alive / 2).max(Duration::from_millis(100));
+            Some(interval.unwrap_or(max_interval).min(max_interval))
+        };
+
+        let timeout = interval.map(|interval| interval.max(Duration::from_millis(1)));
+        self.stream.set_read_timeout(timeout)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn subscribe(&mut self, topics: &[&str]) -> Result<()> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&be_u16(self.packet_id()));
+        for topic in topics {
+            write_string(&mut body, topic);
+            // Maximum QoS 0.
+            body.push(0);
+        }
+
+        self.send(SUBSCRIBE, &body)
+    }
+
+    pub(crate) fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) -> Result<()> {
+        let mut body = Vec::with_capacity(topic.len() + payload.len() + 2);
+        write_string(&mut body, topic);
+        body.extend_from_slice(payload);
+
+        let header = if retain { PUBLISH | 0x01 } else { PUBLISH };
+        self.send(header, &body)
+    }
+
+    // Waits for the next published message. Returns `None` when no message
+    // arrives before the next keep alive ping is due.
+    pub(crate) fn poll(&mut self) -> Result<Option<(String, Vec<u8>)>> {
+        let keep_alive = self.keep_alive > Duration::from_secs(0);
+        if keep_alive && self.last_sent.elapsed() >= self.keep_alive / 2 {
+            self.send(PINGREQ, &[])?;
+        }
+
+        let mut first = [0u8; 1];
+        match self.stream.read(&mut first) {
+            Ok(0) => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "connection closed by broker",
+                )))
+            }
+            Ok(_) => {}
+            Err(ref err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::TimedOut =>
+            {
+                return Ok(None)
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        let body = self.read_body()?;
+        if first[0] & 0xf0 != PUBLISH {
+            // CONNACK, SUBACK, PINGRESP and other acknowledgements.
+            return Ok(None);
+        }
+
+        let topic_len = match body.get(..2) {
+            Some(len) => (usize::from(len[0]) << 8) | usize::from(len[1]),
+            None => return Err(protocol_error("invalid PUBLISH packet")),
+        };
+
+        let mut pos = 2 + topic_len;
+        let topic = match body.get(2..pos) {
+            Some(topic) => String::from_utf8_lossy(topic).into_owned(),
+            None => return Err(protocol_error("invalid PUBLISH packet")),
+        };
+
+        let qos = (first[0] >> 1) & 0x03;
+        if qos > 0 {
+            let packet_id = match body.get(pos..pos + 2) {
+                Some(id) => [id[0], id[1]],
+                None => return Err(protocol_error("invalid PUBLISH packet")),
+            };
+
+            pos += 2;
+            if qos == 1 {
+                self.send(PUBACK, &packet_id)?;
+            }
+        }
+
+        Ok(Some((topic, body[pos..].to_vec())))
+    }
+
+    pub(crate) fn disconnect(&mut self) -> Result<()> {
+        self.send(DISCONNECT, &[])
+    }
+
+    fn packet_id(&mut self) -> u16 {
+        let packet_id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+
+        packet_id
+    }
+
+    fn send(&mut self, header: u8, body: &[u8]) -> Result<()> {
+        let mut packet = Vec::with_capacity(body.len() + 5);
+        packet.push(header);
+
+        // Remaining length, encoded as a variable length integer.
+        let mut len = body.len();
+        loop {
+            let mut byte = (len % 128) as u8;
+            len /= 128;
+            if len > 0 {
+                byte |= 0x80;
+            }
+
+            packet.push(byte);
+            if len == 0 {
+                break;
+            }
+        }
+
+        packet.extend_from_slice(body);
+        self.stream.write_all(&packet)?;
+        self.last_sent = Instant::now();
+
+        Ok(())
+    }
+
+    fn read_packet(&mut self) -> Result<(u8, Vec<u8>)> {
+        let mut first = [0u8; 1];
+        self.stream.read_exact(&mut first)?;
+
+        Ok((first[0] & 0xf0, self.read_body()?))
+    }
+
+    // Reads the remaining length and the rest of a packet. The rest of the
+    // packet is read without a timeout, so a slow broker can't leave the
+    // stream in the middle of a packet.
+    fn read_body(&mut self) -> Result<Vec<u8>> {
+        let timeout = self.stream.read_timeout()?;
+        self.stream.set_read_timeout(None)?;
+        let result = self.read_body_blocking();
+        self.stream.set_read_timeout(timeout)?;
+
+        result
+    }
+
+    fn read_body_blocking(&mut self) -> Result<Vec<u8>> {
+        let mut len = 0usize;
+        let mut shift = 0;
+        loop {
+            let mut byte = [0u8; 1];
+            self.stream.read_exact(&mut byte)?;
+            len |= usize::from(byte[0] & 0x7f) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+
+            shift += 7;
+            if shift > 21 {
+                return Err(protocol_error("invalid remaining length"));
+            }
+        }
+
+        if len > MAX_PACKET_LEN {
+            return Err(protocol_error("packet exceeds the maximum length"));
+        }
+
+        let mut body = vec![0u8; len];
+        self.stream.read_exact(&mut body)?;
+
+        Ok(body)
+    }
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(&be_u16(value.len() as u16));
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+fn be_u16(value: u16) -> [u8; 2] {
+    [(value >> 8) as u8, value as u8]
+}
+
+fn protocol_error(message: &str) -> Error {
+    Error::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
@@ -0,0 +1,328 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Controlling the pixels through OSC (Open Sound Control).
+//!
+//! An [`OscServer`] receives OSC messages over UDP, so audio and VJ software
+//! such as TouchOSC, SuperCollider or Max/MSP can control the pixels
+//! directly. Bundles are supported, and their messages are processed
+//! immediately, regardless of their time tag.
+//!
+//! The following addresses are supported. Color values are either integers
+//! between `0` and `255`, or floats between `0.0` and `1.0`. Brightness values
+//! are floats between `0.0` and `1.0`.
+//!
+//! | Address | Arguments | Action |
+//! |---|---|---|
+//! | `/pixel/{i}/rgb` | red, green, blue | [`Blinkt::set_pixel`] |
+//! | `/pixel/{i}/brightness` | brightness | [`Blinkt::set_pixel_brightness`] |
+//! | `/all/rgb` | red, green, blue | [`Blinkt::set_all_pixels`] |
+//! | `/all/brightness` | brightness | [`Blinkt::set_all_pixels_brightness`] |
+//! | `/clear` | | [`Blinkt::clear`] |
+//! | `/show` | | [`Blinkt::show`] |
+//!
+//! This module requires the `osc` feature.
+//!
+//! ```rust,no_run
+//! use blinkt::osc::OscServer;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::new()?;
+//! let mut server = OscServer::bind("0.0.0.0:9000")?;
+//!
+//! // Send a frame after every packet, for clients that don't send /show.
+//! server.set_auto_show(true);
+//! server.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`OscServer`]: struct.OscServer.html
+//! [`Blinkt::set_pixel`]: ../struct.Blinkt.html#method.set_pixel
+//! [`Blinkt::set_pixel_brightness`]: ../struct.Blinkt.html#method.set_pixel_brightness
+//! [`Blinkt::set_all_pixels`]: ../struct.Blinkt.html#method.set_all_pixels
+//! [`Blinkt::set_all_pixels_brightness`]: ../struct.Blinkt.html#method.set_all_pixels_brightness
+//! [`Blinkt::clear`]: ../struct.Blinkt.html#method.clear
+//! [`Blinkt::show`]: ../struct.Blinkt.html#method.show
+
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::str;
+
+use crate::{Blinkt, Result};
+
+const BUNDLE_ID: &[u8] = b"#bundle\0";
+// Maximum UDP payload size.
+const MAX_PACKET_LEN: usize = 65_507;
+// Maximum nesting depth for bundles.
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Arg {
+    Int(i32),
+    Float(f32),
+    Other,
+}
+
+impl Arg {
+    fn color_value(self) -> Option<u8> {
+        match self {
+            Arg::Int(value) => Some(value.max(0).min(255) as u8),
+            Arg::Float(value) => Some((value.max(0.0).min(1.0) * 255.0).round() as u8),
+            Arg::Other => None,
+        }
+    }
+
+    fn brightness(self) -> Option<f32> {
+        match self {
+            Arg::Int(value) => Some(value as f32 / 255.0),
+            Arg::Float(value) => Some(value),
+            Arg::Other => None,
+        }
+    }
+}
+
+/// An OSC server that controls the pixels of a `Blinkt`.
+#[derive(Debug)]
+pub struct OscServer {
+    socket: UdpSocket,
+    auto_show: bool,
+    buffer: Vec<u8>,
+}
+
+impl OscServer {
+    /// Constructs a new `OscServer` listening on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<OscServer> {
+        Ok(OscServer {
+            socket: UdpSocket::bind(addr)?,
+            auto_show: false,
+            buffer: vec![0u8; MAX_PACKET_LEN],
+        })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Returns `true` if a frame is sent after every packet that changes the
+    /// local buffer.
+    pub fn auto_show(&self) -> bool {
+        self.auto_show
+    }
+
+    /// When enabled, sends a frame after every packet that changes the local
+    /// buffer, so clients don't need to send `/show`.
+    ///
+    /// By default, this is set to `false`.
+    pub fn set_auto_show(&mut self, auto_show: bool) {
+        self.auto_show = auto_show;
+    }
+
+    /// Receives and processes packets until an error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Waits for a single packet, and processes it.
+    ///
+    /// Returns `Ok(true)` if a frame was sent to the pixels. Messages with
+    /// unsupported addresses or arguments, and packets that aren't valid
+    /// OSC packets, are ignored.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        let (len, _) = self.socket.recv_from(&mut self.buffer)?;
+
+        let mut state = State::default();
+        process_packet(blinkt, &self.buffer[..len], &mut state, 0);
+
+        if state.show || (self.auto_show && state.changed) {
+            blinkt.swap();
+            blinkt.show()?;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    changed: bool,
+    show: bool,
+}
+
+fn process_packet(blinkt: &mut Blinkt, packet: &[u8], state: &mut State, depth: usize) {
+    if !packet.starts_with(BUNDLE_ID) {
+        process_message(blinkt, packet, state);
+        return;
+    }
+
+    if depth >= MAX_DEPTH {
+        return;
+    }
+
+    // Skip the identifier and the time tag.
+    let mut pos = 16;
+    while let Some(size) = read_i32(packet, pos) {
+        let start = pos + 4;
+        let end = start + size.max(0) as usize;
+        if end > packet.len() {
+            return;
+        }
+
+        process_packet(blinkt, &packet[start..end], state, depth + 1);
+        pos = end;
+    }
+}
+
+fn process_message(blinkt: &mut Blinkt, message: &[u8], state: &mut State) {
+    let (address, pos) = match read_string(message, 0) {
+        Some(address) => address,
+        None => return,
+    };
+
+    let args = match read_string(message, pos) {
+        Some((tags, pos)) if tags.starts_with(',') => read_args(message, &tags[1..], pos),
+        // Type tags are optional in older implementations.
+        _ => Vec::new(),
+    };
+
+    let parts: Vec<&str> = address.split('/').skip(1).collect();
+    let handled = match (parts.as_slice(), args.as_slice()) {
+        (["pixel", index, "rgb"], [red, green, blue]) => {
+            match (index.parse(), rgb(*red, *green, *blue)) {
+                (Ok(index), Some((red, green, blue))) => {
+                    blinkt.set_pixel(index, red, green, blue);
+                    true
+                }
+                _ => false,
+            }
+        }
+        (["pixel", index, "brightness"], [brightness]) => {
+            match (index.parse(), brightness.brightness()) {
+                (Ok(index), Some(brightness)) => {
+                    blinkt.set_pixel_brightness(index, brightness);
+                    true
+                }
+                _ => false,
+            }
+        }
+        (["all", "rgb"], [red, green, blue]) => match rgb(*red, *green, *blue) {
+            Some((red, green, blue)) => {
+                blinkt.set_all_pixels(red, green, blue);
+                true
+            }
+            None => false,
+        },
+        (["all", "brightness"], [brightness]) => match brightness.brightness() {
+            Some(brightness) => {
+                blinkt.set_all_pixels_brightness(brightness);
+                true
+            }
+            None => false,
+        },
+        (["clear"], _) => {
+            blinkt.clear();
+            true
+        }
+        (["show"], _) => {
+            state.show = true;
+            false
+        }
+        _ => false,
+    };
+
+    state.changed |= handled;
+}
+
+fn rgb(red: Arg, green: Arg, blue: Arg) -> Option<(u8, u8, u8)> {
+    Some((
+        red.color_value()?,
+        green.color_value()?,
+        blue.color_value()?,
+    ))
+}
+
+fn read_args(message: &[u8], tags: &str, mut pos: usize) -> Vec<Arg> {
+    let mut args = Vec::with_capacity(tags.len());
+
+    for tag in tags.chars() {
+        let arg =
+            match tag {
+                'i' => read_i32(message, pos).map(|value| (Arg::Int(value), 4)),
+                'f' => read_i32(message, pos)
+                    .map(|value| (Arg::Float(f32::from_bits(value as u32)), 4)),
+                'd' => read_i64(message, pos)
+                    .map(|value| (Arg::Float(f64::from_bits(value as u64) as f32), 8)),
+                'h' | 't' => read_i64(message, pos).map(|_| (Arg::Other, 8)),
+                's' | 'S' => read_string(message, pos).map(|(_, end)| (Arg::Other, end - pos)),
+                'b' => read_i32(message, pos)
+                    .map(|size| (Arg::Other, 4 + padded(size.max(0) as usize))),
+                'c' | 'r' | 'm' => read_i32(message, pos).map(|_| (Arg::Other, 4)),
+                'T' => Some((Arg::Int(1), 0)),
+                'F' | 'N' | 'I' => Some((Arg::Int(0), 0)),
+                _ => None,
+            };
+
+        match arg {
+            Some((arg, size)) => {
+                args.push(arg);
+                pos += size;
+            }
+            None => break,
+        }
+    }
+
+    args
+}
+
+// Reads a null-terminated string padded to a multiple of 4 bytes. Returns
+// the string and the position following the padding.
+fn read_string(data: &[u8], pos: usize) -> Option<(&str, usize)> {
+    let bytes = data.get(pos..)?;
+    let len = bytes.iter().position(|&byte| byte == 0)?;
+    let string = str::from_utf8(&bytes[..len]).ok()?;
+
+    Some((string, pos + padded(len + 1)))
+}
+
+fn read_i32(data: &[u8], pos: usize) -> Option<i32> {
+    let bytes = data.get(pos..pos + 4)?;
+
+    Some(
+        (i32::from(bytes[0]) << 24)
+            | (i32::from(bytes[1]) << 16)
+            | (i32::from(bytes[2]) << 8)
+            | i32::from(bytes[3]),
+    )
+}
+
+fn read_i64(data: &[u8], pos: usize) -> Option<i64> {
+    let high = read_i32(data, pos)?;
+    let low = read_i32(data, pos + 4)?;
+
+    Some((i64::from(high) << 32) | i64::from(low as u32))
+}
+
+fn padded(len: usize) -> usize {
+    (len + 3) & !3
+}
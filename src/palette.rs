@@ -0,0 +1,156 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::color::{Color, Interpolation};
+
+const HEAT: [u32; 16] = [
+    0x00_0000, 0x33_0000, 0x66_0000, 0x99_0000, 0xcc_0000, 0xff_0000, 0xff_3300, 0xff_6600,
+    0xff_9900, 0xff_cc00, 0xff_ff00, 0xff_ff33, 0xff_ff66, 0xff_ff99, 0xff_ffcc, 0xff_ffff,
+];
+
+const RAINBOW: [u32; 16] = [
+    0xff_0000, 0xd5_2a00, 0xab_5500, 0xab_7f00, 0xab_ab00, 0x56_d500, 0x00_ff00, 0x00_d52a,
+    0x00_ab55, 0x00_56aa, 0x00_00ff, 0x2a_00d5, 0x55_00ab, 0x7f_0081, 0xab_0055, 0xd5_002b,
+];
+
+const OCEAN: [u32; 16] = [
+    0x19_1970, 0x00_008b, 0x19_1970, 0x00_0080, 0x00_008b, 0x00_00cd, 0x2e_8b57, 0x00_8080,
+    0x5f_9ea0, 0x00_00ff, 0x00_8b8b, 0x64_95ed, 0x7f_ffd4, 0x2e_8b57, 0x00_ffff, 0x87_cefa,
+];
+
+/// A set of anchor colors, with interpolated colors in between.
+///
+/// Effects that look up their colors in a `Palette` can easily be rethemed by
+/// swapping out the palette.
+///
+/// ```rust
+/// use blinkt::{Color, Palette};
+///
+/// let palette = Palette::new(&[Color::new(0, 0, 0), Color::new(255, 255, 255)]);
+/// assert_eq!(palette.color_at(0.5), Color::new(128, 128, 128));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Palette {
+    colors: Vec<Color>,
+    interpolation: Option<Interpolation>,
+}
+
+impl Palette {
+    /// Constructs a new `Palette` with the specified anchor colors, spaced
+    /// evenly between `0.0` and `1.0`.
+    pub fn new(colors: &[Color]) -> Palette {
+        Palette {
+            colors: colors.to_vec(),
+            interpolation: Some(Interpolation::Rgb),
+        }
+    }
+
+    /// Constructs a black-body inspired palette that goes from black through
+    /// red, orange and yellow to white. Suitable for fire effects.
+    pub fn heat() -> Palette {
+        Palette::from_hex(&HEAT)
+    }
+
+    /// Constructs a palette that cycles through the hues of the rainbow.
+    pub fn rainbow() -> Palette {
+        Palette::from_hex(&RAINBOW)
+    }
+
+    /// Constructs a palette consisting of deep blues, teals and aquas.
+    pub fn ocean() -> Palette {
+        Palette::from_hex(&OCEAN)
+    }
+
+    fn from_hex(colors: &[u32]) -> Palette {
+        let colors: Vec<Color> = colors
+            .iter()
+            .map(|rgb| Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, *rgb as u8))
+            .collect();
+
+        Palette::new(&colors)
+    }
+
+    /// Returns the anchor colors.
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// Returns the interpolation used between anchor colors, or `None` if
+    /// interpolation is disabled.
+    pub fn interpolation(&self) -> Option<Interpolation> {
+        self.interpolation
+    }
+
+    /// Sets the interpolation used between anchor colors. If set to `None`,
+    /// [`color_at`] returns the nearest anchor color at or below the
+    /// specified position.
+    ///
+    /// By default, this is set to `Some(Interpolation::Rgb)`.
+    ///
+    /// [`color_at`]: #method.color_at
+    pub fn set_interpolation(&mut self, interpolation: Option<Interpolation>) {
+        self.interpolation = interpolation;
+    }
+
+    /// Returns the color at `position`, interpolated between the nearest
+    /// anchor colors.
+    ///
+    /// `position` is specified as a floating point value between `0.0` (first
+    /// anchor color) and `1.0` (last anchor color). Values outside of that
+    /// range are clamped. An empty palette always returns black.
+    pub fn color_at(&self, position: f32) -> Color {
+        if self.colors.is_empty() {
+            return Color::default();
+        }
+
+        let scaled = position.max(0.0).min(1.0) * (self.colors.len() - 1) as f32;
+        self.lookup(scaled, false)
+    }
+
+    /// Returns the color at `position`, where `position` wraps around
+    /// after `1.0`, and the last anchor color blends back into the first.
+    ///
+    /// This is useful for cyclic effects such as rotating rainbows.
+    pub fn color_at_wrapping(&self, position: f32) -> Color {
+        if self.colors.is_empty() {
+            return Color::default();
+        }
+
+        let position = position - position.floor();
+        self.lookup(position * self.colors.len() as f32, true)
+    }
+
+    fn lookup(&self, scaled: f32, wrap: bool) -> Color {
+        let len = self.colors.len();
+        let idx = (scaled as usize).min(len - 1);
+        let next = if wrap {
+            (idx + 1) % len
+        } else {
+            (idx + 1).min(len - 1)
+        };
+
+        match self.interpolation {
+            Some(interpolation) => {
+                self.colors[idx].interpolate(self.colors[next], scaled - idx as f32, interpolation)
+            }
+            None => self.colors[idx],
+        }
+    }
+}
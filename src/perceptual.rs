@@ -0,0 +1,78 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::pixel::Pixel;
+
+// Maps the linear 5-bit brightness values to perceived lightness based on the
+// CIE 1931 lightness formula. Because the 5-bit brightness field is too coarse
+// to represent the low end of the curve, each entry stores the smallest
+// brightness field that can reach the target luminance, and a scaling factor
+// (8.8 fixed-point) that's applied to the red, green and blue values to make
+// up the difference.
+#[derive(Debug, Clone)]
+pub(crate) struct PerceptualCurve {
+    table: [(u8, u16); 32],
+}
+
+impl PerceptualCurve {
+    pub(crate) fn new() -> PerceptualCurve {
+        let mut table = [(0u8, 0u16); 32];
+
+        for (brightness, entry) in table.iter_mut().enumerate() {
+            let luminance = cie1931(brightness as f32 / 31.0) * 31.0;
+            if luminance <= 0.0 {
+                continue;
+            }
+
+            let field = luminance.ceil().max(1.0).min(31.0);
+            let scale = ((luminance / field) * 256.0).round().min(256.0);
+
+            *entry = (field as u8, scale as u16);
+        }
+
+        PerceptualCurve { table }
+    }
+
+    #[inline]
+    pub(crate) fn apply(&self, pixel: &mut Pixel) {
+        let (field, scale) = self.table[pixel.brightness_raw() as usize];
+        let (red, green, blue) = pixel.rgb();
+
+        let scale_channel = |value: u8| ((u32::from(value) * u32::from(scale)) >> 8) as u8;
+
+        pixel.set_rgb(
+            scale_channel(red),
+            scale_channel(green),
+            scale_channel(blue),
+        );
+        pixel.set_brightness_raw(field);
+    }
+}
+
+// Converts lightness (0.0-1.0) to relative luminance (0.0-1.0).
+fn cie1931(lightness: f32) -> f32 {
+    let lightness = lightness * 100.0;
+
+    if lightness <= 8.0 {
+        lightness / 903.3
+    } else {
+        ((lightness + 16.0) / 116.0).powi(3)
+    }
+}
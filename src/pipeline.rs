@@ -0,0 +1,292 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::time::{Duration, Instant};
+
+use crate::animation::as_secs_f32;
+use crate::filter::{Filter, FilterChain};
+use crate::pixel::Pixel;
+
+/// A complete set of output adjustments, such as a bright and vivid look for
+/// daytime, or a dim and warm look for nighttime.
+///
+/// Pipelines are registered through [`Blinkt::add_pipeline`], and can be
+/// switched at any time through [`Blinkt::switch_pipeline`], optionally
+/// crossfading from the previous pipeline. The color values stored in the
+/// local buffer aren't modified.
+///
+/// Each pipeline applies its filters first, followed by the gamma curve and
+/// the brightness scale.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// # use std::time::Duration;
+/// #
+/// # use blinkt::{Blinkt, Filter, Pipeline};
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mut blinkt = Blinkt::new()?;
+///
+/// blinkt.add_pipeline("day", Pipeline::new().with_filter(Filter::Saturate(1.3)));
+/// blinkt.add_pipeline(
+///     "night",
+///     Pipeline::new()
+///         .with_filter(Filter::Sepia)
+///         .with_gamma(2.2)
+///         .with_brightness(0.3),
+/// );
+///
+/// blinkt.switch_pipeline(Some("day"), Duration::from_secs(0));
+/// // ...
+/// blinkt.switch_pipeline(Some("night"), Duration::from_secs(5));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Blinkt::add_pipeline`]: struct.Blinkt.html#method.add_pipeline
+/// [`Blinkt::switch_pipeline`]: struct.Blinkt.html#method.switch_pipeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    filters: Vec<Filter>,
+    gamma: f32,
+    brightness: f32,
+}
+
+impl Pipeline {
+    /// Constructs a new `Pipeline` without any filters, with the gamma set
+    /// to `1.0` and the brightness scale set to `1.0`, which leaves all color
+    /// values unchanged.
+    pub fn new() -> Pipeline {
+        Pipeline {
+            filters: Vec::new(),
+            gamma: 1.0,
+            brightness: 1.0,
+        }
+    }
+
+    /// Adds a color filter. Filters are applied in the order they were
+    /// added.
+    pub fn with_filter(mut self, filter: Filter) -> Pipeline {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Sets the gamma exponent applied to the red, green and blue values.
+    ///
+    /// Values above `1.0` darken the midtones, values below `1.0` brighten
+    /// them. Values below `0.1` are treated as `0.1`.
+    pub fn with_gamma(mut self, gamma: f32) -> Pipeline {
+        self.gamma = gamma.max(0.1);
+        self
+    }
+
+    /// Sets the brightness scale applied to the red, green and blue values.
+    ///
+    /// `brightness` is specified as a floating point value between `0.0`
+    /// (0%) and `1.0` (100%).
+    pub fn with_brightness(mut self, brightness: f32) -> Pipeline {
+        self.brightness = brightness.max(0.0).min(1.0);
+        self
+    }
+
+    /// Returns the color filters.
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    /// Returns the gamma exponent.
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Returns the brightness scale.
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+}
+
+impl Default for Pipeline {
+    fn default() -> Pipeline {
+        Pipeline::new()
+    }
+}
+
+// A pipeline with its filters combined into a single matrix, and its gamma
+// curve and brightness scale combined into a lookup table.
+#[derive(Debug, Clone)]
+struct CompiledPipeline {
+    filter_chain: Option<FilterChain>,
+    curve: [u8; 256],
+}
+
+impl CompiledPipeline {
+    fn new(pipeline: &Pipeline) -> CompiledPipeline {
+        let mut curve = [0u8; 256];
+        for (value, entry) in curve.iter_mut().enumerate() {
+            let scaled = (value as f32 / 255.0).powf(pipeline.gamma) * pipeline.brightness;
+            *entry = (scaled * 255.0).round() as u8;
+        }
+
+        CompiledPipeline {
+            filter_chain: if pipeline.filters.is_empty() {
+                None
+            } else {
+                Some(FilterChain::new(&pipeline.filters))
+            },
+            curve,
+        }
+    }
+
+    #[inline]
+    fn apply(&self, pixel: &mut Pixel) {
+        if let Some(ref filter_chain) = self.filter_chain {
+            filter_chain.apply(pixel);
+        }
+
+        let (red, green, blue) = pixel.rgb();
+        pixel.set_rgb(
+            self.curve[red as usize],
+            self.curve[green as usize],
+            self.curve[blue as usize],
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Crossfade {
+    from: Option<CompiledPipeline>,
+    started: Instant,
+    duration: Duration,
+    progress: f32,
+}
+
+// Keeps track of the registered pipelines, the active pipeline, and any
+// crossfade in progress.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Pipelines {
+    registered: Vec<(String, Pipeline)>,
+    active: Option<(String, CompiledPipeline)>,
+    crossfade: Option<Crossfade>,
+}
+
+impl Pipelines {
+    pub(crate) fn add(&mut self, name: &str, pipeline: Pipeline) {
+        if let Some((ref active_name, ref mut compiled)) = self.active {
+            if active_name == name {
+                *compiled = CompiledPipeline::new(&pipeline);
+            }
+        }
+
+        match self
+            .registered
+            .iter_mut()
+            .find(|(existing, _)| existing == name)
+        {
+            Some(entry) => entry.1 = pipeline,
+            None => self.registered.push((name.to_owned(), pipeline)),
+        }
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> Option<Pipeline> {
+        if self.active_name() == Some(name) {
+            self.active = None;
+        }
+
+        let idx = self
+            .registered
+            .iter()
+            .position(|(existing, _)| existing == name)?;
+
+        Some(self.registered.remove(idx).1)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Pipeline> {
+        self.registered
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, pipeline)| pipeline)
+    }
+
+    pub(crate) fn active_name(&self) -> Option<&str> {
+        self.active.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    pub(crate) fn switch(&mut self, name: Option<&str>, crossfade: Duration) -> bool {
+        let next = match name {
+            Some(name) => match self.get(name) {
+                Some(pipeline) => Some((name.to_owned(), CompiledPipeline::new(pipeline))),
+                None => return false,
+            },
+            None => None,
+        };
+
+        let previous = std::mem::replace(&mut self.active, next);
+        self.crossfade = if crossfade > Duration::from_millis(0) {
+            Some(Crossfade {
+                from: previous.map(|(_, compiled)| compiled),
+                started: Instant::now(),
+                duration: crossfade,
+                progress: 0.0,
+            })
+        } else {
+            None
+        };
+
+        true
+    }
+
+    // Updates the crossfade progress. Called once before each frame.
+    pub(crate) fn prepare(&mut self) {
+        let finished = match self.crossfade {
+            Some(ref mut crossfade) => {
+                crossfade.progress =
+                    as_secs_f32(crossfade.started.elapsed()) / as_secs_f32(crossfade.duration);
+                crossfade.progress >= 1.0
+            }
+            None => false,
+        };
+
+        if finished {
+            self.crossfade = None;
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.active.is_none() && self.crossfade.is_none()
+    }
+
+    #[inline]
+    pub(crate) fn apply(&self, pixel: &mut Pixel) {
+        let original = *pixel;
+        if let Some((_, ref compiled)) = self.active {
+            compiled.apply(pixel);
+        }
+
+        if let Some(ref crossfade) = self.crossfade {
+            let mut from = original;
+            if let Some(ref compiled) = crossfade.from {
+                compiled.apply(&mut from);
+            }
+
+            *pixel = from.mix(pixel, crossfade.progress);
+        }
+    }
+}
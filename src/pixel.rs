@@ -18,13 +18,36 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-const DEFAULT_BRIGHTNESS: u8 = 7;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::color::Color;
+
+pub(crate) const DEFAULT_BRIGHTNESS: u8 = 7;
 
 const IDX_BRIGHTNESS: usize = 0;
 const IDX_BLUE: usize = 1;
 const IDX_GREEN: usize = 2;
 const IDX_RED: usize = 3;
 
+/// Determines how setters that only change the color, such as
+/// [`Blinkt::set_pixel`], affect the brightness value of a pixel.
+///
+/// [`Blinkt::set_pixel`]: struct.Blinkt.html#method.set_pixel
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BrightnessPolicy {
+    /// The current brightness value is left unchanged.
+    Preserve,
+    /// The brightness value is reset to the built-in default of 7/31
+    /// (roughly 22%).
+    Reset,
+    /// The brightness value is set to the global default configured through
+    /// [`Blinkt::set_default_brightness`].
+    ///
+    /// [`Blinkt::set_default_brightness`]: struct.Blinkt.html#method.set_default_brightness
+    Global,
+}
+
 /// A pixel on an LED strip or board.
 #[derive(Debug, Copy, Clone)]
 pub struct Pixel {
@@ -52,6 +75,23 @@ impl Pixel {
         self.value[IDX_BLUE] = blue;
     }
 
+    /// Returns the red, green and blue values as a `Color`.
+    #[inline]
+    pub fn color(&self) -> Color {
+        Color::new(
+            self.value[IDX_RED],
+            self.value[IDX_GREEN],
+            self.value[IDX_BLUE],
+        )
+    }
+
+    /// Sets the values for red, green and blue.
+    #[inline]
+    pub fn set_color<C: Into<Color>>(&mut self, color: C) {
+        let color = color.into();
+        self.set_rgb(color.red, color.green, color.blue);
+    }
+
     /// Returns a tuple containing the values for red, green, blue and brightness.
     #[inline]
     pub fn rgbb(&self) -> (u8, u8, u8, f32) {
@@ -135,12 +175,114 @@ impl Pixel {
         self.set_rgb(0, 0, 0);
     }
 
+    // Multiplies the red, green and blue values by factor, rounding to the
+    // nearest value. Values that are already at or below floor are left
+    // unchanged, and values above floor won't drop below it. When factor is
+    // below 1.0, values always decrease by at least 1, so repeated fades
+    // don't get stuck on small values due to rounding.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn fade(&mut self, factor: f32, floor: u8) {
+        let factor = factor.max(0.0).min(1.0);
+        let fade = |value: u8| {
+            if value <= floor {
+                return value;
+            }
+
+            let mut faded = (f32::from(value) * factor).round() as u8;
+            if faded >= value && factor < 1.0 {
+                faded = value - 1;
+            }
+
+            faded.max(floor)
+        };
+
+        self.value[IDX_RED] = fade(self.value[IDX_RED]);
+        self.value[IDX_GREEN] = fade(self.value[IDX_GREEN]);
+        self.value[IDX_BLUE] = fade(self.value[IDX_BLUE]);
+    }
+
+    // Returns a linear interpolation between self and other, where alpha is
+    // between 0.0 (self) and 1.0 (other).
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn mix(&self, other: &Pixel, alpha: f32) -> Pixel {
+        let alpha = alpha.max(0.0).min(1.0);
+        let lerp =
+            |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * alpha).round() as u8;
+
+        Pixel {
+            value: [
+                0b1110_0000
+                    | lerp(
+                        self.value[IDX_BRIGHTNESS] & 0b0001_1111,
+                        other.value[IDX_BRIGHTNESS] & 0b0001_1111,
+                    ),
+                lerp(self.value[IDX_BLUE], other.value[IDX_BLUE]),
+                lerp(self.value[IDX_GREEN], other.value[IDX_GREEN]),
+                lerp(self.value[IDX_RED], other.value[IDX_RED]),
+            ],
+        }
+    }
+
+    // Returns the red, green and blue values as they appear on the pixel,
+    // scaled by the brightness value.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn displayed_rgb(&self) -> (u8, u8, u8) {
+        let brightness = u16::from(self.brightness_raw());
+        let scale = |value: u8| ((u16::from(value) * brightness) / 31) as u8;
+
+        (
+            scale(self.value[IDX_RED]),
+            scale(self.value[IDX_GREEN]),
+            scale(self.value[IDX_BLUE]),
+        )
+    }
+
+    // Returns the 5-bit brightness value.
+    #[cfg(any(feature = "std", feature = "serde"))]
+    #[inline]
+    pub(crate) fn brightness_raw(&self) -> u8 {
+        0b0001_1111 & self.value[IDX_BRIGHTNESS]
+    }
+
+    // Sets the 5-bit brightness value.
+    #[cfg(any(feature = "std", feature = "serde"))]
+    #[inline]
+    pub(crate) fn set_brightness_raw(&mut self, brightness: u8) {
+        self.value[IDX_BRIGHTNESS] = 0b1110_0000 | (0b0001_1111 & brightness);
+    }
+
     #[inline]
     pub(crate) fn bytes(&self) -> &[u8] {
         &self.value
     }
 }
 
+// Pixels are serialized as (red, green, blue, brightness) tuples, using the
+// 5-bit brightness value so no precision is lost.
+#[cfg(feature = "serde")]
+impl Serialize for Pixel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (red, green, blue) = self.rgb();
+        (red, green, blue, self.brightness_raw()).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Pixel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Pixel, D::Error> {
+        let (red, green, blue, brightness) = <(u8, u8, u8, u8)>::deserialize(deserializer)?;
+
+        let mut pixel = Pixel::default();
+        pixel.set_rgb(red, green, blue);
+        pixel.set_brightness_raw(brightness);
+
+        Ok(pixel)
+    }
+}
+
 impl Default for Pixel {
     fn default() -> Pixel {
         Pixel {
@@ -0,0 +1,60 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// Translates logical pixel indices to physical positions on the strip, as
+// configured through Blinkt::set_pixel_map().
+pub(crate) struct PixelMap {
+    map: Box<dyn Fn(usize) -> usize + Send>,
+    // The logical pixel sent at each physical position.
+    sources: Vec<Option<usize>>,
+}
+
+impl PixelMap {
+    pub(crate) fn new(map: Box<dyn Fn(usize) -> usize + Send>, num_pixels: usize) -> PixelMap {
+        let mut pixel_map = PixelMap {
+            map,
+            sources: Vec::new(),
+        };
+        pixel_map.resize(num_pixels);
+
+        pixel_map
+    }
+
+    // Rebuilds the lookup table for the specified number of pixels. When
+    // multiple logical pixels map to the same physical position, the pixel
+    // with the highest index wins.
+    pub(crate) fn resize(&mut self, num_pixels: usize) {
+        self.sources.clear();
+        self.sources.resize(num_pixels, None);
+
+        for idx in 0..num_pixels {
+            if let Some(source) = self.sources.get_mut((self.map)(idx)) {
+                *source = Some(idx);
+            }
+        }
+    }
+
+    // Returns the logical pixel for the specified physical position, or
+    // None if no pixel maps to it.
+    #[inline]
+    pub(crate) fn source(&self, position: usize) -> Option<usize> {
+        self.sources.get(position).and_then(|source| *source)
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// A minimal PNG encoder for 8-bit RGB images. Image data is stored in
+// uncompressed deflate blocks, which keeps the encoder small and free of
+// external dependencies at the cost of larger files.
+
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const MAX_STORED_BLOCK: usize = 65_535;
+
+/// Writes `rgb` as a PNG image. `rgb` contains 3 bytes (red, green, blue) for
+/// each pixel, in row-major order.
+pub(crate) fn write_png<W: Write>(
+    writer: &mut W,
+    width: u32,
+    height: u32,
+    rgb: &[u8],
+) -> io::Result<()> {
+    if width == 0 || height == 0 || rgb.len() != (width as usize) * (height as usize) * 3 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid image dimensions",
+        ));
+    }
+
+    writer.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&be_bytes(width));
+    header.extend_from_slice(&be_bytes(height));
+    // Bit depth 8, color type RGB, default compression, filter and interlace.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(writer, b"IHDR", &header)?;
+
+    // Every scanline starts with a filter type byte (0 = none).
+    let stride = width as usize * 3;
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in rgb.chunks(stride) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    write_chunk(writer, b"IDAT", &zlib_stored(&raw))?;
+    write_chunk(writer, b"IEND", &[])?;
+
+    Ok(())
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&be_bytes(data.len() as u32))?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+
+    let crc = crc32(crc32(!0, kind), data);
+    writer.write_all(&be_bytes(!crc))?;
+
+    Ok(())
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let num_blocks = (data.len() / MAX_STORED_BLOCK) + 1;
+    let mut output = Vec::with_capacity(data.len() + num_blocks * 5 + 6);
+
+    // CMF (deflate, 32K window) and FLG (no preset dictionary, fastest).
+    output.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        output.extend_from_slice(&[1, 0x00, 0x00, 0xff, 0xff]);
+    }
+
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+
+        output.push(if last { 1 } else { 0 });
+        output.extend_from_slice(&[len as u8, (len >> 8) as u8]);
+        output.extend_from_slice(&[!len as u8, (!len >> 8) as u8]);
+        output.extend_from_slice(block);
+    }
+
+    output.extend_from_slice(&be_bytes(adler32(data)));
+
+    output
+}
+
+fn be_bytes(value: u32) -> [u8; 4] {
+    [
+        (value >> 24) as u8,
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ]
+}
+
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += u32::from(*byte);
+            b += a;
+        }
+
+        a %= 65_521;
+        b %= 65_521;
+    }
+
+    (b << 16) | a
+}
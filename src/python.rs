@@ -0,0 +1,290 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Python bindings.
+//!
+//! Builds a `blinkt` Python extension module with the same interface as
+//! Pimoroni's Python library, so existing scripts can switch to the Rust
+//! implementation without any changes. The module-level functions drive a
+//! Blinkt! board, while the `Blinkt` class can also drive APA102 and SK9822
+//! strips of any length through the SPI peripheral.
+//!
+//! Build and install the module with [maturin], using the `pyproject.toml`
+//! in the repository:
+//!
+//! ```text
+//! pip install maturin
+//! maturin build --release
+//! pip install target/wheels/blinkt-*.whl
+//! ```
+//!
+//! ```text
+//! import blinkt
+//!
+//! blinkt.set_brightness(0.1)
+//! blinkt.set_pixel(0, 255, 0, 0)
+//! blinkt.show()
+//!
+//! strip = blinkt.Blinkt(num_pixels=144)
+//! strip.set_all(0, 0, 255)
+//! strip.show()
+//! ```
+//!
+//! This module requires the `python` feature.
+//!
+//! [maturin]: https://github.com/PyO3/maturin
+
+// The code generated by PyO3 0.20 trips the `non_local_definitions` lint on
+// recent compilers.
+#![allow(unknown_lints, non_local_definitions)]
+
+use std::fmt;
+
+use pyo3::exceptions::{PyIOError, PyIndexError};
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+
+use crate::{Blinkt, Error};
+
+/// The number of pixels on a Blinkt! board.
+pub const NUM_PIXELS: usize = 8;
+
+const SPI_CLOCK_SPEED_HZ: u32 = 16_000_000;
+
+// Used by the module-level functions, and created when one of them is first
+// called.
+static DEFAULT: GILOnceCell<Py<PyBlinkt>> = GILOnceCell::new();
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        PyIOError::new_err(err.to_string())
+    }
+}
+
+/// The `Blinkt` Python class.
+///
+/// Without any arguments, the class drives a Blinkt! board. With
+/// `num_pixels`, it drives a strip through the SPI peripheral instead.
+#[pyclass(name = "Blinkt")]
+pub struct PyBlinkt {
+    blinkt: Blinkt,
+    clear_on_exit: bool,
+}
+
+impl fmt::Debug for PyBlinkt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PyBlinkt")
+            .field("clear_on_exit", &self.clear_on_exit)
+            .finish()
+    }
+}
+
+#[pymethods]
+impl PyBlinkt {
+    #[new]
+    #[pyo3(signature = (num_pixels = None, clock_speed_hz = SPI_CLOCK_SPEED_HZ))]
+    fn new(num_pixels: Option<usize>, clock_speed_hz: u32) -> PyResult<PyBlinkt> {
+        let blinkt = match num_pixels {
+            Some(num_pixels) => Blinkt::with_spi(clock_speed_hz, num_pixels)?,
+            None => Blinkt::new()?,
+        };
+
+        Ok(PyBlinkt {
+            blinkt,
+            clear_on_exit: true,
+        })
+    }
+
+    /// The number of pixels.
+    #[getter]
+    fn num_pixels(&self) -> usize {
+        self.blinkt.num_pixels()
+    }
+
+    fn __len__(&self) -> usize {
+        self.blinkt.num_pixels()
+    }
+
+    /// Sets the color, and optionally the brightness, of a single pixel.
+    #[pyo3(signature = (x, r, g, b, brightness = None))]
+    fn set_pixel(&mut self, x: usize, r: u8, g: u8, b: u8, brightness: Option<f32>) {
+        match brightness {
+            Some(brightness) => self.blinkt.set_pixel_rgbb(x, r, g, b, brightness),
+            None => self.blinkt.set_pixel(x, r, g, b),
+        }
+    }
+
+    /// Sets the color, and optionally the brightness, of all pixels.
+    #[pyo3(signature = (r, g, b, brightness = None))]
+    fn set_all(&mut self, r: u8, g: u8, b: u8, brightness: Option<f32>) {
+        match brightness {
+            Some(brightness) => self.blinkt.set_all_pixels_rgbb(r, g, b, brightness),
+            None => self.blinkt.set_all_pixels(r, g, b),
+        }
+    }
+
+    /// Sets the brightness of all pixels, from 0.0 to 1.0.
+    fn set_brightness(&mut self, brightness: f32) {
+        self.blinkt.set_all_pixels_brightness(brightness);
+    }
+
+    /// Returns the color and brightness of a single pixel as
+    /// `(r, g, b, brightness)`.
+    fn get_pixel(&self, x: usize) -> PyResult<(u8, u8, u8, f32)> {
+        match self.blinkt.frame().iter().nth(x) {
+            Some(pixel) => Ok(pixel.rgbb()),
+            None => Err(PyIndexError::new_err("pixel index out of range")),
+        }
+    }
+
+    /// Turns off all pixels. Call `show()` to update the pixels.
+    fn clear(&mut self) {
+        self.blinkt.clear();
+    }
+
+    /// Sends the colors and brightness values to the pixels.
+    fn show(&mut self) -> PyResult<()> {
+        self.blinkt.swap();
+        Ok(self.blinkt.show()?)
+    }
+
+    /// Sets whether the pixels are turned off when the program exits.
+    #[pyo3(signature = (value = true))]
+    fn set_clear_on_exit(&mut self, value: bool) {
+        self.clear_on_exit = value;
+        self.blinkt.set_clear_on_drop(value);
+    }
+}
+
+// Runs `f` on the Blinkt! board used by the module-level functions.
+fn with_default<T, F>(py: Python<'_>, f: F) -> PyResult<T>
+where
+    F: FnOnce(&mut PyBlinkt) -> PyResult<T>,
+{
+    let default = DEFAULT.get_or_try_init(py, || {
+        PyBlinkt::new(None, SPI_CLOCK_SPEED_HZ).and_then(|blinkt| Py::new(py, blinkt))
+    })?;
+
+    let mut blinkt = default.try_borrow_mut(py)?;
+    f(&mut blinkt)
+}
+
+/// Sets the color, and optionally the brightness, of a single pixel.
+#[pyfunction]
+#[pyo3(signature = (x, r, g, b, brightness = None))]
+fn set_pixel(
+    py: Python<'_>,
+    x: usize,
+    r: u8,
+    g: u8,
+    b: u8,
+    brightness: Option<f32>,
+) -> PyResult<()> {
+    with_default(py, |blinkt| {
+        blinkt.set_pixel(x, r, g, b, brightness);
+        Ok(())
+    })
+}
+
+/// Sets the color, and optionally the brightness, of all pixels.
+#[pyfunction]
+#[pyo3(signature = (r, g, b, brightness = None))]
+fn set_all(py: Python<'_>, r: u8, g: u8, b: u8, brightness: Option<f32>) -> PyResult<()> {
+    with_default(py, |blinkt| {
+        blinkt.set_all(r, g, b, brightness);
+        Ok(())
+    })
+}
+
+/// Sets the brightness of all pixels, from 0.0 to 1.0.
+#[pyfunction]
+fn set_brightness(py: Python<'_>, brightness: f32) -> PyResult<()> {
+    with_default(py, |blinkt| {
+        blinkt.set_brightness(brightness);
+        Ok(())
+    })
+}
+
+/// Returns the color and brightness of a single pixel as
+/// `(r, g, b, brightness)`.
+#[pyfunction]
+fn get_pixel(py: Python<'_>, x: usize) -> PyResult<(u8, u8, u8, f32)> {
+    with_default(py, |blinkt| blinkt.get_pixel(x))
+}
+
+/// Turns off all pixels. Call `show()` to update the pixels.
+#[pyfunction]
+fn clear(py: Python<'_>) -> PyResult<()> {
+    with_default(py, |blinkt| {
+        blinkt.clear();
+        Ok(())
+    })
+}
+
+/// Sends the colors and brightness values to the pixels.
+#[pyfunction]
+fn show(py: Python<'_>) -> PyResult<()> {
+    with_default(py, PyBlinkt::show)
+}
+
+/// Sets whether the pixels are turned off when the program exits.
+#[pyfunction]
+#[pyo3(signature = (value = true))]
+fn set_clear_on_exit(py: Python<'_>, value: bool) -> PyResult<()> {
+    with_default(py, |blinkt| {
+        blinkt.set_clear_on_exit(value);
+        Ok(())
+    })
+}
+
+// Registered with `atexit`, because the board used by the module-level
+// functions is never dropped.
+#[pyfunction]
+fn clear_on_exit(py: Python<'_>) -> PyResult<()> {
+    if let Some(default) = DEFAULT.get(py) {
+        let mut blinkt = default.try_borrow_mut(py)?;
+        if blinkt.clear_on_exit {
+            blinkt.clear();
+            blinkt.show()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "blinkt")]
+fn init(py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyBlinkt>()?;
+    module.add("NUM_PIXELS", NUM_PIXELS)?;
+
+    module.add_function(wrap_pyfunction!(set_pixel, module)?)?;
+    module.add_function(wrap_pyfunction!(set_all, module)?)?;
+    module.add_function(wrap_pyfunction!(set_brightness, module)?)?;
+    module.add_function(wrap_pyfunction!(get_pixel, module)?)?;
+    module.add_function(wrap_pyfunction!(clear, module)?)?;
+    module.add_function(wrap_pyfunction!(show, module)?)?;
+    module.add_function(wrap_pyfunction!(set_clear_on_exit, module)?)?;
+
+    let exit = wrap_pyfunction!(clear_on_exit, module)?;
+    py.import("atexit")?.call_method1("register", (exit,))?;
+
+    Ok(())
+}
@@ -0,0 +1,236 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Recording and replaying the frames sent to the pixels.
+//!
+//! [`Blinkt::record`] stores every frame sent through `show()` in a
+//! recording file, together with the time at which it was sent. A
+//! [`Recording`] loads such a file, and replays the frames onto any
+//! `Blinkt`, regardless of its output, with the original timing. This makes
+//! it possible to debug effects offline, or to demo animations without
+//! running the code that generated them.
+//!
+//! Recording files contain one frame per line. Each line starts with the
+//! number of microseconds since the first frame, followed by the red, green,
+//! blue and 5-bit brightness values of every pixel, as whitespace-separated
+//! hexadecimal byte values. Anything following a `#` on a line is treated as
+//! a comment.
+//!
+//! ```rust,no_run
+//! use blinkt::recording::Recording;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+//! blinkt.record("effect.rec")?;
+//!
+//! // Run the effect ...
+//!
+//! // ... and replay it later, at half speed.
+//! let recording = Recording::load("effect.rec")?;
+//! recording.play_at_speed(&mut blinkt, 0.5)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Blinkt::record`]: ../struct.Blinkt.html#method.record
+//! [`Recording`]: struct.Recording.html
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::capture;
+use crate::event::ShowEvent;
+use crate::pixel::Pixel;
+use crate::{Blinkt, Error, Frame, Result};
+
+// Writes frames to a recording file. Every frame is flushed immediately, so
+// the recording is complete up to the last frame if the process is
+// terminated.
+pub(crate) struct Recorder {
+    writer: BufWriter<File>,
+    first_frame: Option<Instant>,
+}
+
+impl Recorder {
+    pub(crate) fn create(path: &Path) -> Result<Recorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "# blinkt recording")?;
+        writer.flush()?;
+
+        Ok(Recorder {
+            writer,
+            first_frame: None,
+        })
+    }
+
+    pub(crate) fn record(&mut self, event: &ShowEvent<'_>) -> Result<()> {
+        let first_frame = *self.first_frame.get_or_insert(event.started());
+        let elapsed = event.started().duration_since(first_frame);
+        let micros = elapsed.as_secs() * 1_000_000 + u64::from(elapsed.subsec_micros());
+
+        write!(self.writer, "{}", micros)?;
+        for pixel in event.pixels() {
+            let (red, green, blue) = pixel.rgb();
+            write!(
+                self.writer,
+                "  {:02x} {:02x} {:02x} {:02x}",
+                red,
+                green,
+                blue,
+                pixel.brightness_raw()
+            )?;
+        }
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// A sequence of timestamped frames loaded from a recording file.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    frames: Vec<(Duration, Frame)>,
+}
+
+impl Recording {
+    /// Parses the contents of a recording file.
+    pub fn parse(text: &str) -> Result<Recording> {
+        let mut frames = Vec::new();
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut tokens = line.splitn(2, char::is_whitespace);
+            let micros: u64 = tokens
+                .next()
+                .unwrap_or("")
+                .parse()
+                .map_err(|_| invalid_data(format!("invalid timestamp: {}", line)))?;
+
+            let bytes = capture::parse_hex(tokens.next().unwrap_or(""))?;
+            if bytes.len() % 4 != 0 {
+                return Err(invalid_data(format!(
+                    "incomplete pixel data at {} us",
+                    micros
+                )));
+            }
+
+            let pixels: Vec<Pixel> = bytes
+                .chunks(4)
+                .map(|values| {
+                    let mut pixel = Pixel::default();
+                    pixel.set_rgb(values[0], values[1], values[2]);
+                    pixel.set_brightness_raw(values[3]);
+                    pixel
+                })
+                .collect();
+
+            frames.push((Duration::from_micros(micros), Frame::from_pixels(&pixels)));
+        }
+
+        Ok(Recording { frames })
+    }
+
+    /// Loads a recording file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Recording> {
+        Recording::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Returns the number of recorded frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns `true` if the recording doesn't contain any frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns the time between the first and the last frame.
+    pub fn duration(&self) -> Duration {
+        match self.frames.last() {
+            Some(&(timestamp, _)) => timestamp,
+            None => Duration::from_secs(0),
+        }
+    }
+
+    /// Returns the recorded frames, together with the time at which they
+    /// were sent, relative to the first frame.
+    pub fn frames(&self) -> &[(Duration, Frame)] {
+        &self.frames
+    }
+
+    /// Replays the recording onto `blinkt` with the original timing.
+    ///
+    /// Each frame is copied to the local buffer and sent to the pixels.
+    /// Frames with a different number of pixels than `blinkt` are truncated
+    /// or padded in the same way as [`Blinkt::set_frame`]. Returns once the
+    /// last frame has been sent.
+    ///
+    /// [`Blinkt::set_frame`]: ../struct.Blinkt.html#method.set_frame
+    pub fn play(&self, blinkt: &mut Blinkt) -> Result<()> {
+        self.play_at_speed(blinkt, 1.0)
+    }
+
+    /// Replays the recording onto `blinkt`, with the timing scaled by
+    /// `speed`.
+    ///
+    /// A `speed` of `2.0` plays the recording twice as fast, and `0.5` at
+    /// half speed. A `speed` of `0.0` or less sends all frames without
+    /// waiting.
+    pub fn play_at_speed(&self, blinkt: &mut Blinkt, speed: f32) -> Result<()> {
+        let started = Instant::now();
+
+        for &(timestamp, ref frame) in &self.frames {
+            if speed > 0.0 {
+                let target = started + scale(timestamp, speed);
+                let now = Instant::now();
+                if target > now {
+                    thread::sleep(target - now);
+                }
+            }
+
+            blinkt.set_frame(frame);
+            blinkt.swap();
+            blinkt.show()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+// Divides `duration` by `speed`, with microsecond precision.
+fn scale(duration: Duration, speed: f32) -> Duration {
+    let micros = duration.as_secs() as f64 * 1_000_000.0 + f64::from(duration.subsec_micros());
+
+    Duration::from_micros((micros / f64::from(speed)) as u64)
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::apa102;
+use crate::SerialOutput;
+
+pub(crate) type SharedOutput = Arc<Mutex<Box<dyn SerialOutput + Send>>>;
+
+// Acquires the lock on the output, ignoring poisoning so a panic on another
+// thread doesn't prevent us from sending the next frame.
+pub(crate) fn lock_output(
+    output: &Mutex<Box<dyn SerialOutput + Send>>,
+) -> MutexGuard<'_, Box<dyn SerialOutput + Send>> {
+    match output.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+struct State {
+    frame: Vec<u8>,
+    interval: Option<Duration>,
+    timeout: Option<Duration>,
+    last_write: Instant,
+    last_update: Instant,
+    tripped: bool,
+    stop: bool,
+}
+
+impl State {
+    // Returns the time at which the last frame should be re-transmitted.
+    fn refresh_deadline(&self) -> Option<Instant> {
+        self.interval.map(|interval| self.last_write + interval)
+    }
+
+    // Returns the time at which the watchdog turns off the pixels, unless
+    // show() is called before then.
+    fn watchdog_deadline(&self) -> Option<Instant> {
+        if self.tripped || self.frame.is_empty() {
+            return None;
+        }
+
+        self.timeout.map(|timeout| self.last_update + timeout)
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        match (self.refresh_deadline(), self.watchdog_deadline()) {
+            (Some(refresh), Some(watchdog)) => Some(refresh.min(watchdog)),
+            (refresh, None) => refresh,
+            (None, watchdog) => watchdog,
+        }
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+// Keeps an eye on the output on a background thread. When a refresh interval
+// is set, the last frame is periodically re-transmitted, so pixels that lost
+// their state (for instance after a brief power interruption) are restored
+// without requiring a call to show(). When a watchdog timeout is set, the
+// pixels are turned off if show() isn't called in time.
+pub(crate) struct Refresher {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Refresher {
+    pub(crate) fn new(
+        output: SharedOutput,
+        interval: Option<Duration>,
+        timeout: Option<Duration>,
+    ) -> Refresher {
+        let now = Instant::now();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                frame: Vec::new(),
+                interval,
+                timeout,
+                last_write: now,
+                last_update: now,
+                tripped: false,
+                stop: false,
+            }),
+            wake: Condvar::new(),
+        });
+
+        let thread_shared = shared.clone();
+        let thread = thread::spawn(move || run(&thread_shared, &output));
+
+        Refresher {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    pub(crate) fn interval(&self) -> Option<Duration> {
+        self.shared.lock().interval
+    }
+
+    pub(crate) fn set_interval(&self, interval: Option<Duration>) {
+        self.shared.lock().interval = interval;
+        self.shared.wake.notify_one();
+    }
+
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.shared.lock().timeout
+    }
+
+    pub(crate) fn set_timeout(&self, timeout: Option<Duration>) {
+        let mut state = self.shared.lock();
+        state.timeout = timeout;
+        state.last_update = Instant::now();
+        self.shared.wake.notify_one();
+    }
+
+    // Stores the frame that was just sent. The caller should still hold the
+    // lock on the output, so the refresher can't send an outdated frame in
+    // between.
+    pub(crate) fn update(&self, frame: &[u8]) {
+        let mut state = self.shared.lock();
+        // Reuse the existing buffer to avoid allocating on every frame.
+        state.frame.clear();
+        state.frame.extend_from_slice(frame);
+
+        let now = Instant::now();
+        state.last_write = now;
+        state.last_update = now;
+        state.tripped = false;
+    }
+
+    // Resets the watchdog without sending a new frame. Returns false if the
+    // watchdog already turned off the pixels, in which case the frame needs
+    // to be sent again.
+    pub(crate) fn feed(&self) -> bool {
+        let mut state = self.shared.lock();
+        state.last_update = Instant::now();
+
+        !state.tripped
+    }
+}
+
+impl Drop for Refresher {
+    fn drop(&mut self) {
+        self.shared.lock().stop = true;
+        self.shared.wake.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(shared: &Shared, output: &Mutex<Box<dyn SerialOutput + Send>>) {
+    loop {
+        {
+            let state = shared.lock();
+            if state.stop {
+                return;
+            }
+
+            match state.next_deadline() {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if deadline > now {
+                        let _ = shared.wake.wait_timeout(state, deadline - now);
+                        continue;
+                    }
+                }
+                None => {
+                    drop(shared.wake.wait(state));
+                    continue;
+                }
+            }
+        }
+
+        // Lock the output before the state, in the same order as show().
+        let mut output = lock_output(output);
+        let mut state = shared.lock();
+        if state.stop {
+            return;
+        }
+
+        // Check the deadlines again, since show() may have sent a new frame
+        // in the meantime.
+        let now = Instant::now();
+        let watchdog_expired = match state.watchdog_deadline() {
+            Some(deadline) => deadline <= now,
+            None => false,
+        };
+
+        let refresh_due = match state.refresh_deadline() {
+            Some(deadline) => deadline <= now,
+            None => false,
+        };
+
+        if watchdog_expired {
+            // Keep the blanked frame, so it's re-transmitted instead of the
+            // last frame until show() is called again.
+            blank(&mut state.frame);
+            state.tripped = true;
+        } else if !refresh_due {
+            continue;
+        }
+
+        if !state.frame.is_empty() {
+            // Errors are ignored, and the frame is sent again on the next
+            // interval.
+            let _ = output.write(&state.frame);
+        }
+
+        state.last_write = now;
+    }
+}
+
+// Turns off all pixels in an encoded frame. LED frames always start with
+// three 1 bits, while the start and end frames only contain zeroes.
+fn blank(frame: &mut [u8]) {
+    if frame.len() < apa102::START_FRAME.len() {
+        return;
+    }
+
+    for chunk in frame[apa102::START_FRAME.len()..].chunks_mut(4) {
+        if chunk.len() == 4 && chunk[0] & 0b1110_0000 == 0b1110_0000 {
+            chunk.copy_from_slice(&apa102::OFF_FRAME);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Driving a strip that's connected to another machine over TCP.
+//!
+//! A [`RemoteServer`] runs on the machine the pixels are connected to, and
+//! sends every frame it receives to a local `Blinkt`. A [`RemoteOutput`] is
+//! a [`SerialOutput`] that sends the serialized frames to the server. This
+//! lets the rendering run on a more powerful machine, such as a desktop,
+//! while the Raspberry Pi only displays the result.
+//!
+//! The frames are sent exactly as they were serialized by the client, so
+//! any adjustments such as gamma correction or perceptual brightness are
+//! applied on the client. Each frame is prefixed with its length in bytes,
+//! as a 32-bit big-endian integer.
+//!
+//! On the machine connected to the pixels:
+//!
+//! ```rust,no_run
+//! use blinkt::remote::RemoteServer;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+//! let server = RemoteServer::bind("0.0.0.0:7000")?;
+//! server.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! On the machine that renders the frames:
+//!
+//! ```rust,no_run
+//! use blinkt::remote::RemoteOutput;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let output = RemoteOutput::connect("raspberrypi.local:7000")?;
+//! let mut blinkt = Blinkt::with_output(output, 144);
+//!
+//! blinkt.set_all_pixels(255, 0, 0);
+//! blinkt.show()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`RemoteServer`]: struct.RemoteServer.html
+//! [`RemoteOutput`]: struct.RemoteOutput.html
+//! [`SerialOutput`]: ../trait.SerialOutput.html
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(feature = "mdns")]
+use std::time::Duration;
+
+#[cfg(feature = "mdns")]
+use crate::mdns::{self, Advertiser, SERVICE_REMOTE};
+use crate::{Blinkt, Error, Result, SerialOutput};
+
+// Maximum accepted frame length, which fits 262,143 pixels. Longer frames
+// are rejected to avoid allocating arbitrary amounts of memory.
+const MAX_FRAME_LEN: usize = 1 << 20;
+
+/// A [`SerialOutput`] that sends the serialized frames to a
+/// [`RemoteServer`].
+///
+/// When the connection is lost, the write that detected it returns an
+/// error, and the next write tries to reconnect.
+///
+/// [`SerialOutput`]: ../trait.SerialOutput.html
+/// [`RemoteServer`]: struct.RemoteServer.html
+#[derive(Debug)]
+pub struct RemoteOutput {
+    addr: SocketAddr,
+    stream: Option<TcpStream>,
+    buffer: Vec<u8>,
+}
+
+impl RemoteOutput {
+    /// Connects to the `RemoteServer` listening on `addr`.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<RemoteOutput> {
+        let addr = match addr.to_socket_addrs()?.next() {
+            Some(addr) => addr,
+            None => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no address to connect to",
+                )))
+            }
+        };
+
+        Ok(RemoteOutput {
+            addr,
+            stream: Some(open_stream(addr)?),
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Connects to the first `RemoteServer` that answers an mDNS query
+    /// within `timeout`.
+    ///
+    /// The server has to be advertised through
+    /// [`RemoteServer::advertise`]. This requires the `mdns` feature.
+    ///
+    /// [`RemoteServer::advertise`]: struct.RemoteServer.html#method.advertise
+    #[cfg(feature = "mdns")]
+    pub fn discover(timeout: Duration) -> Result<RemoteOutput> {
+        match mdns::discover(SERVICE_REMOTE, timeout)?.first() {
+            Some(node) => RemoteOutput::connect(node.addr()),
+            None => Err(Error::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no remote server found",
+            ))),
+        }
+    }
+
+    /// Returns the address of the server.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns `true` if the connection to the server is currently open.
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+}
+
+impl SerialOutput for RemoteOutput {
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        if data.len() > MAX_FRAME_LEN {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame exceeds the maximum length",
+            )));
+        }
+
+        if self.stream.is_none() {
+            self.stream = Some(open_stream(self.addr)?);
+        }
+
+        // Send the length and the frame in a single write, so they're not
+        // split into separate packets.
+        self.buffer.clear();
+        self.buffer.extend_from_slice(&be_bytes(data.len() as u32));
+        self.buffer.extend_from_slice(data);
+
+        let result = match self.stream {
+            Some(ref mut stream) => stream.write_all(&self.buffer),
+            None => Ok(()),
+        };
+
+        if let Err(err) = result {
+            self.stream = None;
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A server that receives frames from [`RemoteOutput`] clients, and sends
+/// them to a local `Blinkt`.
+///
+/// Clients are served one at a time. When a client disconnects, the pixels
+/// keep showing the last frame until the next client connects.
+///
+/// [`RemoteOutput`]: struct.RemoteOutput.html
+#[derive(Debug)]
+pub struct RemoteServer {
+    listener: TcpListener,
+}
+
+impl RemoteServer {
+    /// Starts listening for clients on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<RemoteServer> {
+        Ok(RemoteServer {
+            listener: TcpListener::bind(addr)?,
+        })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Advertises the server on the local network through mDNS as `name`,
+    /// until the returned `Advertiser` is dropped.
+    ///
+    /// This requires the `mdns` feature.
+    #[cfg(feature = "mdns")]
+    pub fn advertise(&self, name: &str) -> Result<Advertiser> {
+        Advertiser::spawn(SERVICE_REMOTE, name, self.local_addr()?.port())
+    }
+
+    /// Accepts clients and sends the frames they send to `blinkt`, until
+    /// an error occurs while accepting a client or sending a frame to the
+    /// pixels.
+    ///
+    /// Errors caused by a client, such as a lost connection or an invalid
+    /// frame length, only close the connection to that client.
+    pub fn run(&self, blinkt: &mut Blinkt) -> Result<()> {
+        loop {
+            self.serve_one(blinkt)?;
+        }
+    }
+
+    /// Accepts a single client, and sends the frames it sends to `blinkt`
+    /// until the client disconnects.
+    pub fn serve_one(&self, blinkt: &mut Blinkt) -> Result<()> {
+        let (mut stream, _) = self.listener.accept()?;
+        let mut frame = Vec::new();
+
+        loop {
+            match read_frame(&mut stream, &mut frame) {
+                Ok(true) => blinkt.show_encoded(&frame)?,
+                Ok(false) | Err(_) => return Ok(()),
+            }
+        }
+    }
+}
+
+fn open_stream(addr: SocketAddr) -> Result<TcpStream> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+
+    Ok(stream)
+}
+
+// Reads the next frame into `frame`. Returns `Ok(false)` when the client
+// closed the connection.
+fn read_frame(stream: &mut TcpStream, frame: &mut Vec<u8>) -> io::Result<bool> {
+    let mut len = [0u8; 4];
+    if let Err(err) = stream.read_exact(&mut len) {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            return Ok(false);
+        }
+
+        return Err(err);
+    }
+
+    let len = ((len[0] as usize) << 24)
+        | ((len[1] as usize) << 16)
+        | ((len[2] as usize) << 8)
+        | (len[3] as usize);
+
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame exceeds the maximum length",
+        ));
+    }
+
+    frame.resize(len, 0);
+    stream.read_exact(frame)?;
+
+    Ok(true)
+}
+
+fn be_bytes(value: u32) -> [u8; 4] {
+    [
+        (value >> 24) as u8,
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ]
+}
@@ -0,0 +1,372 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Controlling the pixels through a JSON REST API.
+//!
+//! A [`RestServer`] exposes the pixels over HTTP, so they can be controlled
+//! from shell scripts, phones and other devices with nothing more than
+//! `curl`. Colors are specified as `"#rrggbb"` or `[r, g, b]`, and
+//! brightness values range from `0.0` to `1.0`.
+//!
+//! | Method | Path | Body |
+//! |---|---|---|
+//! | `GET` | `/state` | |
+//! | `GET` | `/snapshot.png` | |
+//! | `PUT` | `/pixels` | An array with a color for every pixel |
+//! | `PUT` | `/pixels/{index}` | `{"color": "#ff0000", "brightness": 0.5}`, both optional |
+//! | `PUT` | `/color` | `{"color": "#ff0000"}` |
+//! | `PUT` | `/brightness` | `{"brightness": 0.5}` |
+//! | `POST` | `/clear` | |
+//! | `GET` | `/effects` | |
+//! | `PUT` | `/effect` | `{"name": "rainbow"}` |
+//! | `DELETE` | `/effect` | |
+//!
+//! `POST` is accepted in place of `PUT`. Successful requests return the
+//! current state, for instance
+//! `{"effect":null,"pixels":[{"color":"#ff0000","brightness":0.5}]}`.
+//! `/snapshot.png` returns a PNG image of the pixels instead, rendered in
+//! the same way as [`Blinkt::save_snapshot`]. Errors return a `4xx` status
+//! code, or `500` if the pixels couldn't be updated, and a JSON object with
+//! an `error` message. Setting pixels stops the current effect.
+//!
+//! The `blinktd` daemon, which also requires the `rest` feature, runs a
+//! `RestServer` with a few built-in effects.
+//!
+//! This module requires the `rest` feature.
+//!
+//! ```rust,no_run
+//! use blinkt::rest::RestServer;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::new()?;
+//! let mut server = RestServer::new()?;
+//!
+//! server.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ```text
+//! curl -X PUT -d '{"color": "#ff8000"}' http://raspberrypi.local:8080/color
+//! ```
+//!
+//! [`RestServer`]: struct.RestServer.html
+//! [`Blinkt::save_snapshot`]: ../struct.Blinkt.html#method.save_snapshot
+
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::animation::Animation;
+use crate::control::{json_color, pixels_json, Effects};
+use crate::http::{self, Request, Response};
+#[cfg(feature = "mdns")]
+use crate::mdns::{Advertiser, SERVICE_REST};
+use crate::{Blinkt, Result};
+
+/// The default TCP port.
+pub const PORT: u16 = 8080;
+
+/// An HTTP server that controls the pixels of a `Blinkt` through a JSON
+/// REST API.
+#[derive(Debug)]
+pub struct RestServer {
+    listener: TcpListener,
+    effects: Effects,
+    brightness: Option<f32>,
+}
+
+impl RestServer {
+    /// Constructs a new `RestServer` listening on the default port on all
+    /// network interfaces.
+    pub fn new() -> Result<RestServer> {
+        RestServer::bind(("0.0.0.0", PORT))
+    }
+
+    /// Constructs a new `RestServer` listening on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<RestServer> {
+        Ok(RestServer {
+            listener: TcpListener::bind(addr)?,
+            effects: Effects::new(),
+            brightness: None,
+        })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Advertises the REST API on the local network through mDNS as `name`,
+    /// until the returned `Advertiser` is dropped.
+    ///
+    /// This requires the `mdns` feature.
+    #[cfg(feature = "mdns")]
+    pub fn advertise(&self, name: &str) -> Result<Advertiser> {
+        Advertiser::spawn(SERVICE_REST, name, self.local_addr()?.port())
+    }
+
+    /// Adds an effect that can be started through the `/effect` endpoint,
+    /// or replaces an existing effect with the same name.
+    ///
+    /// Effects are rendered at roughly 30 frames per second, starting from
+    /// the current contents of the local buffer.
+    pub fn add_effect<A>(&mut self, name: &str, animation: A)
+    where
+        A: Animation + Send + 'static,
+    {
+        self.effects.add(name, Box::new(animation));
+    }
+
+    /// Returns the name of the running effect, if any.
+    pub fn effect(&self) -> Option<&str> {
+        self.effects.selected_name()
+    }
+
+    /// Handles requests until an error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Handles requests until `stop` is set, for instance by a signal
+    /// handler, or an error occurs.
+    ///
+    /// Instead of blocking until a client connects, the server checks `stop`
+    /// every 10 ms while it's idle, so it returns shortly after the flag is
+    /// set.
+    pub fn run_until(&mut self, blinkt: &mut Blinkt, stop: &AtomicBool) -> Result<()> {
+        while !stop.load(Ordering::SeqCst) {
+            self.poll(blinkt, false)?;
+        }
+
+        Ok(())
+    }
+
+    /// Waits for a single request and handles it, or renders the next frame
+    /// of the running effect when it's due.
+    ///
+    /// Returns `Ok(true)` if a request was handled. Errors that only affect
+    /// the connection with the client, such as a timeout or an invalid
+    /// request, are reported to the client if possible, and otherwise
+    /// ignored. Errors while updating the pixels are returned to the client
+    /// as well, and a frame of the running effect that can't be shown is
+    /// skipped, so the server keeps running.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        let running = self.effects.selected().is_some();
+        self.poll(blinkt, !running)
+    }
+
+    // Handles a single request, waiting for one if `wait` is true, or
+    // renders the next frame of the running effect when it's due.
+    fn poll(&mut self, blinkt: &mut Blinkt, wait: bool) -> Result<bool> {
+        if let Some(stream) = http::accept(&self.listener, wait)? {
+            return http::serve(&stream, |request| Ok(self.handle_request(blinkt, request)));
+        }
+
+        let idle = Duration::from_millis(10);
+        if self.effects.selected().is_none() {
+            thread::sleep(idle);
+            return Ok(false);
+        }
+
+        thread::sleep(self.effects.until_next_frame().min(idle));
+
+        if self.effects.until_next_frame() == Duration::from_millis(0) {
+            let _ = self.effects.render(blinkt, self.brightness);
+        }
+
+        Ok(false)
+    }
+
+    fn handle_request(&mut self, blinkt: &mut Blinkt, request: &Request) -> Response {
+        let segments: Vec<&str> = request
+            .path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let method = match &request.method[..] {
+            "POST" => "PUT",
+            method => method,
+        };
+
+        match (method, &segments[..]) {
+            ("GET", ["state"]) => self.state(blinkt),
+            ("GET", ["snapshot.png"]) => {
+                let mut png = Vec::new();
+                match crate::blinkt::write_snapshot_png(&mut png, &blinkt.pixels) {
+                    Ok(()) => Response {
+                        status: 200,
+                        content_type: "image/png",
+                        body: png,
+                    },
+                    Err(_) => Response::error(404, "no pixels to render"),
+                }
+            }
+            ("GET", ["effects"]) => {
+                let names: Vec<&str> = self.effects.names().collect();
+                Response::json(200, &json!(names))
+            }
+            ("PUT", ["pixels"]) => {
+                let colors = match parse_body(request).as_ref().and_then(Value::as_array) {
+                    Some(values) => values.iter().map(json_color).collect::<Option<Vec<_>>>(),
+                    None => None,
+                };
+
+                match colors {
+                    Some(colors) => {
+                        for (pixel, &color) in colors.iter().enumerate() {
+                            blinkt.set_pixel_color(pixel, color);
+                        }
+
+                        self.update(blinkt)
+                    }
+                    None => Response::error(400, "expected an array of colors"),
+                }
+            }
+            ("PUT", ["pixels", index]) => {
+                let pixel = match index.parse::<usize>() {
+                    Ok(pixel) if pixel < blinkt.num_pixels() => pixel,
+                    _ => return Response::error(404, "pixel not found"),
+                };
+
+                let body = parse_body(request);
+                let (color, brightness) = match body {
+                    Some(ref body) => (body.get("color"), body.get("brightness")),
+                    None => return Response::error(400, "expected a JSON object"),
+                };
+
+                let color = match color.map(json_color) {
+                    Some(None) => return Response::error(400, "invalid color"),
+                    color => color.and_then(|color| color),
+                };
+
+                let brightness = match brightness.map(Value::as_f64) {
+                    Some(None) => return Response::error(400, "invalid brightness"),
+                    brightness => brightness.and_then(|brightness| brightness),
+                };
+
+                if let Some(color) = color {
+                    blinkt.set_pixel_color(pixel, color);
+                }
+
+                if let Some(brightness) = brightness {
+                    blinkt.set_pixel_brightness(pixel, brightness as f32);
+                }
+
+                self.update(blinkt)
+            }
+            ("PUT", ["color"]) => {
+                match parse_body(request).and_then(|body| body.get("color").and_then(json_color)) {
+                    Some(color) => {
+                        blinkt.set_all_pixels_color(color);
+                        self.update(blinkt)
+                    }
+                    None => Response::error(400, "expected a color"),
+                }
+            }
+            ("PUT", ["brightness"]) => {
+                let brightness = parse_body(request)
+                    .and_then(|body| body.get("brightness").and_then(Value::as_f64));
+
+                match brightness {
+                    Some(brightness) => {
+                        let brightness = (brightness as f32).max(0.0).min(1.0);
+                        self.brightness = Some(brightness);
+                        blinkt.set_all_pixels_brightness(brightness);
+                        if self.effects.selected().is_some() {
+                            self.state(blinkt)
+                        } else {
+                            self.update(blinkt)
+                        }
+                    }
+                    None => Response::error(400, "expected a brightness"),
+                }
+            }
+            ("PUT", ["clear"]) => {
+                blinkt.clear();
+                self.update(blinkt)
+            }
+            ("PUT", ["effect"]) => {
+                let index = parse_body(request).and_then(|body| {
+                    body.get("name")
+                        .and_then(Value::as_str)
+                        .and_then(|name| self.effects.find(name))
+                });
+
+                match index {
+                    Some(index) => {
+                        self.effects.select(Some(index));
+                        match self.effects.render(blinkt, self.brightness) {
+                            Ok(()) => self.state(blinkt),
+                            Err(err) => Response::error(500, &err.to_string()),
+                        }
+                    }
+                    None => Response::error(404, "effect not found"),
+                }
+            }
+            ("DELETE", ["effect"]) => {
+                self.effects.select(None);
+                self.state(blinkt)
+            }
+            (_, ["state"])
+            | (_, ["snapshot.png"])
+            | (_, ["effects"])
+            | (_, ["pixels"])
+            | (_, ["pixels", _])
+            | (_, ["color"])
+            | (_, ["brightness"])
+            | (_, ["clear"])
+            | (_, ["effect"]) => Response::error(405, "method not allowed"),
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    // Stops the running effect, sends the local buffer to the pixels, and
+    // returns the new state.
+    fn update(&mut self, blinkt: &mut Blinkt) -> Response {
+        self.effects.select(None);
+        blinkt.swap();
+
+        match blinkt.show() {
+            Ok(()) => self.state(blinkt),
+            Err(err) => Response::error(500, &err.to_string()),
+        }
+    }
+
+    fn state(&self, blinkt: &Blinkt) -> Response {
+        Response::json(
+            200,
+            &json!({
+                "effect": self.effects.selected_name(),
+                "pixels": pixels_json(blinkt),
+            }),
+        )
+    }
+}
+
+fn parse_body(request: &Request) -> Option<Value> {
+    serde_json::from_str::<Value>(&String::from_utf8_lossy(&request.body)).ok()
+}
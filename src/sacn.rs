@@ -0,0 +1,392 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Receiving DMX data through sACN (ANSI E1.31).
+//!
+//! A [`SacnReceiver`] joins the multicast group of every mapped universe,
+//! and copies the channel values of the E1.31 data packets for each
+//! universe to a range of pixels. Each universe holds the red, green and
+//! blue values for up to 170 consecutive pixels. Unicast packets sent
+//! directly to the receiver are accepted as well.
+//!
+//! When multiple sources send data for the same universe, only the source
+//! with the highest priority is used. A source that stops sending data for
+//! longer than the configured timeout is considered lost, at which point the
+//! next source takes over, and the [`LossPolicy`] determines what happens to
+//! the pixels in the meantime.
+//!
+//! This module requires the `sacn` feature.
+//!
+//! ```rust,no_run
+//! use blinkt::sacn::{LossPolicy, SacnReceiver};
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 300)?;
+//!
+//! let mut receiver = SacnReceiver::new()?;
+//! receiver.map_universe(1, 0)?;
+//! receiver.map_universe(2, 170)?;
+//! receiver.set_loss_policy(LossPolicy::Blank);
+//!
+//! receiver.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SacnReceiver`]: struct.SacnReceiver.html
+//! [`LossPolicy`]: enum.LossPolicy.html
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::dmx::{self, UniverseMap};
+use crate::{Blinkt, Result};
+
+pub use crate::dmx::FlushPolicy;
+
+/// The UDP port used by sACN.
+pub const PORT: u16 = 5568;
+
+const ACN_ID: &[u8; 12] = b"ASC-E1.17\0\0\0";
+const VECTOR_ROOT_DATA: u32 = 0x0000_0004;
+const VECTOR_ROOT_EXTENDED: u32 = 0x0000_0008;
+const VECTOR_FRAMING_DATA: u32 = 0x0000_0002;
+const VECTOR_FRAMING_SYNC: u32 = 0x0000_0001;
+const OPTION_PREVIEW: u8 = 0x80;
+const OPTION_TERMINATED: u8 = 0x40;
+const DATA_HEADER_LEN: usize = 126;
+const SYNC_PACKET_LEN: usize = 49;
+// Maximum data packet size: a 126-byte header and 512 channels.
+const MAX_PACKET_LEN: usize = 638;
+// Network data loss timeout defined by E1.31.
+const DEFAULT_TIMEOUT_MS: u64 = 2500;
+
+/// Determines what happens to the pixels of a universe when its source is
+/// lost.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LossPolicy {
+    /// Keeps showing the last values received for the universe.
+    Hold,
+    /// Turns off the pixels of the universe.
+    Blank,
+}
+
+// The source currently in control of a universe.
+#[derive(Debug, Copy, Clone)]
+struct Source {
+    universe: u16,
+    cid: [u8; 16],
+    priority: u8,
+    last_seen: Instant,
+}
+
+/// An sACN receiver that drives the pixels of a `Blinkt`.
+#[derive(Debug)]
+pub struct SacnReceiver {
+    socket: UdpSocket,
+    universes: UniverseMap,
+    sources: Vec<Source>,
+    flush_policy: FlushPolicy,
+    loss_policy: LossPolicy,
+    timeout: Duration,
+    buffer: Vec<u8>,
+}
+
+impl SacnReceiver {
+    /// Constructs a new `SacnReceiver` listening on the standard sACN port
+    /// on all network interfaces.
+    pub fn new() -> Result<SacnReceiver> {
+        SacnReceiver::bind(("0.0.0.0", PORT))
+    }
+
+    /// Constructs a new `SacnReceiver` listening on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<SacnReceiver> {
+        let socket = UdpSocket::bind(addr)?;
+        let timeout = Duration::from_millis(DEFAULT_TIMEOUT_MS);
+        socket.set_read_timeout(Some(poll_interval(timeout)))?;
+
+        Ok(SacnReceiver {
+            socket,
+            universes: UniverseMap::default(),
+            sources: Vec::new(),
+            flush_policy: FlushPolicy::AllUniverses,
+            loss_policy: LossPolicy::Hold,
+            timeout,
+            buffer: vec![0u8; MAX_PACKET_LEN],
+        })
+    }
+
+    /// Returns the address the receiver is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Maps `universe` onto the pixels starting at `start_pixel`, and joins
+    /// its multicast group.
+    ///
+    /// The universe covers up to 170 pixels, depending on the number of
+    /// channels sent by the source. Values for pixels beyond the end of the
+    /// strip are ignored. Mapping a universe again replaces its start pixel.
+    pub fn map_universe(&mut self, universe: u16, start_pixel: usize) -> Result<()> {
+        if !self.universes.contains(universe) {
+            self.socket
+                .join_multicast_v4(&multicast_addr(universe), &Ipv4Addr::UNSPECIFIED)?;
+        }
+
+        self.universes.map(universe, start_pixel);
+
+        Ok(())
+    }
+
+    /// Removes the mapping for `universe`, and leaves its multicast group.
+    pub fn unmap_universe(&mut self, universe: u16) {
+        if self.universes.contains(universe) {
+            let _ = self
+                .socket
+                .leave_multicast_v4(&multicast_addr(universe), &Ipv4Addr::UNSPECIFIED);
+        }
+
+        self.universes.unmap(universe);
+        self.sources.retain(|source| source.universe != universe);
+    }
+
+    /// Returns the flush policy.
+    pub fn flush_policy(&self) -> FlushPolicy {
+        self.flush_policy
+    }
+
+    /// Sets the flush policy, which determines when the received values
+    /// are sent to the pixels.
+    ///
+    /// By default, this is set to `FlushPolicy::AllUniverses`.
+    pub fn set_flush_policy(&mut self, flush_policy: FlushPolicy) {
+        self.flush_policy = flush_policy;
+    }
+
+    /// Returns the loss policy.
+    pub fn loss_policy(&self) -> LossPolicy {
+        self.loss_policy
+    }
+
+    /// Sets the loss policy, which determines what happens to the pixels of
+    /// a universe when its source is lost.
+    ///
+    /// By default, this is set to `LossPolicy::Hold`.
+    pub fn set_loss_policy(&mut self, loss_policy: LossPolicy) {
+        self.loss_policy = loss_policy;
+    }
+
+    /// Returns the stream loss timeout.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Sets the stream loss timeout.
+    ///
+    /// A source that hasn't sent any data for a universe for the duration of
+    /// `timeout` is considered lost.
+    ///
+    /// By default, this is set to 2.5 seconds, as defined by E1.31.
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.socket.set_read_timeout(Some(poll_interval(timeout)))?;
+        self.timeout = timeout;
+
+        Ok(())
+    }
+
+    /// Receives and processes packets until an error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Waits for a single packet, and processes it.
+    ///
+    /// Returns `Ok(true)` if a frame was sent to the pixels. To detect lost
+    /// sources, this returns early when no packet arrives within a fraction
+    /// of the timeout. Packets that aren't valid E1.31 packets are ignored.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        let len = match self.socket.recv_from(&mut self.buffer) {
+            Ok((len, _)) => len,
+            Err(ref err)
+                if err.kind() == io::ErrorKind::WouldBlock
+                    || err.kind() == io::ErrorKind::TimedOut =>
+            {
+                return self.expire_sources(blinkt);
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let shown = if len < 22 || self.buffer[4..16] != ACN_ID[..] {
+            false
+        } else {
+            match be_u32(&self.buffer[18..22]) {
+                VECTOR_ROOT_DATA => self.process_data(blinkt, len)?,
+                VECTOR_ROOT_EXTENDED => self.process_sync(blinkt, len)?,
+                _ => false,
+            }
+        };
+
+        Ok(self.expire_sources(blinkt)? || shown)
+    }
+
+    fn process_data(&mut self, blinkt: &mut Blinkt, len: usize) -> Result<bool> {
+        let packet = &self.buffer[..len];
+        if len < DATA_HEADER_LEN || be_u32(&packet[40..44]) != VECTOR_FRAMING_DATA {
+            return Ok(false);
+        }
+
+        let options = packet[112];
+        let universe = be_u16(&packet[113..115]);
+        // Only DMX data (start code 0) is used, and preview data is ignored.
+        if packet[125] != 0 || options & OPTION_PREVIEW != 0 || !self.universes.contains(universe) {
+            return Ok(false);
+        }
+
+        let mut cid = [0u8; 16];
+        cid.copy_from_slice(&packet[22..38]);
+        let priority = packet[108];
+
+        if options & OPTION_TERMINATED != 0 {
+            if self.is_current(universe, &cid) {
+                return self.lose_source(blinkt, universe);
+            }
+
+            return Ok(false);
+        }
+
+        let now = Instant::now();
+        let timeout = self.timeout;
+        match self
+            .sources
+            .iter_mut()
+            .find(|source| source.universe == universe)
+        {
+            Some(source) => {
+                let expired = now.duration_since(source.last_seen) >= timeout;
+                if source.cid != cid && priority <= source.priority && !expired {
+                    return Ok(false);
+                }
+
+                source.cid = cid;
+                source.priority = priority;
+                source.last_seen = now;
+            }
+            None => self.sources.push(Source {
+                universe,
+                cid,
+                priority,
+                last_seen: now,
+            }),
+        }
+
+        let count = (be_u16(&packet[123..125]) as usize).saturating_sub(1);
+        let end = (DATA_HEADER_LEN + count).min(len);
+        self.universes
+            .apply(blinkt, universe, &self.buffer[DATA_HEADER_LEN..end]);
+
+        let flush = match self.flush_policy {
+            FlushPolicy::Immediate => true,
+            FlushPolicy::AllUniverses => self.universes.all_received(),
+            FlushPolicy::Sync => false,
+        };
+
+        if flush {
+            dmx::flush(blinkt, &mut self.universes)?;
+        }
+
+        Ok(flush)
+    }
+
+    fn process_sync(&mut self, blinkt: &mut Blinkt, len: usize) -> Result<bool> {
+        if len < SYNC_PACKET_LEN
+            || be_u32(&self.buffer[40..44]) != VECTOR_FRAMING_SYNC
+            || self.flush_policy != FlushPolicy::Sync
+        {
+            return Ok(false);
+        }
+
+        dmx::flush(blinkt, &mut self.universes)?;
+
+        Ok(true)
+    }
+
+    fn is_current(&self, universe: u16, cid: &[u8; 16]) -> bool {
+        self.sources
+            .iter()
+            .any(|source| source.universe == universe && source.cid == *cid)
+    }
+
+    // Removes the sources that timed out, and applies the loss policy to
+    // their universes.
+    fn expire_sources(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        let timeout = self.timeout;
+        let expired: Vec<u16> = self
+            .sources
+            .iter()
+            .filter(|source| source.last_seen.elapsed() >= timeout)
+            .map(|source| source.universe)
+            .collect();
+
+        let mut shown = false;
+        for universe in expired {
+            shown |= self.lose_source(blinkt, universe)?;
+        }
+
+        Ok(shown)
+    }
+
+    fn lose_source(&mut self, blinkt: &mut Blinkt, universe: u16) -> Result<bool> {
+        self.sources.retain(|source| source.universe != universe);
+
+        match self.loss_policy {
+            LossPolicy::Hold => Ok(false),
+            LossPolicy::Blank => {
+                self.universes.blank(blinkt, universe);
+                dmx::flush(blinkt, &mut self.universes)?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+// Returns the multicast group for `universe` (239.255.{high}.{low}).
+fn multicast_addr(universe: u16) -> Ipv4Addr {
+    Ipv4Addr::new(239, 255, (universe >> 8) as u8, universe as u8)
+}
+
+// Returns how long to wait for a packet before checking for lost sources.
+fn poll_interval(timeout: Duration) -> Duration {
+    (timeout / 4).max(Duration::from_millis(10))
+}
+
+fn be_u16(bytes: &[u8]) -> u16 {
+    (u16::from(bytes[0]) << 8) | u16::from(bytes[1])
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    (u32::from(bytes[0]) << 24)
+        | (u32::from(bytes[1]) << 16)
+        | (u32::from(bytes[2]) << 8)
+        | u32::from(bytes[3])
+}
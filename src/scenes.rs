@@ -0,0 +1,202 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Named lighting scenes.
+//!
+//! A [`Scene`] combines a [`Frame`] with an optional brightness setting.
+//! Scenes are stored under a name in a [`SceneManager`], such as `"reading"`,
+//! `"movie"` or `"off"`, and can be recalled instantly or crossfaded to at
+//! runtime.
+//!
+//! With the `serde` feature enabled, `SceneManager` implements `Serialize`
+//! and `Deserialize`, so scenes can be persisted in any format supported by
+//! serde, such as TOML or JSON.
+//!
+//! ```rust,no_run
+//! # use std::error::Error;
+//! #
+//! use std::time::Duration;
+//!
+//! use blinkt::easing::Easing;
+//! use blinkt::scenes::{Scene, SceneManager};
+//! use blinkt::{Blinkt, Color, Frame};
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let mut blinkt = Blinkt::new()?;
+//!
+//! let mut reading = Frame::new(8);
+//! reading.set_all_pixels_color(Color::from_kelvin(4000));
+//!
+//! let mut scenes = SceneManager::new();
+//! scenes.insert("reading", Scene::new(reading).with_brightness(0.8));
+//! scenes.insert("off", Scene::new(Frame::new(8)));
+//!
+//! scenes.crossfade(&mut blinkt, "reading", Duration::from_secs(2), Easing::EaseInOutSine)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Scene`]: struct.Scene.html
+//! [`SceneManager`]: struct.SceneManager.html
+//! [`Frame`]: ../struct.Frame.html
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::easing::Easing;
+use crate::{Blinkt, Frame, Result};
+
+/// A frame combined with an optional brightness setting.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Scene {
+    frame: Frame,
+    brightness: Option<f32>,
+}
+
+impl Scene {
+    /// Constructs a new `Scene` containing `frame`.
+    pub fn new(frame: Frame) -> Scene {
+        Scene {
+            frame,
+            brightness: None,
+        }
+    }
+
+    /// Overrides the brightness of all pixels in the frame when the scene is
+    /// recalled.
+    ///
+    /// `brightness` is specified as a floating point value between `0.0` (0%)
+    /// and `1.0` (100%).
+    pub fn with_brightness(mut self, brightness: f32) -> Scene {
+        self.brightness = Some(brightness);
+        self
+    }
+
+    /// Returns the frame.
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// Returns the brightness override, or `None` if the brightness values
+    /// stored in the frame are used.
+    pub fn brightness(&self) -> Option<f32> {
+        self.brightness
+    }
+
+    /// Returns the frame as it's displayed, with the brightness override
+    /// applied.
+    pub fn render(&self) -> Frame {
+        let mut frame = self.frame.clone();
+        if let Some(brightness) = self.brightness {
+            frame.set_all_pixels_brightness(brightness);
+        }
+
+        frame
+    }
+}
+
+/// A collection of named scenes.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SceneManager {
+    scenes: BTreeMap<String, Scene>,
+}
+
+impl SceneManager {
+    /// Constructs a new, empty `SceneManager`.
+    pub fn new() -> SceneManager {
+        SceneManager::default()
+    }
+
+    /// Stores `scene` under `name`, and returns the scene that was
+    /// previously stored under the same name, if any.
+    pub fn insert<S: Into<String>>(&mut self, name: S, scene: Scene) -> Option<Scene> {
+        self.scenes.insert(name.into(), scene)
+    }
+
+    /// Removes and returns the scene stored under `name`.
+    pub fn remove(&mut self, name: &str) -> Option<Scene> {
+        self.scenes.remove(name)
+    }
+
+    /// Returns the scene stored under `name`.
+    pub fn get(&self, name: &str) -> Option<&Scene> {
+        self.scenes.get(name)
+    }
+
+    /// Returns an iterator over all scene names, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.scenes.keys().map(String::as_str)
+    }
+
+    /// Returns the number of scenes.
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Returns `true` if no scenes are stored.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// Copies the scene stored under `name` to the local buffer of `blinkt`,
+    /// and sends it to the pixels.
+    ///
+    /// Returns `Ok(false)` if no scene is stored under `name`.
+    pub fn recall(&self, blinkt: &mut Blinkt, name: &str) -> Result<bool> {
+        let scene = match self.scenes.get(name) {
+            Some(scene) => scene,
+            None => return Ok(false),
+        };
+
+        blinkt.set_frame(&scene.render());
+        blinkt.swap();
+        blinkt.show()?;
+
+        Ok(true)
+    }
+
+    /// Crossfades from the current contents of the local buffer of `blinkt`
+    /// to the scene stored under `name`, and blocks until the transition has
+    /// finished.
+    ///
+    /// Returns `Ok(false)` if no scene is stored under `name`. See
+    /// `Blinkt::transition_to()` for more information.
+    pub fn crossfade(
+        &self,
+        blinkt: &mut Blinkt,
+        name: &str,
+        duration: Duration,
+        easing: Easing,
+    ) -> Result<bool> {
+        let scene = match self.scenes.get(name) {
+            Some(scene) => scene,
+            None => return Ok(false),
+        };
+
+        blinkt.transition_to(&scene.render(), duration, easing)?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::ops::Range;
+
+use crate::color::{Color, Interpolation};
+use crate::{Blinkt, IterMut};
+
+/// A contiguous range of pixels that can be addressed as an independent strip.
+///
+/// Pixels within a `Segment` are numbered starting at `0`, regardless of
+/// their position on the physical strip. Any pixels outside of the segment
+/// can't be modified through the segment.
+///
+/// A `Segment` is created through `Blinkt::segment()`.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # use blinkt::Blinkt;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mut blinkt = Blinkt::with_spi(16_000_000, 300)?;
+///
+/// blinkt.segment(0..100).set_all_pixels(255, 0, 0);
+/// blinkt.segment(100..200).set_pixel(0, 0, 255, 0);
+/// blinkt.segment(200..300).clear();
+///
+/// blinkt.show()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Segment<'a> {
+    blinkt: &'a mut Blinkt,
+    range: Range<usize>,
+}
+
+impl<'a> Segment<'a> {
+    pub(crate) fn new(blinkt: &'a mut Blinkt, range: Range<usize>) -> Segment<'a> {
+        let len = blinkt.pixels.len();
+        let end = range.end.min(len);
+        let start = range.start.min(end);
+
+        Segment {
+            blinkt,
+            range: start..end,
+        }
+    }
+
+    /// Returns the number of pixels in the segment.
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Returns `true` if the segment doesn't contain any pixels.
+    pub fn is_empty(&self) -> bool {
+        self.range.start == self.range.end
+    }
+
+    /// Returns the range of physical pixels covered by the segment.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns a mutable iterator over all `Pixel`s in the segment.
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            iter_mut: self.blinkt.pixels[self.range.clone()].iter_mut(),
+        }
+    }
+
+    // Converts a segment pixel index to a physical pixel index.
+    #[inline]
+    fn physical(&self, pixel: usize) -> Option<usize> {
+        if pixel < self.len() {
+            Some(self.range.start + pixel)
+        } else {
+            None
+        }
+    }
+
+    /// Sets the red, green and blue values for a single pixel.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    pub fn set_pixel(&mut self, pixel: usize, red: u8, green: u8, blue: u8) {
+        if let Some(pixel) = self.physical(pixel) {
+            self.blinkt.set_pixel(pixel, red, green, blue);
+        }
+    }
+
+    /// Sets the red, green, blue and brightness values for a single pixel.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_pixel_rgbb(&mut self, pixel: usize, red: u8, green: u8, blue: u8, brightness: f32) {
+        if let Some(pixel) = self.physical(pixel) {
+            self.blinkt
+                .set_pixel_rgbb(pixel, red, green, blue, brightness);
+        }
+    }
+
+    /// Sets the color for a single pixel.
+    ///
+    /// Pixels are numbered starting at `0`.
+    pub fn set_pixel_color<C: Into<Color>>(&mut self, pixel: usize, color: C) {
+        if let Some(pixel) = self.physical(pixel) {
+            self.blinkt.set_pixel_color(pixel, color);
+        }
+    }
+
+    /// Sets the brightness value for a single pixel.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_pixel_brightness(&mut self, pixel: usize, brightness: f32) {
+        if let Some(pixel) = self.physical(pixel) {
+            self.blinkt.set_pixel_brightness(pixel, brightness);
+        }
+    }
+
+    /// Sets the red, green and blue values for all pixels in the segment.
+    ///
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    pub fn set_all_pixels(&mut self, red: u8, green: u8, blue: u8) {
+        for pixel in self.range.clone() {
+            self.blinkt.set_pixel(pixel, red, green, blue);
+        }
+    }
+
+    /// Sets the red, green, blue and brightness values for all pixels in the
+    /// segment.
+    ///
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_all_pixels_rgbb(&mut self, red: u8, green: u8, blue: u8, brightness: f32) {
+        for pixel in self.range.clone() {
+            self.blinkt
+                .set_pixel_rgbb(pixel, red, green, blue, brightness);
+        }
+    }
+
+    /// Sets the color for all pixels in the segment.
+    pub fn set_all_pixels_color<C: Into<Color>>(&mut self, color: C) {
+        let color = color.into();
+        self.set_all_pixels(color.red, color.green, color.blue);
+    }
+
+    /// Sets the brightness value for all pixels in the segment.
+    ///
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_all_pixels_brightness(&mut self, brightness: f32) {
+        for pixel in self.range.clone() {
+            self.blinkt.set_pixel_brightness(pixel, brightness);
+        }
+    }
+
+    /// Fills all pixels in the segment with a gradient from `start` to `end`.
+    pub fn fill_gradient<C: Into<Color>>(
+        &mut self,
+        start: C,
+        end: C,
+        interpolation: Interpolation,
+    ) {
+        self.blinkt
+            .fill_gradient_range(self.range.clone(), start, end, interpolation);
+    }
+
+    /// Sets the red, green and blue values for all pixels in the segment to
+    /// `0`.
+    pub fn clear(&mut self) {
+        self.set_all_pixels(0, 0, 0);
+    }
+}
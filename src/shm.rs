@@ -0,0 +1,217 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Sharing a frame buffer with other processes through shared memory.
+//!
+//! A [`SharedFrame`] creates a file in shared memory, usually under
+//! `/dev/shm`, which other processes can map into their own address space
+//! and write to directly, while the process that owns the pixels shows its
+//! contents at a fixed frame rate. This keeps a heavy renderer, possibly
+//! written in another language, completely separate from the process that
+//! sends the frames to the pixels, without any copying or serialization in
+//! between.
+//!
+//! The file contains 8-bit red, green and blue values for every pixel, so
+//! it's exactly `num_pixels * 3` bytes long. Frames are read without any
+//! synchronization, so when a writer updates the buffer while it's being
+//! read, a mix of two frames may be shown until the next frame.
+//!
+//! New files can only be read and written by the user that owns the pixels.
+//! Use [`SharedFrame::with_mode`] to let renderers running as other users
+//! write to the buffer, keeping in mind that this also allows them to
+//! truncate it, or to fill it with frames of their own.
+//!
+//! ```rust,no_run
+//! use blinkt::shm::SharedFrame;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+//! let frame = SharedFrame::create("/dev/shm/blinkt", blinkt.num_pixels())?;
+//!
+//! frame.run(&mut blinkt, 60.0)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! In Python:
+//!
+//! ```text
+//! import mmap, os
+//!
+//! fd = os.open("/dev/shm/blinkt", os.O_RDWR)
+//! buffer = mmap.mmap(fd, 0)
+//! buffer[0:3] = bytes([255, 128, 0])
+//! ```
+//!
+//! [`SharedFrame`]: struct.SharedFrame.html
+//! [`SharedFrame::with_mode`]: struct.SharedFrame.html#method.with_mode
+
+use std::fs::{self, File, OpenOptions, Permissions};
+use std::io;
+use std::os::unix::fs::{FileExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Blinkt, Result};
+
+/// The default path of the shared memory file.
+pub const PATH: &str = "/dev/shm/blinkt";
+
+/// A frame buffer in shared memory that other processes can write to.
+///
+/// The shared memory file is removed when the `SharedFrame` is dropped.
+#[derive(Debug)]
+pub struct SharedFrame {
+    path: PathBuf,
+    file: File,
+    len: usize,
+}
+
+impl SharedFrame {
+    /// Creates a shared frame buffer for `num_pixels` pixels at the default
+    /// path.
+    pub fn new(num_pixels: usize) -> Result<SharedFrame> {
+        SharedFrame::create(PATH, num_pixels)
+    }
+
+    /// Creates a shared frame buffer for `num_pixels` pixels at `path`.
+    ///
+    /// An existing file at `path` is resized to `num_pixels * 3` bytes,
+    /// keeping its contents, so a renderer that's already running can keep
+    /// using it. New files are filled with zeros, and can only be read and
+    /// written by the current user.
+    pub fn create<P: AsRef<Path>>(path: P, num_pixels: usize) -> Result<SharedFrame> {
+        let path = path.as_ref().to_path_buf();
+        let len = num_pixels * 3;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .mode(0o600)
+            .open(&path)?;
+        file.set_len(len as u64)?;
+
+        Ok(SharedFrame { path, file, len })
+    }
+
+    /// Changes the permissions of the shared memory file to `mode`, for
+    /// instance `0o660` to let renderers in the same group write to it.
+    ///
+    /// Every user that can write to the file can change the colors of the
+    /// pixels, so only grant access to users you trust. A writer that
+    /// truncates the file causes [`read`] and [`show`] to fail.
+    ///
+    /// [`read`]: #method.read
+    /// [`show`]: #method.show
+    pub fn with_mode(self, mode: u32) -> Result<SharedFrame> {
+        self.file.set_permissions(Permissions::from_mode(mode))?;
+
+        Ok(self)
+    }
+
+    /// Returns the path of the shared memory file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the size of the frame buffer in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the frame buffer doesn't contain any pixels.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Copies the current contents of the frame buffer into `buffer`, which
+    /// is resized to fit.
+    ///
+    /// Returns an error if another process truncated the shared memory file.
+    pub fn read(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        buffer.clear();
+        buffer.resize(self.len, 0);
+
+        // The file is read through the file descriptor rather than a memory
+        // mapping, so a file that's truncated by another process results in
+        // a short read instead of a SIGBUS.
+        let mut offset = 0;
+        while offset < self.len {
+            match self.file.read_at(&mut buffer[offset..], offset as u64) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "shared memory file was truncated",
+                    )
+                    .into())
+                }
+                Ok(read) => offset += read,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends the current contents of the frame buffer to the pixels.
+    ///
+    /// Pixels that don't fit in the frame buffer keep their current color.
+    pub fn show(&self, blinkt: &mut Blinkt) -> Result<()> {
+        let mut data = Vec::with_capacity(self.len);
+        self.read(&mut data)?;
+
+        blinkt.show_rgb(&data)
+    }
+
+    /// Sends the contents of the frame buffer to the pixels `fps` times per
+    /// second, until an error occurs.
+    ///
+    /// The frame rate is limited to a range of 0.1 to 1000 frames per
+    /// second.
+    pub fn run(&self, blinkt: &mut Blinkt, fps: f32) -> Result<()> {
+        let fps = fps.max(0.1).min(1000.0);
+        let frame_interval = Duration::from_micros((1_000_000.0 / fps) as u64);
+        let mut next_frame = Instant::now();
+
+        loop {
+            self.show(blinkt)?;
+
+            // Skip any frames we've missed.
+            let now = Instant::now();
+            next_frame += frame_interval;
+            if next_frame <= now {
+                next_frame = now + frame_interval;
+            }
+
+            thread::sleep(next_frame - now);
+        }
+    }
+}
+
+impl Drop for SharedFrame {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Built-in signal handling for guaranteed cleanup.
+//!
+//! Drop methods aren't called when a process is terminated by a signal, so
+//! pixels stay lit when a user presses <kbd>Ctrl</kbd> + <kbd>C</kbd>.
+//! [`clear_on_signal`] installs handlers for `SIGINT` and `SIGTERM` that
+//! clear the pixels and release the GPIO or SPI peripheral before the process
+//! exits.
+//!
+//! This module requires the `signals` feature.
+//!
+//! ```rust,no_run
+//! # use std::error::Error;
+//! #
+//! use std::sync::{Arc, Mutex};
+//! use std::thread;
+//! use std::time::Duration;
+//!
+//! use blinkt::{signals, Blinkt};
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let blinkt = Arc::new(Mutex::new(Blinkt::new()?));
+//! signals::clear_on_signal(&blinkt);
+//!
+//! loop {
+//!     {
+//!         let mut blinkt = blinkt.lock().unwrap();
+//!         blinkt.set_all_pixels(255, 0, 0);
+//!         blinkt.show()?;
+//!     }
+//!
+//!     thread::sleep(Duration::from_millis(250));
+//! }
+//! # }
+//! ```
+//!
+//! [`clear_on_signal`]: fn.clear_on_signal.html
+
+use std::process;
+use std::sync::{Arc, Mutex};
+
+use simple_signal::{self, Signal};
+
+use crate::animation;
+use crate::Blinkt;
+
+/// Installs handlers for `SIGINT` and `SIGTERM` that clear all pixels and
+/// exit the process.
+///
+/// When a signal is caught, the handler waits for `blinkt` to be unlocked,
+/// fades out the pixels if a fade duration was set through
+/// `Blinkt::set_clear_on_drop_fade()`, clears them, and releases the GPIO
+/// pins or SPI peripheral, which restores the original pin state. The
+/// process then exits with the conventional status code of `128` plus the
+/// signal number. Pixels are cleared regardless of the value of
+/// `clear_on_drop`.
+///
+/// Installing handlers replaces any handlers for the same signals that were
+/// previously installed through the `simple-signal` crate.
+pub fn clear_on_signal(blinkt: &Arc<Mutex<Blinkt>>) {
+    let blinkt = blinkt.clone();
+
+    simple_signal::set_handler(&[Signal::Int, Signal::Term], move |signals| {
+        {
+            let mut blinkt = animation::lock(&blinkt);
+            blinkt.release();
+        }
+
+        let signal = match signals.first() {
+            Some(Signal::Term) => 15,
+            _ => 2,
+        };
+
+        process::exit(128 + signal);
+    });
+}
@@ -0,0 +1,153 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::color::Color;
+
+/// A small two-dimensional image with transparency, that can be drawn onto a
+/// [`Matrix`] through [`Matrix::blit`].
+///
+/// Each pixel stores a color and an 8-bit alpha value, where `0` is fully
+/// transparent and `255` is fully opaque. Additionally, a transparent color
+/// can be set, which skips all pixels of that color regardless of their
+/// alpha value. This is convenient for sprites created from images without
+/// an alpha channel.
+///
+/// Pixels are stored in row-major order, starting at the top left pixel.
+///
+/// ```rust
+/// use blinkt::{Color, Sprite};
+///
+/// let red = Color::new(255, 0, 0);
+/// let black = Color::new(0, 0, 0);
+///
+/// let mut heart = Sprite::from_colors(
+///     3,
+///     2,
+///     &[red, black, red,
+///       black, red, black],
+/// );
+/// heart.set_transparent_color(Some(black));
+///
+/// assert_eq!(heart.pixel(1, 1), Some((red, 255)));
+/// ```
+///
+/// [`Matrix`]: struct.Matrix.html
+/// [`Matrix::blit`]: struct.Matrix.html#method.blit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sprite {
+    width: usize,
+    height: usize,
+    pixels: Vec<(Color, u8)>,
+    transparent_color: Option<Color>,
+}
+
+impl Sprite {
+    /// Constructs a new `Sprite` with the specified dimensions, where all
+    /// pixels are fully transparent.
+    pub fn new(width: usize, height: usize) -> Sprite {
+        Sprite {
+            width,
+            height,
+            pixels: vec![(Color::new(0, 0, 0), 0); width * height],
+            transparent_color: None,
+        }
+    }
+
+    /// Constructs a new `Sprite` with the specified dimensions, where all
+    /// pixels are fully opaque and set to the values in `colors`, in
+    /// row-major order.
+    ///
+    /// If `colors` contains fewer values than the number of pixels, the
+    /// remaining pixels are fully transparent. Any additional values are
+    /// ignored.
+    pub fn from_colors(width: usize, height: usize, colors: &[Color]) -> Sprite {
+        let mut sprite = Sprite::new(width, height);
+        for (pixel, color) in sprite.pixels.iter_mut().zip(colors) {
+            *pixel = (*color, 255);
+        }
+
+        sprite
+    }
+
+    /// Returns the width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the color and alpha value of the pixel at the specified
+    /// coordinates, or `None` if the coordinates are out of bounds.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<(Color, u8)> {
+        self.index(x, y).map(|idx| self.pixels[idx])
+    }
+
+    /// Sets the pixel at the specified coordinates to a fully opaque color.
+    ///
+    /// Coordinates that are out of bounds are ignored.
+    pub fn set_pixel<C: Into<Color>>(&mut self, x: usize, y: usize, color: C) {
+        self.set_pixel_alpha(x, y, color, 255);
+    }
+
+    /// Sets the color and alpha value of the pixel at the specified
+    /// coordinates.
+    ///
+    /// `alpha` is specified as a value between `0` (fully transparent) and
+    /// `255` (fully opaque). Coordinates that are out of bounds are ignored.
+    pub fn set_pixel_alpha<C: Into<Color>>(&mut self, x: usize, y: usize, color: C, alpha: u8) {
+        if let Some(idx) = self.index(x, y) {
+            self.pixels[idx] = (color.into(), alpha);
+        }
+    }
+
+    /// Returns the transparent color, or `None` if no transparent color is
+    /// set.
+    pub fn transparent_color(&self) -> Option<Color> {
+        self.transparent_color
+    }
+
+    /// Sets the transparent color. Pixels of this color aren't drawn.
+    ///
+    /// By default, this is set to `None`.
+    pub fn set_transparent_color(&mut self, transparent_color: Option<Color>) {
+        self.transparent_color = transparent_color;
+    }
+
+    // Returns the color and opacity of the pixel at the specified
+    // coordinates, or None if the pixel shouldn't be drawn.
+    pub(crate) fn visible_pixel(&self, x: usize, y: usize) -> Option<(Color, f32)> {
+        match self.pixel(x, y) {
+            Some((_, 0)) | None => None,
+            Some((color, _)) if Some(color) == self.transparent_color => None,
+            Some((color, alpha)) => Some((color, f32::from(alpha) / 255.0)),
+        }
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+}
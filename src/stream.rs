@@ -0,0 +1,320 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Displaying raw frames written by other programs.
+//!
+//! A [`RawReader`] reads frames from any byte stream, and sends them to the
+//! pixels. Each frame contains 8-bit red, green and blue values for every
+//! pixel, so a frame for a `Blinkt` with `n` pixels is exactly `n * 3` bytes
+//! long. Because there's no framing, any program that can write bytes to a
+//! file can drive the pixels, whether it's written in Python, a shell script
+//! or `ffmpeg`.
+//!
+//! [`RawReader::open_fifo`] reads frames from a named pipe (FIFO), which is
+//! created if it doesn't exist yet. Writers can open and close the FIFO as
+//! often as they like, as long as they write complete frames.
+//! [`RawReader::stdin`] reads frames from the standard input, so a renderer
+//! can be piped straight into a program that owns the pixels, such as
+//! `some-renderer | blinkt-ctl stream`.
+//!
+//! A [`RawWriter`] works the other way around. It implements `io::Write`,
+//! and shows every complete frame written to it, so anything that writes to
+//! an `io::Write`, such as a decoder or `io::copy()` from a network stream,
+//! can drive the pixels directly.
+//!
+//! ```rust,no_run
+//! use blinkt::stream::RawReader;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+//! let mut reader = RawReader::open_fifo("/tmp/blinkt.fifo")?.with_fps(30.0);
+//!
+//! reader.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ```text
+//! ffmpeg -re -i input.mp4 -vf scale=144:1 -f rawvideo -pix_fmt rgb24 - > /tmp/blinkt.fifo
+//! ```
+//!
+//! [`RawReader`]: struct.RawReader.html
+//! [`RawReader::open_fifo`]: struct.RawReader.html#method.open_fifo
+//! [`RawReader::stdin`]: struct.RawReader.html#method.stdin
+//! [`RawWriter`]: struct.RawWriter.html
+
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Stdin, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Blinkt, Error, Result};
+
+/// Reads raw RGB frames from a byte stream, and sends them to the pixels.
+#[derive(Debug)]
+pub struct RawReader<R> {
+    reader: R,
+    data: Vec<u8>,
+    frame_interval: Option<Duration>,
+    next_frame: Option<Instant>,
+}
+
+impl<R: Read> RawReader<R> {
+    /// Constructs a new `RawReader` that reads frames from `reader`.
+    ///
+    /// By default, every frame is sent to the pixels as soon as it has been
+    /// read.
+    pub fn new(reader: R) -> RawReader<R> {
+        RawReader {
+            reader,
+            data: Vec::new(),
+            frame_interval: None,
+            next_frame: None,
+        }
+    }
+
+    /// Limits the frame rate to `fps` frames per second.
+    ///
+    /// Frames that arrive early are held back until they're due, which in
+    /// turn slows down the writer once the pipe's buffer is full, so
+    /// pre-rendered frames play at the intended speed. The frame rate is
+    /// limited to a range of 0.1 to 1000 frames per second.
+    pub fn with_fps(mut self, fps: f32) -> RawReader<R> {
+        let fps = fps.max(0.1).min(1000.0);
+        self.frame_interval = Some(Duration::from_micros((1_000_000.0 / fps) as u64));
+        self
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consumes the `RawReader`, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads frames and sends them to the pixels until the end of the
+    /// stream is reached, or an error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        while self.receive(blinkt)? {}
+
+        Ok(())
+    }
+
+    /// Reads a single frame, waits until it's due, and sends it to the
+    /// pixels.
+    ///
+    /// Returns `Ok(false)` once the end of the stream is reached. An
+    /// incomplete frame at the end of the stream is discarded. Without any
+    /// pixels, frames are empty, so this returns `Ok(false)` immediately.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        self.data.resize(blinkt.num_pixels() * 3, 0);
+        // An empty read never reaches the end of the stream.
+        if self.data.is_empty() {
+            return Ok(false);
+        }
+
+        if let Err(err) = self.reader.read_exact(&mut self.data) {
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                return Ok(false);
+            }
+
+            return Err(err.into());
+        }
+
+        if let Some(frame_interval) = self.frame_interval {
+            let now = Instant::now();
+            let due = match self.next_frame {
+                // Don't try to catch up after the writer fell behind.
+                Some(due) if due > now => {
+                    thread::sleep(due - now);
+                    due
+                }
+                _ => now,
+            };
+
+            self.next_frame = Some(due + frame_interval);
+        }
+
+        blinkt.show_rgb(&self.data)?;
+
+        Ok(true)
+    }
+}
+
+impl RawReader<Stdin> {
+    /// Constructs a new `RawReader` that reads frames from the standard
+    /// input.
+    ///
+    /// [`run`] returns once the standard input is closed.
+    ///
+    /// [`run`]: #method.run
+    pub fn stdin() -> RawReader<Stdin> {
+        RawReader::new(io::stdin())
+    }
+}
+
+impl RawReader<File> {
+    /// Constructs a new `RawReader` that reads frames from the named pipe
+    /// (FIFO) at `path`, creating it if it doesn't exist.
+    ///
+    /// The FIFO is opened for both reading and writing, so the reader
+    /// doesn't see the end of the stream when a writer closes it, and
+    /// simply waits for the next writer instead. As a result, [`run`] only
+    /// returns when an error occurs.
+    ///
+    /// Returns an error if `path` exists but isn't a FIFO.
+    ///
+    /// [`run`]: #method.run
+    pub fn open_fifo<P: AsRef<Path>>(path: P) -> Result<RawReader<File>> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+                Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "path contains a null byte",
+                ))
+            })?;
+
+            if unsafe { libc::mkfifo(c_path.as_ptr(), 0o666) } != 0 {
+                let err = io::Error::last_os_error();
+                // Another process may have created it in the meantime.
+                if err.kind() != io::ErrorKind::AlreadyExists {
+                    return Err(err.into());
+                }
+            }
+        }
+
+        if !is_fifo(path)? {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "path isn't a FIFO",
+            )));
+        }
+
+        Ok(RawReader::new(
+            OpenOptions::new().read(true).write(true).open(path)?,
+        ))
+    }
+}
+
+/// An `io::Write` adapter that sends every `num_pixels * 3` bytes written
+/// to it to the pixels as a single frame.
+///
+/// Bytes that don't complete a frame yet are kept until the rest of the
+/// frame is written. Each call to `write` consumes at most the remainder of
+/// the current frame, so when sending a frame to the pixels fails, the error
+/// is returned by the `write` call that completed the frame, and the frame
+/// is discarded.
+///
+/// ```rust,no_run
+/// use std::io;
+/// use std::net::TcpListener;
+///
+/// use blinkt::stream::RawWriter;
+/// use blinkt::Blinkt;
+///
+/// # fn main() -> blinkt::Result<()> {
+/// let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+/// let (mut stream, _) = TcpListener::bind("0.0.0.0:7001")?.accept()?;
+///
+/// io::copy(&mut stream, &mut RawWriter::new(&mut blinkt))?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RawWriter<'a> {
+    blinkt: &'a mut Blinkt,
+    data: Vec<u8>,
+}
+
+impl<'a> RawWriter<'a> {
+    /// Constructs a new `RawWriter` that sends the frames written to it to
+    /// `blinkt`.
+    pub fn new(blinkt: &'a mut Blinkt) -> RawWriter<'a> {
+        RawWriter {
+            blinkt,
+            data: Vec::new(),
+        }
+    }
+
+    /// Returns a reference to the underlying `Blinkt`.
+    pub fn get_ref(&self) -> &Blinkt {
+        self.blinkt
+    }
+
+    /// Returns a mutable reference to the underlying `Blinkt`.
+    pub fn get_mut(&mut self) -> &mut Blinkt {
+        self.blinkt
+    }
+
+    /// Returns the number of bytes of the current, incomplete frame that
+    /// have been written so far.
+    pub fn pending(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl<'a> Write for RawWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let frame_len = self.blinkt.num_pixels() * 3;
+        if frame_len == 0 {
+            return Ok(buf.len());
+        }
+
+        // The number of pixels may have changed since the previous write.
+        if self.data.len() >= frame_len {
+            self.data.clear();
+        }
+
+        let len = buf.len().min(frame_len - self.data.len());
+        self.data.extend_from_slice(&buf[..len]);
+
+        if self.data.len() == frame_len {
+            let result = self.blinkt.show_rgb(&self.data);
+            self.data.clear();
+
+            if let Err(err) = result {
+                return Err(match err {
+                    Error::Io(err) => err,
+                    err => io::Error::other(err),
+                });
+            }
+        }
+
+        Ok(len)
+    }
+
+    /// Does nothing, because incomplete frames can't be shown.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn is_fifo(path: &Path) -> io::Result<bool> {
+    Ok(path.metadata()?.file_type().is_fifo())
+}
@@ -0,0 +1,110 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::{Result, SerialOutput};
+
+/// A [`SerialOutput`] that forwards every write to several outputs.
+///
+/// This allows the same frames to be sent to multiple backends at once, for
+/// instance a [`Capture`] to inspect the serialized bytes, while the pixels
+/// are still driven by the hardware. Use [`Blinkt::add_output`] to add an
+/// output next to the GPIO or SPI peripheral.
+///
+/// Outputs receive the data in the order they were added. A failing output
+/// doesn't prevent the remaining outputs from receiving the data, but the
+/// first error is returned.
+///
+/// ```rust
+/// use blinkt::capture::Capture;
+/// use blinkt::{Blinkt, TeeOutput};
+///
+/// # fn main() -> blinkt::Result<()> {
+/// let first = Capture::new();
+/// let second = Capture::new();
+/// let output = TeeOutput::new()
+///     .with_output(first.clone())
+///     .with_output(second.clone());
+///
+/// let mut blinkt = Blinkt::with_output(output, 1);
+/// blinkt.set_pixel(0, 255, 0, 0);
+/// blinkt.show()?;
+///
+/// assert_eq!(first.take(), second.take());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`SerialOutput`]: trait.SerialOutput.html
+/// [`Capture`]: capture/struct.Capture.html
+/// [`Blinkt::add_output`]: struct.Blinkt.html#method.add_output
+#[derive(Default)]
+pub struct TeeOutput {
+    outputs: Vec<Box<dyn SerialOutput + Send>>,
+}
+
+impl TeeOutput {
+    /// Constructs a new `TeeOutput` without any outputs.
+    pub fn new() -> TeeOutput {
+        TeeOutput::default()
+    }
+
+    /// Adds `output` to the list of outputs.
+    pub fn with_output<T>(mut self, output: T) -> TeeOutput
+    where
+        T: SerialOutput + Send + 'static,
+    {
+        self.push(output);
+        self
+    }
+
+    /// Adds `output` to the list of outputs.
+    pub fn push<T>(&mut self, output: T)
+    where
+        T: SerialOutput + Send + 'static,
+    {
+        self.outputs.push(Box::new(output));
+    }
+
+    /// Returns the number of outputs.
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Returns `true` if no outputs have been added.
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+}
+
+impl SerialOutput for TeeOutput {
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        let mut result = Ok(());
+
+        for output in &mut self.outputs {
+            if let Err(err) = output.write(data) {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+
+        result
+    }
+}
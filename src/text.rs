@@ -0,0 +1,389 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Text rendering with a built-in 5x7 pixel font.
+//!
+//! [`draw_text`] renders ASCII strings onto a [`Matrix`]. Each character is
+//! 5 pixels wide and 7 pixels high, followed by a single column of spacing.
+//! Characters outside the printable ASCII range are drawn as `?`.
+//!
+//! [`Marquee`] scrolls text across a matrix, or across a 1-pixel-high strip.
+//! Because a strip can't show the full height of a character, each pixel
+//! displays a single vertical slice (column) of the text, with its
+//! intensity based on the number of lit dots in that column.
+//!
+//! ```rust,no_run
+//! # use std::error::Error;
+//! # use std::thread;
+//! # use std::time::Duration;
+//! #
+//! # use blinkt::text::Marquee;
+//! # use blinkt::{Blinkt, Color, Layout, Matrix};
+//! #
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let blinkt = Blinkt::with_spi(16_000_000, 32 * 8)?;
+//! let mut matrix = Matrix::new(blinkt, 32, 8, Layout::Serpentine);
+//!
+//! let mut marquee = Marquee::new("Hello, world!");
+//! marquee.set_speed(12.0);
+//! marquee.set_color(Color::new(255, 128, 0));
+//!
+//! loop {
+//!     marquee.render_matrix(&mut matrix, 0);
+//!     matrix.show()?;
+//!
+//!     thread::sleep(Duration::from_millis(20));
+//!     marquee.advance(Duration::from_millis(20));
+//! }
+//! # }
+//! ```
+//!
+//! [`draw_text`]: fn.draw_text.html
+//! [`Marquee`]: struct.Marquee.html
+//! [`Matrix`]: ../struct.Matrix.html
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::animation::as_secs_f32;
+use crate::color::Color;
+use crate::frame::Frame;
+use crate::matrix::Matrix;
+
+/// The width of a character in pixels, excluding spacing.
+pub const CHAR_WIDTH: usize = 5;
+/// The height of a character in pixels.
+pub const CHAR_HEIGHT: usize = 7;
+/// The horizontal distance between the start of two consecutive characters.
+pub const CHAR_ADVANCE: usize = CHAR_WIDTH + 1;
+
+// Printable ASCII characters (0x20 - 0x7E). Each character is stored as 5
+// columns, where bit 0 is the top row.
+const FONT: [[u8; CHAR_WIDTH]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // '!'
+    [0x00, 0x07, 0x00, 0x07, 0x00], // '"'
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // '#'
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // '$'
+    [0x23, 0x13, 0x08, 0x64, 0x62], // '%'
+    [0x36, 0x49, 0x55, 0x22, 0x50], // '&'
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '''
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // '('
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // ')'
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // '*'
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // '+'
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ','
+    [0x08, 0x08, 0x08, 0x08, 0x08], // '-'
+    [0x00, 0x60, 0x60, 0x00, 0x00], // '.'
+    [0x20, 0x10, 0x08, 0x04, 0x02], // '/'
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // '0'
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // '1'
+    [0x42, 0x61, 0x51, 0x49, 0x46], // '2'
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // '3'
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // '4'
+    [0x27, 0x45, 0x45, 0x45, 0x39], // '5'
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // '6'
+    [0x01, 0x71, 0x09, 0x05, 0x03], // '7'
+    [0x36, 0x49, 0x49, 0x49, 0x36], // '8'
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // '9'
+    [0x00, 0x36, 0x36, 0x00, 0x00], // ':'
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ';'
+    [0x08, 0x14, 0x22, 0x41, 0x00], // '<'
+    [0x14, 0x14, 0x14, 0x14, 0x14], // '='
+    [0x00, 0x41, 0x22, 0x14, 0x08], // '>'
+    [0x02, 0x01, 0x51, 0x09, 0x06], // '?'
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // '@'
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // 'A'
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // 'B'
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // 'C'
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // 'D'
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // 'E'
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // 'F'
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // 'G'
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // 'H'
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // 'I'
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // 'J'
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // 'K'
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // 'L'
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // 'M'
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // 'N'
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // 'O'
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // 'P'
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // 'Q'
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // 'R'
+    [0x46, 0x49, 0x49, 0x49, 0x31], // 'S'
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // 'T'
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // 'U'
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // 'V'
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // 'W'
+    [0x63, 0x14, 0x08, 0x14, 0x63], // 'X'
+    [0x07, 0x08, 0x70, 0x08, 0x07], // 'Y'
+    [0x61, 0x51, 0x49, 0x45, 0x43], // 'Z'
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // '['
+    [0x02, 0x04, 0x08, 0x10, 0x20], // '\'
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ']'
+    [0x04, 0x02, 0x01, 0x02, 0x04], // '^'
+    [0x40, 0x40, 0x40, 0x40, 0x40], // '_'
+    [0x00, 0x01, 0x02, 0x04, 0x00], // '`'
+    [0x20, 0x54, 0x54, 0x54, 0x78], // 'a'
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // 'b'
+    [0x38, 0x44, 0x44, 0x44, 0x20], // 'c'
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // 'd'
+    [0x38, 0x54, 0x54, 0x54, 0x18], // 'e'
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // 'f'
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // 'g'
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // 'h'
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // 'i'
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // 'j'
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // 'k'
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // 'l'
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // 'm'
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // 'n'
+    [0x38, 0x44, 0x44, 0x44, 0x38], // 'o'
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // 'p'
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // 'q'
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // 'r'
+    [0x48, 0x54, 0x54, 0x54, 0x20], // 's'
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // 't'
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // 'u'
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // 'v'
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // 'w'
+    [0x44, 0x28, 0x10, 0x28, 0x44], // 'x'
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // 'y'
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // 'z'
+    [0x00, 0x08, 0x36, 0x41, 0x00], // '{'
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // '|'
+    [0x00, 0x41, 0x36, 0x08, 0x00], // '}'
+    [0x08, 0x04, 0x08, 0x10, 0x08], // '~'
+];
+
+/// Returns the 5x7 glyph for `c` as 5 columns, where bit 0 of each column
+/// is the top row.
+///
+/// Characters outside the printable ASCII range return the glyph for `?`.
+pub fn glyph(c: char) -> [u8; CHAR_WIDTH] {
+    match c {
+        ' '..='~' => FONT[c as usize - 0x20],
+        _ => FONT[b'?' as usize - 0x20],
+    }
+}
+
+/// Returns the width of `text` in pixels, excluding the spacing after the
+/// last character.
+pub fn text_width(text: &str) -> usize {
+    (text.chars().count() * CHAR_ADVANCE).saturating_sub(1)
+}
+
+/// Draws `text` onto `matrix`, with the top left corner of the first
+/// character located at `x`, `y`.
+///
+/// `color` is called with the index and value of each character, and
+/// returns the color used to draw it. Only the lit dots are drawn, all other
+/// pixels are left unchanged. Dots that fall outside the matrix are ignored,
+/// so `x` and `y` can be negative to draw partially visible text.
+pub fn draw_text<F>(matrix: &mut Matrix, x: isize, y: isize, text: &str, mut color: F)
+where
+    F: FnMut(usize, char) -> Color,
+{
+    for (idx, c) in text.chars().enumerate() {
+        let left = x + (idx * CHAR_ADVANCE) as isize;
+        if left >= matrix.width() as isize {
+            break;
+        } else if left + (CHAR_WIDTH as isize) <= 0 {
+            continue;
+        }
+
+        let char_color = color(idx, c);
+        for (column, bits) in glyph(c).iter().enumerate() {
+            for row in 0..CHAR_HEIGHT {
+                let (px, py) = (left + column as isize, y + row as isize);
+                if bits & (1 << row) != 0 && px >= 0 && py >= 0 {
+                    matrix.set_xy(px as usize, py as usize, char_color);
+                }
+            }
+        }
+    }
+}
+
+/// Text that scrolls from right to left across a matrix or strip.
+///
+/// The text enters on the right edge, and starts over once it has completely
+/// left the display on the left edge.
+pub struct Marquee {
+    text: String,
+    speed: f32,
+    position: f32,
+    color: Box<dyn FnMut(usize, char) -> Color + Send>,
+}
+
+impl Marquee {
+    /// Constructs a new `Marquee` for `text`.
+    ///
+    /// By default, the text is white, and scrolls at 10 pixels per second.
+    pub fn new(text: &str) -> Marquee {
+        Marquee {
+            text: text.to_owned(),
+            speed: 10.0,
+            position: 0.0,
+            color: Box::new(|_, _| Color::new(255, 255, 255)),
+        }
+    }
+
+    /// Returns the text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, and restarts scrolling.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_owned();
+        self.position = 0.0;
+    }
+
+    /// Returns the scroll speed in pixels per second.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Sets the scroll speed in pixels per second.
+    ///
+    /// Negative values are treated as `0.0`.
+    pub fn set_speed(&mut self, pixels_per_second: f32) {
+        self.speed = pixels_per_second.max(0.0);
+    }
+
+    /// Sets the color for all characters.
+    pub fn set_color<C: Into<Color>>(&mut self, color: C) {
+        let color = color.into();
+        self.color = Box::new(move |_, _| color);
+    }
+
+    /// Sets a callback that returns the color for each character, based on
+    /// its index and value.
+    pub fn set_color_fn<F>(&mut self, color: F)
+    where
+        F: FnMut(usize, char) -> Color + Send + 'static,
+    {
+        self.color = Box::new(color);
+    }
+
+    /// Returns the number of pixels the text has scrolled since it was
+    /// started.
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Restarts scrolling from the right edge.
+    pub fn reset(&mut self) {
+        self.position = 0.0;
+    }
+
+    /// Scrolls the text based on the configured speed and the amount of time
+    /// that has passed.
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.position += self.speed * as_secs_f32(elapsed);
+    }
+
+    /// Clears `matrix`, and draws the visible part of the text with its top
+    /// edge located at row `y`.
+    pub fn render_matrix(&mut self, matrix: &mut Matrix, y: isize) {
+        let x = self.left_edge(matrix.width());
+
+        matrix.clear();
+        draw_text(matrix, x, y, &self.text, &mut self.color);
+    }
+
+    /// Renders the visible part of the text to `frame`, which represents a
+    /// 1-pixel-high strip.
+    ///
+    /// Each pixel displays a single column of the text, scaled by the
+    /// number of lit dots in that column. Pixels between characters are set
+    /// to black.
+    pub fn render_strip(&mut self, frame: &mut Frame) {
+        let x = self.left_edge(frame.len());
+        let mut chars = self.text.chars().enumerate();
+        let mut current: Option<(usize, [u8; CHAR_WIDTH], Color)> = None;
+
+        for idx in 0..frame.len() {
+            let column = idx as isize - x;
+            let dots = if column < 0 {
+                0
+            } else {
+                let (char_idx, column) = (
+                    column as usize / CHAR_ADVANCE,
+                    column as usize % CHAR_ADVANCE,
+                );
+
+                // Characters are visited in order, so we only need to look
+                // up the glyph and color once per character.
+                loop {
+                    match current {
+                        Some((current_idx, _, _)) if current_idx >= char_idx => break,
+                        _ => (),
+                    }
+
+                    match chars.next() {
+                        Some((next_idx, c)) => {
+                            current = Some((next_idx, glyph(c), (self.color)(next_idx, c)))
+                        }
+                        None => break,
+                    }
+                }
+
+                match current {
+                    Some((current_idx, bits, _))
+                        if current_idx == char_idx && column < CHAR_WIDTH =>
+                    {
+                        bits[column].count_ones()
+                    }
+                    _ => 0,
+                }
+            };
+
+            if dots == 0 {
+                frame.set_pixel(idx, 0, 0, 0);
+            } else if let Some((_, _, color)) = current {
+                let scale = |value: u8| (u32::from(value) * dots / CHAR_HEIGHT as u32) as u8;
+                frame.set_pixel(idx, scale(color.red), scale(color.green), scale(color.blue));
+            }
+        }
+    }
+
+    // Returns the x coordinate of the first character for a display of the
+    // specified width.
+    fn left_edge(&self, width: usize) -> isize {
+        let period = (text_width(&self.text) + width) as f32;
+        if period < 1.0 {
+            return 0;
+        }
+
+        width as isize - (self.position % period) as isize
+    }
+}
+
+impl fmt::Debug for Marquee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Marquee")
+            .field("text", &self.text)
+            .field("speed", &self.speed)
+            .field("position", &self.position)
+            .finish()
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Receiving frames through the TPM2 and TPM2.net protocols.
+//!
+//! TPM2 is spoken by PC-based matrix software such as Jinx!. A
+//! [`Tpm2Reader`] reads TPM2 frames from a serial connection, or any other
+//! byte stream, and a [`Tpm2NetReceiver`] receives TPM2.net packets over
+//! UDP. The data of each frame is interpreted as 8-bit red, green and blue
+//! values, starting at the first pixel. Command packets are ignored.
+//!
+//! Serial ports are opened as regular files. The baud rate and other port
+//! settings need to be configured separately, for instance with
+//! `stty -F /dev/ttyACM0 115200 raw`.
+//!
+//! This module requires the `tpm2` feature.
+//!
+//! ```rust,no_run
+//! use std::fs::File;
+//!
+//! use blinkt::tpm2::Tpm2Reader;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 144)?;
+//! let mut reader = Tpm2Reader::new(File::open("/dev/ttyACM0")?);
+//!
+//! reader.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Tpm2Reader`]: struct.Tpm2Reader.html
+//! [`Tpm2NetReceiver`]: struct.Tpm2NetReceiver.html
+
+use std::io::{self, Read};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::{Blinkt, Result};
+
+/// The UDP port used by TPM2.net.
+pub const PORT: u16 = 65506;
+
+const SERIAL_START: u8 = 0xc9;
+const NET_START: u8 = 0x9c;
+const END: u8 = 0x36;
+const TYPE_DATA: u8 = 0xda;
+// Maximum TPM2.net packet size: a 6-byte header, 1490 bytes of data and
+// the end byte.
+const MAX_PACKET_LEN: usize = 1497;
+
+/// Reads TPM2 frames from a serial connection or any other byte stream.
+#[derive(Debug)]
+pub struct Tpm2Reader<R> {
+    reader: R,
+    data: Vec<u8>,
+}
+
+impl<R: Read> Tpm2Reader<R> {
+    /// Constructs a new `Tpm2Reader` that reads frames from `reader`.
+    pub fn new(reader: R) -> Tpm2Reader<R> {
+        Tpm2Reader {
+            reader,
+            data: Vec::new(),
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consumes the `Tpm2Reader`, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads and processes frames until the end of the stream is reached,
+    /// or an error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        while self.receive(blinkt)?.is_some() {}
+
+        Ok(())
+    }
+
+    /// Reads a single frame, and processes it.
+    ///
+    /// Any bytes preceding the start of the frame are skipped. Returns
+    /// `Ok(Some(true))` if the frame was sent to the pixels, `Ok(Some(false))`
+    /// if the frame was ignored, or `Ok(None)` once the end of the stream is
+    /// reached.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<Option<bool>> {
+        let mut byte = [0u8; 1];
+        loop {
+            if self.reader.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+
+            if byte[0] == SERIAL_START {
+                break;
+            }
+        }
+
+        let mut header = [0u8; 3];
+        let mut end = [0u8; 1];
+        if let Err(err) = self.reader.read_exact(&mut header) {
+            return end_of_stream(err);
+        }
+
+        let len = (usize::from(header[1]) << 8) | usize::from(header[2]);
+        self.data.resize(len, 0);
+        if let Err(err) = self
+            .reader
+            .read_exact(&mut self.data)
+            .and_then(|_| self.reader.read_exact(&mut end))
+        {
+            return end_of_stream(err);
+        }
+
+        if header[0] != TYPE_DATA || end[0] != END {
+            return Ok(Some(false));
+        }
+
+        blinkt.show_rgb(&self.data)?;
+
+        Ok(Some(true))
+    }
+}
+
+/// Receives TPM2.net packets over UDP.
+///
+/// Frames that don't fit in a single packet are split into multiple
+/// packets. All packets of a frame, except for the last one, are expected to
+/// contain the same number of bytes, which is how senders such as Jinx!
+/// split frames. The frame is sent to the pixels once its last packet
+/// arrives.
+#[derive(Debug)]
+pub struct Tpm2NetReceiver {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+}
+
+impl Tpm2NetReceiver {
+    /// Constructs a new `Tpm2NetReceiver` listening on the standard
+    /// TPM2.net port on all network interfaces.
+    pub fn new() -> Result<Tpm2NetReceiver> {
+        Tpm2NetReceiver::bind(("0.0.0.0", PORT))
+    }
+
+    /// Constructs a new `Tpm2NetReceiver` listening on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Tpm2NetReceiver> {
+        Ok(Tpm2NetReceiver {
+            socket: UdpSocket::bind(addr)?,
+            buffer: vec![0u8; MAX_PACKET_LEN],
+        })
+    }
+
+    /// Returns the address the receiver is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Receives and processes packets until an error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Waits for a single packet, and processes it.
+    ///
+    /// Returns `Ok(true)` if a frame was sent to the pixels. Packets that
+    /// aren't valid TPM2.net data packets are ignored.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        let (len, _) = self.socket.recv_from(&mut self.buffer)?;
+        let packet = &self.buffer[..len];
+
+        if len < 7 || packet[0] != NET_START || packet[1] != TYPE_DATA {
+            return Ok(false);
+        }
+
+        let data_len = (usize::from(packet[2]) << 8) | usize::from(packet[3]);
+        if 6 + data_len >= len || packet[6 + data_len] != END {
+            return Ok(false);
+        }
+
+        // Packet numbers start at 1.
+        let packet_number = usize::from(packet[4].max(1));
+        let num_packets = usize::from(packet[5].max(1));
+        let offset = (packet_number - 1) * data_len / 3;
+
+        blinkt.set_pixels_rgb(offset, &packet[6..6 + data_len]);
+
+        if packet_number < num_packets {
+            return Ok(false);
+        }
+
+        blinkt.swap();
+        blinkt.show()?;
+
+        Ok(true)
+    }
+}
+
+// Treats a stream that ends in the middle of a frame the same as a stream
+// that ends between frames.
+fn end_of_stream(err: io::Error) -> Result<Option<bool>> {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        Ok(None)
+    } else {
+        Err(err.into())
+    }
+}
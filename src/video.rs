@@ -0,0 +1,246 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Playing videos and animated images through `ffmpeg`.
+//!
+//! [`Video::spawn`] starts `ffmpeg` to decode any video or animated image
+//! it supports, scaled to the dimensions of a strip or [`Matrix`], and plays
+//! it in real time. [`Video::new`] plays a stream that's already been
+//! decoded to `rawvideo` with the `rgb24` pixel format, for instance by an
+//! `ffmpeg` process started elsewhere.
+//!
+//! Frames are scaled to fill the entire area while preserving the aspect
+//! ratio, and any parts that extend beyond the edges are cropped, the same
+//! way [`Matrix::draw_image`] resizes images. `ffmpeg` needs to be installed
+//! separately, for instance with `sudo apt install ffmpeg`.
+//!
+//! ```rust,no_run
+//! use blinkt::video::Video;
+//! use blinkt::{Blinkt, Layout, Matrix};
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let blinkt = Blinkt::with_spi(16_000_000, 16 * 16)?;
+//! let mut matrix = Matrix::new(blinkt, 16, 16, Layout::Serpentine);
+//!
+//! Video::spawn("fireworks.gif", 16, 16)?.play_matrix(&mut matrix)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A stream decoded elsewhere:
+//!
+//! ```text
+//! ffmpeg -re -i input.mp4 -vf scale=16:16 -f rawvideo -pix_fmt rgb24 - | my-player
+//! ```
+//!
+//! [`Video::spawn`]: struct.Video.html#method.spawn
+//! [`Video::new`]: struct.Video.html#method.new
+//! [`Matrix`]: ../struct.Matrix.html
+//! [`Matrix::draw_image`]: ../struct.Matrix.html#method.draw_image
+
+use std::ffi::OsStr;
+use std::io::{self, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+use crate::{Blinkt, Error, Matrix, Result};
+
+/// Plays raw RGB video frames on a strip or matrix.
+#[derive(Debug)]
+pub struct Video<R> {
+    reader: R,
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+    child: Option<Child>,
+}
+
+impl Video<ChildStdout> {
+    /// Starts `ffmpeg` to decode `input`, scaled to `width` by `height`
+    /// pixels, and plays it once, at its original speed.
+    ///
+    /// `input` can be anything `ffmpeg` accepts, such as a file name or a
+    /// URL. For a strip, use the number of pixels as the `width`, and `1` as
+    /// the `height`.
+    ///
+    /// `ffmpeg` is stopped when the `Video` is dropped.
+    pub fn spawn<S: AsRef<OsStr>>(
+        input: S,
+        width: usize,
+        height: usize,
+    ) -> Result<Video<ChildStdout>> {
+        Video::spawn_ffmpeg(input.as_ref(), width, height, false)
+    }
+
+    /// Starts `ffmpeg` to decode `input`, scaled to `width` by `height`
+    /// pixels, and plays it in a loop, at its original speed.
+    ///
+    /// `input` needs to be a file, because `ffmpeg` can only loop inputs it
+    /// can seek in.
+    pub fn spawn_looping<S: AsRef<OsStr>>(
+        input: S,
+        width: usize,
+        height: usize,
+    ) -> Result<Video<ChildStdout>> {
+        Video::spawn_ffmpeg(input.as_ref(), width, height, true)
+    }
+
+    fn spawn_ffmpeg(
+        input: &OsStr,
+        width: usize,
+        height: usize,
+        looping: bool,
+    ) -> Result<Video<ChildStdout>> {
+        if width == 0 || height == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid video dimensions",
+            )));
+        }
+
+        let mut command = Command::new("ffmpeg");
+        command.args(["-nostdin", "-loglevel", "error"].iter());
+        if looping {
+            command.args(["-stream_loop", "-1"].iter());
+        }
+
+        let filter = format!(
+            "scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h}",
+            w = width,
+            h = height
+        );
+
+        let mut child = command
+            .arg("-re")
+            .arg("-i")
+            .arg(input)
+            .args(["-vf", &filter, "-f", "rawvideo", "-pix_fmt", "rgb24", "-"].iter())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdout = match child.stdout.take() {
+            Some(stdout) => stdout,
+            None => {
+                let _ = child.kill();
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "ffmpeg's output isn't available",
+                )));
+            }
+        };
+
+        let mut video = Video::new(stdout, width, height);
+        video.child = Some(child);
+
+        Ok(video)
+    }
+}
+
+impl<R: Read> Video<R> {
+    /// Constructs a new `Video` that reads `width` by `height` pixel frames,
+    /// in the `rawvideo` format with the `rgb24` pixel format, from
+    /// `reader`.
+    ///
+    /// Frames are played as fast as they can be read.
+    pub fn new(reader: R, width: usize, height: usize) -> Video<R> {
+        Video {
+            reader,
+            width,
+            height,
+            data: vec![0u8; width * height * 3],
+            child: None,
+        }
+    }
+
+    /// Returns the width of the frames in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the frames in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Plays the video on a strip until it ends, or an error occurs.
+    ///
+    /// The frames are sent to the pixels in row-major order, starting at
+    /// the top left pixel.
+    pub fn play(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        while self.receive(blinkt)? {}
+
+        Ok(())
+    }
+
+    /// Plays the video on a matrix until it ends, or an error occurs.
+    pub fn play_matrix(&mut self, matrix: &mut Matrix) -> Result<()> {
+        while self.receive_matrix(matrix)? {}
+
+        Ok(())
+    }
+
+    /// Reads a single frame, and sends it to the pixels of a strip.
+    ///
+    /// Returns `Ok(false)` once the video has ended.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        if !self.read_frame()? {
+            return Ok(false);
+        }
+
+        blinkt.show_rgb(&self.data)?;
+
+        Ok(true)
+    }
+
+    /// Reads a single frame, and sends it to the pixels of a matrix.
+    ///
+    /// Parts of the frame that extend beyond the edges of the matrix are
+    /// cropped. Returns `Ok(false)` once the video has ended.
+    pub fn receive_matrix(&mut self, matrix: &mut Matrix) -> Result<bool> {
+        if !self.read_frame()? {
+            return Ok(false);
+        }
+
+        matrix.show_rgb(self.width, &self.data)?;
+
+        Ok(true)
+    }
+
+    fn read_frame(&mut self) -> Result<bool> {
+        if self.data.is_empty() {
+            return Ok(false);
+        }
+
+        match self.reader.read_exact(&mut self.data) {
+            Ok(()) => Ok(true),
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl<R> Drop for Video<R> {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
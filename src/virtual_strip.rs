@@ -0,0 +1,185 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::ops::Range;
+
+use crate::color::Color;
+use crate::Blinkt;
+
+/// A logical strip that maps its pixels to arbitrary physical pixels.
+///
+/// A `VirtualStrip` decouples the addressing order used by your application
+/// from the order in which the pixels are wired. Logical pixels are added in
+/// order through runs of physical pixels, which can be reversed, and gaps,
+/// which don't map to any physical pixel.
+///
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # use blinkt::{Blinkt, VirtualStrip};
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let mut blinkt = Blinkt::with_spi(16_000_000, 60)?;
+///
+/// // Two strips that are wired back-to-back, with the second one running
+/// // in the opposite direction, and a 5-pixel gap in between.
+/// let strip = VirtualStrip::new().run(0..30).gap(5).reversed_run(30..60);
+///
+/// strip.set_pixel(&mut blinkt, 0, 255, 0, 0);
+/// strip.set_pixel(&mut blinkt, strip.len() - 1, 0, 0, 255);
+/// blinkt.show()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VirtualStrip {
+    map: Vec<Option<usize>>,
+}
+
+impl VirtualStrip {
+    /// Constructs a new, empty `VirtualStrip`.
+    pub fn new() -> VirtualStrip {
+        VirtualStrip { map: Vec::new() }
+    }
+
+    /// Constructs a new `VirtualStrip` from a list of physical pixel
+    /// indices, where `None` indicates a logical pixel that doesn't map to a
+    /// physical pixel.
+    pub fn from_map(map: Vec<Option<usize>>) -> VirtualStrip {
+        VirtualStrip { map }
+    }
+
+    /// Appends a logical pixel for each physical pixel in `range`, in
+    /// ascending order.
+    pub fn run(mut self, range: Range<usize>) -> VirtualStrip {
+        self.map.extend(range.map(Some));
+        self
+    }
+
+    /// Appends a logical pixel for each physical pixel in `range`, in
+    /// descending order.
+    pub fn reversed_run(mut self, range: Range<usize>) -> VirtualStrip {
+        self.map.extend(range.rev().map(Some));
+        self
+    }
+
+    /// Appends a single logical pixel mapped to the specified physical pixel.
+    pub fn pixel(mut self, pixel: usize) -> VirtualStrip {
+        self.map.push(Some(pixel));
+        self
+    }
+
+    /// Appends `len` logical pixels that don't map to any physical pixel.
+    /// Changes to these pixels are ignored.
+    pub fn gap(mut self, len: usize) -> VirtualStrip {
+        self.map.extend((0..len).map(|_| None));
+        self
+    }
+
+    /// Returns the number of logical pixels.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the strip doesn't contain any logical pixels.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the physical pixel index for the specified logical pixel, or
+    /// `None` if the logical pixel is out of bounds or part of a gap.
+    pub fn physical(&self, pixel: usize) -> Option<usize> {
+        self.map.get(pixel).and_then(|physical| *physical)
+    }
+
+    /// Sets the red, green and blue values for a single logical pixel in the
+    /// local buffer of `blinkt`.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    pub fn set_pixel(&self, blinkt: &mut Blinkt, pixel: usize, red: u8, green: u8, blue: u8) {
+        if let Some(pixel) = self.physical(pixel) {
+            blinkt.set_pixel(pixel, red, green, blue);
+        }
+    }
+
+    /// Sets the red, green, blue and brightness values for a single logical
+    /// pixel in the local buffer of `blinkt`.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_pixel_rgbb(
+        &self,
+        blinkt: &mut Blinkt,
+        pixel: usize,
+        red: u8,
+        green: u8,
+        blue: u8,
+        brightness: f32,
+    ) {
+        if let Some(pixel) = self.physical(pixel) {
+            blinkt.set_pixel_rgbb(pixel, red, green, blue, brightness);
+        }
+    }
+
+    /// Sets the color for a single logical pixel in the local buffer of
+    /// `blinkt`.
+    ///
+    /// Pixels are numbered starting at `0`.
+    pub fn set_pixel_color<C: Into<Color>>(&self, blinkt: &mut Blinkt, pixel: usize, color: C) {
+        if let Some(pixel) = self.physical(pixel) {
+            blinkt.set_pixel_color(pixel, color);
+        }
+    }
+
+    /// Sets the brightness value for a single logical pixel in the local
+    /// buffer of `blinkt`.
+    ///
+    /// Pixels are numbered starting at `0`.
+    /// `brightness` is specified as a floating point value between `0.0` (0%) and `1.0` (100%), and is converted to a 5-bit value.
+    pub fn set_pixel_brightness(&self, blinkt: &mut Blinkt, pixel: usize, brightness: f32) {
+        if let Some(pixel) = self.physical(pixel) {
+            blinkt.set_pixel_brightness(pixel, brightness);
+        }
+    }
+
+    /// Sets the red, green and blue values for all logical pixels in the
+    /// local buffer of `blinkt`.
+    ///
+    /// `red`, `green` and `blue` are specified as 8-bit values between `0` (0%) and `255` (100%).
+    pub fn set_all_pixels(&self, blinkt: &mut Blinkt, red: u8, green: u8, blue: u8) {
+        for pixel in self.map.iter().filter_map(|physical| *physical) {
+            blinkt.set_pixel(pixel, red, green, blue);
+        }
+    }
+
+    /// Sets the color for all logical pixels in the local buffer of `blinkt`.
+    pub fn set_all_pixels_color<C: Into<Color>>(&self, blinkt: &mut Blinkt, color: C) {
+        let color = color.into();
+        self.set_all_pixels(blinkt, color.red, color.green, color.blue);
+    }
+
+    /// Sets the red, green and blue values for all logical pixels in the
+    /// local buffer of `blinkt` to `0`.
+    pub fn clear(&self, blinkt: &mut Blinkt) {
+        self.set_all_pixels(blinkt, 0, 0, 0);
+    }
+}
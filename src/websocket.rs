@@ -0,0 +1,338 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// Live preview of the pixels in a web browser, streamed over a WebSocket.
+
+use std::io::{self, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::event::ShowEvent;
+use crate::http::{Request, Response};
+use crate::Result;
+
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const OPCODE_BINARY: u8 = 0x82;
+// Maximum time to wait for a client to send its request, or to accept the
+// next frame.
+const CLIENT_TIMEOUT_SECS: u64 = 5;
+
+// Web page that connects to the WebSocket, and draws the pixels.
+const PREVIEW_PAGE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Blinkt preview</title>
+<style>
+body { background: #111; margin: 2em; }
+canvas { width: 100%; }
+</style>
+</head>
+<body>
+<canvas id="strip"></canvas>
+<script>
+const canvas = document.getElementById("strip");
+const context = canvas.getContext("2d");
+
+function connect() {
+  const socket = new WebSocket("ws://" + location.host + "/");
+  socket.binaryType = "arraybuffer";
+  socket.onmessage = (message) => {
+    const data = new Uint8Array(message.data);
+    const len = data.length / 4;
+    canvas.width = len * 16;
+    canvas.height = 16;
+    context.fillStyle = "#000";
+    context.fillRect(0, 0, canvas.width, canvas.height);
+    for (let pixel = 0; pixel < len; pixel++) {
+      const [red, green, blue, brightness] = data.slice(pixel * 4, pixel * 4 + 4);
+      context.fillStyle = `rgba(${red}, ${green}, ${blue}, ${brightness / 255})`;
+      context.beginPath();
+      context.arc(pixel * 16 + 8, 8, 6, 0, 2 * Math.PI);
+      context.fill();
+    }
+  };
+  socket.onclose = () => setTimeout(connect, 1000);
+}
+
+connect();
+</script>
+</body>
+</html>
+"##;
+
+#[derive(Debug, Default)]
+struct Latest {
+    frame: Arc<Vec<u8>>,
+    sequence: u64,
+    closed: bool,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    latest: Mutex<Latest>,
+    updated: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Latest> {
+        match self.latest.lock() {
+            Ok(latest) => latest,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+// Streams frames to every connected browser. Each client is served by a
+// separate thread that only sends the most recent frame, so slow clients
+// skip frames instead of delaying the pixels.
+//
+// The server stops accepting connections, and all clients are disconnected,
+// when the `PreviewServer` is dropped.
+#[derive(Debug)]
+pub(crate) struct PreviewServer {
+    local_addr: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+impl PreviewServer {
+    pub(crate) fn spawn<A: ToSocketAddrs>(addr: A) -> Result<PreviewServer> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let shared = Arc::new(Shared::default());
+
+        let accept_shared = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_shared.lock().closed {
+                    break;
+                }
+
+                if let Ok(stream) = stream {
+                    let shared = accept_shared.clone();
+                    thread::spawn(move || {
+                        let _ = serve(&stream, &shared);
+                    });
+                }
+            }
+        });
+
+        Ok(PreviewServer { local_addr, shared })
+    }
+
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    // Makes the frame available to all clients. Each pixel is sent as four
+    // bytes: red, green, blue and the brightness scaled to 0-255.
+    pub(crate) fn publish(&self, event: &ShowEvent<'_>) {
+        let mut frame = Vec::with_capacity(event.pixels().len() * 4);
+        for pixel in event.pixels() {
+            let (red, green, blue, brightness) = pixel.rgbb();
+            frame.extend_from_slice(&[red, green, blue, (brightness * 255.0).round() as u8]);
+        }
+
+        let mut latest = self.shared.lock();
+        latest.frame = Arc::new(frame);
+        latest.sequence += 1;
+        self.shared.updated.notify_all();
+    }
+}
+
+impl Drop for PreviewServer {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.updated.notify_all();
+
+        // Wake up the accept loop, so it notices the server was closed.
+        let _ = TcpStream::connect(self.local_addr);
+    }
+}
+
+// Upgrades the connection to a WebSocket and streams frames, or returns the
+// preview page for any other request.
+fn serve(stream: &TcpStream, shared: &Shared) -> io::Result<()> {
+    let timeout = Some(Duration::from_secs(CLIENT_TIMEOUT_SECS));
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    stream.set_nodelay(true)?;
+
+    let request = Request::read(&mut BufReader::new(stream))?;
+    let key = match request.header("Sec-WebSocket-Key") {
+        Some(key) => key,
+        None => {
+            let page = Response {
+                status: 200,
+                content_type: "text/html; charset=utf-8",
+                body: PREVIEW_PAGE.as_bytes().to_vec(),
+            };
+
+            return page.write_to(&mut &*stream);
+        }
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )?;
+
+    let mut sequence = 0;
+    loop {
+        let frame = {
+            let mut latest = shared.lock();
+            while latest.sequence == sequence && !latest.closed {
+                latest = match shared.updated.wait(latest) {
+                    Ok(latest) => latest,
+                    Err(poisoned) => poisoned.into_inner(),
+                };
+            }
+
+            if latest.closed {
+                // Close frame.
+                return stream.write_all(&[0x88, 0]);
+            }
+
+            sequence = latest.sequence;
+            latest.frame.clone()
+        };
+
+        write_message(&mut stream, &frame)?;
+    }
+}
+
+fn write_message<W: Write>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    let mut header = vec![OPCODE_BINARY];
+    if payload.len() < 126 {
+        header.push(payload.len() as u8);
+    } else if payload.len() <= 0xffff {
+        header.push(126);
+        header.extend_from_slice(&[(payload.len() >> 8) as u8, payload.len() as u8]);
+    } else {
+        header.push(127);
+        let len = payload.len() as u64;
+        header.extend((0..8).rev().map(|byte| (len >> (byte * 8)) as u8));
+    }
+
+    writer.write_all(&header)?;
+    writer.write_all(payload)
+}
+
+// Calculates the Sec-WebSocket-Accept value for a Sec-WebSocket-Key.
+fn accept_key(key: &str) -> String {
+    let mut input = key.trim().as_bytes().to_vec();
+    input.extend_from_slice(WEBSOCKET_GUID.as_bytes());
+
+    base64(&sha1(&input))
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [
+        0x6745_2301,
+        0xefcd_ab89,
+        0x98ba_dcfe,
+        0x1032_5476,
+        0xc3d2_e1f0,
+    ];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    let bits = (data.len() as u64) * 8;
+    message.extend((0..8).rev().map(|byte| (bits >> (byte * 8)) as u8));
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = word
+                .iter()
+                .fold(0, |value, &byte| (value << 8) | u32::from(byte));
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let (mut a, mut b, mut c, mut d, mut e) =
+            (state[0], state[1], state[2], state[3], state[4]);
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (value, add) in state.iter_mut().zip(&[a, b, c, d, e]) {
+            *value = value.wrapping_add(*add);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (bytes, value) in digest.chunks_mut(4).zip(&state) {
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (value >> (24 - 8 * i)) as u8;
+        }
+    }
+
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len() * 4 / 3 + 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).cloned().unwrap_or(0),
+            chunk.get(2).cloned().unwrap_or(0),
+        ];
+        let value = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(value >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
@@ -0,0 +1,540 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Compatibility with the WLED JSON API.
+//!
+//! A [`WledServer`] implements the subset of the [WLED JSON API] used by
+//! the WLED mobile apps and most integrations, such as the one included
+//! with Home Assistant, so they can control the pixels without any new
+//! client software. The pixels are exposed as a single segment.
+//!
+//! The following endpoints are supported.
+//!
+//! | Method | Path | |
+//! |---|---|---|
+//! | `GET` | `/json` | State, info, effects and palettes |
+//! | `GET` | `/json/si` | State and info |
+//! | `GET` | `/json/state` | State |
+//! | `GET` | `/json/info` | Info |
+//! | `GET` | `/json/eff` | Effect names |
+//! | `GET` | `/json/pal` | Palette names |
+//! | `POST` | `/json`, `/json/si` or `/json/state` | Updates the state |
+//!
+//! State updates support `on` (including `"t"` to toggle), `bri`, and the
+//! `on`, `bri`, `col`, `fx` and `i` (individual pixels) properties of the
+//! first segment. Effect `0` is WLED's solid color mode, and is followed by
+//! the effects added with [`add_effect`]. Other properties, such as
+//! transitions, presets and palettes, are ignored. Devices are discovered
+//! by their IP address, since mDNS announcements aren't sent.
+//!
+//! This module requires the `wled` feature.
+//!
+//! ```rust,no_run
+//! use blinkt::wled::WledServer;
+//! use blinkt::Blinkt;
+//!
+//! # fn main() -> blinkt::Result<()> {
+//! let mut blinkt = Blinkt::with_spi(16_000_000, 60)?;
+//! let mut server = WledServer::new()?;
+//! server.set_name("Desk strip");
+//!
+//! server.run(&mut blinkt)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [WLED JSON API]: https://kno.wled.ge/interfaces/json-api/
+//! [`WledServer`]: struct.WledServer.html
+//! [`add_effect`]: struct.WledServer.html#method.add_effect
+
+use std::fs;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::animation::Animation;
+use crate::control::{parse_hex, Effects};
+use crate::http::{self, Request, Response};
+use crate::{Blinkt, Color, Result};
+
+/// The default TCP port used by WLED.
+pub const PORT: u16 = 80;
+
+// WLED version reported to clients. Clients use the version to determine
+// which parts of the API are available.
+const WLED_VERSION: &str = "0.13.3";
+const WLED_VERSION_ID: u32 = 2_208_222;
+// Network interfaces checked for a MAC address, which clients use to
+// identify devices.
+const INTERFACES: [&str; 2] = ["eth0", "wlan0"];
+
+/// An HTTP server that controls the pixels of a `Blinkt` through the WLED
+/// JSON API.
+#[derive(Debug)]
+pub struct WledServer {
+    listener: TcpListener,
+    name: String,
+    mac: String,
+    started: Instant,
+    effects: Effects,
+    on: bool,
+    brightness: u8,
+    color: Color,
+    pixels: Option<Vec<Color>>,
+}
+
+impl WledServer {
+    /// Constructs a new `WledServer` listening on the default WLED port on
+    /// all network interfaces.
+    ///
+    /// Binding to port 80 usually requires root privileges.
+    pub fn new() -> Result<WledServer> {
+        WledServer::bind(("0.0.0.0", PORT))
+    }
+
+    /// Constructs a new `WledServer` listening on `addr`.
+    ///
+    /// The name defaults to `blinkt`. The pixels are initially turned on
+    /// at half brightness, with WLED's default orange color.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<WledServer> {
+        Ok(WledServer {
+            listener: TcpListener::bind(addr)?,
+            name: "blinkt".to_owned(),
+            mac: mac_address(),
+            started: Instant::now(),
+            effects: Effects::new(),
+            on: true,
+            brightness: 128,
+            color: Color::from((255, 160, 0)),
+            pixels: None,
+        })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Returns the device name shown in WLED clients.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sets the device name shown in WLED clients.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_owned();
+    }
+
+    /// Adds an effect that can be selected in WLED clients, or replaces an
+    /// existing effect with the same name.
+    ///
+    /// Effects are rendered at roughly 30 frames per second, starting from
+    /// the current contents of the local buffer.
+    pub fn add_effect<A>(&mut self, name: &str, animation: A)
+    where
+        A: Animation + Send + 'static,
+    {
+        self.effects.add(name, Box::new(animation));
+    }
+
+    /// Sends the initial state to the pixels, and handles requests until an
+    /// error occurs.
+    pub fn run(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        self.show(blinkt)?;
+
+        loop {
+            self.receive(blinkt)?;
+        }
+    }
+
+    /// Waits for a single request and handles it, or renders the next frame
+    /// of the selected effect when it's due.
+    ///
+    /// Returns `Ok(true)` if a request was handled. Errors that only affect
+    /// the connection with the client, such as a timeout or an invalid
+    /// request, are reported to the client if possible, and otherwise
+    /// ignored.
+    pub fn receive(&mut self, blinkt: &mut Blinkt) -> Result<bool> {
+        if let Some(stream) = http::accept(&self.listener, !self.effect_active())? {
+            return http::serve(&stream, |request| self.handle_request(blinkt, request));
+        }
+
+        thread::sleep(
+            self.effects
+                .until_next_frame()
+                .min(Duration::from_millis(10)),
+        );
+
+        if self.effects.until_next_frame() == Duration::from_millis(0) {
+            self.effects
+                .render(blinkt, Some(f32::from(self.brightness) / 255.0))?;
+        }
+
+        Ok(false)
+    }
+
+    fn effect_active(&self) -> bool {
+        self.on && self.effects.selected().is_some()
+    }
+
+    fn handle_request(&mut self, blinkt: &mut Blinkt, request: &Request) -> Result<Response> {
+        let path = request.path.trim_end_matches('/');
+
+        match (&request.method[..], path) {
+            ("GET", "/json") => Ok(Response::json(
+                200,
+                &json!({
+                    "state": self.state_json(blinkt),
+                    "info": self.info_json(blinkt),
+                    "effects": self.effects_json(),
+                    "palettes": ["Default"],
+                }),
+            )),
+            ("GET", "/json/si") => Ok(Response::json(
+                200,
+                &json!({
+                    "state": self.state_json(blinkt),
+                    "info": self.info_json(blinkt),
+                }),
+            )),
+            ("GET", "/json/state") => Ok(Response::json(200, &self.state_json(blinkt))),
+            ("GET", "/json/info") => Ok(Response::json(200, &self.info_json(blinkt))),
+            ("GET", "/json/eff") => Ok(Response::json(200, &self.effects_json())),
+            ("GET", "/json/pal") => Ok(Response::json(200, &json!(["Default"]))),
+            ("POST", "/json") | ("POST", "/json/si") | ("POST", "/json/state") => {
+                let update =
+                    match serde_json::from_str::<Value>(&String::from_utf8_lossy(&request.body)) {
+                        Ok(update) => update,
+                        Err(_) => return Ok(Response::error(400, "invalid JSON")),
+                    };
+
+                if !self.update(blinkt, &update) {
+                    return Ok(Response::error(400, "invalid state"));
+                }
+
+                self.show(blinkt)?;
+
+                if update.get("v").and_then(Value::as_bool) == Some(true) {
+                    Ok(Response::json(200, &self.state_json(blinkt)))
+                } else {
+                    Ok(Response::json(200, &json!({ "success": true })))
+                }
+            }
+            (_, "/json")
+            | (_, "/json/si")
+            | (_, "/json/state")
+            | (_, "/json/info")
+            | (_, "/json/eff")
+            | (_, "/json/pal") => Ok(Response::error(405, "method not allowed")),
+            _ => Ok(Response::error(404, "not found")),
+        }
+    }
+
+    // Applies a state update. Returns `false` if the update is invalid, in
+    // which case the state isn't changed.
+    fn update(&mut self, blinkt: &Blinkt, update: &Value) -> bool {
+        let mut on = self.on;
+        let mut brightness = self.brightness;
+        let mut color = self.color;
+        let mut pixels = self.pixels.clone();
+        let mut effect = self.effects.selected();
+
+        let mut apply = |state: &Value| -> Option<()> {
+            match state.get("on") {
+                Some(&Value::Bool(value)) => on = value,
+                Some(Value::String(value)) if value == "t" => on = !on,
+                Some(_) => return None,
+                None => {}
+            }
+
+            if let Some(value) = state.get("bri") {
+                let value = value.as_u64()?;
+                brightness = value.min(255) as u8;
+            }
+
+            Some(())
+        };
+
+        if apply(update).is_none() {
+            return false;
+        }
+
+        let segments = match update.get("seg") {
+            Some(Value::Array(segments)) => segments.iter().collect(),
+            Some(segment) => vec![segment],
+            None => Vec::new(),
+        };
+
+        for segment in segments {
+            match segment.get("id").map(Value::as_u64) {
+                Some(Some(0)) | None => {}
+                _ => continue,
+            }
+
+            if apply(segment).is_none() {
+                return false;
+            }
+
+            if let Some(colors) = segment.get("col") {
+                match colors.as_array().map(|colors| colors.first()) {
+                    Some(Some(value)) => match wled_color(value) {
+                        Some(value) => {
+                            color = value;
+                            pixels = None;
+                        }
+                        None => return false,
+                    },
+                    Some(None) => {}
+                    None => return false,
+                }
+            }
+
+            if let Some(value) = segment.get("fx") {
+                match value.as_u64() {
+                    Some(0) => effect = None,
+                    Some(index) if (index as usize) <= self.effects.names().count() => {
+                        effect = Some(index as usize - 1)
+                    }
+                    _ => return false,
+                }
+            }
+
+            if let Some(values) = segment.get("i") {
+                let base = pixels
+                    .take()
+                    .unwrap_or_else(|| vec![color; blinkt.num_pixels()]);
+
+                match values
+                    .as_array()
+                    .and_then(|values| set_pixels(base, values))
+                {
+                    Some(values) => {
+                        pixels = Some(values);
+                        effect = None;
+                    }
+                    None => return false,
+                }
+            }
+        }
+
+        if on && !self.on {
+            self.effects.restart();
+        }
+
+        self.on = on;
+        self.brightness = brightness;
+        self.color = color;
+        self.pixels = pixels;
+        self.effects.select(effect);
+
+        true
+    }
+
+    // Sends the current state to the pixels.
+    fn show(&mut self, blinkt: &mut Blinkt) -> Result<()> {
+        let brightness = f32::from(self.brightness) / 255.0;
+
+        if self.effect_active() {
+            return self.effects.render(blinkt, Some(brightness));
+        }
+
+        if !self.on {
+            blinkt.clear();
+        } else if let Some(ref pixels) = self.pixels {
+            for (pixel, &color) in pixels.iter().enumerate() {
+                blinkt.set_pixel_color(pixel, color);
+            }
+        } else {
+            blinkt.set_all_pixels_color(self.color);
+        }
+
+        blinkt.set_all_pixels_brightness(brightness);
+        blinkt.swap();
+        blinkt.show()
+    }
+
+    fn state_json(&self, blinkt: &Blinkt) -> Value {
+        let (red, green, blue) = self.color.rgb();
+        let len = blinkt.num_pixels();
+        let effect = self.effects.selected().map_or(0, |index| index + 1);
+
+        json!({
+            "on": self.on,
+            "bri": self.brightness,
+            "transition": 0,
+            "ps": -1,
+            "pl": -1,
+            "nl": { "on": false, "dur": 60, "mode": 1, "tbri": 0, "rem": -1 },
+            "udpn": { "send": false, "recv": false },
+            "lor": 0,
+            "mainseg": 0,
+            "seg": [{
+                "id": 0,
+                "start": 0,
+                "stop": len,
+                "len": len,
+                "grp": 1,
+                "spc": 0,
+                "of": 0,
+                "on": self.on,
+                "frz": false,
+                "bri": 255,
+                "cct": 127,
+                "col": [[red, green, blue], [0, 0, 0], [0, 0, 0]],
+                "fx": effect,
+                "sx": 128,
+                "ix": 128,
+                "pal": 0,
+                "sel": true,
+                "rev": false,
+                "mi": false,
+            }],
+        })
+    }
+
+    fn info_json(&self, blinkt: &Blinkt) -> Value {
+        json!({
+            "ver": WLED_VERSION,
+            "vid": WLED_VERSION_ID,
+            "leds": {
+                "count": blinkt.num_pixels(),
+                "rgbw": false,
+                "wv": false,
+                "cct": false,
+                "pwr": 0,
+                "fps": 0,
+                "maxpwr": 0,
+                "maxseg": 1,
+                "seglc": [1],
+                "lc": 1,
+            },
+            "str": false,
+            "name": self.name,
+            "udpport": 21324,
+            "live": false,
+            "lm": "",
+            "lip": "",
+            "ws": -1,
+            "fxcount": self.effects.names().count() + 1,
+            "palcount": 1,
+            "wifi": { "bssid": "", "rssi": 0, "signal": 100, "channel": 1 },
+            "arch": "blinkt",
+            "core": env!("CARGO_PKG_VERSION"),
+            "freeheap": 0,
+            "uptime": self.started.elapsed().as_secs(),
+            "opt": 0,
+            "brand": "WLED",
+            "product": "blinkt",
+            "mac": self.mac,
+        })
+    }
+
+    fn effects_json(&self) -> Value {
+        let names: Vec<&str> = Some("Solid")
+            .into_iter()
+            .chain(self.effects.names())
+            .collect();
+
+        json!(names)
+    }
+}
+
+// Parses a WLED color, either as `[r, g, b]`, `[r, g, b, w]` or `"rrggbb"`.
+// The white channel is ignored.
+fn wled_color(value: &Value) -> Option<Color> {
+    match *value {
+        Value::String(ref hex) => parse_hex(&format!("#{}", hex)),
+        Value::Array(ref values) if values.len() == 3 || values.len() == 4 => {
+            let mut rgb = [0u8; 3];
+            for (channel, value) in rgb.iter_mut().zip(values) {
+                *channel = value.as_u64()?.min(255) as u8;
+            }
+
+            Some(Color::from((rgb[0], rgb[1], rgb[2])))
+        }
+        _ => None,
+    }
+}
+
+// Applies an individual pixel update, which consists of colors, each
+// optionally preceded by a pixel index, or by the start and end of a range
+// of pixels. Colors without an index are applied to the next pixel.
+fn set_pixels(mut pixels: Vec<Color>, values: &[Value]) -> Option<Vec<Color>> {
+    let mut next: usize = 0;
+    let mut indexes = Vec::with_capacity(2);
+
+    for value in values {
+        if let Some(index) = value.as_u64() {
+            if indexes.len() == 2 {
+                return None;
+            }
+
+            // Indexes beyond the end of the strip are ignored anyway, so
+            // saturate instead of truncating them on 32-bit platforms.
+            indexes.push(index.min(usize::MAX as u64) as usize);
+            continue;
+        }
+
+        let color = wled_color(value)?;
+        let (start, end) = match indexes[..] {
+            [] => (next, next.saturating_add(1)),
+            [index] => (index, index.saturating_add(1)),
+            [start, end] => (start, end),
+            _ => unreachable!(),
+        };
+
+        if start > end {
+            return None;
+        }
+
+        next = end;
+        indexes.clear();
+
+        let len = pixels.len();
+        for value in &mut pixels[start.min(len)..end.min(len)] {
+            *value = color;
+        }
+    }
+
+    Some(pixels)
+}
+
+// Returns the MAC address of the first available network interface, as
+// 12 lowercase hex digits, or zeros if none is available.
+fn mac_address() -> String {
+    for interface in &INTERFACES {
+        let path = format!("/sys/class/net/{}/address", interface);
+        if let Ok(address) = fs::read_to_string(path) {
+            let mac: String = address
+                .trim()
+                .chars()
+                .filter(|c| c.is_ascii_hexdigit())
+                .collect();
+
+            if mac.len() == 12 {
+                return mac.to_lowercase();
+            }
+        }
+    }
+
+    "000000000000".to_owned()
+}
@@ -0,0 +1,222 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::mem;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::refresh::{self, SharedOutput};
+use crate::Result;
+
+#[derive(Default)]
+struct State {
+    // The next frame to send, and the minimum gap since the previous frame.
+    // The buffer is swapped with the one owned by the writer thread, so no
+    // allocations are needed once both have grown to the size of a frame.
+    frame: Vec<u8>,
+    min_frame_gap: Duration,
+    pending: bool,
+    busy: bool,
+    error: Option<crate::Error>,
+    stop: bool,
+    #[cfg(feature = "async")]
+    wakers: Vec<Waker>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+// Sends encoded frames to the output on a background thread, so show
+// doesn't have to wait for the transfer to finish.
+pub(crate) struct Writer {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Writer {
+    pub(crate) fn new(output: SharedOutput) -> Writer {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            changed: Condvar::new(),
+        });
+
+        let thread_shared = shared.clone();
+        let thread = thread::spawn(move || run(&thread_shared, &output));
+
+        Writer {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    // Queues a frame. If the previous frame hasn't been picked up yet, it's
+    // replaced, so the output never falls behind. Returns any error that
+    // occurred while sending a previous frame.
+    pub(crate) fn submit(&self, frame: &[u8], min_frame_gap: Duration) -> Result<()> {
+        let mut state = self.shared.lock();
+        state.frame.clear();
+        state.frame.extend_from_slice(frame);
+        state.min_frame_gap = min_frame_gap;
+        state.pending = true;
+        self.shared.changed.notify_all();
+
+        match state.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    // Returns a future that completes once all queued frames have been sent.
+    #[cfg(feature = "async")]
+    pub(crate) fn wait_async(&self) -> WaitAsync<'_> {
+        WaitAsync { writer: self }
+    }
+
+    // Returns Poll::Ready once all queued frames have been sent, or registers
+    // the waker to be woken up when that happens.
+    #[cfg(feature = "async")]
+    fn poll_wait(&self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let mut state = self.shared.lock();
+        if state.pending || state.busy {
+            state.wakers.push(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        match state.error.take() {
+            Some(err) => Poll::Ready(Err(err)),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    // Blocks until all queued frames have been sent. Returns any error that
+    // occurred while sending a previous frame.
+    pub(crate) fn wait(&self) -> Result<()> {
+        let mut state = self.shared.lock();
+        while state.pending || state.busy {
+            state = match self.shared.changed.wait(state) {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+
+        match state.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub(crate) struct WaitAsync<'a> {
+    writer: &'a Writer,
+}
+
+#[cfg(feature = "async")]
+impl<'a> Future for WaitAsync<'a> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.writer.poll_wait(cx)
+    }
+}
+
+impl Drop for Writer {
+    // Finishes sending any queued frames before stopping the thread.
+    fn drop(&mut self) {
+        self.shared.lock().stop = true;
+        self.shared.changed.notify_all();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(shared: &Shared, output: &SharedOutput) {
+    let mut frame = Vec::new();
+    let mut last_write: Option<Instant> = None;
+
+    loop {
+        let min_frame_gap = {
+            let mut state = shared.lock();
+            loop {
+                if state.pending {
+                    mem::swap(&mut state.frame, &mut frame);
+                    state.pending = false;
+                    state.busy = true;
+                    break state.min_frame_gap;
+                } else if state.stop {
+                    return;
+                }
+
+                state = match shared.changed.wait(state) {
+                    Ok(guard) => guard,
+                    Err(poisoned) => poisoned.into_inner(),
+                };
+            }
+        };
+
+        // Give the pixels enough time to latch the previous frame.
+        if let Some(last_write) = last_write {
+            let elapsed = last_write.elapsed();
+            if elapsed < min_frame_gap {
+                thread::sleep(min_frame_gap - elapsed);
+            }
+        }
+
+        let result = refresh::lock_output(output).write(&frame);
+        last_write = Some(Instant::now());
+
+        let mut state = shared.lock();
+        state.busy = false;
+        if let Err(err) = result {
+            state.error = Some(err);
+        }
+
+        shared.changed.notify_all();
+
+        #[cfg(feature = "async")]
+        {
+            if !state.pending {
+                for waker in state.wakers.drain(..) {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// artnet.rs - Verifies the ArtDmx, ArtSync and ArtPoll handling of the
+// Art-Net node, including packets with truncated or oversized length fields.
+
+#![cfg(feature = "artnet")]
+
+use std::net::UdpSocket;
+
+use blinkt::artnet::{ArtNetNode, FlushPolicy};
+use blinkt::capture::Capture;
+use blinkt::Blinkt;
+
+const NUM_PIXELS: usize = 4;
+
+struct Fixture {
+    node: ArtNetNode,
+    blinkt: Blinkt,
+    capture: Capture,
+    client: UdpSocket,
+}
+
+impl Fixture {
+    fn new(flush_policy: FlushPolicy) -> Fixture {
+        let mut node = ArtNetNode::bind("127.0.0.1:0").unwrap();
+        node.set_flush_policy(flush_policy);
+
+        let capture = Capture::new();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(node.local_addr().unwrap()).unwrap();
+
+        Fixture {
+            node,
+            blinkt: Blinkt::with_output(capture.clone(), NUM_PIXELS),
+            capture,
+            client,
+        }
+    }
+
+    // Sends `packet` to the node, and returns whether a frame was sent.
+    fn send(&mut self, packet: &[u8]) -> bool {
+        self.client.send(packet).unwrap();
+        self.node.receive(&mut self.blinkt).unwrap()
+    }
+
+    fn pixels(&self) -> Vec<(u8, u8, u8)> {
+        self.blinkt
+            .frame()
+            .iter()
+            .map(|pixel| pixel.rgb())
+            .collect()
+    }
+}
+
+fn header(opcode: u16) -> Vec<u8> {
+    let mut packet = b"Art-Net\0".to_vec();
+    packet.extend_from_slice(&[opcode as u8, (opcode >> 8) as u8, 0, 14]);
+    packet
+}
+
+// Builds an ArtDmx packet with `length` in the length field, regardless of
+// the number of channel values that follow.
+fn art_dmx(universe: u16, length: u16, channels: &[u8]) -> Vec<u8> {
+    let mut packet = header(0x5000);
+    packet.extend_from_slice(&[0, 0, universe as u8, (universe >> 8) as u8]);
+    packet.extend_from_slice(&[(length >> 8) as u8, length as u8]);
+    packet.extend_from_slice(channels);
+    packet
+}
+
+#[test]
+fn dmx_sets_the_mapped_pixels() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+    fixture.node.map_universe(0x0102, 1);
+
+    assert!(fixture.send(&art_dmx(0x0102, 6, &[1, 2, 3, 4, 5, 6])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(0, 0, 0), (1, 2, 3), (4, 5, 6), (0, 0, 0)]
+    );
+    assert!(!fixture.capture.take().is_empty());
+}
+
+#[test]
+fn unmapped_universes_are_ignored() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+    fixture.node.map_universe(1, 0);
+
+    assert!(!fixture.send(&art_dmx(2, 3, &[255, 255, 255])));
+    assert!(fixture.pixels().iter().all(|&rgb| rgb == (0, 0, 0)));
+    assert!(fixture.capture.take().is_empty());
+}
+
+#[test]
+fn truncated_packets_only_set_the_received_channels() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+    fixture.node.map_universe(0, 0);
+
+    // The length field claims 4 pixels, but only 1 pixel and part of the
+    // next one follow.
+    assert!(fixture.send(&art_dmx(0, 12, &[10, 20, 30, 40, 50])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(10, 20, 30), (0, 0, 0), (0, 0, 0), (0, 0, 0)]
+    );
+}
+
+#[test]
+fn oversized_length_fields_are_limited_to_the_packet() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+    fixture.node.map_universe(0, 2);
+
+    // More channels than fit in a universe, and more pixels than the strip
+    // has. Values beyond the end of the strip are ignored.
+    let channels: Vec<u8> = (0..600).map(|channel| channel as u8).collect();
+    assert!(fixture.send(&art_dmx(0, 0xffff, &channels)));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(0, 0, 0), (0, 0, 0), (0, 1, 2), (3, 4, 5)]
+    );
+}
+
+#[test]
+fn short_packets_are_ignored() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+    fixture.node.map_universe(0, 0);
+
+    let packet = art_dmx(0, 3, &[255, 255, 255]);
+    assert!(!fixture.send(&packet[..17]));
+    assert!(!fixture.send(&packet[..11]));
+    assert!(!fixture.send(b"Not-Art\0\x00\x50\x00\x0e\x00\x00\x00\x00\x00\x03\xff\xff\xff"));
+    assert!(fixture.pixels().iter().all(|&rgb| rgb == (0, 0, 0)));
+}
+
+#[test]
+fn all_universes_waits_for_every_mapped_universe() {
+    let mut fixture = Fixture::new(FlushPolicy::AllUniverses);
+    fixture.node.map_universe(0, 0);
+    fixture.node.map_universe(1, 2);
+
+    assert!(!fixture.send(&art_dmx(0, 3, &[1, 1, 1])));
+    assert!(!fixture.send(&art_dmx(0, 3, &[2, 2, 2])));
+    assert!(fixture.send(&art_dmx(1, 3, &[3, 3, 3])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(2, 2, 2), (0, 0, 0), (3, 3, 3), (0, 0, 0)]
+    );
+
+    // A new frame starts after every flush.
+    assert!(!fixture.send(&art_dmx(1, 3, &[4, 4, 4])));
+}
+
+#[test]
+fn sync_flushes_the_received_universes() {
+    let mut fixture = Fixture::new(FlushPolicy::Sync);
+    fixture.node.map_universe(0, 0);
+
+    assert!(!fixture.send(&art_dmx(0, 3, &[9, 8, 7])));
+    assert!(fixture.capture.take().is_empty());
+
+    assert!(fixture.send(&header(0x5200)));
+    assert_eq!(fixture.pixels()[0], (9, 8, 7));
+    assert!(!fixture.capture.take().is_empty());
+}
+
+#[test]
+fn poll_is_answered_with_a_poll_reply() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+    fixture.node.set_name("Test node");
+    fixture.node.map_universe(0x0123, 0);
+
+    assert!(!fixture.send(&header(0x2000)));
+
+    let mut reply = [0u8; 512];
+    let len = fixture.client.recv(&mut reply).unwrap();
+
+    assert_eq!(len, 239);
+    assert_eq!(&reply[..8], b"Art-Net\0");
+    assert_eq!(&reply[8..10], &[0x00, 0x21]);
+    assert_eq!(&reply[26..35], b"Test node");
+    assert_eq!((reply[18], reply[19]), (0x01, 0x02));
+    assert_eq!((reply[173], reply[190]), (1, 0x03));
+}
@@ -0,0 +1,69 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// conformance.rs - Verifies the serialized output against known-good byte
+// streams stored in tests/captures.
+
+use std::path::Path;
+
+use blinkt::capture::{self, Capture};
+use blinkt::Blinkt;
+
+fn assert_capture<F>(name: &str, num_pixels: usize, render: F)
+where
+    F: FnOnce(&mut Blinkt),
+{
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("captures")
+        .join(name);
+    let expected = capture::load_hex(&path).unwrap();
+
+    let capture = Capture::new();
+    let mut blinkt = Blinkt::with_output(capture.clone(), num_pixels);
+    blinkt.set_clear_on_drop(false);
+
+    render(&mut blinkt);
+    blinkt.show().unwrap();
+
+    assert_eq!(capture.take(), expected, "{}", name);
+}
+
+#[test]
+fn blinkt_all_red() {
+    assert_capture("blinkt_all_red.hex", 8, |blinkt| {
+        blinkt.set_all_pixels(255, 0, 0);
+    });
+}
+
+#[test]
+fn strip_mixed_brightness() {
+    assert_capture("strip_mixed_brightness.hex", 3, |blinkt| {
+        blinkt.set_pixel_rgbb(0, 255, 128, 0, 1.0);
+        blinkt.set_pixel_rgbb(1, 0, 0, 255, 0.5);
+    });
+}
+
+#[test]
+fn strip_17_white() {
+    assert_capture("strip_17_white.hex", 17, |blinkt| {
+        blinkt.set_all_pixels_rgbb(255, 255, 255, 1.0 / 31.0);
+    });
+}
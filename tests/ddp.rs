@@ -0,0 +1,185 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// ddp.rs - Verifies that the DDP receiver places data at the byte offsets
+// from the packet header, including packets with truncated or oversized
+// length fields.
+
+#![cfg(feature = "ddp")]
+
+use std::net::UdpSocket;
+
+use blinkt::capture::Capture;
+use blinkt::ddp::DdpReceiver;
+use blinkt::Blinkt;
+
+const NUM_PIXELS: usize = 4;
+
+const PUSH: u8 = 0x41;
+const NO_PUSH: u8 = 0x40;
+
+struct Fixture {
+    receiver: DdpReceiver,
+    blinkt: Blinkt,
+    client: UdpSocket,
+}
+
+impl Fixture {
+    fn new() -> Fixture {
+        let receiver = DdpReceiver::bind("127.0.0.1:0").unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(receiver.local_addr().unwrap()).unwrap();
+
+        Fixture {
+            receiver,
+            blinkt: Blinkt::with_output(Capture::new(), NUM_PIXELS),
+            client,
+        }
+    }
+
+    // Sends `packet` to the receiver, and returns whether a frame was sent.
+    fn send(&mut self, packet: &[u8]) -> bool {
+        self.client.send(packet).unwrap();
+        self.receiver.receive(&mut self.blinkt).unwrap()
+    }
+
+    fn pixels(&self) -> Vec<(u8, u8, u8)> {
+        self.blinkt
+            .frame()
+            .iter()
+            .map(|pixel| pixel.rgb())
+            .collect()
+    }
+}
+
+// Builds a packet with `len` in the length field, regardless of the amount
+// of data that follows.
+fn packet(flags: u8, id: u8, offset: u32, len: u16, data: &[u8]) -> Vec<u8> {
+    let mut packet = vec![flags, 0, 0, id];
+    packet.extend_from_slice(&offset.to_be_bytes());
+    packet.extend_from_slice(&len.to_be_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
+
+fn display(flags: u8, offset: u32, data: &[u8]) -> Vec<u8> {
+    packet(flags, 1, offset, data.len() as u16, data)
+}
+
+#[test]
+fn push_shows_the_received_data() {
+    let mut fixture = Fixture::new();
+
+    assert!(fixture.send(&display(PUSH, 0, &[1, 2, 3, 4, 5, 6])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(1, 2, 3), (4, 5, 6), (0, 0, 0), (0, 0, 0)]
+    );
+}
+
+#[test]
+fn offsets_are_in_bytes() {
+    let mut fixture = Fixture::new();
+
+    // Neither packet starts or ends on a pixel boundary.
+    assert!(!fixture.send(&display(NO_PUSH, 1, &[2, 3, 4, 5])));
+    assert!(!fixture.send(&display(NO_PUSH, 5, &[6, 7, 8, 9])));
+    assert!(fixture.send(&display(PUSH, 9, &[10])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(0, 2, 3), (4, 5, 6), (7, 8, 9), (10, 0, 0)]
+    );
+
+    // Later packets only replace the bytes they cover.
+    assert!(fixture.send(&display(PUSH, 4, &[40])));
+    assert_eq!(fixture.pixels()[1], (4, 40, 6));
+}
+
+#[test]
+fn data_beyond_the_strip_is_ignored() {
+    let mut fixture = Fixture::new();
+
+    assert!(fixture.send(&display(PUSH, 9, &[1, 2, 3, 4, 5, 6])));
+    assert_eq!(fixture.pixels()[3], (1, 2, 3));
+
+    assert!(fixture.send(&display(PUSH, 12, &[7, 8, 9])));
+    assert!(fixture.send(&display(PUSH, u32::MAX, &[7, 8, 9])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(0, 0, 0), (0, 0, 0), (0, 0, 0), (1, 2, 3)]
+    );
+}
+
+#[test]
+fn truncated_packets_only_use_the_received_data() {
+    let mut fixture = Fixture::new();
+
+    assert!(fixture.send(&packet(PUSH, 1, 0, 12, &[1, 2, 3, 4])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(1, 2, 3), (4, 0, 0), (0, 0, 0), (0, 0, 0)]
+    );
+}
+
+#[test]
+fn oversized_length_fields_are_limited_to_the_packet() {
+    let mut fixture = Fixture::new();
+
+    let data: Vec<u8> = (0..2000).map(|byte| byte as u8).collect();
+    assert!(fixture.send(&packet(PUSH, 1, 3, 0xffff, &data)));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(0, 0, 0), (0, 1, 2), (3, 4, 5), (6, 7, 8)]
+    );
+}
+
+#[test]
+fn timecodes_are_skipped() {
+    let mut fixture = Fixture::new();
+
+    let mut data = vec![0xff, 0xff, 0xff, 0xff];
+    data.extend_from_slice(&[1, 2, 3]);
+    assert!(fixture.send(&packet(PUSH | 0x10, 1, 0, 3, &data)));
+    assert_eq!(fixture.pixels()[0], (1, 2, 3));
+
+    // A timecode that doesn't fit in the packet.
+    assert!(fixture.send(&packet(PUSH | 0x10, 1, 0, 3, &[9, 9])));
+    assert_eq!(fixture.pixels()[0], (1, 2, 3));
+}
+
+#[test]
+fn invalid_packets_are_ignored() {
+    let mut fixture = Fixture::new();
+
+    let valid = display(PUSH, 0, &[255, 255, 255]);
+    assert!(!fixture.send(&valid[..9]));
+    // Version 2.
+    assert!(!fixture.send(&display(0x81, 0, &[255, 255, 255])));
+    // Query.
+    assert!(!fixture.send(&display(PUSH | 0x02, 0, &[255, 255, 255])));
+    // Config and status destinations.
+    assert!(!fixture.send(&packet(PUSH, 250, 0, 3, &[255, 255, 255])));
+    assert!(!fixture.send(&packet(PUSH, 251, 0, 3, &[255, 255, 255])));
+
+    // Broadcasts to all destinations are accepted.
+    assert!(fixture.send(&packet(PUSH, 255, 0, 3, &[255, 255, 255])));
+    assert_eq!(fixture.pixels()[0], (255, 255, 255));
+    assert_eq!(fixture.pixels()[1], (0, 0, 0));
+}
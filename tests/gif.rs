@@ -0,0 +1,313 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// gif.rs - Decodes the animated GIFs written by record_gif(), and verifies
+// the frame delays, the color tables and the LZW compressed pixel data.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use blinkt::capture::Capture;
+use blinkt::Blinkt;
+
+const CELL_SIZE: usize = 16;
+// Delay of the final frame, in hundredths of a second.
+const LAST_FRAME_DELAY: u16 = 100;
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("blinkt-test-{}-{}.gif", name, process::id()))
+}
+
+#[derive(Debug)]
+struct Frame {
+    delay: u16,
+    palette_len: usize,
+    rgb: Vec<(u8, u8, u8)>,
+}
+
+struct Gif {
+    width: usize,
+    height: usize,
+    frames: Vec<Frame>,
+}
+
+fn le_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+// Concatenates the data sub-blocks starting at `pos`, and returns the data
+// and the position following the block terminator.
+fn sub_blocks(gif: &[u8], mut pos: usize) -> (Vec<u8>, usize) {
+    let mut data = Vec::new();
+    loop {
+        let len = gif[pos] as usize;
+        pos += 1;
+        if len == 0 {
+            return (data, pos);
+        }
+
+        data.extend_from_slice(&gif[pos..pos + len]);
+        pos += len;
+    }
+}
+
+fn lzw_decode(min_code_size: u8, data: &[u8]) -> Vec<u8> {
+    let clear_code = 1usize << min_code_size;
+    let end_code = clear_code + 1;
+    let reset = || -> Vec<Vec<u8>> { (0..=end_code).map(|code| vec![code as u8]).collect() };
+
+    let mut table = reset();
+    let mut code_size = min_code_size + 1;
+    let mut prev: Option<Vec<u8>> = None;
+    let mut output = Vec::new();
+
+    let (mut buffer, mut bits, mut pos) = (0u32, 0u8, 0);
+    loop {
+        while bits < code_size {
+            buffer |= u32::from(data[pos]) << bits;
+            bits += 8;
+            pos += 1;
+        }
+        let code = (buffer & ((1 << code_size) - 1)) as usize;
+        buffer >>= code_size;
+        bits -= code_size;
+
+        if code == clear_code {
+            table = reset();
+            code_size = min_code_size + 1;
+            prev = None;
+            continue;
+        }
+        if code == end_code {
+            break;
+        }
+
+        let entry = match prev {
+            Some(ref prev) if code == table.len() => {
+                let mut entry = prev.clone();
+                entry.push(prev[0]);
+                entry
+            }
+            _ => table[code].clone(),
+        };
+        output.extend_from_slice(&entry);
+
+        if let Some(mut prev) = prev.take() {
+            if table.len() < 4096 {
+                prev.push(entry[0]);
+                table.push(prev);
+            }
+        }
+        if table.len() == 1 << code_size && code_size < 12 {
+            code_size += 1;
+        }
+
+        prev = Some(entry);
+    }
+
+    // Nothing but padding follows the end code.
+    assert!(data.len() - pos <= 1);
+
+    output
+}
+
+fn decode(gif: &[u8]) -> Gif {
+    assert_eq!(&gif[..6], b"GIF89a");
+    let width = le_u16(&gif[6..]) as usize;
+    let height = le_u16(&gif[8..]) as usize;
+    // No global color table.
+    assert_eq!(gif[10] & 0x80, 0);
+
+    let mut frames = Vec::new();
+    let mut delay = None;
+    let mut looped = false;
+    let mut pos = 13;
+    loop {
+        match gif[pos] {
+            0x21 => {
+                let label = gif[pos + 1];
+                let (data, next) = sub_blocks(gif, pos + 2);
+                match label {
+                    0xf9 => delay = Some(le_u16(&data[1..])),
+                    0xff => {
+                        assert_eq!(&data[..11], b"NETSCAPE2.0");
+                        // Loop forever.
+                        assert_eq!(data[11..], [1, 0, 0]);
+                        looped = true;
+                    }
+                    _ => panic!("unexpected extension {:#x}", label),
+                }
+                pos = next;
+            }
+            0x2c => {
+                let descriptor = &gif[pos + 1..pos + 10];
+                assert_eq!(descriptor[..4], [0, 0, 0, 0]);
+                assert_eq!(le_u16(&descriptor[4..]) as usize, width);
+                assert_eq!(le_u16(&descriptor[6..]) as usize, height);
+
+                let flags = descriptor[8];
+                assert_eq!(flags & 0x80, 0x80, "missing local color table");
+                let palette_len = 2 << (flags & 0x07);
+                let palette: Vec<(u8, u8, u8)> = gif[pos + 10..pos + 10 + palette_len * 3]
+                    .chunks(3)
+                    .map(|color| (color[0], color[1], color[2]))
+                    .collect();
+                pos += 10 + palette_len * 3;
+
+                let min_code_size = gif[pos];
+                let (data, next) = sub_blocks(gif, pos + 1);
+                let indices = lzw_decode(min_code_size, &data);
+                assert_eq!(indices.len(), width * height);
+
+                frames.push(Frame {
+                    delay: delay.take().expect("missing graphic control extension"),
+                    palette_len,
+                    rgb: indices
+                        .iter()
+                        .map(|&index| palette[index as usize])
+                        .collect(),
+                });
+                pos = next;
+            }
+            0x3b => {
+                assert_eq!(pos + 1, gif.len());
+                break;
+            }
+            byte => panic!("unexpected block {:#x}", byte),
+        }
+    }
+
+    assert!(looped);
+
+    Gif {
+        width,
+        height,
+        frames,
+    }
+}
+
+// Records the frames sent by `show`, and decodes the resulting GIF.
+fn record<F: FnOnce(&mut Blinkt)>(name: &str, num_pixels: usize, show: F) -> Gif {
+    let path = temp_path(name);
+
+    let mut blinkt = Blinkt::with_output(Capture::new(), num_pixels);
+    blinkt.set_all_pixels_brightness(1.0);
+    blinkt.record_gif(&path).unwrap();
+    show(&mut blinkt);
+    blinkt.clear_show_callbacks();
+
+    let gif = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    decode(&gif)
+}
+
+// Returns the color of each pixel in the top row of the image.
+fn pixels(gif: &Gif, frame: &Frame) -> Vec<(u8, u8, u8)> {
+    (0..gif.width / CELL_SIZE)
+        .map(|idx| frame.rgb[idx * CELL_SIZE])
+        .collect()
+}
+
+#[test]
+fn recordings_round_trip() {
+    let gif = record("round-trip", 3, |blinkt| {
+        blinkt.set_pixel(0, 255, 0, 0);
+        blinkt.show().unwrap();
+        thread::sleep(Duration::from_millis(50));
+
+        blinkt.set_pixel(1, 1, 2, 3);
+        blinkt.set_pixel(2, 0, 128, 255);
+        blinkt.show().unwrap();
+    });
+
+    assert_eq!((gif.width, gif.height), (3 * CELL_SIZE, CELL_SIZE));
+    assert_eq!(gif.frames.len(), 2);
+
+    let first = &gif.frames[0];
+    assert!(first.delay >= 5);
+    assert_eq!(first.palette_len, 2);
+    assert_eq!(pixels(&gif, first), vec![(255, 0, 0), (0, 0, 0), (0, 0, 0)]);
+
+    let last = &gif.frames[1];
+    assert_eq!(last.delay, LAST_FRAME_DELAY);
+    assert_eq!(last.palette_len, 4);
+    assert_eq!(
+        pixels(&gif, last),
+        vec![(255, 0, 0), (1, 2, 3), (0, 128, 255)]
+    );
+
+    // Every row of a frame is the same.
+    for frame in &gif.frames {
+        for row in frame.rgb.chunks(gif.width) {
+            assert_eq!(row, &frame.rgb[..gif.width]);
+        }
+    }
+}
+
+#[test]
+fn short_frames_are_replaced() {
+    let gif = record("short", 1, |blinkt| {
+        blinkt.set_pixel(0, 1, 1, 1);
+        blinkt.show().unwrap();
+        blinkt.set_pixel(0, 2, 2, 2);
+        blinkt.show().unwrap();
+    });
+
+    assert_eq!(gif.frames.len(), 1);
+    assert_eq!(pixels(&gif, &gif.frames[0]), vec![(2, 2, 2)]);
+}
+
+#[test]
+fn frames_with_many_colors_use_a_fixed_palette() {
+    // Every color of the 3-3-2 palette, and one that isn't part of it.
+    let mut colors: Vec<(u8, u8, u8)> = (0..=255u32)
+        .map(|index| {
+            (
+                ((index >> 5) * 255 / 7) as u8,
+                (((index >> 2) & 0x07) * 255 / 7) as u8,
+                ((index & 0x03) * 255 / 3) as u8,
+            )
+        })
+        .collect();
+    colors.push((1, 1, 1));
+
+    let gif = record("palette", colors.len(), |blinkt| {
+        for (idx, &(red, green, blue)) in colors.iter().enumerate() {
+            blinkt.set_pixel(idx, red, green, blue);
+        }
+        blinkt.show().unwrap();
+    });
+
+    assert_eq!(gif.frames.len(), 1);
+    let frame = &gif.frames[0];
+    assert_eq!(frame.palette_len, 256);
+
+    let mut expected = colors.clone();
+    expected[256] = (0, 0, 0);
+    assert_eq!(pixels(&gif, frame), expected);
+    for row in frame.rgb.chunks(gif.width) {
+        assert_eq!(row, &frame.rgb[..gif.width]);
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+
+// mdns.rs - Verifies that an advertised service can be discovered.
+
+#![cfg(feature = "mdns")]
+
+use std::net::TcpListener;
+use std::time::Duration;
+
+use blinkt::mdns::{self, Advertiser};
+
+#[test]
+fn advertised_service_is_discovered() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let _advertiser = Advertiser::spawn("_blinkt-test._tcp.local", "Test node", port).unwrap();
+
+    let nodes = mdns::discover("_blinkt-test._tcp.local", Duration::from_secs(1)).unwrap();
+    let node = nodes.iter().find(|node| node.name() == "Test node").unwrap();
+
+    assert_eq!(node.addr().port(), port);
+    assert_eq!(node.host(), format!("{}.local", mdns::host_name()));
+}
+
+#[test]
+fn long_names_are_rejected() {
+    let name: String = (0..64).map(|_| 'x').collect();
+
+    assert!(Advertiser::spawn("_blinkt-test._tcp.local", &name, 7000).is_err());
+}
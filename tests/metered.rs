@@ -0,0 +1,94 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// metered.rs - Verifies the render time accounting and demotion of metered
+// animations.
+
+use std::thread;
+use std::time::Duration;
+
+use blinkt::animation::{Animation, BudgetPolicy, Metered};
+use blinkt::Frame;
+
+// Takes longer than `budget` to render each of the first `slow` frames.
+fn slow_animation(slow: u32) -> impl Animation {
+    let mut frames = 0;
+    move |_t: Duration, _frame: &mut Frame| {
+        frames += 1;
+        if frames <= slow {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
+
+#[test]
+fn render_time_is_measured() {
+    let mut metered = Metered::new(slow_animation(1));
+    let meter = metered.meter();
+    let mut frame = Frame::new(4);
+
+    for _ in 0..3 {
+        metered.render(Duration::from_millis(0), &mut frame);
+    }
+
+    let stats = meter.stats();
+    assert_eq!(stats.frames(), 3);
+    assert_eq!(stats.skipped(), 0);
+    assert_eq!(stats.over_budget(), 0);
+    assert!(stats.max() >= Duration::from_millis(5));
+    assert!(stats.mean() < stats.max());
+    assert_eq!(stats, metered.stats());
+}
+
+#[test]
+fn report_counts_frames_over_budget() {
+    let mut metered =
+        Metered::new(slow_animation(2)).with_budget(Duration::from_millis(1), BudgetPolicy::Report);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    metered.on_over_budget(move |stats| sender.send(stats.over_budget()).unwrap());
+    let mut frame = Frame::new(4);
+
+    for _ in 0..4 {
+        metered.render(Duration::from_millis(0), &mut frame);
+    }
+
+    assert_eq!(metered.stats().over_budget(), 2);
+    assert_eq!(metered.demotion(), 1);
+    assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+}
+
+#[test]
+fn demoted_animations_skip_frames_and_recover() {
+    let mut metered =
+        Metered::new(slow_animation(1)).with_budget(Duration::from_millis(1), BudgetPolicy::Demote);
+    let mut frame = Frame::new(4);
+
+    metered.render(Duration::from_millis(0), &mut frame);
+    assert_eq!(metered.demotion(), 2);
+
+    for _ in 0..60 {
+        metered.render(Duration::from_millis(0), &mut frame);
+    }
+
+    let stats = metered.stats();
+    assert_eq!(metered.demotion(), 1);
+    assert_eq!(stats.frames() + stats.skipped(), 61);
+    assert!(stats.skipped() > 0);
+}
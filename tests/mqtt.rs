@@ -0,0 +1,97 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// mqtt.rs - Verifies that a keep alive interval of zero disables pings.
+
+#![cfg(feature = "mqtt")]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use blinkt::capture::Capture;
+use blinkt::mqtt::{MqttClient, MqttOptions};
+use blinkt::Blinkt;
+
+// Reads a single packet, and returns its type and body, or `None` once the
+// client disconnected.
+fn read_packet(stream: &mut TcpStream) -> Option<(u8, Vec<u8>)> {
+    let mut byte = [0u8; 1];
+    stream.read_exact(&mut byte).ok()?;
+    let kind = byte[0] & 0xf0;
+
+    let mut len = 0;
+    let mut shift = 0;
+    loop {
+        stream.read_exact(&mut byte).ok()?;
+        len |= usize::from(byte[0] & 0x7f) << shift;
+        shift += 7;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).ok()?;
+
+    Some((kind, body))
+}
+
+#[test]
+fn zero_keep_alive_disables_pings() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let broker = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let (kind, connect) = read_packet(&mut stream).unwrap();
+        assert_eq!(kind, 0x10);
+        stream.write_all(&[0x20, 2, 0, 0]).unwrap();
+
+        // Give the client time to send pings before the first message.
+        thread::sleep(Duration::from_millis(200));
+        let topic = b"blinkt/power/set";
+        let mut publish = vec![0x30, (topic.len() + 4) as u8, 0, topic.len() as u8];
+        publish.extend_from_slice(topic);
+        publish.extend_from_slice(b"ON");
+        stream.write_all(&publish).unwrap();
+
+        let mut kinds = Vec::new();
+        while let Some((kind, _)) = read_packet(&mut stream) {
+            kinds.push(kind);
+        }
+
+        (connect, kinds)
+    });
+
+    {
+        let options = MqttOptions::new(&addr.to_string()).with_keep_alive(Duration::from_secs(0));
+        let mut client = MqttClient::connect(options).unwrap();
+        let mut blinkt = Blinkt::with_output(Capture::new(), 4);
+
+        assert!(client.receive(&mut blinkt).unwrap());
+    }
+
+    let (connect, kinds) = broker.join().unwrap();
+    // The keep alive interval follows the protocol name, level and flags.
+    assert_eq!(&connect[8..10], &[0, 0]);
+    assert!(!kinds.contains(&0xc0));
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// osc.rs - Verifies the OSC message and bundle handling of the OSC server,
+// including nested bundles and packets with truncated or oversized length
+// fields.
+
+#![cfg(feature = "osc")]
+
+use std::net::UdpSocket;
+
+use blinkt::capture::Capture;
+use blinkt::osc::OscServer;
+use blinkt::Blinkt;
+
+const NUM_PIXELS: usize = 4;
+// Bundles nested deeper than this are ignored.
+const MAX_DEPTH: usize = 8;
+
+struct Fixture {
+    server: OscServer,
+    blinkt: Blinkt,
+    capture: Capture,
+    client: UdpSocket,
+}
+
+impl Fixture {
+    fn new() -> Fixture {
+        let server = OscServer::bind("127.0.0.1:0").unwrap();
+        let capture = Capture::new();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(server.local_addr().unwrap()).unwrap();
+
+        Fixture {
+            server,
+            blinkt: Blinkt::with_output(capture.clone(), NUM_PIXELS),
+            capture,
+            client,
+        }
+    }
+
+    // Sends `packet` to the server, and returns whether a frame was sent.
+    fn send(&mut self, packet: &[u8]) -> bool {
+        self.client.send(packet).unwrap();
+        self.server.receive(&mut self.blinkt).unwrap()
+    }
+
+    fn pixels(&self) -> Vec<(u8, u8, u8)> {
+        self.blinkt
+            .frame()
+            .iter()
+            .map(|pixel| pixel.rgb())
+            .collect()
+    }
+}
+
+// Encodes a null-terminated string, padded to a multiple of 4 bytes.
+fn string(value: &str) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.resize((value.len() + 4) & !3, 0);
+    bytes
+}
+
+fn message(address: &str, args: &[i32]) -> Vec<u8> {
+    let mut message = string(address);
+    message.extend(string(&format!(",{}", "i".repeat(args.len()))));
+    for arg in args {
+        message.extend_from_slice(&arg.to_be_bytes());
+    }
+    message
+}
+
+fn bundle(elements: &[Vec<u8>]) -> Vec<u8> {
+    let mut bundle = b"#bundle\0".to_vec();
+    // Immediately.
+    bundle.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+    for element in elements {
+        bundle.extend_from_slice(&(element.len() as i32).to_be_bytes());
+        bundle.extend_from_slice(element);
+    }
+    bundle
+}
+
+// Wraps `packet` in `depth` nested bundles.
+fn nested(packet: Vec<u8>, depth: usize) -> Vec<u8> {
+    (0..depth).fold(packet, |packet, _| bundle(&[packet]))
+}
+
+#[test]
+fn messages_set_the_pixels() {
+    let mut fixture = Fixture::new();
+
+    assert!(!fixture.send(&message("/pixel/1/rgb", &[1, 2, 3])));
+    assert!(fixture.capture.take().is_empty());
+
+    assert!(fixture.send(&message("/show", &[])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(0, 0, 0), (1, 2, 3), (0, 0, 0), (0, 0, 0)]
+    );
+    assert!(!fixture.capture.take().is_empty());
+}
+
+#[test]
+fn float_colors_are_scaled() {
+    let mut fixture = Fixture::new();
+
+    let mut packet = string("/all/rgb");
+    packet.extend(string(",fif"));
+    packet.extend_from_slice(&1.0f32.to_bits().to_be_bytes());
+    packet.extend_from_slice(&300i32.to_be_bytes());
+    packet.extend_from_slice(&0.5f32.to_bits().to_be_bytes());
+
+    fixture.server.set_auto_show(true);
+    assert!(fixture.send(&packet));
+    assert_eq!(fixture.pixels(), vec![(255, 255, 128); NUM_PIXELS]);
+}
+
+#[test]
+fn bundles_are_processed_in_order() {
+    let mut fixture = Fixture::new();
+
+    let packet = bundle(&[
+        message("/all/rgb", &[9, 9, 9]),
+        message("/pixel/0/rgb", &[1, 2, 3]),
+        message("/show", &[]),
+    ]);
+    assert!(fixture.send(&packet));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(1, 2, 3), (9, 9, 9), (9, 9, 9), (9, 9, 9)]
+    );
+}
+
+#[test]
+fn nested_bundles_are_processed() {
+    let mut fixture = Fixture::new();
+
+    let packet = bundle(&[
+        message("/pixel/0/rgb", &[1, 1, 1]),
+        bundle(&[
+            message("/pixel/1/rgb", &[2, 2, 2]),
+            bundle(&[message("/pixel/2/rgb", &[3, 3, 3])]),
+        ]),
+        message("/show", &[]),
+    ]);
+    assert!(fixture.send(&packet));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(1, 1, 1), (2, 2, 2), (3, 3, 3), (0, 0, 0)]
+    );
+}
+
+#[test]
+fn deeply_nested_bundles_are_ignored() {
+    let mut fixture = Fixture::new();
+    fixture.server.set_auto_show(true);
+
+    assert!(fixture.send(&nested(message("/all/rgb", &[1, 2, 3]), MAX_DEPTH)));
+    assert_eq!(fixture.pixels(), vec![(1, 2, 3); NUM_PIXELS]);
+
+    assert!(!fixture.send(&nested(message("/all/rgb", &[4, 5, 6]), MAX_DEPTH + 1)));
+
+    // Recursion is limited without overflowing the stack.
+    assert!(!fixture.send(&nested(message("/all/rgb", &[4, 5, 6]), 1000)));
+    assert_eq!(fixture.pixels(), vec![(1, 2, 3); NUM_PIXELS]);
+}
+
+#[test]
+fn oversized_bundle_elements_are_ignored() {
+    let mut fixture = Fixture::new();
+    fixture.server.set_auto_show(true);
+
+    // The second element claims more bytes than the bundle contains, so it
+    // and anything following it are ignored.
+    let mut packet = bundle(&[message("/pixel/0/rgb", &[1, 2, 3])]);
+    let oversized = message("/pixel/1/rgb", &[4, 5, 6]);
+    packet.extend_from_slice(&(oversized.len() as i32 + 4).to_be_bytes());
+    packet.extend(oversized);
+    assert!(fixture.send(&packet));
+
+    packet = bundle(&[]);
+    packet.extend_from_slice(&i32::MAX.to_be_bytes());
+    packet.extend(message("/all/rgb", &[7, 8, 9]));
+    assert!(!fixture.send(&packet));
+
+    assert_eq!(
+        fixture.pixels(),
+        vec![(1, 2, 3), (0, 0, 0), (0, 0, 0), (0, 0, 0)]
+    );
+}
+
+#[test]
+fn negative_bundle_element_sizes_are_skipped() {
+    let mut fixture = Fixture::new();
+    fixture.server.set_auto_show(true);
+
+    let mut packet = bundle(&[]);
+    packet.extend_from_slice(&(-1i32).to_be_bytes());
+    let element = message("/pixel/3/rgb", &[1, 2, 3]);
+    packet.extend_from_slice(&(element.len() as i32).to_be_bytes());
+    packet.extend(element);
+    assert!(fixture.send(&packet));
+    assert_eq!(fixture.pixels()[3], (1, 2, 3));
+}
+
+#[test]
+fn truncated_messages_are_ignored() {
+    let mut fixture = Fixture::new();
+    fixture.server.set_auto_show(true);
+
+    // Missing the last argument.
+    let packet = message("/all/rgb", &[1, 2, 3]);
+    assert!(!fixture.send(&packet[..packet.len() - 4]));
+    assert!(!fixture.send(&packet[..packet.len() - 1]));
+
+    // Missing the null terminator of the type tags and address.
+    let mut packet = string("/all/rgb");
+    packet.extend_from_slice(b",iii");
+    assert!(!fixture.send(&packet));
+    assert!(!fixture.send(b"/clear"));
+
+    // A truncated bundle header.
+    assert!(!fixture.send(&bundle(&[message("/clear", &[])])[..12]));
+
+    assert_eq!(fixture.pixels(), vec![(0, 0, 0); NUM_PIXELS]);
+    assert!(fixture.capture.take().is_empty());
+}
+
+#[test]
+fn oversized_blobs_are_ignored() {
+    let mut fixture = Fixture::new();
+    fixture.server.set_auto_show(true);
+
+    // The blob size exceeds the message, so the arguments following it
+    // can't be read.
+    let mut packet = string("/pixel/0/brightness");
+    packet.extend(string(",bf"));
+    packet.extend_from_slice(&i32::MAX.to_be_bytes());
+    packet.extend_from_slice(&0.5f32.to_bits().to_be_bytes());
+    assert!(!fixture.send(&packet));
+    assert!(fixture.capture.take().is_empty());
+}
@@ -0,0 +1,55 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// panic_hook.rs - Verifies the panic hook turns off the pixels, and stops
+// touching the output once Blinkt is dropped. Panic hooks are global, so
+// everything runs in a single test.
+
+use std::panic;
+
+use blinkt::capture::Capture;
+use blinkt::Blinkt;
+
+#[test]
+fn panic_hook_is_inert_after_drop() {
+    let capture = Capture::new();
+    let mut blinkt = Blinkt::with_output(capture.clone(), 4);
+    blinkt.install_panic_hook();
+    blinkt.set_all_pixels(255, 255, 255);
+    blinkt.swap();
+    blinkt.show().unwrap();
+    capture.take();
+
+    assert!(panic::catch_unwind(|| panic!("first")).is_err());
+    let blanked = capture.take();
+    assert!(!blanked.is_empty());
+    assert_eq!(&blanked[4..8], &[0xe0, 0, 0, 0]);
+    assert!(blinkt.show().is_err());
+    drop(blinkt);
+
+    let capture = Capture::new();
+    let mut blinkt = Blinkt::with_output(capture.clone(), 4);
+    blinkt.install_panic_hook();
+    drop(blinkt);
+    capture.take();
+
+    assert!(panic::catch_unwind(|| panic!("second")).is_err());
+    assert!(capture.take().is_empty());
+}
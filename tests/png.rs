@@ -0,0 +1,219 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// png.rs - Decodes the PNG images written by save_snapshot(), and verifies
+// the chunk checksums, the zlib stream and the pixel data.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use blinkt::capture::Capture;
+use blinkt::Blinkt;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const CELL_SIZE: usize = 16;
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("blinkt-test-{}-{}.png", name, process::id()))
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + u32::from(*byte)) % 65_521;
+        b = (b + a) % 65_521;
+    }
+
+    (b << 16) | a
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+// Splits a PNG image into its chunks, and verifies the checksum of each
+// chunk.
+fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    assert_eq!(png[..8], SIGNATURE);
+
+    let mut chunks = Vec::new();
+    let mut pos = 8;
+    while pos < png.len() {
+        let len = be_u32(&png[pos..]) as usize;
+        let kind_and_data = &png[pos + 4..pos + 8 + len];
+        let crc = be_u32(&png[pos + 8 + len..]);
+        assert_eq!(crc, crc32(kind_and_data));
+
+        let mut kind = [0u8; 4];
+        kind.copy_from_slice(&kind_and_data[..4]);
+        chunks.push((kind, kind_and_data[4..].to_vec()));
+        pos += 12 + len;
+    }
+
+    chunks
+}
+
+// Decompresses a zlib stream that only contains stored deflate blocks, and
+// verifies its checksum.
+fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+    assert_eq!((u16::from(zlib[0]) << 8 | u16::from(zlib[1])) % 31, 0);
+    assert_eq!(zlib[0] & 0x0f, 8);
+
+    let mut data = Vec::new();
+    let mut pos = 2;
+    loop {
+        let header = zlib[pos];
+        assert_eq!(header & 0x06, 0, "compressed block");
+
+        let len = u16::from_le_bytes([zlib[pos + 1], zlib[pos + 2]]);
+        let nlen = u16::from_le_bytes([zlib[pos + 3], zlib[pos + 4]]);
+        assert_eq!(len, !nlen);
+
+        pos += 5;
+        data.extend_from_slice(&zlib[pos..pos + len as usize]);
+        pos += len as usize;
+
+        if header & 0x01 == 1 {
+            break;
+        }
+    }
+
+    assert_eq!(be_u32(&zlib[pos..]), adler32(&data));
+    assert_eq!(pos + 4, zlib.len());
+
+    data
+}
+
+// Decodes a PNG snapshot, and returns its width, height and pixel data.
+fn decode(png: &[u8]) -> (usize, usize, Vec<u8>) {
+    let chunks = chunks(png);
+    let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+    assert_eq!(kinds, vec![b"IHDR", b"IDAT", b"IEND"]);
+
+    let header = &chunks[0].1;
+    assert_eq!(header.len(), 13);
+    let width = be_u32(&header[0..]) as usize;
+    let height = be_u32(&header[4..]) as usize;
+    // 8-bit RGB, not interlaced.
+    assert_eq!(header[8..], [8, 2, 0, 0, 0]);
+
+    let raw = inflate_stored(&chunks[1].1);
+    let stride = width * 3;
+    assert_eq!(raw.len(), (stride + 1) * height);
+
+    let mut rgb = Vec::with_capacity(stride * height);
+    for row in raw.chunks(stride + 1) {
+        assert_eq!(row[0], 0, "filtered scanline");
+        rgb.extend_from_slice(&row[1..]);
+    }
+
+    (width, height, rgb)
+}
+
+fn snapshot(blinkt: &Blinkt, name: &str) -> (usize, usize, Vec<u8>) {
+    let path = temp_path(name);
+    blinkt.save_snapshot(&path).unwrap();
+    let png = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    decode(&png)
+}
+
+// Returns the color of the image pixel at (x, y).
+fn color(width: usize, rgb: &[u8], x: usize, y: usize) -> (u8, u8, u8) {
+    let pos = (y * width + x) * 3;
+    (rgb[pos], rgb[pos + 1], rgb[pos + 2])
+}
+
+#[test]
+fn crc32_matches_the_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+}
+
+#[test]
+fn snapshots_round_trip() {
+    let mut blinkt = Blinkt::with_output(Capture::new(), 3);
+    blinkt.set_all_pixels_brightness(1.0);
+    blinkt.set_pixel(0, 255, 0, 0);
+    blinkt.set_pixel(1, 1, 2, 3);
+    blinkt.set_pixel(2, 0, 128, 255);
+
+    let (width, height, rgb) = snapshot(&blinkt, "round-trip");
+    assert_eq!((width, height), (3 * CELL_SIZE, CELL_SIZE));
+
+    let expected = [(255, 0, 0), (1, 2, 3), (0, 128, 255)];
+    for y in 0..height {
+        for x in 0..width {
+            assert_eq!(color(width, &rgb, x, y), expected[x / CELL_SIZE]);
+        }
+    }
+}
+
+#[test]
+fn snapshots_are_scaled_by_brightness() {
+    let mut blinkt = Blinkt::with_output(Capture::new(), 2);
+    blinkt.set_all_pixels(248, 124, 31);
+    blinkt.set_pixel_brightness(0, 1.0);
+    blinkt.set_pixel_brightness(1, 0.0);
+
+    let (width, _, rgb) = snapshot(&blinkt, "brightness");
+    assert_eq!(color(width, &rgb, 0, 0), (248, 124, 31));
+    assert_eq!(color(width, &rgb, CELL_SIZE, 0), (0, 0, 0));
+}
+
+#[test]
+fn large_snapshots_span_multiple_blocks() {
+    // 16 scanlines of 200 * 16 pixels don't fit in a single stored block.
+    let num_pixels = 200;
+    let mut blinkt = Blinkt::with_output(Capture::new(), num_pixels);
+    blinkt.set_all_pixels_brightness(1.0);
+    for idx in 0..num_pixels {
+        blinkt.set_pixel(idx, idx as u8, 255 - idx as u8, 7);
+    }
+
+    let (width, height, rgb) = snapshot(&blinkt, "large");
+    assert!((width * 3 + 1) * height > 65_535);
+    for idx in 0..num_pixels {
+        let x = idx * CELL_SIZE + CELL_SIZE / 2;
+        assert_eq!(
+            color(width, &rgb, x, height - 1),
+            (idx as u8, 255 - idx as u8, 7)
+        );
+    }
+}
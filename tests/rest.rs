@@ -0,0 +1,143 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// rest.rs - Verifies the PNG snapshot, error responses, client timeouts and
+// shutdown of the REST server.
+
+#![cfg(feature = "rest")]
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use blinkt::capture::{Capture, FaultInjector};
+use blinkt::rest::RestServer;
+use blinkt::Blinkt;
+
+// Sends a request without a body, and returns the response headers and body.
+fn request(blinkt: &mut Blinkt, method: &str, path: &str) -> (String, Vec<u8>) {
+    let mut server = RestServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        method, path
+    );
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        response
+    });
+
+    assert!(server.receive(blinkt).unwrap());
+    let response = client.join().unwrap();
+
+    let split = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .unwrap();
+
+    (
+        String::from_utf8(response[..split].to_vec()).unwrap(),
+        response[split + 4..].to_vec(),
+    )
+}
+
+#[test]
+fn snapshot_is_a_png_image() {
+    let mut blinkt = Blinkt::with_output(Capture::new(), 4);
+    blinkt.set_pixel(0, 255, 0, 0);
+
+    let (headers, body) = request(&mut blinkt, "GET", "/snapshot.png");
+
+    assert!(headers.starts_with("HTTP/1.1 200"));
+    assert!(headers.contains("Content-Type: image/png"));
+    assert_eq!(&body[..8], b"\x89PNG\r\n\x1a\n");
+}
+
+#[test]
+fn snapshot_only_supports_get() {
+    let mut blinkt = Blinkt::with_output(Capture::new(), 4);
+
+    let (headers, _) = request(&mut blinkt, "PUT", "/snapshot.png");
+
+    assert!(headers.starts_with("HTTP/1.1 405"));
+}
+
+#[test]
+fn output_errors_are_returned_to_the_client() {
+    let output = FaultInjector::new(Capture::new(), 0).with_error_rate(1.0);
+    let mut blinkt = Blinkt::with_output(output, 4);
+
+    let (headers, body) = request(&mut blinkt, "POST", "/clear");
+
+    assert!(headers.starts_with("HTTP/1.1 500"));
+    assert!(String::from_utf8(body).unwrap().contains("\"error\""));
+}
+
+#[test]
+fn slow_clients_time_out() {
+    let mut blinkt = Blinkt::with_output(Capture::new(), 4);
+    let mut server = RestServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+
+    // Sends a byte every 200 ms, which never triggers a per-read timeout.
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        for &byte in b"GET /state HTTP/1.1\r\nHost: localhost\r\n".iter().cycle() {
+            if stream.write_all(&[byte]).is_err() {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(200));
+        }
+    });
+
+    let start = Instant::now();
+    assert!(!server.receive(&mut blinkt).unwrap());
+    assert!(start.elapsed() < Duration::from_secs(8));
+
+    client.join().unwrap();
+}
+
+#[test]
+fn run_until_returns_when_stopped() {
+    let mut blinkt = Blinkt::with_output(Capture::new(), 4);
+    let mut server = RestServer::bind("127.0.0.1:0").unwrap();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopper = {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            stop.store(true, Ordering::SeqCst);
+        })
+    };
+
+    server.run_until(&mut blinkt, &stop).unwrap();
+    stopper.join().unwrap();
+}
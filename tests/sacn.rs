@@ -0,0 +1,247 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// sacn.rs - Verifies the data and sync handling of the sACN receiver,
+// including source priorities and takeover, and packets with truncated or
+// oversized property value counts.
+
+#![cfg(feature = "sacn")]
+
+use std::net::UdpSocket;
+use std::thread;
+use std::time::Duration;
+
+use blinkt::capture::Capture;
+use blinkt::sacn::{FlushPolicy, LossPolicy, SacnReceiver};
+use blinkt::Blinkt;
+
+const NUM_PIXELS: usize = 4;
+
+const SOURCE_A: [u8; 16] = [0xa; 16];
+const SOURCE_B: [u8; 16] = [0xb; 16];
+
+struct Fixture {
+    receiver: SacnReceiver,
+    blinkt: Blinkt,
+    client: UdpSocket,
+}
+
+impl Fixture {
+    fn new(flush_policy: FlushPolicy) -> Fixture {
+        let mut receiver = SacnReceiver::bind("127.0.0.1:0").unwrap();
+        receiver.set_flush_policy(flush_policy);
+        receiver.map_universe(1, 0).unwrap();
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(receiver.local_addr().unwrap()).unwrap();
+
+        Fixture {
+            receiver,
+            blinkt: Blinkt::with_output(Capture::new(), NUM_PIXELS),
+            client,
+        }
+    }
+
+    // Sends `packet` to the receiver, and returns whether a frame was sent.
+    fn send(&mut self, packet: &[u8]) -> bool {
+        self.client.send(packet).unwrap();
+        self.receiver.receive(&mut self.blinkt).unwrap()
+    }
+
+    fn pixels(&self) -> Vec<(u8, u8, u8)> {
+        self.blinkt
+            .frame()
+            .iter()
+            .map(|pixel| pixel.rgb())
+            .collect()
+    }
+}
+
+// Builds the root layer of an E1.31 packet.
+fn root_layer(vector: u32, cid: &[u8; 16]) -> Vec<u8> {
+    let mut packet = vec![0x00, 0x10, 0x00, 0x00];
+    packet.extend_from_slice(b"ASC-E1.17\0\0\0");
+    packet.extend_from_slice(&[0x70, 0x00]);
+    packet.extend_from_slice(&vector.to_be_bytes());
+    packet.extend_from_slice(cid);
+    packet
+}
+
+// Builds a data packet with `count` as the property value count, regardless
+// of the number of channel values that follow.
+fn data(cid: &[u8; 16], priority: u8, options: u8, count: u16, channels: &[u8]) -> Vec<u8> {
+    let mut packet = root_layer(0x0000_0004, cid);
+    packet.extend_from_slice(&[0x70, 0x00]);
+    packet.extend_from_slice(&0x0000_0002u32.to_be_bytes());
+    packet.extend_from_slice(&[0u8; 64]);
+    packet.extend_from_slice(&[priority, 0, 0, 0, options]);
+    // Universe 1.
+    packet.extend_from_slice(&[0x00, 0x01]);
+    packet.extend_from_slice(&[0x70, 0x00, 0x02, 0xa1, 0x00, 0x00, 0x00, 0x01]);
+    packet.extend_from_slice(&count.to_be_bytes());
+    // Start code.
+    packet.push(0);
+    packet.extend_from_slice(channels);
+    packet
+}
+
+// Builds a data packet with a property value count that matches `channels`.
+fn dmx(cid: &[u8; 16], priority: u8, channels: &[u8]) -> Vec<u8> {
+    data(cid, priority, 0, channels.len() as u16 + 1, channels)
+}
+
+fn sync() -> Vec<u8> {
+    let mut packet = root_layer(0x0000_0008, &SOURCE_A);
+    packet.extend_from_slice(&[0x70, 0x00]);
+    packet.extend_from_slice(&0x0000_0001u32.to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0, 0, 0]);
+    packet
+}
+
+#[test]
+fn data_sets_the_mapped_pixels() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+
+    assert!(fixture.send(&dmx(&SOURCE_A, 100, &[1, 2, 3, 4, 5, 6])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(1, 2, 3), (4, 5, 6), (0, 0, 0), (0, 0, 0)]
+    );
+}
+
+#[test]
+fn truncated_packets_only_set_the_received_channels() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+
+    // The count claims 4 pixels, but only 1 pixel and part of the next one
+    // follow.
+    assert!(fixture.send(&data(&SOURCE_A, 100, 0, 13, &[10, 20, 30, 40, 50])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(10, 20, 30), (0, 0, 0), (0, 0, 0), (0, 0, 0)]
+    );
+
+    // A count of 0 doesn't even include the start code.
+    assert!(fixture.send(&data(&SOURCE_A, 100, 0, 0, &[255, 255, 255])));
+    assert_eq!(fixture.pixels()[0], (10, 20, 30));
+}
+
+#[test]
+fn oversized_counts_are_limited_to_the_packet() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+
+    let channels: Vec<u8> = (0..600).map(|channel| channel as u8).collect();
+    assert!(fixture.send(&data(&SOURCE_A, 100, 0, 0xffff, &channels)));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(0, 1, 2), (3, 4, 5), (6, 7, 8), (9, 10, 11)]
+    );
+}
+
+#[test]
+fn invalid_packets_are_ignored() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+
+    let packet = dmx(&SOURCE_A, 100, &[255, 255, 255]);
+    assert!(!fixture.send(&packet[..125]));
+    assert!(!fixture.send(&packet[..21]));
+
+    // Preview data.
+    assert!(!fixture.send(&data(&SOURCE_A, 100, 0x80, 4, &[255, 255, 255])));
+
+    // Alternate start code.
+    let mut alternate = packet.clone();
+    alternate[125] = 0xdd;
+    assert!(!fixture.send(&alternate));
+
+    // Unmapped universe.
+    let mut unmapped = packet;
+    unmapped[114] = 2;
+    assert!(!fixture.send(&unmapped));
+
+    assert!(fixture.pixels().iter().all(|&rgb| rgb == (0, 0, 0)));
+}
+
+#[test]
+fn higher_priority_sources_take_over() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+
+    assert!(fixture.send(&dmx(&SOURCE_A, 100, &[1, 1, 1])));
+
+    // Lower and equal priorities from another source are ignored.
+    assert!(!fixture.send(&dmx(&SOURCE_B, 50, &[2, 2, 2])));
+    assert!(!fixture.send(&dmx(&SOURCE_B, 100, &[2, 2, 2])));
+    assert_eq!(fixture.pixels()[0], (1, 1, 1));
+
+    assert!(fixture.send(&dmx(&SOURCE_B, 150, &[3, 3, 3])));
+    assert_eq!(fixture.pixels()[0], (3, 3, 3));
+
+    // The previous source is now outranked.
+    assert!(!fixture.send(&dmx(&SOURCE_A, 100, &[4, 4, 4])));
+
+    // The current source can lower its own priority.
+    assert!(fixture.send(&dmx(&SOURCE_B, 10, &[5, 5, 5])));
+    assert!(fixture.send(&dmx(&SOURCE_A, 100, &[6, 6, 6])));
+    assert_eq!(fixture.pixels()[0], (6, 6, 6));
+}
+
+#[test]
+fn lost_sources_can_be_taken_over() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+    fixture
+        .receiver
+        .set_timeout(Duration::from_millis(50))
+        .unwrap();
+
+    assert!(fixture.send(&dmx(&SOURCE_A, 200, &[1, 1, 1])));
+    thread::sleep(Duration::from_millis(60));
+
+    assert!(fixture.send(&dmx(&SOURCE_B, 10, &[2, 2, 2])));
+    assert_eq!(fixture.pixels()[0], (2, 2, 2));
+}
+
+#[test]
+fn terminated_streams_are_blanked() {
+    let mut fixture = Fixture::new(FlushPolicy::Immediate);
+    fixture.receiver.set_loss_policy(LossPolicy::Blank);
+
+    assert!(fixture.send(&dmx(&SOURCE_A, 100, &[1, 2, 3, 4, 5, 6])));
+
+    // Only the current source can terminate the stream.
+    assert!(!fixture.send(&data(&SOURCE_B, 100, 0x40, 1, &[])));
+    assert_eq!(fixture.pixels()[1], (4, 5, 6));
+
+    assert!(fixture.send(&data(&SOURCE_A, 100, 0x40, 1, &[])));
+    assert!(fixture.pixels().iter().all(|&rgb| rgb == (0, 0, 0)));
+
+    // Any source can take over afterwards.
+    assert!(fixture.send(&dmx(&SOURCE_B, 1, &[7, 7, 7])));
+    assert_eq!(fixture.pixels()[0], (7, 7, 7));
+}
+
+#[test]
+fn sync_flushes_the_received_universes() {
+    let mut fixture = Fixture::new(FlushPolicy::Sync);
+
+    assert!(!fixture.send(&dmx(&SOURCE_A, 100, &[9, 8, 7])));
+    assert!(!fixture.send(&sync()[..48]));
+    assert!(fixture.send(&sync()));
+    assert_eq!(fixture.pixels()[0], (9, 8, 7));
+}
@@ -0,0 +1,71 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+
+// shm.rs - Verifies the permissions of the shared memory file, and that a
+// truncated file is reported as an error.
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process;
+
+use blinkt::shm::SharedFrame;
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("blinkt-test-{}-{}", name, process::id()))
+}
+
+#[test]
+fn new_files_are_private() {
+    let frame = SharedFrame::create(temp_path("private"), 4).unwrap();
+    let mode = fs::metadata(frame.path()).unwrap().permissions().mode();
+
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn mode_can_be_changed() {
+    let frame = SharedFrame::create(temp_path("mode"), 4)
+        .unwrap()
+        .with_mode(0o660)
+        .unwrap();
+    let mode = fs::metadata(frame.path()).unwrap().permissions().mode();
+
+    assert_eq!(mode & 0o777, 0o660);
+}
+
+#[test]
+fn truncated_file_is_an_error() {
+    let frame = SharedFrame::create(temp_path("truncated"), 4).unwrap();
+    let mut buffer = Vec::new();
+    assert!(frame.read(&mut buffer).is_ok());
+    assert_eq!(buffer.len(), 12);
+
+    OpenOptions::new()
+        .write(true)
+        .open(frame.path())
+        .unwrap()
+        .set_len(5)
+        .unwrap();
+
+    assert!(frame.read(&mut buffer).is_err());
+}
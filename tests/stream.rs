@@ -0,0 +1,38 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+
+// stream.rs - Verifies that raw frame readers stop instead of spinning when
+// there are no pixels to read frames for.
+
+use std::io;
+
+use blinkt::capture::Capture;
+use blinkt::stream::RawReader;
+use blinkt::Blinkt;
+
+#[test]
+fn empty_frames_end_the_stream() {
+    let mut blinkt = Blinkt::with_output(Capture::new(), 0);
+    let mut reader = RawReader::new(io::repeat(0));
+
+    assert!(!reader.receive(&mut blinkt).unwrap());
+    reader.run(&mut blinkt).unwrap();
+}
@@ -0,0 +1,222 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// tpm2.rs - Verifies the TPM2.net packet numbering and the serial TPM2
+// framing, including frames with truncated or oversized length fields.
+
+#![cfg(feature = "tpm2")]
+
+use std::io::Cursor;
+use std::net::UdpSocket;
+
+use blinkt::capture::Capture;
+use blinkt::tpm2::{Tpm2NetReceiver, Tpm2Reader};
+use blinkt::Blinkt;
+
+const NUM_PIXELS: usize = 4;
+
+struct Fixture {
+    receiver: Tpm2NetReceiver,
+    blinkt: Blinkt,
+    capture: Capture,
+    client: UdpSocket,
+}
+
+impl Fixture {
+    fn new() -> Fixture {
+        let receiver = Tpm2NetReceiver::bind("127.0.0.1:0").unwrap();
+        let capture = Capture::new();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(receiver.local_addr().unwrap()).unwrap();
+
+        Fixture {
+            receiver,
+            blinkt: Blinkt::with_output(capture.clone(), NUM_PIXELS),
+            capture,
+            client,
+        }
+    }
+
+    // Sends `packet` to the receiver, and returns whether a frame was sent.
+    fn send(&mut self, packet: &[u8]) -> bool {
+        self.client.send(packet).unwrap();
+        self.receiver.receive(&mut self.blinkt).unwrap()
+    }
+
+    fn pixels(&self) -> Vec<(u8, u8, u8)> {
+        pixels(&self.blinkt)
+    }
+}
+
+fn pixels(blinkt: &Blinkt) -> Vec<(u8, u8, u8)> {
+    blinkt.frame().iter().map(|pixel| pixel.rgb()).collect()
+}
+
+// Builds a TPM2.net data packet with `len` in the length field, regardless
+// of the amount of data that follows.
+fn packet(number: u8, count: u8, len: u16, data: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0x9c, 0xda];
+    packet.extend_from_slice(&len.to_be_bytes());
+    packet.extend_from_slice(&[number, count]);
+    packet.extend_from_slice(data);
+    packet.push(0x36);
+    packet
+}
+
+fn data(number: u8, count: u8, data: &[u8]) -> Vec<u8> {
+    packet(number, count, data.len() as u16, data)
+}
+
+// Builds a serial TPM2 frame.
+fn frame(kind: u8, data: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0xc9, kind];
+    frame.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    frame.extend_from_slice(data);
+    frame.push(0x36);
+    frame
+}
+
+#[test]
+fn single_packet_frames_are_shown() {
+    let mut fixture = Fixture::new();
+
+    assert!(fixture.send(&data(1, 1, &[1, 2, 3, 4, 5, 6])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(1, 2, 3), (4, 5, 6), (0, 0, 0), (0, 0, 0)]
+    );
+    assert!(!fixture.capture.take().is_empty());
+}
+
+#[test]
+fn packet_numbers_determine_the_offset() {
+    let mut fixture = Fixture::new();
+
+    // The packets of a frame may arrive out of order. Only the last packet
+    // sends the frame to the pixels.
+    assert!(!fixture.send(&data(2, 3, &[4, 4, 4])));
+    assert!(!fixture.send(&data(1, 3, &[1, 1, 1])));
+    assert!(fixture.capture.take().is_empty());
+
+    assert!(fixture.send(&data(3, 3, &[9, 9, 9])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(1, 1, 1), (4, 4, 4), (9, 9, 9), (0, 0, 0)]
+    );
+    assert!(!fixture.capture.take().is_empty());
+}
+
+#[test]
+fn packet_number_zero_is_the_first_packet() {
+    let mut fixture = Fixture::new();
+
+    assert!(fixture.send(&data(0, 0, &[7, 8, 9])));
+    assert_eq!(fixture.pixels()[0], (7, 8, 9));
+}
+
+#[test]
+fn packets_beyond_the_strip_are_ignored() {
+    let mut fixture = Fixture::new();
+
+    assert!(!fixture.send(&data(1, 255, &[1, 2, 3, 4, 5, 6, 7, 8, 9])));
+    assert!(fixture.send(&data(255, 255, &[1, 2, 3, 4, 5, 6, 7, 8, 9])));
+    assert_eq!(
+        fixture.pixels(),
+        vec![(1, 2, 3), (4, 5, 6), (7, 8, 9), (0, 0, 0)]
+    );
+}
+
+#[test]
+fn truncated_packets_are_ignored() {
+    let mut fixture = Fixture::new();
+
+    let mut truncated = packet(1, 1, 6, &[1, 2, 3]);
+    assert!(!fixture.send(&truncated));
+    truncated.pop();
+    assert!(!fixture.send(&truncated));
+    assert!(!fixture.send(&data(1, 1, &[1, 2, 3])[..6]));
+    assert_eq!(fixture.pixels(), vec![(0, 0, 0); NUM_PIXELS]);
+    assert!(fixture.capture.take().is_empty());
+}
+
+#[test]
+fn oversized_length_fields_are_ignored() {
+    let mut fixture = Fixture::new();
+
+    assert!(!fixture.send(&packet(1, 1, 0xffff, &[1, 2, 3])));
+
+    // The length field exceeds the largest packet the receiver accepts.
+    let large = vec![1u8; 1600];
+    assert!(!fixture.send(&data(1, 1, &large)));
+    assert_eq!(fixture.pixels(), vec![(0, 0, 0); NUM_PIXELS]);
+}
+
+#[test]
+fn invalid_packets_are_ignored() {
+    let mut fixture = Fixture::new();
+
+    let mut command = data(1, 1, &[1, 2, 3]);
+    command[1] = 0xc0;
+    assert!(!fixture.send(&command));
+
+    let mut serial = data(1, 1, &[1, 2, 3]);
+    serial[0] = 0xc9;
+    assert!(!fixture.send(&serial));
+
+    let mut unterminated = data(1, 1, &[1, 2, 3]);
+    *unterminated.last_mut().unwrap() = 0;
+    assert!(!fixture.send(&unterminated));
+
+    assert_eq!(fixture.pixels(), vec![(0, 0, 0); NUM_PIXELS]);
+}
+
+#[test]
+fn serial_frames_are_shown() {
+    let mut stream = vec![0, 0x36];
+    stream.extend(frame(0xda, &[1, 2, 3]));
+    stream.extend(frame(0xc0, &[4, 5, 6]));
+    stream.extend(frame(0xda, &[7, 8, 9, 10, 11, 12]));
+
+    let mut blinkt = Blinkt::with_output(Capture::new(), NUM_PIXELS);
+    let mut reader = Tpm2Reader::new(Cursor::new(stream));
+
+    assert_eq!(reader.receive(&mut blinkt).unwrap(), Some(true));
+    assert_eq!(pixels(&blinkt)[0], (1, 2, 3));
+    assert_eq!(reader.receive(&mut blinkt).unwrap(), Some(false));
+    assert_eq!(reader.receive(&mut blinkt).unwrap(), Some(true));
+    assert_eq!(pixels(&blinkt)[1], (10, 11, 12));
+    assert_eq!(reader.receive(&mut blinkt).unwrap(), None);
+}
+
+#[test]
+fn truncated_serial_frames_end_the_stream() {
+    let mut stream = frame(0xda, &[1, 2, 3]);
+    // The length field claims far more data than the stream contains.
+    stream.extend_from_slice(&[0xc9, 0xda, 0xff, 0xff, 4, 5, 6]);
+
+    let capture = Capture::new();
+    let mut blinkt = Blinkt::with_output(capture.clone(), NUM_PIXELS);
+    let mut reader = Tpm2Reader::new(Cursor::new(stream));
+
+    reader.run(&mut blinkt).unwrap();
+    assert_eq!(pixels(&blinkt)[0], (1, 2, 3));
+    assert_eq!(pixels(&blinkt)[1], (0, 0, 0));
+    assert!(!capture.take().is_empty());
+}
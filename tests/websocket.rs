@@ -0,0 +1,90 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// websocket.rs - Verifies the WebSocket handshake and frames of the preview
+// server.
+
+#![cfg(feature = "websocket")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+use blinkt::capture::Capture;
+use blinkt::Blinkt;
+
+// Sends a WebSocket handshake with `key`, and returns the response headers.
+fn handshake(stream: &mut TcpStream, key: &str) -> String {
+    write!(
+        stream,
+        "GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        key
+    )
+    .unwrap();
+
+    let mut reader = BufReader::new(stream);
+    let mut headers = String::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" || line.is_empty() {
+            return headers;
+        }
+
+        headers.push_str(&line);
+    }
+}
+
+#[test]
+fn accept_key_matches_rfc_6455() {
+    let mut blinkt = Blinkt::with_output(Capture::new(), 2);
+    let addr = blinkt.serve_preview("127.0.0.1:0").unwrap();
+
+    // The examples from RFC 6455 section 1.3, and a second key whose input
+    // spans two SHA-1 blocks as well.
+    for &(key, accept) in &[
+        ("dGhlIHNhbXBsZSBub25jZQ==", "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="),
+        ("x3JJHMbDL1EzLkh9GBhXDw==", "HSmrc0sMlYUkAGmm5OPpG2HaGWk="),
+    ] {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let headers = handshake(&mut stream, key);
+
+        assert!(headers.starts_with("HTTP/1.1 101"));
+        assert!(headers.contains(&format!("Sec-WebSocket-Accept: {}\r\n", accept)));
+    }
+}
+
+#[test]
+fn frames_are_sent_as_binary_messages() {
+    let mut blinkt = Blinkt::with_output(Capture::new(), 2);
+    let addr = blinkt.serve_preview("127.0.0.1:0").unwrap();
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    handshake(&mut stream, "dGhlIHNhbXBsZSBub25jZQ==");
+
+    blinkt.set_pixel_rgbb(0, 255, 128, 0, 1.0);
+    blinkt.swap();
+    blinkt.show().unwrap();
+
+    let mut message = [0u8; 10];
+    stream.read_exact(&mut message).unwrap();
+
+    assert_eq!(&message[..2], &[0x82, 8]);
+    assert_eq!(&message[2..6], &[255, 128, 0, 255]);
+}
@@ -0,0 +1,100 @@
+// Copyright (c) 2016-2019 Rene van der Meer
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL
+// THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+// wled.rs - Verifies that individual pixel updates sent to the WLED server
+// are bounds-checked.
+
+#![cfg(feature = "wled")]
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+
+use blinkt::capture::Capture;
+use blinkt::wled::WledServer;
+use blinkt::Blinkt;
+
+const NUM_PIXELS: usize = 4;
+
+// Posts `body` to /json/state, and returns the response status code and the
+// colors of the pixels afterwards.
+fn post_state(body: &str) -> (u16, Vec<(u8, u8, u8)>) {
+    let mut server = WledServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    let mut blinkt = Blinkt::with_output(Capture::new(), NUM_PIXELS);
+
+    let request = format!(
+        "POST /json/state HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+
+    let client = thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response[9..12].parse::<u16>().unwrap()
+    });
+
+    assert!(server.receive(&mut blinkt).unwrap());
+    let status = client.join().unwrap();
+
+    (status, blinkt.frame().iter().map(|pixel| pixel.rgb()).collect())
+}
+
+#[test]
+fn huge_index_is_ignored() {
+    let (status, pixels) = post_state(r#"{"seg":{"i":[18446744073709551615,"ff0000"]}}"#);
+
+    assert_eq!(status, 200);
+    assert!(pixels.iter().all(|&rgb| rgb != (255, 0, 0)));
+}
+
+#[test]
+fn huge_range_is_limited_to_the_strip() {
+    let (status, pixels) = post_state(r#"{"seg":{"i":[0,4000000000,"ff0000"]}}"#);
+
+    assert_eq!(status, 200);
+    assert_eq!(pixels.len(), NUM_PIXELS);
+    assert!(pixels.iter().all(|&rgb| rgb == pixels[0]));
+    assert_ne!(pixels[0], (0, 0, 0));
+}
+
+#[test]
+fn out_of_range_indexes_are_ignored() {
+    let (status, pixels) =
+        post_state(r#"{"seg":{"i":[1,"00ff00",2,9,"0000ff",18446744073709551615,"ff0000"]}}"#);
+
+    assert_eq!(status, 200);
+    assert_eq!(pixels[1].0, 0);
+    assert!(pixels[1].1 > 0);
+    assert_eq!(pixels[2].2, pixels[3].2);
+    assert!(pixels[2].2 > 0);
+    assert!(pixels.iter().all(|&(red, _, _)| red == 0 || pixels[0].0 == red));
+}
+
+#[test]
+fn reversed_range_is_rejected() {
+    let (status, _) = post_state(r#"{"seg":{"i":[3,1,"ff0000"]}}"#);
+
+    assert_eq!(status, 400);
+}